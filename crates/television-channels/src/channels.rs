@@ -1,9 +1,11 @@
 use crate::entry::Entry;
 use television_derive::{Broadcast, ToCliChannel, ToUnitChannel};
+use television_fuzzy::matcher::config::CaseSensitivity;
 
 mod alias;
+pub mod cable;
 mod env;
-mod files;
+pub mod files;
 mod git_repos;
 pub mod remote_control;
 pub mod stdin;
@@ -46,6 +48,21 @@ mod text;
 ///   ```ignore
 ///   fn total_count(&self) -> u32;
 ///   ```
+/// - `loading`: Check whether the channel's source is still producing
+///   entries (e.g. the file system is still being crawled).
+///   ```ignore
+///   fn loading(&self) -> bool;
+///   ```
+/// - `reload`: Load the entries of the channel again from its source (e.g.
+///   crawl the file system again), keeping the current search pattern.
+///   ```ignore
+///   fn reload(&mut self);
+///   ```
+/// - `set_case_sensitivity`: Change how letter case is taken into account
+///   when matching, re-running the current search.
+///   ```ignore
+///   fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity);
+///   ```
 ///
 pub trait OnAir: Send {
     /// Find entries that match the given pattern.
@@ -71,6 +88,15 @@ pub trait OnAir: Send {
     /// Check if the channel is currently running.
     fn running(&self) -> bool;
 
+    /// Check if the channel's source is still producing entries.
+    fn loading(&self) -> bool;
+
+    /// Load the entries of the channel again from its source.
+    fn reload(&mut self);
+
+    /// Set how letter case is taken into account when matching.
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity);
+
     /// Turn off
     fn shutdown(&self);
 }
@@ -137,6 +163,22 @@ pub enum TelevisionChannel {
     #[exclude_from_unit]
     #[exclude_from_cli]
     RemoteControl(remote_control::RemoteControl),
+    /// A channel defined in the configuration from shell commands.
+    ///
+    /// This channel allows to search through the output of a command.
+    #[exclude_from_unit]
+    #[exclude_from_cli]
+    Cable(cable::Channel),
+}
+
+impl TelevisionChannel {
+    /// The name of the channel, as displayed to the user.
+    pub fn name(&self) -> String {
+        match self {
+            TelevisionChannel::Cable(channel) => channel.name.clone(),
+            _ => UnitChannel::from(self).to_string(),
+        }
+    }
 }
 
 impl From<&Entry> for TelevisionChannel {
@@ -167,6 +209,9 @@ macro_rules! variant_to_module {
     (RemoteControl) => {
         remote_control::RemoteControl
     };
+    (Cable) => {
+        cable::Channel
+    };
 }
 
 /// A macro that generates two methods for the `TelevisionChannel` enum based on
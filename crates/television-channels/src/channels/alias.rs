@@ -2,7 +2,11 @@ use crate::channels::OnAir;
 use crate::entry::Entry;
 use crate::entry::PreviewType;
 use devicons::FileIcon;
-use television_fuzzy::matcher::{config::Config, injector::Injector, Matcher};
+use television_fuzzy::matcher::{
+    config::{CaseSensitivity, Config},
+    injector::Injector,
+    Matcher,
+};
 use television_utils::indices::sep_name_and_value_indices;
 use television_utils::strings::preprocess_line;
 use tracing::debug;
@@ -22,6 +26,7 @@ impl Alias {
 pub struct Channel {
     matcher: Matcher<Alias>,
     file_icon: FileIcon,
+    load_handle: tokio::task::JoinHandle<()>,
 }
 
 const NUM_THREADS: usize = 1;
@@ -48,11 +53,12 @@ impl Channel {
     pub fn new() -> Self {
         let matcher = Matcher::new(Config::default().n_threads(NUM_THREADS));
         let injector = matcher.injector();
-        tokio::spawn(load_aliases(injector));
+        let load_handle = tokio::spawn(load_aliases(injector));
 
         Self {
             matcher,
             file_icon: FileIcon::from(FILE_ICON_STR),
+            load_handle,
         }
     }
 }
@@ -129,7 +135,23 @@ impl OnAir for Channel {
         self.matcher.status.running
     }
 
-    fn shutdown(&self) {}
+    fn loading(&self) -> bool {
+        !self.load_handle.is_finished()
+    }
+
+    fn reload(&mut self) {
+        self.load_handle.abort();
+        self.matcher.restart();
+        self.load_handle = tokio::spawn(load_aliases(self.matcher.injector()));
+    }
+
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.matcher.set_case_sensitivity(case_sensitivity);
+    }
+
+    fn shutdown(&self) {
+        self.load_handle.abort();
+    }
 }
 
 #[allow(clippy::unused_async)]
@@ -0,0 +1,441 @@
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Stdio};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use devicons::FileIcon;
+use serde::Deserialize;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::channels::OnAir;
+use crate::entry::{Entry, PreviewType};
+use television_fuzzy::matcher::{
+    config::{CaseSensitivity, Config},
+    injector::Injector,
+    Matcher,
+};
+use television_utils::command::{
+    expand_command, kill_shell_command, shell_command,
+};
+use television_utils::strings::preprocess_line;
+
+/// A channel defined in the configuration from shell commands.
+///
+/// ```toml
+/// [[cable_channels]]
+/// name = "git-branch"
+/// source_command = "git branch --format='%(refname:short)'"
+/// preview_command = "git show {}"
+/// dedup = true
+/// icon = { glyph = "\ue725", color = "#f1502f" }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct CableChannelPrototype {
+    /// The name used to select the channel.
+    pub name: String,
+    /// The command whose output lines are the entries of the channel.
+    pub source_command: String,
+    /// The command used to preview an entry, `{}` being substituted with the
+    /// entry.
+    pub preview_command: Option<String>,
+    /// Whether duplicate lines of the source command are dropped, only
+    /// keeping the first one.
+    #[serde(default)]
+    pub dedup: bool,
+    /// The icon displayed in front of every entry, instead of a generic
+    /// shell icon.
+    #[serde(default)]
+    pub icon: Option<CableIcon>,
+}
+
+/// An icon for the entries of a cable channel.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct CableIcon {
+    /// The character displayed, typically a nerd font glyph.
+    pub glyph: char,
+    /// The color of the glyph as a hex string (e.g. `#f1502f`), the color of
+    /// the generic shell icon being used if unset.
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+impl CableIcon {
+    fn to_file_icon(&self) -> FileIcon {
+        let default = FileIcon::from(DEFAULT_ICON);
+        FileIcon {
+            icon: self.glyph,
+            color: self.color.as_deref().map_or(default.color, static_str),
+        }
+    }
+}
+
+const DEFAULT_ICON: &str = "sh";
+
+/// Get a `'static` copy of the given string, as required by `FileIcon`.
+///
+/// Each distinct string is only leaked once so that recreating channels
+/// doesn't leak more memory.
+fn static_str(s: &str) -> &'static str {
+    static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut interned = INTERNED
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(interned) = interned.get(s) {
+        return interned;
+    }
+    let leaked: &'static str = Box::leak(s.to_string().into_boxed_str());
+    interned.insert(leaked);
+    leaked
+}
+
+pub struct Channel {
+    pub name: String,
+    matcher: Matcher<String>,
+    source_command: String,
+    preview_type: PreviewType,
+    icon: FileIcon,
+    dedup: bool,
+    crawl_handle: JoinHandle<()>,
+    /// The process running the source command, while it does.
+    source_process: Arc<Mutex<Option<Child>>>,
+}
+
+impl Channel {
+    pub fn new(prototype: &CableChannelPrototype) -> Self {
+        let matcher = Matcher::new(Config::default());
+        let source_process = Arc::new(Mutex::new(None));
+        let crawl_handle = spawn_source_command(
+            &prototype.source_command,
+            prototype.dedup,
+            matcher.injector(),
+            source_process.clone(),
+        );
+        Self {
+            name: prototype.name.clone(),
+            matcher,
+            source_command: prototype.source_command.clone(),
+            preview_type: prototype
+                .preview_command
+                .clone()
+                .map_or(PreviewType::Basic, PreviewType::Command),
+            icon: prototype.icon.as_ref().map_or_else(
+                || FileIcon::from(DEFAULT_ICON),
+                CableIcon::to_file_icon,
+            ),
+            dedup: prototype.dedup,
+            crawl_handle,
+            source_process,
+        }
+    }
+
+    /// Stop reading the output of the source command and kill it, since
+    /// aborting a blocking task doesn't stop it.
+    fn kill_source_command(&self) {
+        self.crawl_handle.abort();
+        let child = self
+            .source_process
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(mut child) = child {
+            debug!("Killing source command {:?}", child.id());
+            kill_shell_command(&mut child);
+            let _ = child.wait();
+        }
+    }
+}
+
+impl From<&CableChannelPrototype> for Channel {
+    fn from(prototype: &CableChannelPrototype) -> Self {
+        Self::new(prototype)
+    }
+}
+
+/// Turn a line of the source command's output into an entry name, skipping
+/// blank lines.
+fn parse_line(line: &str) -> Option<String> {
+    let line = preprocess_line(line);
+    if line.trim().is_empty() {
+        None
+    } else {
+        Some(line)
+    }
+}
+
+/// Run the source command in the background, pushing its output lines into
+/// `injector`.
+///
+/// The process is kept in `source_process` until it exits so that it can be
+/// killed.
+fn spawn_source_command(
+    source_command: &str,
+    dedup: bool,
+    injector: Injector<String>,
+    source_process: Arc<Mutex<Option<Child>>>,
+) -> JoinHandle<()> {
+    let source_command = expand_command(source_command);
+    tokio::task::spawn_blocking(move || {
+        debug!("Running source command: {:?}", source_command);
+        let mut child = match shell_command(&source_command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                warn!("Failed to run {:?}: {:?}", source_command, e);
+                return;
+            }
+        };
+        let stdout = child.stdout.take();
+        *source_process
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(child);
+        if let Some(stdout) = stdout {
+            let mut seen = HashSet::new();
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                let Some(name) = parse_line(&line) else {
+                    continue;
+                };
+                if !dedup || seen.insert(name.clone()) {
+                    let () = injector.push(name, |e, cols| {
+                        cols[0] = e.clone().into();
+                    });
+                }
+            }
+        }
+        // the process is gone already if it was killed
+        let child = source_process
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(mut child) = child {
+            let _ = child.wait();
+        }
+    })
+}
+
+impl OnAir for Channel {
+    fn find(&mut self, pattern: &str) {
+        self.matcher.find(pattern);
+    }
+
+    fn results(&mut self, num_entries: u32, offset: u32) -> Vec<Entry> {
+        self.matcher.tick();
+        self.matcher
+            .results(num_entries, offset)
+            .into_iter()
+            .map(|item| {
+                Entry::new(item.matched_string, self.preview_type.clone())
+                    .with_name_match_ranges(item.match_indices)
+                    .with_icon(self.icon)
+            })
+            .collect()
+    }
+
+    fn get_result(&self, index: u32) -> Option<Entry> {
+        self.matcher.get_result(index).map(|item| {
+            Entry::new(item.matched_string, self.preview_type.clone())
+                .with_icon(self.icon)
+        })
+    }
+
+    fn result_count(&self) -> u32 {
+        self.matcher.matched_item_count
+    }
+
+    fn total_count(&self) -> u32 {
+        self.matcher.total_item_count
+    }
+
+    fn running(&self) -> bool {
+        self.matcher.status.running
+    }
+
+    fn loading(&self) -> bool {
+        !self.crawl_handle.is_finished()
+    }
+
+    fn reload(&mut self) {
+        self.kill_source_command();
+        self.matcher.restart();
+        self.source_process = Arc::new(Mutex::new(None));
+        self.crawl_handle = spawn_source_command(
+            &self.source_command,
+            self.dedup,
+            self.matcher.injector(),
+            self.source_process.clone(),
+        );
+    }
+
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.matcher.set_case_sensitivity(case_sensitivity);
+    }
+
+    fn shutdown(&self) {
+        debug!("Shutting down cable channel");
+        self.kill_source_command();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("main"), Some("main".to_string()));
+        assert_eq!(parse_line("main\r"), Some("main".to_string()));
+        assert_eq!(
+            parse_line("feature/a\tb"),
+            Some("feature/a    b".to_string())
+        );
+        assert_eq!(parse_line(""), None);
+        assert_eq!(parse_line("   "), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_source_command() {
+        let mut channel = Channel::new(&CableChannelPrototype {
+            name: "test".to_string(),
+            source_command: "printf 'a\\n\\nb\\nc\\n'".to_string(),
+            preview_command: Some("echo {}".to_string()),
+            dedup: false,
+            icon: None,
+        });
+        channel.find("");
+        loop {
+            let finished = channel.crawl_handle.is_finished();
+            channel.results(0, 0);
+            if finished && !channel.running() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let mut names: Vec<String> = channel
+            .results(channel.result_count(), 0)
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["a", "b", "c"]);
+        assert_eq!(
+            channel.get_result(0).unwrap().preview_type,
+            PreviewType::Command("echo {}".to_string())
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_loading() {
+        let mut channel = Channel::new(&CableChannelPrototype {
+            name: "test".to_string(),
+            source_command: "echo a; sleep 0.3; echo b".to_string(),
+            preview_command: None,
+            dedup: false,
+            icon: None,
+        });
+        assert!(channel.loading());
+        let started = std::time::Instant::now();
+        while channel.loading() {
+            assert!(started.elapsed() < std::time::Duration::from_secs(10));
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        // the flag only clears once the source is exhausted
+        while channel.running() || channel.total_count() < 2 {
+            channel.results(0, 0);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(channel.total_count(), 2);
+        assert!(!channel.loading());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_icon() {
+        let mut channel = Channel::new(&CableChannelPrototype {
+            name: "test".to_string(),
+            source_command: "echo main".to_string(),
+            preview_command: None,
+            dedup: false,
+            icon: Some(CableIcon {
+                glyph: '\u{e725}',
+                color: Some("#f1502f".to_string()),
+            }),
+        });
+        channel.find("");
+        while channel.running() || channel.total_count() < 1 {
+            channel.results(0, 0);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let entries = channel.results(1, 0);
+        assert_eq!(
+            entries[0].icon,
+            Some(FileIcon {
+                icon: '\u{e725}',
+                color: "#f1502f",
+            })
+        );
+        // without a color, the generic icon's is used
+        let icon = CableIcon {
+            glyph: 'x',
+            color: None,
+        };
+        assert_eq!(icon.to_file_icon().color, FileIcon::from("sh").color);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_source_command_is_killed() {
+        let mut channel = Channel::new(&CableChannelPrototype {
+            name: "test".to_string(),
+            source_command: "sleep 30 | cat".to_string(),
+            preview_command: None,
+            dedup: false,
+            icon: None,
+        });
+        let pid = |channel: &Channel| {
+            let started = std::time::Instant::now();
+            loop {
+                if let Some(child) =
+                    channel.source_process.lock().unwrap().as_ref()
+                {
+                    return child.id();
+                }
+                assert!(
+                    started.elapsed() < std::time::Duration::from_secs(10)
+                );
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        };
+        let is_running = |pid: u32| {
+            std::process::Command::new("kill")
+                .args(["-0", &pid.to_string()])
+                .stderr(Stdio::null())
+                .status()
+                .unwrap()
+                .success()
+        };
+
+        let first = pid(&channel);
+        channel.reload();
+        assert!(!is_running(first));
+        let second = pid(&channel);
+        assert_ne!(first, second);
+        channel.shutdown();
+        assert!(!is_running(second));
+        assert!(channel.source_process.lock().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_static_str() {
+        let first = static_str("#f1502f");
+        let second = static_str(&String::from("#f1502f"));
+        assert_eq!(first, "#f1502f");
+        assert!(std::ptr::eq(first, second));
+    }
+}
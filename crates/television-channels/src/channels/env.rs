@@ -2,7 +2,11 @@ use devicons::FileIcon;
 
 use super::OnAir;
 use crate::entry::{Entry, PreviewType};
-use television_fuzzy::matcher::{config::Config, Matcher};
+use television_fuzzy::matcher::{
+    config::{CaseSensitivity, Config},
+    injector::Injector,
+    Matcher,
+};
 use television_utils::indices::sep_name_and_value_indices;
 use television_utils::strings::preprocess_line;
 
@@ -24,18 +28,7 @@ const FILE_ICON_STR: &str = "config";
 impl Channel {
     pub fn new() -> Self {
         let matcher = Matcher::new(Config::default().n_threads(NUM_THREADS));
-        let injector = matcher.injector();
-        for (name, value) in std::env::vars() {
-            let () = injector.push(
-                EnvVar {
-                    name: preprocess_line(&name),
-                    value: preprocess_line(&value),
-                },
-                |e, cols| {
-                    cols[0] = (e.name.clone() + &e.value).into();
-                },
-            );
-        }
+        load_env_vars(&matcher.injector());
         Channel {
             matcher,
             file_icon: FileIcon::from(FILE_ICON_STR),
@@ -43,6 +36,20 @@ impl Channel {
     }
 }
 
+fn load_env_vars(injector: &Injector<EnvVar>) {
+    for (name, value) in std::env::vars() {
+        let () = injector.push(
+            EnvVar {
+                name: preprocess_line(&name),
+                value: preprocess_line(&value),
+            },
+            |e, cols| {
+                cols[0] = (e.name.clone() + &e.value).into();
+            },
+        );
+    }
+}
+
 impl Default for Channel {
     fn default() -> Self {
         Self::new()
@@ -115,5 +122,18 @@ impl OnAir for Channel {
         self.matcher.status.running
     }
 
+    fn loading(&self) -> bool {
+        false
+    }
+
+    fn reload(&mut self) {
+        self.matcher.restart();
+        load_env_vars(&self.matcher.injector());
+    }
+
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.matcher.set_case_sensitivity(case_sensitivity);
+    }
+
     fn shutdown(&self) {}
 }
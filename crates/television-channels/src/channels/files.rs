@@ -3,12 +3,71 @@ use crate::entry::{Entry, PreviewType};
 use devicons::FileIcon;
 use std::collections::HashSet;
 use std::path::PathBuf;
-use television_fuzzy::matcher::{config::Config, injector::Injector, Matcher};
+use television_fuzzy::matcher::{
+    config::{CaseSensitivity, Config},
+    injector::Injector,
+    Matcher,
+};
 use television_utils::files::{walk_builder, DEFAULT_NUM_THREADS};
 use television_utils::strings::preprocess_line;
 
+/// Options controlling how the files channel walks directories.
+///
+/// # Examples
+/// ```
+/// use television_channels::channels::files::WalkOptions;
+///
+/// let options = WalkOptions::default().follow_links(true).max_depth(Some(2));
+/// assert!(options.respect_ignore);
+/// assert!(options.follow_links);
+/// assert_eq!(options.max_depth, Some(2));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalkOptions {
+    /// Whether `.gitignore`, `.ignore` and global git excludes are honored.
+    /// Hidden files are skipped either way.
+    pub respect_ignore: bool,
+    /// Whether symlinks to directories are followed.
+    pub follow_links: bool,
+    /// How deep to descend into directories, `1` only lists the entries of
+    /// the walked directories themselves.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        Self {
+            respect_ignore: true,
+            follow_links: false,
+            max_depth: None,
+        }
+    }
+}
+
+impl WalkOptions {
+    /// Set whether ignore files are honored.
+    pub fn respect_ignore(mut self, respect_ignore: bool) -> Self {
+        self.respect_ignore = respect_ignore;
+        self
+    }
+
+    /// Set whether symlinks to directories are followed.
+    pub fn follow_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Set how deep to descend into directories.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
 pub struct Channel {
     matcher: Matcher<String>,
+    paths: Vec<PathBuf>,
+    options: WalkOptions,
     crawl_handle: tokio::task::JoinHandle<()>,
     // PERF: cache results (to make deleting characters smoother) with
     // a shallow stack of sub-patterns as keys (e.g. "a", "ab", "abc")
@@ -16,11 +75,27 @@ pub struct Channel {
 
 impl Channel {
     pub fn new(paths: Vec<PathBuf>) -> Self {
-        let matcher = Matcher::new(Config::default().match_paths(true));
+        Self::with_options(paths, WalkOptions::default())
+    }
+
+    /// Create a channel listing the files under `paths`, walked according
+    /// to `options`.
+    pub fn with_options(paths: Vec<PathBuf>, options: WalkOptions) -> Self {
+        // files are only stat'ed for their modification time when they tie
+        let matcher = Matcher::new(Config::default().match_paths(true))
+            .with_recency(|path: &String| {
+                std::fs::metadata(path).and_then(|m| m.modified()).ok()
+            });
         // start loading files in the background
-        let crawl_handle = tokio::spawn(load_files(paths, matcher.injector()));
+        let crawl_handle = tokio::spawn(load_files(
+            paths.clone(),
+            options,
+            matcher.injector(),
+        ));
         Channel {
             matcher,
+            paths,
+            options,
             crawl_handle,
         }
     }
@@ -108,19 +183,49 @@ impl OnAir for Channel {
         self.matcher.status.running
     }
 
+    fn loading(&self) -> bool {
+        !self.crawl_handle.is_finished()
+    }
+
+    fn reload(&mut self) {
+        self.crawl_handle.abort();
+        self.matcher.restart();
+        self.crawl_handle = tokio::spawn(load_files(
+            self.paths.clone(),
+            self.options,
+            self.matcher.injector(),
+        ));
+    }
+
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.matcher.set_case_sensitivity(case_sensitivity);
+    }
+
     fn shutdown(&self) {
         self.crawl_handle.abort();
     }
 }
 
 #[allow(clippy::unused_async)]
-async fn load_files(paths: Vec<PathBuf>, injector: Injector<String>) {
+async fn load_files(
+    paths: Vec<PathBuf>,
+    options: WalkOptions,
+    injector: Injector<String>,
+) {
     if paths.is_empty() {
         return;
     }
     let current_dir = std::env::current_dir().unwrap();
     let mut builder =
         walk_builder(&paths[0], *DEFAULT_NUM_THREADS, None, None);
+    builder
+        .git_ignore(options.respect_ignore)
+        .git_global(options.respect_ignore)
+        .git_exclude(options.respect_ignore)
+        .ignore(options.respect_ignore)
+        .parents(options.respect_ignore)
+        .follow_links(options.follow_links)
+        .max_depth(options.max_depth);
     paths[1..].iter().for_each(|path| {
         builder.add(path);
     });
@@ -148,3 +253,159 @@ async fn load_files(paths: Vec<PathBuf>, injector: Injector<String>) {
         })
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wait for the channel to finish loading and return the names of all
+    /// its entries.
+    fn entry_names(channel: &mut Channel) -> Vec<String> {
+        loop {
+            let finished = channel.crawl_handle.is_finished();
+            channel.results(0, 0);
+            if finished && !channel.running() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let mut names: Vec<String> = channel
+            .results(channel.result_count(), 0)
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reload() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-files-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let first = directory.join("first.txt");
+        let second = directory.join("second.txt");
+        std::fs::write(&first, "").unwrap();
+
+        let mut channel = Channel::new(vec![directory.clone()]);
+        channel.find("txt");
+        assert_eq!(
+            entry_names(&mut channel),
+            vec![first.to_string_lossy().to_string()]
+        );
+
+        std::fs::remove_file(&first).unwrap();
+        std::fs::write(&second, "").unwrap();
+        channel.reload();
+        // the pattern is kept across reloads
+        assert_eq!(channel.matcher.last_pattern, "txt");
+        assert_eq!(
+            entry_names(&mut channel),
+            vec![second.to_string_lossy().to_string()]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_gitignore() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-files-gitignore-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("target")).unwrap();
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&directory)
+            .args(["init", "--quiet"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        std::fs::write(directory.join(".gitignore"), "target/\n").unwrap();
+        let kept = directory.join("main.rs");
+        let ignored = directory.join("target").join("main.o");
+        std::fs::write(&kept, "").unwrap();
+        std::fs::write(&ignored, "").unwrap();
+
+        let mut channel = Channel::new(vec![directory.clone()]);
+        channel.find("main");
+        assert_eq!(
+            entry_names(&mut channel),
+            vec![kept.to_string_lossy().to_string()]
+        );
+
+        let mut channel = Channel::with_options(
+            vec![directory.clone()],
+            WalkOptions::default().respect_ignore(false),
+        );
+        channel.find("main");
+        assert_eq!(
+            entry_names(&mut channel),
+            vec![
+                kept.to_string_lossy().to_string(),
+                ignored.to_string_lossy().to_string()
+            ]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_max_depth() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-files-max-depth-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("nested")).unwrap();
+        let top = directory.join("top.txt");
+        let nested = directory.join("nested").join("nested.txt");
+        std::fs::write(&top, "").unwrap();
+        std::fs::write(&nested, "").unwrap();
+
+        let mut channel = Channel::with_options(
+            vec![directory.clone()],
+            WalkOptions::default().max_depth(Some(1)),
+        );
+        channel.find("txt");
+        assert_eq!(
+            entry_names(&mut channel),
+            vec![top.to_string_lossy().to_string()]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_follow_links() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-files-follow-links-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(directory.join("real")).unwrap();
+        let real = directory.join("real").join("file.txt");
+        std::fs::write(&real, "").unwrap();
+        std::os::unix::fs::symlink("real", directory.join("link")).unwrap();
+
+        let mut channel = Channel::new(vec![directory.clone()]);
+        channel.find("txt");
+        assert_eq!(
+            entry_names(&mut channel),
+            vec![real.to_string_lossy().to_string()]
+        );
+
+        let mut channel = Channel::with_options(
+            vec![directory.clone()],
+            WalkOptions::default().follow_links(true),
+        );
+        channel.find("txt");
+        assert_eq!(
+            entry_names(&mut channel),
+            vec![
+                directory
+                    .join("link")
+                    .join("file.txt")
+                    .to_string_lossy()
+                    .to_string(),
+                real.to_string_lossy().to_string(),
+            ]
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -7,7 +7,11 @@ use tracing::debug;
 
 use crate::channels::OnAir;
 use crate::entry::{Entry, PreviewType};
-use television_fuzzy::matcher::{config::Config, injector::Injector, Matcher};
+use television_fuzzy::matcher::{
+    config::{CaseSensitivity, Config},
+    injector::Injector,
+    Matcher,
+};
 use television_utils::files::{walk_builder, DEFAULT_NUM_THREADS};
 use television_utils::strings::preprocess_line;
 
@@ -20,11 +24,7 @@ pub struct Channel {
 impl Channel {
     pub fn new() -> Self {
         let matcher = Matcher::new(Config::default().match_paths(true));
-        let base_dirs = BaseDirs::new().unwrap();
-        let crawl_handle = tokio::spawn(crawl_for_repos(
-            base_dirs.home_dir().to_path_buf(),
-            matcher.injector(),
-        ));
+        let crawl_handle = spawn_crawl(matcher.injector());
         Channel {
             matcher,
             icon: FileIcon::from("git"),
@@ -33,6 +33,14 @@ impl Channel {
     }
 }
 
+fn spawn_crawl(injector: Injector<String>) -> JoinHandle<()> {
+    let base_dirs = BaseDirs::new().unwrap();
+    tokio::spawn(crawl_for_repos(
+        base_dirs.home_dir().to_path_buf(),
+        injector,
+    ))
+}
+
 impl Default for Channel {
     fn default() -> Self {
         Self::new()
@@ -78,6 +86,20 @@ impl OnAir for Channel {
         self.matcher.status.running
     }
 
+    fn loading(&self) -> bool {
+        !self.crawl_handle.is_finished()
+    }
+
+    fn reload(&mut self) {
+        self.crawl_handle.abort();
+        self.matcher.restart();
+        self.crawl_handle = spawn_crawl(self.matcher.injector());
+    }
+
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.matcher.set_case_sensitivity(case_sensitivity);
+    }
+
     fn shutdown(&self) {
         debug!("Shutting down git repos channel");
         self.crawl_handle.abort();
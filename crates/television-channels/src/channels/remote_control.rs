@@ -2,7 +2,10 @@ use crate::channels::{CliTvChannel, OnAir, TelevisionChannel, UnitChannel};
 use crate::entry::{Entry, PreviewType};
 use clap::ValueEnum;
 use devicons::FileIcon;
-use television_fuzzy::matcher::{config::Config, Matcher};
+use television_fuzzy::matcher::{
+    config::{CaseSensitivity, Config},
+    Matcher,
+};
 
 pub struct RemoteControl {
     matcher: Matcher<String>,
@@ -83,5 +86,16 @@ impl OnAir for RemoteControl {
         self.matcher.status.running
     }
 
+    fn loading(&self) -> bool {
+        false
+    }
+
+    /// The available channels are fixed so there is nothing to reload.
+    fn reload(&mut self) {}
+
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.matcher.set_case_sensitivity(case_sensitivity);
+    }
+
     fn shutdown(&self) {}
 }
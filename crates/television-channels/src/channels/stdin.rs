@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::io::BufRead;
 use std::path::Path;
 
@@ -5,7 +6,10 @@ use devicons::FileIcon;
 
 use super::OnAir;
 use crate::entry::{Entry, PreviewType};
-use television_fuzzy::matcher::{config::Config, Matcher};
+use television_fuzzy::matcher::{
+    config::{CaseSensitivity, Config},
+    Matcher,
+};
 use television_utils::strings::preprocess_line;
 
 pub struct Channel {
@@ -17,9 +21,28 @@ const NUM_THREADS: usize = 2;
 
 impl Channel {
     pub fn new() -> Self {
+        Self::with_dedup(false)
+    }
+
+    /// Create a channel from stdin, dropping duplicate lines if `dedup` is
+    /// set.
+    pub fn with_dedup(dedup: bool) -> Self {
+        Self::from_reader(std::io::stdin().lock(), dedup)
+    }
+
+    /// Create a channel whose entries are the lines read from `reader`,
+    /// only keeping the first occurrence of each line if `dedup` is set.
+    ///
+    /// Invalid UTF-8 is replaced rather than stopping at the first
+    /// offending line.
+    pub fn from_reader(reader: impl BufRead, dedup: bool) -> Self {
         let mut lines = Vec::new();
-        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
-            lines.push(preprocess_line(&line));
+        let mut seen = HashSet::new();
+        for line in reader.split(b'\n').map_while(Result::ok) {
+            let line = preprocess_line(&String::from_utf8_lossy(&line));
+            if !dedup || seen.insert(line.clone()) {
+                lines.push(line);
+            }
         }
         let matcher = Matcher::new(Config::default().n_threads(NUM_THREADS));
         let injector = matcher.injector();
@@ -95,5 +118,66 @@ impl OnAir for Channel {
         self.matcher.status.running
     }
 
+    fn loading(&self) -> bool {
+        false
+    }
+
+    /// Stdin can only be consumed once so there is nothing to reload.
+    fn reload(&mut self) {}
+
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.matcher.set_case_sensitivity(case_sensitivity);
+    }
+
     fn shutdown(&self) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_from_reader() {
+        let input: &[u8] = b"first\nsecond\tcolumn\r\n\xffthird\n";
+        let mut channel = Channel::from_reader(input, false);
+        channel.find("");
+        while channel.running() || channel.result_count() < 3 {
+            channel.results(0, 0);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let mut names: Vec<String> = channel
+            .results(channel.result_count(), 0)
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["first", "second    column", "␀third"]);
+        assert_eq!(channel.total_count(), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_dedup() {
+        let input: &[u8] = b"b\na\nb\nc\na\na\n";
+        let mut channel = Channel::from_reader(input, true);
+        channel.find("");
+        while channel.running() || channel.result_count() < 3 {
+            channel.results(0, 0);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let names: Vec<String> = channel
+            .results(channel.result_count(), 0)
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        // the first occurrence of each line is kept, in order
+        assert_eq!(names, vec!["b", "a", "c"]);
+        assert_eq!(channel.total_count(), 3);
+
+        let mut channel = Channel::from_reader(input, false);
+        while channel.running() || channel.total_count() < 6 {
+            channel.results(0, 0);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(channel.total_count(), 6);
+    }
+}
@@ -8,7 +8,11 @@ use std::{
     path::{Path, PathBuf},
     sync::{atomic::AtomicUsize, Arc},
 };
-use television_fuzzy::matcher::{config::Config, injector::Injector, Matcher};
+use television_fuzzy::matcher::{
+    config::{CaseSensitivity, Config},
+    injector::Injector,
+    Matcher,
+};
 use television_utils::files::{
     is_not_text, walk_builder, DEFAULT_NUM_THREADS,
 };
@@ -35,71 +39,92 @@ impl CandidateLine {
     }
 }
 
+/// Where the lines of a text channel come from.
+enum Source {
+    /// Crawl the given directories for text files.
+    Directories(Vec<PathBuf>),
+    /// Read the given files.
+    FilePaths(Vec<PathBuf>),
+    /// Use lines that were already found by another text channel.
+    Entries(Vec<Entry>),
+}
+
+impl Source {
+    /// Start loading the lines of this source in the background.
+    fn load(
+        &self,
+        injector: Injector<CandidateLine>,
+    ) -> tokio::task::JoinHandle<()> {
+        match self {
+            Source::Directories(directories) => tokio::spawn(
+                crawl_for_candidates(directories.clone(), injector),
+            ),
+            Source::FilePaths(file_paths) => {
+                let file_paths = file_paths.clone();
+                let current_dir = std::env::current_dir().unwrap();
+                tokio::spawn(async move {
+                    let mut lines_in_mem = 0;
+                    for path in file_paths {
+                        if lines_in_mem > MAX_LINES_IN_MEM {
+                            break;
+                        }
+                        if let Some(injected_lines) =
+                            try_inject_lines(&injector, &current_dir, &path)
+                        {
+                            lines_in_mem += injected_lines;
+                        }
+                    }
+                })
+            }
+            Source::Entries(entries) => {
+                let entries = entries.clone();
+                tokio::spawn(async move {
+                    for entry in entries.into_iter().take(MAX_LINES_IN_MEM) {
+                        injector.push(
+                            CandidateLine::new(
+                                entry.display_name().into(),
+                                entry.value.unwrap(),
+                                entry.line_number.unwrap(),
+                            ),
+                            |c, cols| {
+                                cols[0] = c.line.clone().into();
+                            },
+                        );
+                    }
+                })
+            }
+        }
+    }
+}
+
 #[allow(clippy::module_name_repetitions)]
 pub struct Channel {
     matcher: Matcher<CandidateLine>,
+    source: Source,
     crawl_handle: tokio::task::JoinHandle<()>,
 }
 
 impl Channel {
     pub fn new(directories: Vec<PathBuf>) -> Self {
-        let matcher = Matcher::new(Config::default());
-        // start loading files in the background
-        let crawl_handle = tokio::spawn(crawl_for_candidates(
-            directories,
-            matcher.injector(),
-        ));
-        Channel {
-            matcher,
-            crawl_handle,
-        }
+        Self::from_source(Source::Directories(directories))
     }
 
     fn from_file_paths(file_paths: Vec<PathBuf>) -> Self {
-        let matcher = Matcher::new(Config::default());
-        let injector = matcher.injector();
-        let current_dir = std::env::current_dir().unwrap();
-        let crawl_handle = tokio::spawn(async move {
-            let mut lines_in_mem = 0;
-            for path in file_paths {
-                if lines_in_mem > MAX_LINES_IN_MEM {
-                    break;
-                }
-                if let Some(injected_lines) =
-                    try_inject_lines(&injector, &current_dir, &path)
-                {
-                    lines_in_mem += injected_lines;
-                }
-            }
-        });
-
-        Channel {
-            matcher,
-            crawl_handle,
-        }
+        Self::from_source(Source::FilePaths(file_paths))
     }
 
     fn from_text_entries(entries: Vec<Entry>) -> Self {
-        let matcher = Matcher::new(Config::default());
-        let injector = matcher.injector();
-        let load_handle = tokio::spawn(async move {
-            for entry in entries.into_iter().take(MAX_LINES_IN_MEM) {
-                injector.push(
-                    CandidateLine::new(
-                        entry.display_name().into(),
-                        entry.value.unwrap(),
-                        entry.line_number.unwrap(),
-                    ),
-                    |c, cols| {
-                        cols[0] = c.line.clone().into();
-                    },
-                );
-            }
-        });
+        Self::from_source(Source::Entries(entries))
+    }
 
+    fn from_source(source: Source) -> Self {
+        let matcher = Matcher::new(Config::default());
+        // start loading lines in the background
+        let crawl_handle = source.load(matcher.injector());
         Channel {
             matcher,
-            crawl_handle: load_handle,
+            source,
+            crawl_handle,
         }
     }
 }
@@ -210,6 +235,20 @@ impl OnAir for Channel {
         self.matcher.status.running
     }
 
+    fn loading(&self) -> bool {
+        !self.crawl_handle.is_finished()
+    }
+
+    fn reload(&mut self) {
+        self.crawl_handle.abort();
+        self.matcher.restart();
+        self.crawl_handle = self.source.load(self.matcher.injector());
+    }
+
+    fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        self.matcher.set_case_sensitivity(case_sensitivity);
+    }
+
     fn shutdown(&self) {
         self.crawl_handle.abort();
     }
@@ -22,6 +22,9 @@ pub struct Entry {
     pub icon: Option<FileIcon>,
     /// The optional line number associated with the entry.
     pub line_number: Option<usize>,
+    /// The optional (1-based) column associated with the entry, in
+    /// characters from the start of its line.
+    pub column: Option<usize>,
     /// The type of preview associated with the entry.
     pub preview_type: PreviewType,
 }
@@ -40,7 +43,8 @@ impl Entry {
     ///                 .with_name_match_ranges(vec![(0, 1)])
     ///                 .with_value_match_ranges(vec![(0, 1)])
     ///                 .with_icon(FileIcon::default())
-    ///                 .with_line_number(0);
+    ///                 .with_line_number(0)
+    ///                 .with_column(4);
     /// ```
     ///
     /// # Arguments
@@ -59,6 +63,7 @@ impl Entry {
             value_match_ranges: None,
             icon: None,
             line_number: None,
+            column: None,
             preview_type,
         }
     }
@@ -99,14 +104,31 @@ impl Entry {
         self
     }
 
+    pub fn with_column(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+
     pub fn display_name(&self) -> &str {
         self.display_name.as_ref().unwrap_or(&self.name)
     }
 
+    /// The position of the entry within its file, e.g. `:12` or `:12:5` when
+    /// it has a column.
+    ///
+    /// Columns are only meaningful along with a line number.
+    pub fn position_suffix(&self) -> Option<String> {
+        let line_number = self.line_number?;
+        Some(match self.column {
+            Some(column) => format!(":{line_number}:{column}"),
+            None => format!(":{line_number}"),
+        })
+    }
+
     pub fn stdout_repr(&self) -> String {
         let mut repr = self.name.clone();
-        if let Some(line_number) = self.line_number {
-            repr.push_str(&format!(":{line_number}"));
+        if let Some(position) = self.position_suffix() {
+            repr.push_str(&position);
         }
         repr
     }
@@ -120,6 +142,7 @@ pub const ENTRY_PLACEHOLDER: Entry = Entry {
     value_match_ranges: None,
     icon: None,
     line_number: None,
+    column: None,
     preview_type: PreviewType::EnvVar,
 };
 
@@ -130,4 +153,6 @@ pub enum PreviewType {
     Directory,
     EnvVar,
     Files,
+    /// The preview is the output of the given command template.
+    Command(String),
 }
@@ -235,6 +235,39 @@ fn impl_tv_channel(ast: &syn::DeriveInput) -> TokenStream {
                 }
             }
 
+            fn loading(&self) -> bool {
+                match self {
+                    #(
+                        #enum_name::#variant_names(ref channel) => {
+                            channel.loading()
+                        }
+                    )*
+                }
+            }
+
+            fn reload(&mut self) {
+                match self {
+                    #(
+                        #enum_name::#variant_names(ref mut channel) => {
+                            channel.reload();
+                        }
+                    )*
+                }
+            }
+
+            fn set_case_sensitivity(
+                &mut self,
+                case_sensitivity: CaseSensitivity,
+            ) {
+                match self {
+                    #(
+                        #enum_name::#variant_names(ref mut channel) => {
+                            channel.set_case_sensitivity(case_sensitivity);
+                        }
+                    )*
+                }
+            }
+
             fn shutdown(&self) {
                 match self {
                     #(
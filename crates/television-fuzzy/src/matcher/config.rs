@@ -57,6 +57,50 @@ impl Config {
     }
 }
 
+/// How letter case is taken into account when matching.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CaseSensitivity {
+    /// Case-insensitive unless the pattern contains an uppercase letter.
+    #[default]
+    Smart,
+    /// Always case-insensitive.
+    Insensitive,
+    /// Always case-sensitive.
+    Sensitive,
+}
+
+impl CaseSensitivity {
+    /// The next mode in the cycle smart -> insensitive -> sensitive.
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::Smart => Self::Insensitive,
+            Self::Insensitive => Self::Sensitive,
+            Self::Sensitive => Self::Smart,
+        }
+    }
+}
+
+impl std::fmt::Display for CaseSensitivity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Smart => write!(f, "smart case"),
+            Self::Insensitive => write!(f, "ignore case"),
+            Self::Sensitive => write!(f, "match case"),
+        }
+    }
+}
+
+impl From<CaseSensitivity> for nucleo::pattern::CaseMatching {
+    fn from(case_sensitivity: CaseSensitivity) -> Self {
+        match case_sensitivity {
+            CaseSensitivity::Smart => Self::Smart,
+            CaseSensitivity::Insensitive => Self::Ignore,
+            CaseSensitivity::Sensitive => Self::Respect,
+        }
+    }
+}
+
 impl From<&Config> for nucleo::Config {
     fn from(config: &Config) -> Self {
         let mut matcher_config = nucleo::Config::DEFAULT;
@@ -1,8 +1,16 @@
 use injector::Injector;
+use nucleo::pattern::Atom;
+use parking_lot::Mutex;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use crate::matcher::{
-    config::Config, lazy::MATCHER, matched_item::MatchedItem,
+    config::{CaseSensitivity, Config},
+    lazy::MATCHER,
+    matched_item::MatchedItem,
 };
 
 pub mod config;
@@ -12,6 +20,23 @@ pub mod matched_item;
 
 const MATCHER_TICK_TIMEOUT: u64 = 2;
 
+/// Get the last modification time of an item, if it has one.
+pub type Recency<I> = fn(&I) -> Option<SystemTime>;
+
+/// The runs of tied matches ordered by recency so far, so that results
+/// aren't scored and ordered again on every frame.
+#[derive(Default)]
+struct TieBreaks {
+    /// The pattern, item count and match count the runs were ordered for.
+    key: (Vec<Atom>, u32, u32),
+    /// The indices of the matches of each run, in order, by the index of
+    /// the first match of the run.
+    runs: BTreeMap<u32, Vec<u32>>,
+    /// The modification times of the items looked at so far, by matched
+    /// string.
+    recencies: HashMap<String, Option<SystemTime>>,
+}
+
 /// The status of the fuzzy matcher.
 ///
 /// This currently only contains a boolean indicating whether the matcher is
@@ -40,6 +65,11 @@ impl From<nucleo::Status> for Status {
 ///
 /// The matcher can be used to find items that match a given pattern and to
 /// retrieve the matched items as well as the indices of the matched characters.
+///
+/// Matched items are ordered by decreasing score. Ties are broken
+/// deterministically: shorter items come first, then the most recently
+/// modified ones if the matcher was given a `Recency` (see `with_recency`),
+/// then items pushed first.
 pub struct Matcher<I>
 where
     I: Sync + Send + Clone + 'static,
@@ -54,6 +84,14 @@ where
     pub status: Status,
     /// The last pattern that was matched against.
     pub last_pattern: String,
+    /// How letter case is taken into account when matching.
+    case_sensitivity: CaseSensitivity,
+    /// The configuration of the inner matcher, to score items the same way
+    /// when breaking ties.
+    matcher_config: nucleo::Config,
+    /// How to get the modification time of items, to break ties.
+    recency: Option<Recency<I>>,
+    tie_breaks: Mutex<TieBreaks>,
 }
 
 impl<I> Matcher<I>
@@ -62,9 +100,10 @@ where
 {
     /// Create a new fuzzy matcher with the given configuration.
     pub fn new(config: Config) -> Self {
+        let matcher_config: nucleo::Config = (&config).into();
         Self {
             inner: nucleo::Nucleo::new(
-                (&config).into(),
+                matcher_config.clone(),
                 Arc::new(|| {}),
                 config.n_threads,
                 1,
@@ -73,9 +112,24 @@ where
             matched_item_count: 0,
             status: Status::default(),
             last_pattern: String::new(),
+            case_sensitivity: CaseSensitivity::default(),
+            matcher_config,
+            recency: None,
+            tie_breaks: Mutex::new(TieBreaks::default()),
         }
     }
 
+    /// Rank the most recently modified items first among items with the
+    /// same score and length.
+    ///
+    /// Ties aren't broken when the pattern is empty. The modification time
+    /// of an item is only looked up once per pattern.
+    #[must_use]
+    pub fn with_recency(mut self, recency: Recency<I>) -> Self {
+        self.recency = Some(recency);
+        self
+    }
+
     /// Tick the fuzzy matcher.
     ///
     /// This should be called periodically to update the state of the matcher.
@@ -83,6 +137,18 @@ where
         self.status = self.inner.tick(MATCHER_TICK_TIMEOUT).into();
     }
 
+    /// Remove all the items from the fuzzy matcher.
+    ///
+    /// The current pattern is kept but the injectors created before the
+    /// restart are disconnected, meaning a new injector should be requested to
+    /// push items again.
+    pub fn restart(&mut self) {
+        self.inner.restart(true);
+        self.total_item_count = 0;
+        self.matched_item_count = 0;
+        *self.tie_breaks.lock() = TieBreaks::default();
+    }
+
     /// Get an injector that can be used to push items into the fuzzy matcher.
     ///
     /// This can be used at any time to push items into the fuzzy matcher.
@@ -109,18 +175,55 @@ where
     ///
     /// This should be called whenever the pattern changes.
     /// The `Matcher` will keep track of the last pattern and only reparse the
-    /// pattern if it has changed, allowing for more efficient matching when
-    /// `self.last_pattern` is a prefix of the new `pattern`.
+    /// pattern if it has changed. When the new `pattern` extends the last
+    /// one (see `is_pattern_extension`), only the items matching the last
+    /// pattern are matched again instead of the whole corpus.
+    ///
+    /// # Query syntax
+    /// The pattern is split on whitespace into terms and items have to match
+    /// all of them, in any order. Terms are matched fuzzily unless they use
+    /// one of the following operators:
+    /// - `'term`: items containing `term`
+    /// - `^term`: items starting with `term`
+    /// - `term$`: items ending with `term`
+    /// - `^term$` (or `'term$`): items equal to `term`
+    /// - `!term`: items *not* containing `term`, which can be combined with
+    ///   anchors (e.g. `!.md$`). Negated terms are never matched fuzzily.
+    ///
+    /// Operators are read in that order: a leading `!`, then a leading `^`
+    /// or `'`, then a trailing `$`. A backslash makes the next character
+    /// literal, e.g. `\^` for a fuzzy term starting with `^`, `\$` for one
+    /// ending with `$`, or `\ ` for a space within a term.
+    ///
+    /// With smart case sensitivity, each term is matched case-sensitively
+    /// only if it contains an uppercase letter.
     pub fn find(&mut self, pattern: &str) {
         if pattern != self.last_pattern {
             self.inner.pattern.reparse(
                 0,
                 pattern,
-                nucleo::pattern::CaseMatching::Smart,
+                self.case_sensitivity.into(),
                 nucleo::pattern::Normalization::Smart,
-                pattern.starts_with(&self.last_pattern),
+                is_pattern_extension(&self.last_pattern, pattern),
             );
             self.last_pattern = pattern.to_string();
+            self.tie_breaks.lock().recencies.clear();
+        }
+    }
+
+    /// Set how letter case is taken into account when matching.
+    ///
+    /// The last pattern is matched again using the new case sensitivity.
+    pub fn set_case_sensitivity(&mut self, case_sensitivity: CaseSensitivity) {
+        if case_sensitivity != self.case_sensitivity {
+            self.case_sensitivity = case_sensitivity;
+            self.inner.pattern.reparse(
+                0,
+                &self.last_pattern,
+                case_sensitivity.into(),
+                nucleo::pattern::Normalization::Smart,
+                false,
+            );
         }
     }
 
@@ -166,29 +269,35 @@ where
         let mut col_indices = Vec::new();
         let mut matcher = MATCHER.lock();
 
-        snapshot
-            .matched_items(
-                offset..(num_entries + offset).min(self.matched_item_count),
-            )
-            .map(move |item| {
-                snapshot.pattern().column_pattern(0).indices(
-                    item.matcher_columns[0].slice(..),
-                    &mut matcher,
-                    &mut col_indices,
-                );
-                col_indices.sort_unstable();
-                col_indices.dedup();
+        // out of range offsets yield no results rather than panicking
+        let end = offset
+            .saturating_add(num_entries)
+            .min(self.matched_item_count);
+        self.ordered_matched_items(
+            snapshot,
+            offset.min(end)..end,
+            &mut matcher,
+        )
+        .into_iter()
+        .map(move |item| {
+            snapshot.pattern().column_pattern(0).indices(
+                item.matcher_columns[0].slice(..),
+                &mut matcher,
+                &mut col_indices,
+            );
+            col_indices.sort_unstable();
+            col_indices.dedup();
 
-                let indices = col_indices.drain(..);
+            let indices = col_indices.drain(..);
 
-                let matched_string = item.matcher_columns[0].to_string();
-                MatchedItem {
-                    inner: item.data.clone(),
-                    matched_string,
-                    match_indices: indices.map(|i| (i, i + 1)).collect(),
-                }
-            })
-            .collect()
+            let matched_string = item.matcher_columns[0].to_string();
+            MatchedItem {
+                inner: item.data.clone(),
+                matched_string,
+                match_indices: indices.map(|i| (i, i + 1)).collect(),
+            }
+        })
+        .collect()
     }
 
     /// Get a single matched item.
@@ -208,7 +317,17 @@ where
     /// ```
     pub fn get_result(&self, index: u32) -> Option<MatchedItem<I>> {
         let snapshot = self.inner.snapshot();
-        snapshot.get_matched_item(index).map(|item| {
+        if index >= snapshot.matched_item_count() {
+            return None;
+        }
+        self.ordered_matched_items(
+            snapshot,
+            index..index + 1,
+            &mut MATCHER.lock(),
+        )
+        .into_iter()
+        .next()
+        .map(|item| {
             let matched_string = item.matcher_columns[0].to_string();
             MatchedItem {
                 inner: item.data.clone(),
@@ -217,4 +336,448 @@ where
             }
         })
     }
+
+    /// Get the matched items in the given range, ties in the order of the
+    /// inner matcher being broken by recency.
+    ///
+    /// The inner matcher orders matches by score, then length, so items
+    /// with the same score and length are next to each other. Each run of
+    /// such items is ordered as a whole the first time one of its items is
+    /// requested, and kept until the matches change.
+    fn ordered_matched_items<'a>(
+        &self,
+        snapshot: &'a nucleo::Snapshot<I>,
+        range: Range<u32>,
+        matcher: &mut nucleo::Matcher,
+    ) -> Vec<nucleo::Item<'a, I>> {
+        let pattern = snapshot.pattern().column_pattern(0);
+        let Some(recency) = self.recency.filter(|_| !pattern.atoms.is_empty())
+        else {
+            return snapshot.matched_items(range).collect();
+        };
+        let mut tie_breaks = self.tie_breaks.lock();
+        let key = (
+            pattern.atoms.clone(),
+            snapshot.item_count(),
+            snapshot.matched_item_count(),
+        );
+        if tie_breaks.key != key {
+            tie_breaks.key = key;
+            tie_breaks.runs.clear();
+        }
+
+        let default_config = std::mem::replace(
+            &mut matcher.config,
+            self.matcher_config.clone(),
+        );
+        let mut tie_key = |index: u32| {
+            snapshot.get_matched_item(index).map(|item| {
+                let haystack = item.matcher_columns[0].slice(..);
+                (pattern.score(haystack, matcher), haystack.len())
+            })
+        };
+        let mut items = Vec::with_capacity(range.len());
+        for index in range {
+            let cached = tie_breaks
+                .runs
+                .range(..=index)
+                .next_back()
+                .filter(|(&start, run)| index - start < run.len() as u32)
+                .map(|(&start, run)| run[(index - start) as usize]);
+            let ordered_index = if let Some(ordered_index) = cached {
+                ordered_index
+            } else {
+                let key = tie_key(index);
+                let mut start = index;
+                while start > 0 && tie_key(start - 1) == key {
+                    start -= 1;
+                }
+                let mut end = index + 1;
+                while tie_key(end).is_some_and(|k| Some(k) == key) {
+                    end += 1;
+                }
+                let mut run: Vec<u32> = (start..end).collect();
+                if run.len() > 1 {
+                    let TieBreaks { recencies, .. } = &mut *tie_breaks;
+                    // stable, so remaining ties stay in insertion order
+                    run.sort_by_cached_key(|&i| {
+                        let item = snapshot.get_matched_item(i).unwrap();
+                        Reverse(
+                            *recencies
+                                .entry(item.matcher_columns[0].to_string())
+                                .or_insert_with(|| recency(item.data)),
+                        )
+                    });
+                }
+                let ordered_index = run[(index - start) as usize];
+                tie_breaks.runs.insert(start, run);
+                ordered_index
+            };
+            items.extend(snapshot.get_matched_item(ordered_index));
+        }
+        matcher.config = default_config;
+        items
+    }
+}
+
+/// Whether every item matching `pattern` also matches `last_pattern`, in
+/// which case only the matches of `last_pattern` need to be matched again.
+///
+/// Appending characters usually narrows the matches down, except when it
+/// changes the meaning of the end of the last pattern: a trailing `$` (which
+/// anchors the last word to the end of items) or `\` (which escapes the
+/// next character) turn into literal characters once something follows
+/// them.
+///
+/// # Examples
+/// ```
+/// use television_fuzzy::matcher::is_pattern_extension;
+///
+/// assert!(is_pattern_extension("foo", "foob"));
+/// assert!(is_pattern_extension("foo", "foo bar"));
+/// assert!(!is_pattern_extension("foob", "foo"));
+/// assert!(!is_pattern_extension("fob", "foob"));
+/// assert!(!is_pattern_extension("rs$", "rs$x"));
+/// ```
+pub fn is_pattern_extension(last_pattern: &str, pattern: &str) -> bool {
+    pattern.starts_with(last_pattern)
+        && !last_pattern.ends_with('$')
+        && !last_pattern.ends_with('\\')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched_strings(matcher: &mut Matcher<String>) -> Vec<String> {
+        matcher.tick();
+        while matcher.status.running {
+            matcher.tick();
+        }
+        matcher.results(0, 0);
+        let mut strings: Vec<String> = matcher
+            .results(matcher.matched_item_count, 0)
+            .into_iter()
+            .map(|item| item.matched_string)
+            .collect();
+        strings.sort();
+        strings
+    }
+
+    #[test]
+    fn test_case_sensitivity() {
+        let mut matcher = Matcher::new(Config::default().n_threads(1));
+        let injector = matcher.injector();
+        for s in ["README.md", "readme.txt", "ReadMe.rst"] {
+            injector.push(s.to_string(), |s, cols| cols[0] = s.clone().into());
+        }
+        matcher.find("ReadMe");
+        // smart case: the pattern contains uppercase letters
+        assert_eq!(matched_strings(&mut matcher), vec!["ReadMe.rst"]);
+
+        matcher.set_case_sensitivity(CaseSensitivity::Insensitive);
+        assert_eq!(
+            matched_strings(&mut matcher),
+            vec!["README.md", "ReadMe.rst", "readme.txt"]
+        );
+
+        matcher.set_case_sensitivity(CaseSensitivity::Sensitive);
+        matcher.find("readme");
+        assert_eq!(matched_strings(&mut matcher), vec!["readme.txt"]);
+
+        matcher.set_case_sensitivity(CaseSensitivity::Smart);
+        assert_eq!(matched_strings(&mut matcher).len(), 3);
+    }
+
+    fn corpus() -> Vec<String> {
+        let mut corpus = vec![
+            "src/main.rs".to_string(),
+            "src/main.rs$x".to_string(),
+            "docs/foo bar.md".to_string(),
+            "docs/foo\\.md".to_string(),
+            "Cargo.toml".to_string(),
+        ];
+        for i in 0..2000 {
+            corpus.push(format!("crates/crate-{i}/src/module_{}.rs", i % 37));
+        }
+        corpus
+    }
+
+    fn matcher_with(corpus: &[String]) -> Matcher<String> {
+        let matcher = Matcher::new(Config::default().n_threads(2));
+        let injector = matcher.injector();
+        for s in corpus {
+            injector.push(s.clone(), |s, cols| cols[0] = s.clone().into());
+        }
+        matcher
+    }
+
+    #[test]
+    fn test_incremental_matching_matches_full_rescan() {
+        let corpus = corpus();
+        let mut incremental = matcher_with(&corpus);
+        // typing each pattern one character at a time, then erasing it
+        for pattern in ["crate-1/mod", "rs$x", "foo\\ bar", "'src !x", "Cargo"]
+        {
+            let mut typed = String::new();
+            for c in pattern.chars() {
+                typed.push(c);
+                incremental.find(&typed);
+                let mut full = matcher_with(&corpus);
+                full.find(&typed);
+                assert_eq!(
+                    matched_strings(&mut incremental),
+                    matched_strings(&mut full),
+                    "pattern: {typed:?}"
+                );
+            }
+            incremental.find("");
+            assert_eq!(matched_strings(&mut incremental).len(), corpus.len());
+        }
+    }
+
+    #[test]
+    fn test_equal_score_tie_break() {
+        let mut matcher = Matcher::new(Config::default().n_threads(1));
+        let injector = matcher.injector();
+        for s in ["src/lib/main.rs", "lib/main.rs", "src/main.rs"] {
+            injector.push(s.to_string(), |s, cols| cols[0] = s.clone().into());
+        }
+        matcher.find("main");
+        matched_strings(&mut matcher);
+        let ordered: Vec<String> = matcher
+            .results(3, 0)
+            .into_iter()
+            .map(|item| item.matched_string)
+            .collect();
+        // the shortest first, then in insertion order for equal lengths
+        assert_eq!(
+            ordered,
+            vec!["lib/main.rs", "src/main.rs", "src/lib/main.rs"]
+        );
+        assert_eq!(
+            matcher.get_result(0).unwrap().matched_string,
+            "lib/main.rs"
+        );
+    }
+
+    #[test]
+    fn test_recency_tie_break() {
+        let mut matcher = Matcher::new(Config::default().n_threads(1))
+            .with_recency(|item: &(String, u64)| {
+                (item.1 > 0).then(|| {
+                    SystemTime::UNIX_EPOCH
+                        + std::time::Duration::from_secs(item.1)
+                })
+            });
+        let injector = matcher.injector();
+        let mut items = vec![
+            ("src/lib/main.rs".to_string(), 9),
+            ("old/main.rs".to_string(), 1),
+            ("unknown/main.rs".to_string(), 0),
+            ("new/main.rs".to_string(), 3),
+        ];
+        // a long run of ties
+        for i in 0..600 {
+            items.push((format!("dir/{i:03}/main.rs"), i % 7));
+        }
+        for item in &items {
+            injector.push(item.clone(), |item, cols| {
+                cols[0] = item.0.clone().into()
+            });
+        }
+        matcher.find("main");
+        matcher.tick();
+        while matcher.status.running {
+            matcher.tick();
+        }
+        matcher.results(0, 0);
+        let ordered: Vec<(String, u64)> = matcher
+            .results(matcher.matched_item_count, 0)
+            .into_iter()
+            .map(|item| item.inner)
+            .collect();
+        // shorter first, then the most recent
+        assert_eq!(ordered[0].0, "new/main.rs");
+        assert_eq!(ordered[1].0, "old/main.rs");
+        assert_eq!(ordered[2].0, "src/lib/main.rs");
+        // the whole run is ordered, unknown recencies last
+        assert!(ordered[3..]
+            .windows(2)
+            .all(|pair| pair[0].1 >= pair[1].1 || pair[1].1 == 0));
+        assert_eq!(
+            ordered.iter().position(|item| item.1 == 0),
+            ordered.iter().position(|item| item.0 == "unknown/main.rs")
+        );
+        // any range and single results agree with the full ordering
+        for (offset, count) in [(0, 10), (250, 20), (500, 104), (3, 1)] {
+            let page: Vec<(String, u64)> = matcher
+                .results(count, offset)
+                .into_iter()
+                .map(|item| item.inner)
+                .collect();
+            let offset = offset as usize;
+            assert_eq!(page, ordered[offset..offset + count as usize]);
+        }
+        for (index, item) in ordered.iter().enumerate() {
+            let index = u32::try_from(index).unwrap();
+            assert_eq!(&matcher.get_result(index).unwrap().inner, item);
+        }
+        assert!(matcher.get_result(604).is_none());
+    }
+
+    #[test]
+    fn test_recency_is_looked_up_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+        let mut matcher = Matcher::new(Config::default().n_threads(1))
+            .with_recency(|_: &String| {
+                LOOKUPS.fetch_add(1, Ordering::Relaxed);
+                None
+            });
+        let injector = matcher.injector();
+        for i in 0..10 {
+            injector.push(format!("{i}/main.rs"), |s, cols| {
+                cols[0] = s.clone().into()
+            });
+        }
+        matcher.find("main");
+        matched_strings(&mut matcher);
+        let lookups = LOOKUPS.load(Ordering::Relaxed);
+        assert_eq!(lookups, 10);
+
+        // the ordered runs are reused for every frame
+        matcher.results(10, 0);
+        matcher.get_result(3);
+        assert_eq!(LOOKUPS.load(Ordering::Relaxed), lookups);
+    }
+
+    fn matches(pattern: &str) -> Vec<String> {
+        let mut matcher = matcher_with(
+            &[
+                "src/main.rs",
+                "src/domain.rs",
+                "src/Main.java",
+                "docs/main.md",
+                "Cargo.toml",
+                "^caret",
+                "trailing$",
+            ]
+            .map(String::from),
+        );
+        matcher.find(pattern);
+        matched_strings(&mut matcher)
+    }
+
+    #[test]
+    fn test_exact_terms() {
+        // fuzzy terms match scattered characters
+        assert_eq!(
+            matches("srmn"),
+            vec!["src/Main.java", "src/domain.rs", "src/main.rs"]
+        );
+        assert!(matches("'srmn").is_empty());
+        assert_eq!(
+            matches("'main"),
+            vec![
+                "docs/main.md",
+                "src/Main.java",
+                "src/domain.rs",
+                "src/main.rs"
+            ]
+        );
+        // negated terms are matched exactly
+        assert_eq!(
+            matches("!main"),
+            vec!["Cargo.toml", "^caret", "trailing$"]
+        );
+    }
+
+    #[test]
+    fn test_anchored_terms() {
+        assert_eq!(
+            matches("^src"),
+            vec!["src/Main.java", "src/domain.rs", "src/main.rs"]
+        );
+        assert_eq!(matches(".rs$"), vec!["src/domain.rs", "src/main.rs"]);
+        assert_eq!(matches("^Cargo.toml$"), vec!["Cargo.toml"]);
+        assert_eq!(matches("'Cargo.toml$"), vec!["Cargo.toml"]);
+        assert!(matches("^Cargo$").is_empty());
+        assert_eq!(matches("!.rs$ ^src"), vec!["src/Main.java"]);
+        // escaped operators are literal characters
+        assert_eq!(matches("\\^car"), vec!["^caret"]);
+        assert_eq!(matches("ing\\$"), vec!["trailing$"]);
+    }
+
+    #[test]
+    fn test_multiple_terms() {
+        assert_eq!(matches("main src"), matches("src main"));
+        assert_eq!(
+            matches("^src .rs$ 'main"),
+            vec!["src/domain.rs", "src/main.rs"]
+        );
+        assert_eq!(
+            matches("src main !dom"),
+            vec!["src/Main.java", "src/main.rs"]
+        );
+        // smart case is decided for each term
+        assert_eq!(matches("Main src"), vec!["src/Main.java"]);
+    }
+
+    #[test]
+    fn test_negated_terms() {
+        let corpus = [
+            "config/default.toml",
+            "config/test.toml",
+            "tests/config_test.rs",
+            "src/config.rs",
+            "README.md",
+        ]
+        .map(String::from);
+        let mut matcher = matcher_with(&corpus);
+        matcher.find("config !test");
+        assert_eq!(
+            matched_strings(&mut matcher),
+            vec!["config/default.toml", "src/config.rs"]
+        );
+        // only the positive terms are highlighted
+        let config_len = "config".len() as u32;
+        for item in matcher.results(2, 0) {
+            assert_eq!(item.match_indices.len() as u32, config_len);
+        }
+
+        // a lone negated term keeps everything that doesn't contain it
+        matcher.find("!config");
+        assert_eq!(matched_strings(&mut matcher), vec!["README.md"]);
+        matcher.find("!test");
+        assert_eq!(
+            matched_strings(&mut matcher),
+            vec!["README.md", "config/default.toml", "src/config.rs"]
+        );
+    }
+
+    #[test]
+    fn test_is_pattern_extension() {
+        assert!(is_pattern_extension("", "a"));
+        assert!(is_pattern_extension("foo", "fooB"));
+        assert!(is_pattern_extension("^src", "^src/"));
+        assert!(!is_pattern_extension("foo", "fo"));
+        assert!(!is_pattern_extension("foo", "xfoo"));
+        assert!(!is_pattern_extension("foo$", "foo$b"));
+        assert!(!is_pattern_extension("foo\\", "foo\\ bar"));
+    }
+
+    #[test]
+    fn test_results_out_of_range() {
+        let mut matcher = Matcher::new(Config::default().n_threads(1));
+        let injector = matcher.injector();
+        for s in ["a", "b", "c"] {
+            injector.push(s.to_string(), |s, cols| cols[0] = s.clone().into());
+        }
+        assert_eq!(matched_strings(&mut matcher), vec!["a", "b", "c"]);
+        assert_eq!(matcher.results(2, 2).len(), 1);
+        assert!(matcher.results(10, 5).is_empty());
+        assert!(matcher.results(u32::MAX, u32::MAX).is_empty());
+    }
 }
@@ -2,50 +2,86 @@ use std::sync::Arc;
 
 use television_channels::entry::{Entry, PreviewType};
 
+pub mod ansi;
+pub mod archive;
 pub mod basic;
 pub mod cache;
+pub mod command;
 pub mod directory;
 pub mod env;
+pub mod exif;
 pub mod files;
+pub mod git_blame;
+pub mod git_diff;
+pub mod gzip;
+pub mod highlighting;
+pub mod image;
+pub mod markdown;
 pub mod meta;
+pub mod persistent_cache;
+pub mod sqlite;
+pub mod table;
 
 // previewer types
 pub use basic::BasicPreviewer;
 pub use basic::BasicPreviewerConfig;
+pub use command::CommandPreviewer;
+pub use command::CommandPreviewerConfig;
 pub use directory::DirectoryPreviewer;
 pub use directory::DirectoryPreviewerConfig;
 pub use env::EnvVarPreviewer;
 pub use env::EnvVarPreviewerConfig;
 pub use files::FilePreviewer;
 pub use files::FilePreviewerConfig;
-//use ratatui_image::protocol::StatefulProtocol;
+pub use files::DEFAULT_HEX_PREVIEW_MAX_BYTES;
+pub use files::DEFAULT_MAX_HIGHLIGHTED_LINE_LENGTH;
+pub use files::DEFAULT_PREVIEW_TIMEOUT;
+pub use image::Image;
+pub use image::ImageProtocol;
+use ratatui::text::Line;
 use syntect::highlighting::Style;
 
 #[derive(Clone, Debug)]
 pub enum PreviewContent {
+    AnsiText(Vec<Line<'static>>),
     Empty,
     FileTooLarge,
     SyntectHighlightedText(Vec<Vec<(Style, String)>>),
-    //Image(Box<dyn StatefulProtocol>),
+    /// A decoded image, drawn by the UI with the protocol it supports.
+    Image(Arc<Image>),
     Loading,
     NotSupported,
     PlainText(Vec<String>),
     PlainTextWrapped(String),
+    TimedOut,
+    /// A symlink pointing to a missing file, with its target.
+    BrokenSymlink(String),
+    /// A fifo, socket or device, with its kind.
+    SpecialFile(String),
 }
 
 pub const PREVIEW_NOT_SUPPORTED_MSG: &str =
     "Preview for this file type is not yet supported";
 pub const FILE_TOO_LARGE_MSG: &str = "File too large";
+pub const PREVIEW_TIMED_OUT_MSG: &str = "Preview timed out";
+pub const BROKEN_SYMLINK_MSG: &str = "Broken symlink";
+pub const SPECIAL_FILE_MSG: &str = "Special file";
 
 /// A preview of an entry.
 ///
 /// # Fields
 /// - `title`: The title of the preview.
 /// - `content`: The content of the preview.
+/// - `partial`: Whether the content only covers the beginning of the entry
+///   and more of it can be loaded on demand.
+/// - `blame`: The optional blame annotation of each line of the content,
+///   shown in the gutter.
 #[derive(Clone, Debug)]
 pub struct Preview {
     pub title: String,
     pub content: PreviewContent,
+    pub partial: bool,
+    pub blame: Option<Vec<String>>,
 }
 
 impl Default for Preview {
@@ -53,23 +89,64 @@ impl Default for Preview {
         Preview {
             title: String::new(),
             content: PreviewContent::Empty,
+            partial: false,
+            blame: None,
         }
     }
 }
 
 impl Preview {
     pub fn new(title: String, content: PreviewContent) -> Self {
-        Preview { title, content }
+        Preview {
+            title,
+            content,
+            partial: false,
+            blame: None,
+        }
     }
 
-    pub fn total_lines(&self) -> u16 {
-        match &self.content {
-            PreviewContent::SyntectHighlightedText(lines) => {
-                lines.len().try_into().unwrap_or(u16::MAX)
-            }
+    pub fn with_partial(mut self, partial: bool) -> Self {
+        self.partial = partial;
+        self
+    }
+
+    pub fn with_blame(mut self, blame: Option<Vec<String>>) -> Self {
+        self.blame = blame;
+        self
+    }
+
+    /// An approximation of the memory used by this preview, in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        let content_size = match &self.content {
+            PreviewContent::AnsiText(lines) => lines
+                .iter()
+                .flat_map(|l| l.spans.iter())
+                .map(|s| s.content.len() + std::mem::size_of_val(&s.style))
+                .sum(),
+            PreviewContent::SyntectHighlightedText(lines) => lines
+                .iter()
+                .flatten()
+                .map(|(style, text)| text.len() + std::mem::size_of_val(style))
+                .sum(),
             PreviewContent::PlainText(lines) => {
-                lines.len().try_into().unwrap_or(u16::MAX)
+                lines.iter().map(String::len).sum()
             }
+            PreviewContent::PlainTextWrapped(text) => text.len(),
+            PreviewContent::Image(image) => image.size_in_bytes(),
+            _ => 0,
+        };
+        let blame_size = self
+            .blame
+            .as_ref()
+            .map_or(0, |blame| blame.iter().map(String::len).sum());
+        self.title.len() + content_size + blame_size
+    }
+
+    pub fn total_lines(&self) -> usize {
+        match &self.content {
+            PreviewContent::SyntectHighlightedText(lines) => lines.len(),
+            PreviewContent::PlainText(lines) => lines.len(),
+            PreviewContent::AnsiText(lines) => lines.len(),
             _ => 0,
         }
     }
@@ -81,6 +158,7 @@ pub struct Previewer {
     directory: DirectoryPreviewer,
     file: FilePreviewer,
     env_var: EnvVarPreviewer,
+    command: CommandPreviewer,
 }
 
 #[derive(Debug, Default)]
@@ -89,6 +167,7 @@ pub struct PreviewerConfig {
     directory: DirectoryPreviewerConfig,
     file: FilePreviewerConfig,
     env_var: EnvVarPreviewerConfig,
+    command: CommandPreviewerConfig,
 }
 
 impl PreviewerConfig {
@@ -111,6 +190,11 @@ impl PreviewerConfig {
         self.env_var = config;
         self
     }
+
+    pub fn command(mut self, config: CommandPreviewerConfig) -> Self {
+        self.command = config;
+        self
+    }
 }
 
 impl Previewer {
@@ -121,22 +205,57 @@ impl Previewer {
             directory: DirectoryPreviewer::new(Some(config.directory)),
             file: FilePreviewer::new(Some(config.file)),
             env_var: EnvVarPreviewer::new(Some(config.env_var)),
+            command: CommandPreviewer::new(Some(config.command)),
         }
     }
 
     pub async fn preview(&mut self, entry: &Entry) -> Arc<Preview> {
-        match entry.preview_type {
+        match &entry.preview_type {
             PreviewType::Basic => self.basic.preview(entry),
             PreviewType::Directory => self.directory.preview(entry).await,
             PreviewType::EnvVar => self.env_var.preview(entry),
             PreviewType::Files => self.file.preview(entry).await,
+            PreviewType::Command(command) => {
+                self.command.preview(entry, command)
+            }
         }
     }
 
+    /// Load the rest of the given entry's preview if it is only partially
+    /// loaded.
+    pub async fn load_more(&mut self, entry: &Entry) {
+        if matches!(entry.preview_type, PreviewType::Files) {
+            self.file.load_more(entry).await;
+        }
+    }
+
+    /// Set the maximum length (in bytes) of the lines of file previews,
+    /// which depends on the width of the preview pane.
+    pub fn set_max_line_length(&mut self, max_line_length: usize) {
+        self.file.set_max_line_length(max_line_length);
+    }
+
+    /// Forget the previews computed by commands, whose output may have
+    /// changed since.
+    pub fn clear_command_previews(&mut self) {
+        self.command.clear();
+    }
+
+    /// Switch file previews to the next syntax theme and return its name.
+    pub fn cycle_theme(&mut self) -> String {
+        self.file.cycle_theme().to_string()
+    }
+
+    /// The background color of the current syntax theme, if it has one.
+    pub fn theme_background(&self) -> Option<syntect::highlighting::Color> {
+        self.file.theme_background()
+    }
+
     pub fn set_config(&mut self, config: PreviewerConfig) {
         self.basic = BasicPreviewer::new(Some(config.basic));
         self.directory = DirectoryPreviewer::new(Some(config.directory));
         self.file = FilePreviewer::new(Some(config.file));
         self.env_var = EnvVarPreviewer::new(Some(config.env_var));
+        self.command = CommandPreviewer::new(Some(config.command));
     }
 }
@@ -0,0 +1,301 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+const ESCAPE_CHARACTER: char = '\x1B';
+const BELL_CHARACTER: char = '\x07';
+
+/// Convert a line containing ANSI SGR escape sequences into a styled `Line`.
+///
+/// Supported attributes are the 16 basic colors, 256-color (`38;5;n`) and
+/// truecolor (`38;2;r;g;b`) foregrounds and backgrounds, as well as bold, dim,
+/// italic, underline, reversed and crossed-out modifiers. Other escape sequences
+/// (cursor movements, OSC, etc.) are discarded.
+///
+/// # Examples
+/// ```
+/// use ratatui::style::{Color, Style};
+/// use television_previewers::previewers::ansi::ansi_to_line;
+///
+/// let line = ansi_to_line("\x1b[31merror\x1b[0m: oops");
+/// assert_eq!(line.spans.len(), 2);
+/// assert_eq!(line.spans[0].content, "error");
+/// assert_eq!(line.spans[0].style, Style::default().fg(Color::Red));
+/// assert_eq!(line.spans[1].content, ": oops");
+/// assert_eq!(line.spans[1].style, Style::default());
+/// ```
+pub fn ansi_to_line(input: &str) -> Line<'static> {
+    let mut spans = Vec::new();
+    let mut style = Style::default();
+    let mut current = String::new();
+
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ESCAPE_CHARACTER {
+            current.push(c);
+            continue;
+        }
+        match chars.peek() {
+            // CSI
+            Some('[') => {
+                chars.next();
+                let mut params = String::new();
+                let mut final_char = None;
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7E').contains(&c) {
+                        final_char = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if final_char == Some('m') {
+                    let new_style = apply_sgr(style, &params);
+                    if new_style != style {
+                        push_span(&mut spans, &mut current, style);
+                        style = new_style;
+                    }
+                }
+            }
+            // OSC
+            Some(']') => {
+                chars.next();
+                while let Some(c) = chars.next() {
+                    if c == BELL_CHARACTER {
+                        break;
+                    }
+                    if c == ESCAPE_CHARACTER && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            Some(c) if ('\x30'..='\x7E').contains(c) => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    push_span(&mut spans, &mut current, style);
+
+    Line::from(spans)
+}
+
+fn push_span(
+    spans: &mut Vec<Span<'static>>,
+    content: &mut String,
+    style: Style,
+) {
+    if !content.is_empty() {
+        spans.push(Span::styled(std::mem::take(content), style));
+    }
+}
+
+/// Apply the given SGR parameters (e.g. `"1;31"`) to a style.
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    // an empty parameter list is equivalent to a reset
+    if params.is_empty() {
+        return Style::default();
+    }
+    let codes: Vec<u16> = params
+        .split([';', ':'])
+        .map(|p| p.parse().unwrap_or(0))
+        .collect();
+
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => style = Style::default(),
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            5 => style = style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            9 => style = style.add_modifier(Modifier::CROSSED_OUT),
+            22 => {
+                style = style.remove_modifier(Modifier::BOLD | Modifier::DIM);
+            }
+            23 => style = style.remove_modifier(Modifier::ITALIC),
+            24 => style = style.remove_modifier(Modifier::UNDERLINED),
+            25 => style = style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style = style.remove_modifier(Modifier::REVERSED),
+            29 => style = style.remove_modifier(Modifier::CROSSED_OUT),
+            c @ 30..=37 => style = style.fg(basic_color(c - 30)),
+            38 => {
+                let Some((color, consumed)) = extended_color(&codes[i + 1..])
+                else {
+                    // the remaining parameters can't be interpreted reliably
+                    break;
+                };
+                style = style.fg(color);
+                i += consumed;
+            }
+            39 => style.fg = None,
+            c @ 40..=47 => style = style.bg(basic_color(c - 40)),
+            48 => {
+                let Some((color, consumed)) = extended_color(&codes[i + 1..])
+                else {
+                    // the remaining parameters can't be interpreted reliably
+                    break;
+                };
+                style = style.bg(color);
+                i += consumed;
+            }
+            49 => style.bg = None,
+            c @ 90..=97 => style = style.fg(bright_color(c - 90)),
+            c @ 100..=107 => style = style.bg(bright_color(c - 100)),
+            _ => {}
+        }
+        i += 1;
+    }
+    style
+}
+
+/// Parse the arguments of an extended color (`5;n` or `2;r;g;b`).
+///
+/// Returns the color and the number of parameters consumed.
+fn extended_color(args: &[u16]) -> Option<(Color, usize)> {
+    match args {
+        [5, n, ..] => Some((Color::Indexed(u8::try_from(*n).ok()?), 2)),
+        [2, r, g, b, ..] => Some((
+            Color::Rgb(
+                u8::try_from(*r).ok()?,
+                u8::try_from(*g).ok()?,
+                u8::try_from(*b).ok()?,
+            ),
+            4,
+        )),
+        _ => None,
+    }
+}
+
+fn basic_color(index: u16) -> Color {
+    match index {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        _ => Color::Gray,
+    }
+}
+
+fn bright_color(index: u16) -> Color {
+    match index {
+        0 => Color::DarkGray,
+        1 => Color::LightRed,
+        2 => Color::LightGreen,
+        3 => Color::LightYellow,
+        4 => Color::LightBlue,
+        5 => Color::LightMagenta,
+        6 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(input: &str) -> Vec<(String, Style)> {
+        ansi_to_line(input)
+            .spans
+            .into_iter()
+            .map(|s| (s.content.into_owned(), s.style))
+            .collect()
+    }
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(
+            spans("hello world"),
+            vec![("hello world".to_string(), Style::default())]
+        );
+        assert!(ansi_to_line("").spans.is_empty());
+    }
+
+    #[test]
+    fn test_basic_colors() {
+        assert_eq!(
+            spans("\x1b[32mgreen\x1b[0m \x1b[44mblue bg\x1b[49m"),
+            vec![
+                ("green".to_string(), Style::default().fg(Color::Green)),
+                (" ".to_string(), Style::default()),
+                ("blue bg".to_string(), Style::default().bg(Color::Blue)),
+            ]
+        );
+        assert_eq!(
+            spans("\x1b[91mbright"),
+            vec![("bright".to_string(), Style::default().fg(Color::LightRed))]
+        );
+    }
+
+    #[test]
+    fn test_256_colors() {
+        assert_eq!(
+            spans("\x1b[38;5;208morange\x1b[48;5;16mon black"),
+            vec![
+                (
+                    "orange".to_string(),
+                    Style::default().fg(Color::Indexed(208))
+                ),
+                (
+                    "on black".to_string(),
+                    Style::default()
+                        .fg(Color::Indexed(208))
+                        .bg(Color::Indexed(16))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_truecolor() {
+        assert_eq!(
+            spans("\x1b[38;2;255;128;0mtrue\x1b[39m"),
+            vec![(
+                "true".to_string(),
+                Style::default().fg(Color::Rgb(255, 128, 0))
+            )]
+        );
+    }
+
+    #[test]
+    fn test_bold_and_underline() {
+        assert_eq!(
+            spans("\x1b[1;4;31mbold\x1b[22mnot bold\x1b[m"),
+            vec![
+                (
+                    "bold".to_string(),
+                    Style::default()
+                        .fg(Color::Red)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+                ),
+                (
+                    "not bold".to_string(),
+                    Style::default()
+                        .fg(Color::Red)
+                        .add_modifier(Modifier::UNDERLINED)
+                        .remove_modifier(Modifier::BOLD | Modifier::DIM)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_non_sgr_sequences_are_discarded() {
+        assert_eq!(
+            spans("\x1b[2Ka\x1b]0;title\x07b\x1b[?25lc\x1b"),
+            vec![("abc".to_string(), Style::default())]
+        );
+    }
+
+    #[test]
+    fn test_invalid_extended_colors_are_ignored() {
+        assert_eq!(
+            spans("\x1b[38;5mtext\x1b[38;2;300;0;0mmore"),
+            vec![("textmore".to_string(), Style::default())]
+        );
+    }
+}
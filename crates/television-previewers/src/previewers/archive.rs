@@ -0,0 +1,646 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use flate2::read::GzDecoder;
+
+use crate::previewers::files::human_readable_size;
+
+/// The default maximum number of entries listed in archive previews.
+pub const DEFAULT_ARCHIVE_MAX_ENTRIES: usize = 1000;
+
+/// The archive formats that can be listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Guess the archive format of a file based on its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// An entry of an archive.
+#[derive(Debug, PartialEq, Eq)]
+struct ArchiveEntry {
+    path: String,
+    /// The uncompressed size of the entry.
+    size: u64,
+    is_dir: bool,
+    /// The modification time, formatted as `YYYY-MM-DD HH:MM`.
+    mtime: Option<String>,
+}
+
+/// The entries of an archive, possibly capped to a maximum count.
+#[derive(Debug)]
+struct ArchiveListing {
+    entries: Vec<ArchiveEntry>,
+    /// The number of entries that were not listed, if known.
+    remaining: Option<usize>,
+    /// Whether the listing was capped before reaching the end of the archive.
+    truncated: bool,
+}
+
+/// List the contents of an archive without extracting it.
+///
+/// At most `max_entries` entries are listed, a note is added at the bottom if
+/// the listing was truncated.
+///
+/// Zip archives have a central directory and the data of the entries of
+/// plain tar archives is sought over, but compressed tar archives have to be
+/// decompressed up to their last listed entry, which can take a while:
+/// listing stops early once `cancelled` is set.
+pub fn archive_listing(
+    path: &Path,
+    format: ArchiveFormat,
+    max_entries: usize,
+    cancelled: &AtomicBool,
+) -> io::Result<Vec<String>> {
+    let file = File::open(path)?;
+    let listing = match format {
+        ArchiveFormat::Zip => list_zip(file, max_entries)?,
+        ArchiveFormat::Tar => list_tar(
+            BufReader::new(file),
+            max_entries,
+            skip_by_seeking,
+            cancelled,
+        )?,
+        ArchiveFormat::TarGz => list_tar(
+            GzDecoder::new(BufReader::new(file)),
+            max_entries,
+            skip_by_reading,
+            cancelled,
+        )?,
+    };
+    Ok(format_archive_listing(&listing))
+}
+
+fn format_archive_listing(listing: &ArchiveListing) -> Vec<String> {
+    let mut lines: Vec<String> = listing
+        .entries
+        .iter()
+        .map(|e| {
+            let size = if e.is_dir {
+                String::new()
+            } else {
+                human_readable_size(e.size)
+            };
+            let mtime = e.mtime.as_deref().unwrap_or_default();
+            format!("{size:>6}  {mtime:<16}  {}", e.path)
+        })
+        .collect();
+    if listing.truncated {
+        lines.push(String::new());
+        lines.push(match listing.remaining {
+            Some(remaining) => format!("... and {remaining} more entries"),
+            None => String::from("... and more entries"),
+        });
+    }
+    lines
+}
+
+const ZIP_EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const ZIP_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0201_4b50;
+const ZIP_EOCD_MIN_SIZE: usize = 22;
+const ZIP_CENTRAL_DIRECTORY_HEADER_SIZE: usize = 46;
+
+fn le_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn le_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// List the entries of a zip archive by reading its central directory.
+///
+/// Zip64 archives are not supported.
+fn list_zip<R: Read + Seek>(
+    mut reader: R,
+    max_entries: usize,
+) -> io::Result<ArchiveListing> {
+    // the end of central directory record is located at the end of the file,
+    // followed by a comment of up to 65535 bytes
+    let len = reader.seek(SeekFrom::End(0))?;
+    let tail_len = len.min((ZIP_EOCD_MIN_SIZE + usize::from(u16::MAX)) as u64);
+    reader.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = Vec::new();
+    reader.read_to_end(&mut tail)?;
+    let eocd = (0..=tail.len().saturating_sub(ZIP_EOCD_MIN_SIZE))
+        .rev()
+        .find(|i| le_u32(&tail, *i) == Some(ZIP_EOCD_SIGNATURE))
+        .ok_or_else(|| invalid_data("missing end of central directory"))?;
+    let total_entries = usize::from(le_u16(&tail, eocd + 10).unwrap_or(0));
+    let cd_size = le_u32(&tail, eocd + 12).unwrap_or(0);
+    let cd_offset = le_u32(&tail, eocd + 16).unwrap_or(0);
+
+    reader.seek(SeekFrom::Start(u64::from(cd_offset)))?;
+    let mut cd = Vec::new();
+    reader.take(u64::from(cd_size)).read_to_end(&mut cd)?;
+
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while entries.len() < max_entries
+        && le_u32(&cd, offset) == Some(ZIP_CENTRAL_DIRECTORY_SIGNATURE)
+    {
+        let header = cd
+            .get(offset..offset + ZIP_CENTRAL_DIRECTORY_HEADER_SIZE)
+            .ok_or_else(|| invalid_data("truncated central directory"))?;
+        let time = le_u16(header, 12).unwrap_or(0);
+        let date = le_u16(header, 14).unwrap_or(0);
+        let size = le_u32(header, 24).unwrap_or(0);
+        let name_len = usize::from(le_u16(header, 28).unwrap_or(0));
+        let extra_len = usize::from(le_u16(header, 30).unwrap_or(0));
+        let comment_len = usize::from(le_u16(header, 32).unwrap_or(0));
+        let name_start = offset + ZIP_CENTRAL_DIRECTORY_HEADER_SIZE;
+        let name = cd
+            .get(name_start..name_start + name_len)
+            .ok_or_else(|| invalid_data("truncated central directory"))?;
+        let path = String::from_utf8_lossy(name).into_owned();
+        entries.push(ArchiveEntry {
+            is_dir: path.ends_with('/'),
+            path,
+            size: u64::from(size),
+            mtime: Some(format_dos_datetime(date, time)),
+        });
+        offset = name_start + name_len + extra_len + comment_len;
+    }
+    let remaining = total_entries.saturating_sub(entries.len());
+    Ok(ArchiveListing {
+        entries,
+        remaining: Some(remaining),
+        truncated: remaining > 0,
+    })
+}
+
+/// Format an MS-DOS date and time (as stored in zip archives).
+fn format_dos_datetime(date: u16, time: u16) -> String {
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        1980 + (date >> 9),
+        (date >> 5) & 0x0F,
+        date & 0x1F,
+        time >> 11,
+        (time >> 5) & 0x3F,
+    )
+}
+
+const TAR_BLOCK_SIZE: usize = 512;
+/// The maximum size of the data of GNU long name and pax extended headers,
+/// which are read in memory.
+const TAR_MAX_EXTENDED_HEADER_SIZE: u64 = 64 * 1024;
+
+/// Parse a numeric field of a tar header (octal, or base-256 for large values).
+fn parse_tar_number(field: &[u8]) -> u64 {
+    if field.first().map_or(false, |b| b & 0x80 != 0) {
+        return field[1..]
+            .iter()
+            .fold(0u64, |acc, b| acc.wrapping_shl(8) | u64::from(*b));
+    }
+    let s = String::from_utf8_lossy(field);
+    u64::from_str_radix(s.trim_matches(|c: char| c == '\0' || c == ' '), 8)
+        .unwrap_or(0)
+}
+
+fn parse_tar_string(field: &[u8]) -> String {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// Extract the `path` record from a pax extended header.
+fn parse_pax_path(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data).lines().find_map(|record| {
+        // records look like "<length> <key>=<value>"
+        let (_, key_value) = record.split_once(' ')?;
+        key_value.strip_prefix("path=").map(ToOwned::to_owned)
+    })
+}
+
+/// Skip the next `len` bytes of `reader`.
+fn skip_by_reading<R: Read>(reader: &mut R, len: u64) -> io::Result<()> {
+    io::copy(&mut reader.take(len), &mut io::sink()).map(|_| ())
+}
+
+/// Skip the next `len` bytes of `reader` without reading them.
+fn skip_by_seeking<R: Seek>(reader: &mut R, len: u64) -> io::Result<()> {
+    let offset =
+        i64::try_from(len).map_err(|_| invalid_data("entry too large"))?;
+    reader.seek_relative(offset)
+}
+
+/// List the entries of a tar archive by walking its headers, skipping the
+/// data of entries with `skip`.
+///
+/// Listing stops early once `cancelled` is set.
+fn list_tar<R: Read>(
+    mut reader: R,
+    max_entries: usize,
+    skip: fn(&mut R, u64) -> io::Result<()>,
+    cancelled: &AtomicBool,
+) -> io::Result<ArchiveListing> {
+    let mut entries = Vec::new();
+    let mut header = [0u8; TAR_BLOCK_SIZE];
+    let mut next_path: Option<String> = None;
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(io::Error::from(io::ErrorKind::Interrupted));
+        }
+        if reader.read_exact(&mut header).is_err()
+            || header.iter().all(|b| *b == 0)
+        {
+            return Ok(ArchiveListing {
+                entries,
+                remaining: Some(0),
+                truncated: false,
+            });
+        }
+        if entries.len() >= max_entries {
+            return Ok(ArchiveListing {
+                entries,
+                remaining: None,
+                truncated: true,
+            });
+        }
+        let size = parse_tar_number(&header[124..136]);
+        let mtime = parse_tar_number(&header[136..148]);
+        let type_flag = header[156];
+        let padded_size = size
+            .div_ceil(TAR_BLOCK_SIZE as u64)
+            .checked_mul(TAR_BLOCK_SIZE as u64)
+            .ok_or_else(|| invalid_data("entry too large"))?;
+
+        match type_flag {
+            // GNU long name or pax extended header: the data describes the
+            // next entry
+            b'L' | b'x' => {
+                if size > TAR_MAX_EXTENDED_HEADER_SIZE {
+                    return Err(invalid_data("extended header too large"));
+                }
+                let mut data = Vec::new();
+                (&mut reader).take(padded_size).read_to_end(&mut data)?;
+                data.truncate(usize::try_from(size).unwrap_or(data.len()));
+                next_path = if type_flag == b'L' {
+                    Some(parse_tar_string(&data))
+                } else {
+                    parse_pax_path(&data)
+                };
+                continue;
+            }
+            // pax global header
+            b'g' => {}
+            _ => {
+                let path = next_path.take().unwrap_or_else(|| {
+                    let name = parse_tar_string(&header[0..100]);
+                    let prefix = if &header[257..262] == b"ustar" {
+                        parse_tar_string(&header[345..500])
+                    } else {
+                        String::new()
+                    };
+                    if prefix.is_empty() {
+                        name
+                    } else {
+                        format!("{prefix}/{name}")
+                    }
+                });
+                let is_dir = type_flag == b'5' || path.ends_with('/');
+                entries.push(ArchiveEntry {
+                    path,
+                    size,
+                    is_dir,
+                    mtime: i64::try_from(mtime).ok().map(format_unix_time),
+                });
+            }
+        }
+        skip(&mut reader, padded_size)?;
+    }
+}
+
+/// Format a unix timestamp (in UTC) as `YYYY-MM-DD HH:MM`.
+fn format_unix_time(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86_400);
+    let seconds = timestamp.rem_euclid(86_400);
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        seconds / 3600,
+        (seconds % 3600) / 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::{Cursor, Write};
+
+    static NOT_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+    fn tar_header(path: &str, size: usize, type_flag: u8) -> Vec<u8> {
+        let mut header = vec![0u8; TAR_BLOCK_SIZE];
+        header[..path.len()].copy_from_slice(path.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{size:011o}").as_bytes());
+        // 2024-01-02 03:04 UTC
+        header[136..147]
+            .copy_from_slice(format!("{:011o}", 1_704_164_640).as_bytes());
+        header[156] = type_flag;
+        header[257..263].copy_from_slice(b"ustar\0");
+        header
+    }
+
+    fn tar_fixture() -> Vec<u8> {
+        let mut tar = Vec::new();
+        tar.extend(tar_header("dir/", 0, b'5'));
+        tar.extend(tar_header("dir/hello.txt", 5, b'0'));
+        let mut data = b"hello".to_vec();
+        data.resize(TAR_BLOCK_SIZE, 0);
+        tar.extend(data);
+        tar.extend(tar_header("empty", 0, b'0'));
+        tar.extend(vec![0u8; 2 * TAR_BLOCK_SIZE]);
+        tar
+    }
+
+    fn zip_fixture() -> Vec<u8> {
+        let files: [(&str, &[u8]); 2] =
+            [("hello.txt", b"hello"), ("dir/", b"")];
+        // 2024-01-02 03:04
+        let date: u16 = (44 << 9) | (1 << 5) | 2;
+        let time: u16 = (3 << 11) | (4 << 5);
+        let mut zip = Vec::new();
+        let mut central_directory = Vec::new();
+        for (name, data) in files {
+            let offset = u32::try_from(zip.len()).unwrap();
+            let size = u32::try_from(data.len()).unwrap();
+            let name_len = u16::try_from(name.len()).unwrap();
+            // local file header (stored, no compression)
+            zip.extend(0x0403_4b50u32.to_le_bytes());
+            zip.extend([20, 0, 0, 0, 0, 0]);
+            zip.extend(time.to_le_bytes());
+            zip.extend(date.to_le_bytes());
+            zip.extend([0; 4]);
+            zip.extend(size.to_le_bytes());
+            zip.extend(size.to_le_bytes());
+            zip.extend(name_len.to_le_bytes());
+            zip.extend([0; 2]);
+            zip.extend(name.as_bytes());
+            zip.extend(data);
+            // central directory header
+            central_directory.extend(0x0201_4b50u32.to_le_bytes());
+            central_directory.extend([20, 0, 20, 0, 0, 0, 0, 0]);
+            central_directory.extend(time.to_le_bytes());
+            central_directory.extend(date.to_le_bytes());
+            central_directory.extend([0; 4]);
+            central_directory.extend(size.to_le_bytes());
+            central_directory.extend(size.to_le_bytes());
+            central_directory.extend(name_len.to_le_bytes());
+            central_directory.extend([0; 12]);
+            central_directory.extend(offset.to_le_bytes());
+            central_directory.extend(name.as_bytes());
+        }
+        let cd_offset = u32::try_from(zip.len()).unwrap();
+        let cd_size = u32::try_from(central_directory.len()).unwrap();
+        zip.extend(central_directory);
+        zip.extend(0x0605_4b50u32.to_le_bytes());
+        zip.extend([0; 4]);
+        zip.extend(2u16.to_le_bytes());
+        zip.extend(2u16.to_le_bytes());
+        zip.extend(cd_size.to_le_bytes());
+        zip.extend(cd_offset.to_le_bytes());
+        zip.extend([0; 2]);
+        zip
+    }
+
+    #[test]
+    fn test_archive_format_from_path() {
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("a/b.ZIP")),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("b.tar")),
+            Some(ArchiveFormat::Tar)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("b.tar.gz")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("b.tgz")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(ArchiveFormat::from_path(Path::new("b.gz")), None);
+    }
+
+    #[test]
+    fn test_list_tar() {
+        let listing = list_tar(
+            Cursor::new(tar_fixture()),
+            10,
+            skip_by_seeking,
+            &NOT_CANCELLED,
+        )
+        .unwrap();
+        assert_eq!(
+            format_archive_listing(&listing),
+            vec![
+                "        2024-01-02 03:04  dir/",
+                "    5B  2024-01-02 03:04  dir/hello.txt",
+                "    0B  2024-01-02 03:04  empty",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_tar_gz() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&tar_fixture()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let listing = list_tar(
+            GzDecoder::new(Cursor::new(compressed)),
+            10,
+            skip_by_reading,
+            &NOT_CANCELLED,
+        )
+        .unwrap();
+        assert_eq!(listing.entries.len(), 3);
+        assert_eq!(listing.entries[1].path, "dir/hello.txt");
+        assert_eq!(listing.entries[1].size, 5);
+    }
+
+    #[test]
+    fn test_list_tar_truncated() {
+        let listing = list_tar(
+            Cursor::new(tar_fixture()),
+            2,
+            skip_by_seeking,
+            &NOT_CANCELLED,
+        )
+        .unwrap();
+        assert_eq!(
+            format_archive_listing(&listing),
+            vec![
+                "        2024-01-02 03:04  dir/",
+                "    5B  2024-01-02 03:04  dir/hello.txt",
+                "",
+                "... and more entries",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_tar_long_names() {
+        let long_name = "a/".repeat(80) + "file.txt";
+        let mut data = long_name.clone().into_bytes();
+        let mut tar = tar_header("././@LongLink", data.len(), b'L');
+        data.resize(data.len().div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE, 0);
+        tar.extend(data);
+        tar.extend(tar_header("truncated-name", 0, b'0'));
+        let listing =
+            list_tar(Cursor::new(tar), 10, skip_by_seeking, &NOT_CANCELLED)
+                .unwrap();
+        assert_eq!(listing.entries[0].path, long_name);
+    }
+
+    /// A reader counting the bytes read from it.
+    struct CountingReader {
+        inner: Cursor<Vec<u8>>,
+        read: u64,
+    }
+
+    impl Read for CountingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.read += n as u64;
+            Ok(n)
+        }
+    }
+
+    impl Seek for CountingReader {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_list_tar_seeks_over_data() {
+        let mut tar = tar_header("big", 1024 * 1024, b'0');
+        tar.extend(vec![b'a'; 1024 * 1024]);
+        tar.extend(tar_header("small", 0, b'0'));
+        tar.extend(vec![0u8; 2 * TAR_BLOCK_SIZE]);
+        let mut reader = CountingReader {
+            inner: Cursor::new(tar),
+            read: 0,
+        };
+        let listing =
+            list_tar(&mut reader, 10, skip_by_seeking, &NOT_CANCELLED)
+                .unwrap();
+        assert_eq!(listing.entries.len(), 2);
+        assert!(reader.read < 4 * TAR_BLOCK_SIZE as u64);
+    }
+
+    #[test]
+    fn test_list_tar_cancelled() {
+        let cancelled = AtomicBool::new(true);
+        assert!(list_tar(
+            Cursor::new(tar_fixture()),
+            10,
+            skip_by_seeking,
+            &cancelled
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_list_tar_oversized_sizes() {
+        // base-256 encoded size overflowing once padded
+        let mut tar = tar_header("huge", 0, b'0');
+        tar[124] = 0x80;
+        tar[128..136].fill(0xFF);
+        assert!(list_tar(
+            Cursor::new(tar),
+            10,
+            skip_by_seeking,
+            &NOT_CANCELLED
+        )
+        .is_err());
+
+        let size = usize::try_from(TAR_MAX_EXTENDED_HEADER_SIZE).unwrap() + 1;
+        let mut tar = tar_header("././@LongLink", size, b'L');
+        tar.extend(vec![b'a'; size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE]);
+        assert!(list_tar(
+            Cursor::new(tar),
+            10,
+            skip_by_seeking,
+            &NOT_CANCELLED
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_list_zip() {
+        let listing = list_zip(Cursor::new(zip_fixture()), 10).unwrap();
+        assert_eq!(
+            format_archive_listing(&listing),
+            vec![
+                "    5B  2024-01-02 03:04  hello.txt",
+                "        2024-01-02 03:04  dir/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_zip_truncated() {
+        let listing = list_zip(Cursor::new(zip_fixture()), 1).unwrap();
+        assert_eq!(
+            format_archive_listing(&listing),
+            vec![
+                "    5B  2024-01-02 03:04  hello.txt",
+                "",
+                "... and 1 more entries",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_invalid_zip() {
+        assert!(list_zip(Cursor::new(b"not a zip".to_vec()), 10).is_err());
+    }
+
+    #[test]
+    fn test_format_unix_time() {
+        assert_eq!(format_unix_time(0), "1970-01-01 00:00");
+        assert_eq!(format_unix_time(1_704_164_640), "2024-01-02 03:04");
+        assert_eq!(format_unix_time(951_782_400), "2000-02-29 00:00");
+    }
+}
@@ -22,6 +22,8 @@ impl BasicPreviewer {
         Arc::new(Preview {
             title: entry.name.clone(),
             content: PreviewContent::PlainTextWrapped(entry.name.clone()),
+            partial: false,
+            blame: None,
         })
     }
 }
@@ -96,24 +96,59 @@ where
         }
     }
 
+    /// Move an existing item to the back of the buffer, marking it as the most
+    /// recently used one.
+    /// Returns false if the item isn't in the buffer.
+    pub fn touch(&mut self, key: &T) -> bool {
+        if !self.contains(key) {
+            return false;
+        }
+        if let Some(pos) = self.ring_buffer.iter().position(|k| k == key) {
+            if let Some(item) = self.ring_buffer.remove(pos) {
+                self.ring_buffer.push_back(item);
+            }
+        }
+        true
+    }
+
     pub fn contains(&self, key: &T) -> bool {
         self.known_keys.contains(key)
     }
+
+    pub fn len(&self) -> usize {
+        self.ring_buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring_buffer.is_empty()
+    }
+
+    /// Remove all the items from the buffer.
+    pub fn clear(&mut self) {
+        self.ring_buffer.clear();
+        self.known_keys.clear();
+    }
 }
 
-/// Default size of the preview cache: 100 entries.
+/// Default size of the preview cache: 200 entries.
 ///
 /// This does seem kind of arbitrary for now, will need to play around with it.
-/// At the moment, files over 4 MB are not previewed, so the cache size
-/// shouldn't exceed 400 MB.
-const DEFAULT_PREVIEW_CACHE_SIZE: usize = 100;
+/// The total size of the cache is additionally bounded by
+/// `DEFAULT_PREVIEW_CACHE_BYTE_BUDGET`.
+pub const DEFAULT_PREVIEW_CACHE_SIZE: usize = 200;
+
+/// Default maximum total size of the previews held by the cache: 256 MB.
+pub const DEFAULT_PREVIEW_CACHE_BYTE_BUDGET: usize = 256 * 1024 * 1024;
 
 /// A cache for previews.
-/// The cache is implemented as an LRU cache with a fixed size.
+/// The cache is implemented as an LRU cache bounded by a number of entries and
+/// by a total (approximate) byte size.
 #[derive(Debug)]
 pub struct PreviewCache {
     entries: HashMap<String, Arc<Preview>>,
     ring_set: RingSet<String>,
+    byte_budget: usize,
+    size_in_bytes: usize,
 }
 
 impl PreviewCache {
@@ -122,25 +157,77 @@ impl PreviewCache {
         PreviewCache {
             entries: HashMap::new(),
             ring_set: RingSet::with_capacity(capacity),
+            byte_budget: DEFAULT_PREVIEW_CACHE_BYTE_BUDGET,
+            size_in_bytes: 0,
         }
     }
 
-    pub fn get(&self, key: &str) -> Option<Arc<Preview>> {
-        self.entries.get(key).cloned()
+    /// Set the maximum total size (in bytes) of the previews held by the cache.
+    pub fn with_byte_budget(mut self, byte_budget: usize) -> Self {
+        self.byte_budget = byte_budget;
+        self
+    }
+
+    /// Get the preview for the given key, marking it as recently used.
+    pub fn get(&mut self, key: &str) -> Option<Arc<Preview>> {
+        let preview = self.entries.get(key).cloned()?;
+        self.ring_set.touch(&key.to_string());
+        Some(preview)
     }
 
     /// Insert a new preview into the cache.
-    /// If the cache is full, the oldest entry will be removed.
+    /// If the cache is full, the least recently used entries will be removed.
     /// If the key is already in the cache, the preview will be updated.
     pub fn insert(&mut self, key: String, preview: Arc<Preview>) {
         debug!("Inserting preview into cache: {}", key);
-        self.entries.insert(key.clone(), preview.clone());
-        if let Some(oldest_key) = self.ring_set.push(key) {
+        self.size_in_bytes += preview.size_in_bytes();
+        if let Some(previous) = self.entries.insert(key.clone(), preview) {
+            self.size_in_bytes -= previous.size_in_bytes();
+            self.ring_set.touch(&key);
+        } else if let Some(oldest_key) = self.ring_set.push(key) {
             debug!("Cache full, removing oldest entry: {}", oldest_key);
-            self.entries.remove(&oldest_key);
+            self.remove(&oldest_key);
+        }
+        // always keep at least the most recent entry
+        while self.size_in_bytes > self.byte_budget && self.ring_set.len() > 1
+        {
+            if let Some(oldest_key) = self.ring_set.pop() {
+                debug!(
+                    "Cache over byte budget, removing oldest entry: {}",
+                    oldest_key
+                );
+                self.remove(&oldest_key);
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(preview) = self.entries.remove(key) {
+            self.size_in_bytes -= preview.size_in_bytes();
         }
     }
 
+    /// The number of previews currently in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove all the previews from the cache.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.ring_set.clear();
+        self.size_in_bytes = 0;
+    }
+
+    /// The approximate total size (in bytes) of the previews in the cache.
+    pub fn size_in_bytes(&self) -> usize {
+        self.size_in_bytes
+    }
+
     /// Get the preview for the given key, or insert a new preview if it doesn't exist.
     #[allow(dead_code)]
     pub fn get_or_insert<F>(&mut self, key: String, f: F) -> Arc<Preview>
@@ -166,6 +253,83 @@ impl Default for PreviewCache {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::previewers::PreviewContent;
+
+    fn preview(content: &str) -> Arc<Preview> {
+        Arc::new(Preview::new(
+            String::new(),
+            PreviewContent::PlainText(vec![content.to_string()]),
+        ))
+    }
+
+    #[test]
+    fn test_ring_set_touch() {
+        let mut ring_set = RingSet::with_capacity(3);
+        ring_set.push(1);
+        ring_set.push(2);
+        ring_set.push(3);
+
+        // 1 becomes the most recently used value, 2 is now the oldest
+        assert!(ring_set.touch(&1));
+        assert!(!ring_set.touch(&4));
+        assert_eq!(ring_set.push(4), Some(2));
+        assert!(ring_set.contains(&1));
+        assert_eq!(ring_set.len(), 3);
+    }
+
+    #[test]
+    fn test_preview_cache_evicts_least_recently_used() {
+        let mut cache = PreviewCache::new(2);
+        cache.insert("a".to_string(), preview("a"));
+        cache.insert("b".to_string(), preview("b"));
+        assert_eq!(cache.len(), 2);
+
+        // accessing "a" refreshes its recency so "b" gets evicted
+        assert!(cache.get("a").is_some());
+        cache.insert("c".to_string(), preview("c"));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn test_preview_cache_update_existing_entry() {
+        let mut cache = PreviewCache::new(2);
+        cache.insert("a".to_string(), preview("a"));
+        cache.insert("b".to_string(), preview("b"));
+        let size = cache.size_in_bytes();
+
+        // updating "a" refreshes its recency and keeps the size consistent
+        cache.insert("a".to_string(), preview("a"));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.size_in_bytes(), size);
+        cache.insert("c".to_string(), preview("c"));
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+    }
+
+    #[test]
+    fn test_preview_cache_byte_budget() {
+        let big = "x".repeat(1000);
+        let one = preview(&big).size_in_bytes();
+        let mut cache = PreviewCache::new(10).with_byte_budget(2 * one);
+        cache.insert("a".to_string(), preview(&big));
+        cache.insert("b".to_string(), preview(&big));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.size_in_bytes(), 2 * one);
+
+        cache.insert("c".to_string(), preview(&big));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.size_in_bytes() <= 2 * one);
+        assert!(cache.get("a").is_none());
+
+        // an entry larger than the budget is still kept on its own
+        let huge = "x".repeat(10_000);
+        cache.insert("d".to_string(), preview(&huge));
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("d").is_some());
+    }
 
     #[test]
     fn test_ring_set() {
@@ -0,0 +1,311 @@
+use std::io::{self, Read};
+use std::process::{Child, Output, Stdio};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tracing::{debug, warn};
+
+use crate::previewers::ansi::ansi_to_line;
+use crate::previewers::cache::PreviewCache;
+use crate::previewers::files::DEFAULT_PREVIEW_TIMEOUT;
+use crate::previewers::{meta, Preview, PreviewContent};
+use television_channels::entry;
+use television_utils::command::{
+    expand_command, format_command, kill_shell_command, shell_command,
+};
+
+/// How often to check whether a preview command has exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Default)]
+pub struct CommandPreviewer {
+    cache: Arc<Mutex<PreviewCache>>,
+    config: CommandPreviewerConfig,
+}
+
+#[derive(Debug)]
+pub struct CommandPreviewerConfig {
+    /// How long to wait for a command before killing it and giving up on
+    /// its preview.
+    pub preview_timeout: Duration,
+}
+
+impl Default for CommandPreviewerConfig {
+    fn default() -> Self {
+        CommandPreviewerConfig {
+            preview_timeout: DEFAULT_PREVIEW_TIMEOUT,
+        }
+    }
+}
+
+impl CommandPreviewerConfig {
+    pub fn preview_timeout(mut self, preview_timeout: Duration) -> Self {
+        self.preview_timeout = preview_timeout;
+        self
+    }
+}
+
+impl CommandPreviewer {
+    pub fn new(config: Option<CommandPreviewerConfig>) -> Self {
+        CommandPreviewer {
+            cache: Arc::new(Mutex::new(PreviewCache::default())),
+            config: config.unwrap_or_default(),
+        }
+    }
+
+    /// Forget the previews computed so far, e.g. because the output of the
+    /// commands may have changed.
+    ///
+    /// The previews of the commands still running are dropped once they
+    /// complete.
+    pub fn clear(&mut self) {
+        self.cache = Arc::new(Mutex::new(PreviewCache::default()));
+    }
+
+    /// Preview the given entry with the output of `command`, a template in
+    /// which `{}` is substituted with the entry's name.
+    ///
+    /// The command runs in the background, a loading preview being returned
+    /// in the meantime.
+    pub fn preview(
+        &mut self,
+        entry: &entry::Entry,
+        command: &str,
+    ) -> Arc<Preview> {
+        // the template is expanded before the entry is substituted so that
+        // entries are passed as is
+        let command_line =
+            format_command(&expand_command(command), &entry.name);
+        if let Some(preview) = self.cache.lock().get(&command_line) {
+            return preview;
+        }
+        let preview = meta::loading(&entry.name);
+        self.cache
+            .lock()
+            .insert(command_line.clone(), preview.clone());
+
+        let cache = self.cache.clone();
+        let title = entry.name.clone();
+        let timeout = self.config.preview_timeout;
+        tokio::task::spawn_blocking(move || {
+            let preview =
+                Arc::new(run_preview_command(title, &command_line, timeout));
+            cache.lock().insert(command_line, preview);
+        });
+        preview
+    }
+}
+
+/// Run the given command line and turn its output into a preview, killing
+/// it if it doesn't exit within `timeout`.
+fn run_preview_command(
+    title: String,
+    command_line: &str,
+    timeout: Duration,
+) -> Preview {
+    debug!("Running preview command: {:?}", command_line);
+    let child = match shell_command(command_line)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            warn!("Failed to run {:?}: {:?}", command_line, e);
+            return Preview::new(title, PreviewContent::NotSupported);
+        }
+    };
+    let deadline = Instant::now() + timeout;
+    let output = match wait_for_output(
+        child,
+        || Instant::now() >= deadline,
+        kill_shell_command,
+    ) {
+        Ok(Some(output)) => output,
+        Ok(None) => {
+            warn!("Preview command timed out: {:?}", command_line);
+            return Preview::new(title, PreviewContent::TimedOut);
+        }
+        Err(e) => {
+            warn!("Failed to wait for {:?}: {:?}", command_line, e);
+            return Preview::new(title, PreviewContent::NotSupported);
+        }
+    };
+    // show the errors of failing commands, that's more helpful than an
+    // empty preview
+    let bytes = if output.status.success() || output.stderr.is_empty() {
+        output.stdout
+    } else {
+        output.stderr
+    };
+    Preview::new(title, command_output_to_content(&bytes))
+}
+
+/// Wait for `child` to exit and collect its output, killing it with `kill`
+/// as soon as `should_stop` returns `true`, in which case `None` is
+/// returned.
+pub(crate) fn wait_for_output(
+    mut child: Child,
+    mut should_stop: impl FnMut() -> bool,
+    kill: fn(&mut Child),
+) -> io::Result<Option<Output>> {
+    // both outputs are read while waiting so that the process doesn't block
+    // on a full pipe
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    loop {
+        // stopping takes precedence over a process that just exited, whose
+        // output isn't wanted anymore
+        let stop = if should_stop() {
+            Some(Ok(None))
+        } else {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    return Ok(Some(Output {
+                        status,
+                        stdout: stdout.join().unwrap_or_default(),
+                        stderr: stderr.join().unwrap_or_default(),
+                    }));
+                }
+                Ok(None) => None,
+                Err(e) => Some(Err(e)),
+            }
+        };
+        if let Some(result) = stop {
+            kill(&mut child);
+            let _ = child.wait();
+            return result;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn read_in_background<R: Read + Send + 'static>(
+    reader: Option<R>,
+) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut reader) = reader {
+            let _ = reader.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+fn command_output_to_content(output: &[u8]) -> PreviewContent {
+    let output = String::from_utf8_lossy(output);
+    if output.trim().is_empty() {
+        return PreviewContent::Empty;
+    }
+    PreviewContent::AnsiText(output.lines().map(ansi_to_line).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::{Color, Style};
+
+    #[test]
+    fn test_command_output_to_content() {
+        let PreviewContent::AnsiText(lines) =
+            command_output_to_content(b"\x1b[32m+added\x1b[0m\n-removed\n")
+        else {
+            panic!("expected ANSI text");
+        };
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].spans[0].content, "+added");
+        assert_eq!(lines[0].spans[0].style, Style::default().fg(Color::Green));
+        assert_eq!(lines[1].spans[0].content, "-removed");
+        assert!(matches!(
+            command_output_to_content(b" \n"),
+            PreviewContent::Empty
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_preview_command() {
+        let preview = run_preview_command(
+            "main".to_string(),
+            &format_command("echo {}", "main"),
+            DEFAULT_PREVIEW_TIMEOUT,
+        );
+        assert_eq!(preview.title, "main");
+        let PreviewContent::AnsiText(lines) = preview.content else {
+            panic!("expected ANSI text");
+        };
+        assert_eq!(lines[0].spans[0].content, "main");
+
+        let preview = run_preview_command(
+            String::new(),
+            "echo oops >&2; exit 1",
+            DEFAULT_PREVIEW_TIMEOUT,
+        );
+        let PreviewContent::AnsiText(lines) = preview.content else {
+            panic!("expected ANSI text");
+        };
+        assert_eq!(lines[0].spans[0].content, "oops");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_preview_command_timeout() {
+        let started = Instant::now();
+        // the output pipe is kept open by `sleep`, which must be killed too
+        let preview = run_preview_command(
+            String::new(),
+            "echo partial; sleep 30 | cat",
+            Duration::from_millis(100),
+        );
+        assert!(matches!(preview.content, PreviewContent::TimedOut));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_clear() {
+        let mut previewer = CommandPreviewer::new(None);
+        let entry = entry::Entry::new(
+            "main".to_string(),
+            entry::PreviewType::Command("echo {}".to_string()),
+        );
+        previewer.preview(&entry, "echo {}");
+        while matches!(
+            previewer.preview(&entry, "echo {}").content,
+            PreviewContent::Loading
+        ) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        previewer.clear();
+        assert!(matches!(
+            previewer.preview(&entry, "echo {}").content,
+            PreviewContent::Loading
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_preview_in_background() {
+        let mut previewer = CommandPreviewer::new(None);
+        let entry = entry::Entry::new(
+            "it's here".to_string(),
+            entry::PreviewType::Command("echo {}".to_string()),
+        );
+        let preview = previewer.preview(&entry, "echo {}");
+        assert!(matches!(preview.content, PreviewContent::Loading));
+        let preview = loop {
+            let preview = previewer.preview(&entry, "echo {}");
+            if !matches!(preview.content, PreviewContent::Loading) {
+                break preview;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        let PreviewContent::AnsiText(lines) = &preview.content else {
+            panic!("expected ANSI text");
+        };
+        assert_eq!(lines[0].spans[0].content, "it's here");
+    }
+}
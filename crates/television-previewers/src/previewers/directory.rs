@@ -58,6 +58,8 @@ fn build_tree_preview(entry: &Entry) -> Preview {
                 .map(std::borrow::ToOwned::to_owned)
                 .collect(),
         ),
+        partial: false,
+        blame: None,
     }
 }
 
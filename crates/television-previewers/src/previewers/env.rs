@@ -35,6 +35,8 @@ impl EnvVarPreviewer {
             } else {
                 PreviewContent::Empty
             },
+            partial: false,
+            blame: None,
         });
         self.cache.insert(entry.clone(), preview.clone());
         preview
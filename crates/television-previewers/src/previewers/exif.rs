@@ -0,0 +1,306 @@
+const EXIF_HEADER: &[u8; 6] = b"Exif\0\0";
+const APP1_MARKER: u8 = 0xE1;
+const START_OF_SCAN_MARKER: u8 = 0xDA;
+
+const MAKE_TAG: u16 = 0x010f;
+const MODEL_TAG: u16 = 0x0110;
+const DATE_TIME_TAG: u16 = 0x0132;
+const EXIF_IFD_TAG: u16 = 0x8769;
+const GPS_IFD_TAG: u16 = 0x8825;
+const DATE_TIME_ORIGINAL_TAG: u16 = 0x9003;
+const GPS_LATITUDE_REF_TAG: u16 = 0x0001;
+const GPS_LATITUDE_TAG: u16 = 0x0002;
+const GPS_LONGITUDE_REF_TAG: u16 = 0x0003;
+const GPS_LONGITUDE_TAG: u16 = 0x0004;
+
+const ASCII_TYPE: u16 = 2;
+const SHORT_TYPE: u16 = 3;
+const LONG_TYPE: u16 = 4;
+const RATIONAL_TYPE: u16 = 5;
+
+/// The EXIF tags shown in image previews.
+#[derive(Debug, Default, PartialEq)]
+pub struct ExifMetadata {
+    pub camera: Option<String>,
+    pub date: Option<String>,
+    /// The latitude and longitude, in signed decimal degrees.
+    pub gps: Option<(f64, f64)>,
+}
+
+impl ExifMetadata {
+    /// The lines describing this metadata in a preview, tags that aren't
+    /// present are omitted.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(camera) = &self.camera {
+            lines.push(format!("Camera:     {camera}"));
+        }
+        if let Some(date) = &self.date {
+            lines.push(format!("Date:       {date}"));
+        }
+        if let Some((latitude, longitude)) = self.gps {
+            lines.push(format!("GPS:        {latitude:.6}, {longitude:.6}"));
+        }
+        lines
+    }
+}
+
+/// Read the EXIF metadata of a JPEG image from its header.
+///
+/// Returns `None` if the header isn't a JPEG one or if there is no EXIF
+/// segment before the image data.
+pub fn jpeg_exif(header: &[u8]) -> Option<ExifMetadata> {
+    if !header.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut i = 2;
+    while i + 4 <= header.len() {
+        if header[i] != 0xFF || header[i + 1] == START_OF_SCAN_MARKER {
+            return None;
+        }
+        let length =
+            usize::from(u16::from_be_bytes([header[i + 2], header[i + 3]]));
+        let segment = header.get(i + 4..i + 2 + length)?;
+        if header[i + 1] == APP1_MARKER {
+            if let Some(tiff) = segment.strip_prefix(EXIF_HEADER) {
+                return parse_tiff(tiff);
+            }
+        }
+        i += 2 + length;
+    }
+    None
+}
+
+/// Parse the TIFF structure embedded in an EXIF segment.
+fn parse_tiff(data: &[u8]) -> Option<ExifMetadata> {
+    let big_endian = match data.get(..4)? {
+        b"MM\0*" => true,
+        b"II*\0" => false,
+        _ => return None,
+    };
+    let tiff = Tiff { data, big_endian };
+    let ifd0 = tiff.ifd(tiff.u32(4)?)?;
+
+    let make = find(&ifd0, MAKE_TAG).and_then(|e| tiff.ascii(e));
+    let model = find(&ifd0, MODEL_TAG).and_then(|e| tiff.ascii(e));
+    let camera = match (make, model) {
+        // models usually repeat the make (e.g. "Canon" "Canon EOS 5D")
+        (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+        (Some(make), Some(model)) => Some(format!("{make} {model}")),
+        (make, model) => make.or(model),
+    };
+
+    let exif_ifd = tiff.sub_ifd(&ifd0, EXIF_IFD_TAG).unwrap_or_default();
+    let date = find(&exif_ifd, DATE_TIME_ORIGINAL_TAG)
+        .or_else(|| find(&ifd0, DATE_TIME_TAG))
+        .and_then(|e| tiff.ascii(e))
+        .map(|date| format_exif_date(&date));
+
+    let gps = tiff
+        .sub_ifd(&ifd0, GPS_IFD_TAG)
+        .and_then(|gps_ifd| tiff.gps(&gps_ifd));
+
+    Some(ExifMetadata { camera, date, gps })
+}
+
+/// EXIF dates look like `2023:06:15 14:30:00`, display them as
+/// `2023-06-15 14:30:00`.
+fn format_exif_date(date: &str) -> String {
+    match date.split_once(' ') {
+        Some((day, time)) => format!("{} {time}", day.replace(':', "-")),
+        None => date.replace(':', "-"),
+    }
+}
+
+fn find(entries: &[IfdEntry], tag: u16) -> Option<&IfdEntry> {
+    entries.iter().find(|entry| entry.tag == tag)
+}
+
+struct IfdEntry {
+    tag: u16,
+    kind: u16,
+    count: usize,
+    /// The offset of the entry's 4 bytes value (or value offset) field.
+    value_offset: usize,
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl Tiff<'_> {
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes = self.data.get(offset..offset + 2)?;
+        let bytes = [bytes[0], bytes[1]];
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Option<usize> {
+        let bytes = self.data.get(offset..offset + 4)?;
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        let value = if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        };
+        usize::try_from(value).ok()
+    }
+
+    fn ifd(&self, offset: usize) -> Option<Vec<IfdEntry>> {
+        let count = usize::from(self.u16(offset)?);
+        (0..count)
+            .map(|i| {
+                let start = offset + 2 + 12 * i;
+                Some(IfdEntry {
+                    tag: self.u16(start)?,
+                    kind: self.u16(start + 2)?,
+                    count: self.u32(start + 4)?,
+                    value_offset: start + 8,
+                })
+            })
+            .collect()
+    }
+
+    /// Follow the pointer stored in the given tag to another IFD.
+    fn sub_ifd(
+        &self,
+        entries: &[IfdEntry],
+        tag: u16,
+    ) -> Option<Vec<IfdEntry>> {
+        let entry = find(entries, tag)?;
+        let offset = match entry.kind {
+            SHORT_TYPE => usize::from(self.u16(entry.value_offset)?),
+            LONG_TYPE => self.u32(entry.value_offset)?,
+            _ => return None,
+        };
+        self.ifd(offset)
+    }
+
+    /// The raw bytes of an entry's value, which is stored inline when it
+    /// fits in 4 bytes.
+    fn value(&self, entry: &IfdEntry, item_size: usize) -> Option<&[u8]> {
+        let size = entry.count.checked_mul(item_size)?;
+        let start = if size <= 4 {
+            entry.value_offset
+        } else {
+            self.u32(entry.value_offset)?
+        };
+        self.data.get(start..start.checked_add(size)?)
+    }
+
+    fn ascii(&self, entry: &IfdEntry) -> Option<String> {
+        if entry.kind != ASCII_TYPE {
+            return None;
+        }
+        let value = String::from_utf8_lossy(self.value(entry, 1)?)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string();
+        (!value.is_empty()).then_some(value)
+    }
+
+    fn rationals(&self, entry: &IfdEntry) -> Option<Vec<f64>> {
+        if entry.kind != RATIONAL_TYPE {
+            return None;
+        }
+        let start = self.u32(entry.value_offset)?;
+        self.value(entry, 8)?;
+        (0..entry.count)
+            .map(|i| {
+                let numerator = self.u32(start + 8 * i)?;
+                let denominator = self.u32(start + 8 * i + 4)?;
+                #[allow(clippy::cast_precision_loss)]
+                (denominator != 0)
+                    .then(|| numerator as f64 / denominator as f64)
+            })
+            .collect()
+    }
+
+    fn gps(&self, entries: &[IfdEntry]) -> Option<(f64, f64)> {
+        let coordinate = |tag: u16, ref_tag: u16, negative_ref: &str| {
+            let degrees = match self.rationals(find(entries, tag)?)?[..] {
+                [degrees, minutes, seconds] => {
+                    degrees + minutes / 60.0 + seconds / 3600.0
+                }
+                _ => return None,
+            };
+            let reference = find(entries, ref_tag).and_then(|e| self.ascii(e));
+            Some(if reference.as_deref() == Some(negative_ref) {
+                -degrees
+            } else {
+                degrees
+            })
+        };
+        Some((
+            coordinate(GPS_LATITUDE_TAG, GPS_LATITUDE_REF_TAG, "S")?,
+            coordinate(GPS_LONGITUDE_TAG, GPS_LONGITUDE_REF_TAG, "W")?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &[u8] = include_bytes!("fixtures/exif.jpg");
+
+    #[test]
+    fn test_jpeg_exif() {
+        let exif = jpeg_exif(FIXTURE).unwrap();
+        assert_eq!(exif.camera.as_deref(), Some("Canon EOS 5D"));
+        // the original date is preferred over the modification date
+        assert_eq!(exif.date.as_deref(), Some("2023-06-15 14:30:00"));
+        let (latitude, longitude) = exif.gps.unwrap();
+        assert!((latitude - 48.858_25).abs() < 1e-6);
+        assert!((longitude + 2.294_5).abs() < 1e-6);
+        assert_eq!(
+            exif.lines(),
+            vec![
+                "Camera:     Canon EOS 5D",
+                "Date:       2023-06-15 14:30:00",
+                "GPS:        48.858250, -2.294500",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_little_endian_exif() {
+        let mut tiff = b"II*\0\x08\0\0\0".to_vec();
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&MAKE_TAG.to_le_bytes());
+        tiff.extend_from_slice(&ASCII_TYPE.to_le_bytes());
+        tiff.extend_from_slice(&4u32.to_le_bytes());
+        tiff.extend_from_slice(b"DJI\0");
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        assert_eq!(
+            parse_tiff(&tiff),
+            Some(ExifMetadata {
+                camera: Some(String::from("DJI")),
+                ..Default::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_jpeg_without_exif() {
+        let header = [
+            0xFF, 0xD8, // SOI
+            0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, // APP0
+            0xFF, 0xDA, 0x00, 0x02, // SOS
+        ];
+        assert_eq!(jpeg_exif(&header), None);
+        assert_eq!(jpeg_exif(b"\x89PNG\r\n\x1a\n"), None);
+    }
+
+    #[test]
+    fn test_truncated_exif() {
+        assert_eq!(jpeg_exif(&FIXTURE[..40]), None);
+        let tiff = &FIXTURE[12..];
+        assert_eq!(parse_tiff(&tiff[..120]).unwrap().gps, None);
+    }
+}
@@ -1,87 +1,555 @@
 use color_eyre::Result;
-//use image::{ImageReader, Rgb};
-//use ratatui_image::picker::Picker;
 use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read, Seek};
+use std::io::{self, BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Take};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+use syntect::highlighting::{Style, Theme};
+use syntect::parsing::SyntaxSet;
 
-use syntect::{
-    highlighting::{Theme, ThemeSet},
-    parsing::SyntaxSet,
-};
+use tokio::task::AbortHandle;
 use tracing::{debug, warn};
 
-use super::cache::PreviewCache;
+use super::archive::{
+    archive_listing, ArchiveFormat, DEFAULT_ARCHIVE_MAX_ENTRIES,
+};
+use super::cache::{PreviewCache, DEFAULT_PREVIEW_CACHE_SIZE};
+use super::exif::jpeg_exif;
+use super::git_blame::git_blame;
+use super::git_diff::{diff_to_lines, git_diff};
+use super::gzip::{
+    decompressed_path, has_gzip_magic, is_gzip_path, read_gzip_text,
+    GzipContent,
+};
+use super::highlighting::{LazySyntaxSet, LazyTheme};
+use super::image::{decode_image, DEFAULT_IMAGE_BACKGROUND};
+use super::markdown::{is_markdown_path, render_markdown};
+use super::persistent_cache::PersistentCache;
+use super::sqlite::{
+    is_sqlite_database, is_sqlite_path, sqlite_schema, SQLITE_MAX_TABLES,
+};
+use super::table::{table_preview_lines, TableFormat, DEFAULT_TABLE_MAX_ROWS};
 use crate::previewers::{meta, Preview, PreviewContent};
 use television_channels::entry;
+use television_utils::encoding::{
+    decode_to_utf8, detect_encoding, is_utf16, UTF_8,
+};
 use television_utils::files::FileType;
 use television_utils::files::{get_file_size, is_known_text_extension};
 use television_utils::strings::{
-    preprocess_line, proportion_of_printable_ascii_characters,
-    PRINTABLE_ASCII_THRESHOLD,
+    is_likely_binary, preprocess_line, preprocess_line_with_config,
+    ReplaceNonPrintableConfig, MAX_LINE_LENGTH, TAB_WIDTH,
 };
 use television_utils::syntax::{
-    self, load_highlighting_assets, HighlightingAssetsExt,
+    self, load_highlighting_assets, HighlightingAssetsExt, LineHighlighter,
+    TerminalBackground,
 };
 
+/// The decompressed content of a file, by entry name.
+type Decompressed = (String, Arc<[u8]>);
+
 #[derive(Debug, Default)]
 pub struct FilePreviewer {
     cache: Arc<Mutex<PreviewCache>>,
-    pub syntax_set: Arc<SyntaxSet>,
-    pub syntax_theme: Arc<Theme>,
-    //image_picker: Arc<Mutex<Picker>>,
+    /// Entries for which more lines are currently being loaded.
+    loading_more: Arc<Mutex<HashSet<String>>>,
+    /// The previews currently being computed in the background.
+    in_flight: Arc<Mutex<HashMap<String, InFlight>>>,
+    /// The resolved targets of the symlinks previewed so far.
+    symlink_targets: HashMap<String, PathBuf>,
+    /// The decompressed content of the last gzip file previewed partially,
+    /// by entry name, so that loading its remaining lines doesn't
+    /// decompress it again.
+    decompressed: Arc<Mutex<Option<Decompressed>>>,
+    /// The syntaxes and theme are only loaded once a text preview is first
+    /// computed, which keeps startup fast.
+    pub syntax_set: Arc<LazySyntaxSet>,
+    pub syntax_theme: Arc<LazyTheme>,
+    /// The name (or path) of the current syntax theme.
+    theme_name: String,
+    tab_width: usize,
+    max_line_length: usize,
+    pretty_print_json: bool,
+    hex_preview_max_bytes: usize,
+    archive_max_entries: usize,
+    prefer_git_diff: bool,
+    show_git_blame: bool,
+    table_max_rows: usize,
+    render_markdown: bool,
+    page_breaks: bool,
+    max_highlighted_line_length: usize,
+    /// Extensions of text files, on top of the built-in ones, normalized
+    /// with `normalize_extension`.
+    text_extensions: HashSet<String>,
+    persistent_cache: Option<Arc<PersistentCache>>,
+    preview_timeout: Duration,
+    render_images: bool,
+    image_background: [u8; 3],
+}
+
+/// A preview being computed in the background.
+#[derive(Debug)]
+struct InFlight {
+    handle: AbortHandle,
+    /// Tells the blocking work computing the preview to stop.
+    cancelled: Arc<AtomicBool>,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct FilePreviewerConfig {
     pub theme: String,
+    /// The theme used instead of `theme` on terminals with a light
+    /// background.
+    pub light_theme: Option<String>,
+    /// The background of the terminal, which decides between `theme` and
+    /// `light_theme`.
+    pub terminal_background: TerminalBackground,
+    /// The number of spaces to replace tab characters with.
+    pub tab_width: usize,
+    /// The maximum length (in bytes) of a line before it gets truncated.
+    pub max_line_length: usize,
+    /// The maximum number of previews to keep in cache.
+    pub cache_capacity: usize,
+    /// Whether to pretty-print JSON files before highlighting them.
+    pub pretty_print_json: bool,
+    /// The maximum number of bytes shown in hex dump previews.
+    pub hex_preview_max_bytes: usize,
+    /// The maximum number of entries listed in archive previews.
+    pub archive_max_entries: usize,
+    /// A directory containing additional `.sublime-syntax` definitions.
+    pub syntax_directory: Option<PathBuf>,
+    /// Whether to show the uncommitted changes of files tracked by git
+    /// instead of their content.
+    pub prefer_git_diff: bool,
+    /// Whether to show the commit and age of each line of files tracked by
+    /// git in the gutter of text previews.
+    pub show_git_blame: bool,
+    /// The maximum number of rows shown in previews of CSV/TSV files.
+    pub table_max_rows: usize,
+    /// Whether to render markdown files instead of highlighting their
+    /// source.
+    pub render_markdown: bool,
+    /// Whether to render form feeds as horizontal rules and vertical tabs as
+    /// line breaks in text previews (see
+    /// `ReplaceNonPrintableConfig::page_breaks`).
+    pub page_breaks: bool,
+    /// Files with lines longer than this (in bytes) are shown as plain text
+    /// since highlighting them can be very slow.
+    pub max_highlighted_line_length: usize,
+    /// Extensions of text files, on top of the built-in ones (e.g. `hcl` or
+    /// `conf.d`).
+    pub text_extensions: Vec<String>,
+    /// A directory in which highlighted previews are persisted across runs.
+    pub persistent_cache_directory: Option<PathBuf>,
+    /// How long to wait for a preview to be computed before giving up.
+    pub preview_timeout: Duration,
+    /// Whether to decode PNG and BMP images so that the UI can draw them,
+    /// instead of only describing them.
+    pub render_images: bool,
+    /// The RGB color transparent pixels of images are blended with.
+    pub image_background: [u8; 3],
+}
+
+impl Default for FilePreviewerConfig {
+    fn default() -> Self {
+        FilePreviewerConfig {
+            theme: String::new(),
+            light_theme: None,
+            terminal_background: TerminalBackground::default(),
+            tab_width: TAB_WIDTH,
+            max_line_length: MAX_LINE_LENGTH,
+            cache_capacity: DEFAULT_PREVIEW_CACHE_SIZE,
+            pretty_print_json: true,
+            hex_preview_max_bytes: DEFAULT_HEX_PREVIEW_MAX_BYTES,
+            archive_max_entries: DEFAULT_ARCHIVE_MAX_ENTRIES,
+            syntax_directory: None,
+            prefer_git_diff: false,
+            show_git_blame: false,
+            table_max_rows: DEFAULT_TABLE_MAX_ROWS,
+            render_markdown: true,
+            page_breaks: false,
+            max_highlighted_line_length: DEFAULT_MAX_HIGHLIGHTED_LINE_LENGTH,
+            text_extensions: Vec::new(),
+            persistent_cache_directory: None,
+            preview_timeout: DEFAULT_PREVIEW_TIMEOUT,
+            render_images: true,
+            image_background: DEFAULT_IMAGE_BACKGROUND,
+        }
+    }
 }
 
 impl FilePreviewerConfig {
     pub fn new(theme: String) -> Self {
-        FilePreviewerConfig { theme }
+        FilePreviewerConfig {
+            theme,
+            ..Default::default()
+        }
+    }
+
+    pub fn light_theme(mut self, light_theme: impl Into<String>) -> Self {
+        self.light_theme = Some(light_theme.into());
+        self
+    }
+
+    pub fn terminal_background(
+        mut self,
+        terminal_background: TerminalBackground,
+    ) -> Self {
+        self.terminal_background = terminal_background;
+        self
+    }
+
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = max_line_length;
+        self
+    }
+
+    pub fn cache_capacity(mut self, cache_capacity: usize) -> Self {
+        self.cache_capacity = cache_capacity;
+        self
+    }
+
+    pub fn pretty_print_json(mut self, pretty_print_json: bool) -> Self {
+        self.pretty_print_json = pretty_print_json;
+        self
+    }
+
+    pub fn hex_preview_max_bytes(
+        mut self,
+        hex_preview_max_bytes: usize,
+    ) -> Self {
+        self.hex_preview_max_bytes = hex_preview_max_bytes;
+        self
+    }
+
+    pub fn archive_max_entries(mut self, archive_max_entries: usize) -> Self {
+        self.archive_max_entries = archive_max_entries;
+        self
+    }
+
+    pub fn syntax_directory(
+        mut self,
+        syntax_directory: impl Into<PathBuf>,
+    ) -> Self {
+        self.syntax_directory = Some(syntax_directory.into());
+        self
+    }
+
+    pub fn prefer_git_diff(mut self, prefer_git_diff: bool) -> Self {
+        self.prefer_git_diff = prefer_git_diff;
+        self
+    }
+
+    pub fn show_git_blame(mut self, show_git_blame: bool) -> Self {
+        self.show_git_blame = show_git_blame;
+        self
+    }
+
+    pub fn table_max_rows(mut self, table_max_rows: usize) -> Self {
+        self.table_max_rows = table_max_rows;
+        self
+    }
+
+    pub fn render_markdown(mut self, render_markdown: bool) -> Self {
+        self.render_markdown = render_markdown;
+        self
+    }
+
+    pub fn page_breaks(mut self, page_breaks: bool) -> Self {
+        self.page_breaks = page_breaks;
+        self
+    }
+
+    pub fn max_highlighted_line_length(
+        mut self,
+        max_highlighted_line_length: usize,
+    ) -> Self {
+        self.max_highlighted_line_length = max_highlighted_line_length;
+        self
+    }
+
+    pub fn text_extensions(mut self, text_extensions: Vec<String>) -> Self {
+        self.text_extensions = text_extensions;
+        self
+    }
+
+    pub fn persistent_cache_directory(
+        mut self,
+        persistent_cache_directory: impl Into<PathBuf>,
+    ) -> Self {
+        self.persistent_cache_directory =
+            Some(persistent_cache_directory.into());
+        self
+    }
+
+    pub fn preview_timeout(mut self, preview_timeout: Duration) -> Self {
+        self.preview_timeout = preview_timeout;
+        self
+    }
+
+    pub fn render_images(mut self, render_images: bool) -> Self {
+        self.render_images = render_images;
+        self
+    }
+
+    pub fn image_background(mut self, image_background: [u8; 3]) -> Self {
+        self.image_background = image_background;
+        self
     }
 }
 
 impl FilePreviewer {
     pub fn new(config: Option<FilePreviewerConfig>) -> Self {
-        let hl_assets = load_highlighting_assets();
-        let syntax_set = hl_assets.get_syntax_set().unwrap().clone();
+        let syntax_set = LazySyntaxSet::new(
+            config.as_ref().and_then(|c| c.syntax_directory.clone()),
+        );
 
-        let theme = config.map_or_else(
-            || {
-                let theme_set = ThemeSet::load_defaults();
-                theme_set.themes["base16-ocean.dark"].clone()
+        let tab_width = config.as_ref().map_or(TAB_WIDTH, |c| c.tab_width);
+        let max_line_length = config
+            .as_ref()
+            .map_or(MAX_LINE_LENGTH, |c| c.max_line_length);
+        let cache_capacity = config
+            .as_ref()
+            .map_or(DEFAULT_PREVIEW_CACHE_SIZE, |c| c.cache_capacity);
+        let pretty_print_json =
+            config.as_ref().map_or(true, |c| c.pretty_print_json);
+        let hex_preview_max_bytes =
+            config.as_ref().map_or(DEFAULT_HEX_PREVIEW_MAX_BYTES, |c| {
+                c.hex_preview_max_bytes
+            });
+        let archive_max_entries = config
+            .as_ref()
+            .map_or(DEFAULT_ARCHIVE_MAX_ENTRIES, |c| c.archive_max_entries);
+        let prefer_git_diff =
+            config.as_ref().map_or(false, |c| c.prefer_git_diff);
+        let show_git_blame =
+            config.as_ref().map_or(false, |c| c.show_git_blame);
+        let table_max_rows = config
+            .as_ref()
+            .map_or(DEFAULT_TABLE_MAX_ROWS, |c| c.table_max_rows);
+        let render_markdown =
+            config.as_ref().map_or(true, |c| c.render_markdown);
+        let page_breaks = config.as_ref().map_or(false, |c| c.page_breaks);
+        let max_highlighted_line_length = config
+            .as_ref()
+            .map_or(DEFAULT_MAX_HIGHLIGHTED_LINE_LENGTH, |c| {
+                c.max_highlighted_line_length
+            });
+        let text_extensions = config.as_ref().map_or_else(HashSet::new, |c| {
+            c.text_extensions
+                .iter()
+                .map(|extension| normalize_extension(extension))
+                .collect()
+        });
+        let persistent_cache = config
+            .as_ref()
+            .and_then(|c| c.persistent_cache_directory.as_ref())
+            .map(|directory| Arc::new(PersistentCache::new(directory)));
+        let preview_timeout = config
+            .as_ref()
+            .map_or(DEFAULT_PREVIEW_TIMEOUT, |c| c.preview_timeout);
+        let render_images = config.as_ref().map_or(true, |c| c.render_images);
+        let image_background = config
+            .as_ref()
+            .map_or(DEFAULT_IMAGE_BACKGROUND, |c| c.image_background);
+        let (theme_name, theme) = config.map_or_else(
+            || (syntax::DEFAULT_THEME_NAME.to_string(), LazyTheme::new(None)),
+            |c| {
+                let theme = match (c.terminal_background, c.light_theme) {
+                    (TerminalBackground::Light, Some(light_theme)) => {
+                        light_theme
+                    }
+                    _ => c.theme,
+                };
+                (theme.clone(), LazyTheme::new(Some(theme)))
             },
-            |c| hl_assets.get_theme_no_output(&c.theme).clone(),
         );
-        //info!("getting image picker");
-        //let image_picker = get_image_picker();
-        //info!("got image picker");
-
         FilePreviewer {
-            cache: Arc::new(Mutex::new(PreviewCache::default())),
+            cache: Arc::new(Mutex::new(PreviewCache::new(cache_capacity))),
+            loading_more: Arc::new(Mutex::new(HashSet::new())),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            symlink_targets: HashMap::new(),
+            decompressed: Arc::new(Mutex::new(None)),
             syntax_set: Arc::new(syntax_set),
             syntax_theme: Arc::new(theme),
-            //image_picker: Arc::new(Mutex::new(image_picker)),
+            theme_name,
+            tab_width,
+            max_line_length,
+            pretty_print_json,
+            hex_preview_max_bytes,
+            archive_max_entries,
+            prefer_git_diff,
+            show_git_blame,
+            table_max_rows,
+            render_markdown,
+            page_breaks,
+            max_highlighted_line_length,
+            text_extensions,
+            persistent_cache,
+            preview_timeout,
+            render_images,
+            image_background,
+        }
+    }
+
+    /// Switch to the next bundled syntax theme and return its name.
+    ///
+    /// Cached previews are dropped so that they get highlighted again using
+    /// the new theme.
+    pub fn cycle_theme(&mut self) -> &str {
+        let hl_assets = load_highlighting_assets();
+        let theme_names: Vec<&str> = hl_assets.themes().collect();
+        if theme_names.is_empty() {
+            return &self.theme_name;
+        }
+        let next = theme_names
+            .iter()
+            .position(|name| *name == self.theme_name)
+            .map_or(0, |i| (i + 1) % theme_names.len());
+        self.theme_name = theme_names[next].to_string();
+        self.syntax_theme = Arc::new(LazyTheme::from(
+            hl_assets.get_theme_no_output(&self.theme_name).clone(),
+        ));
+        self.cache.lock().clear();
+        &self.theme_name
+    }
+
+    /// Set the maximum length (in bytes) of the lines of text previews.
+    ///
+    /// Cached previews are dropped when it changes since their lines were
+    /// truncated at the previous length.
+    pub fn set_max_line_length(&mut self, max_line_length: usize) {
+        if max_line_length != self.max_line_length {
+            self.max_line_length = max_line_length;
+            self.cache.lock().clear();
         }
     }
 
+    /// The background color of the current syntax theme, if it has one.
+    pub fn theme_background(&self) -> Option<syntect::highlighting::Color> {
+        self.syntax_theme.get().settings.background
+    }
+
     /// Get a preview for a file entry.
     ///
-    /// # Panics
-    /// Panics if seeking to the start of the file fails.
+    /// Symlinks are followed and the previews of their targets are titled
+    /// `link -> target`.
     pub async fn preview(&mut self, entry: &entry::Entry) -> Arc<Preview> {
+        let preview = self.preview_file(entry).await;
+        let Some(target) = self.symlink_targets.get(&entry.name) else {
+            return preview;
+        };
+        let title = format!("{} -> {}", entry.name, target.display());
+        if preview.title == title {
+            return preview;
+        }
+        let retitled = Arc::new(Preview {
+            title,
+            ..(*preview).clone()
+        });
+        let mut cache = self.cache.lock();
+        // the cached preview may have been replaced by a background
+        // computation in the meantime
+        if cache
+            .get(&entry.name)
+            .map_or(false, |cached| Arc::ptr_eq(&cached, &preview))
+        {
+            cache.insert(entry.name.clone(), retitled.clone());
+        }
+        retitled
+    }
+
+    async fn preview_file(&mut self, entry: &entry::Entry) -> Arc<Preview> {
         let path_buf = PathBuf::from(&entry.name);
 
         // do we have a preview in cache for that entry?
         if let Some(preview) = self.cache.lock().get(&entry.name) {
-            return preview.clone();
+            // loading previews whose computation was aborted are recomputed
+            if !matches!(preview.content, PreviewContent::Loading)
+                || self.in_flight.lock().contains_key(&entry.name)
+            {
+                return preview.clone();
+            }
         }
         debug!("No preview in cache for {:?}", entry.name);
 
+        if let Some(target) = symlink_target(&path_buf) {
+            if !path_buf.exists() {
+                debug!("Broken symlink: {:?} -> {:?}", entry.name, target);
+                let preview = meta::broken_symlink(&entry.name, &target);
+                self.cache_preview(entry.name.clone(), preview.clone())
+                    .await;
+                return preview;
+            }
+            let target = std::fs::canonicalize(&path_buf).unwrap_or(target);
+            self.symlink_targets.insert(entry.name.clone(), target);
+        }
+
+        // opening a fifo blocks until something writes to it and devices
+        // can be read forever, so special files are never opened
+        if let Some(kind) = special_file_kind(&path_buf) {
+            debug!("Special file ({}): {:?}", kind, entry.name);
+            let preview = meta::special_file(&entry.name, kind);
+            self.cache_preview(entry.name.clone(), preview.clone())
+                .await;
+            return preview;
+        }
+
+        // archives are listed without being extracted, but compressed ones
+        // still have to be decompressed to be listed, which is done in the
+        // background
+        if let Some(format) = ArchiveFormat::from_path(&path_buf) {
+            debug!("Previewing archive: {:?}", entry.name);
+            let preview = meta::loading(&entry.name);
+            self.cache_preview(entry.name.clone(), preview.clone())
+                .await;
+            self.compute_archive_preview(entry, format);
+            return preview;
+        }
+
+        // only the first rows of tables are read so their size doesn't
+        // matter either
+        if let Some(format) = TableFormat::from_path(&path_buf) {
+            debug!("Previewing table: {:?}", entry.name);
+            let preview = meta::loading(&entry.name);
+            self.cache_preview(entry.name.clone(), preview.clone())
+                .await;
+            self.compute_table_preview(entry, format);
+            return preview;
+        }
+
+        // only the schema of databases is read, in the background since
+        // counting the rows of their tables can take a while. If the file
+        // turns out not to be a database, it gets previewed like any other
+        // file
+        if is_sqlite_path(&path_buf) && is_sqlite_database(&path_buf) {
+            debug!("Previewing database schema: {:?}", entry.name);
+            let preview = meta::loading(&entry.name);
+            self.cache_preview(entry.name.clone(), preview.clone())
+                .await;
+            self.compute_sqlite_preview(entry);
+            return preview;
+        }
+
+        // gzip files are decompressed in the background and checked against
+        // their decompressed size
+        if is_gzip_path(&path_buf) && has_gzip_magic(&path_buf) {
+            debug!("Previewing gzip file: {:?}", entry.name);
+            let preview = meta::loading(&entry.name);
+            self.cache_preview(entry.name.clone(), preview.clone())
+                .await;
+            self.compute_gzip_preview(entry, PARTIAL_PREVIEW_LINES);
+            return preview;
+        }
+
         // check file size
         if get_file_size(&path_buf).map_or(false, |s| s > Self::MAX_FILE_SIZE)
         {
@@ -95,9 +563,21 @@ impl FilePreviewer {
         // try to determine file type
         debug!("Computing preview for {:?}", entry.name);
         match self.get_file_type(&path_buf) {
+            FileType::Text
+                if self.render_markdown && is_markdown_path(&path_buf) =>
+            {
+                let preview = meta::loading(&entry.name);
+                self.cache_preview(entry.name.clone(), preview.clone())
+                    .await;
+                self.compute_markdown_preview(entry);
+                preview
+            }
             FileType::Text => {
-                match File::open(&path_buf) {
-                    Ok(file) => {
+                match File::open(&path_buf)
+                    .map_err(TextReaderError::Io)
+                    .and_then(|file| bounded_reader(file, Self::MAX_FILE_SIZE))
+                {
+                    Ok(reader) => {
                         // insert a loading preview into the cache
                         let preview = meta::loading(&entry.name);
                         self.cache_preview(
@@ -107,14 +587,26 @@ impl FilePreviewer {
                         .await;
 
                         // compute the highlighted version in the background
-                        let mut reader = BufReader::new(file);
-                        reader.seek(std::io::SeekFrom::Start(0)).unwrap();
-                        self.compute_highlighted_text_preview(entry, reader)
-                            .await;
+                        let highlight = self.highlighted_text_task(
+                            entry,
+                            path_buf,
+                            PARTIAL_PREVIEW_LINES,
+                        );
+                        self.spawn_preview_or_git_diff(
+                            entry,
+                            move |cancelled| highlight(reader, cancelled),
+                        );
                         preview
                     }
-                    Err(e) => {
-                        warn!("Error opening file: {:?}", e);
+                    Err(TextReaderError::TooLarge) => {
+                        debug!("File grew too large: {:?}", entry.name);
+                        let p = meta::file_too_large(&entry.name);
+                        self.cache_preview(entry.name.clone(), p.clone())
+                            .await;
+                        p
+                    }
+                    Err(TextReaderError::Io(e)) => {
+                        warn!("Error reading file: {:?}", e);
                         let p = meta::not_supported(&entry.name);
                         self.cache_preview(entry.name.clone(), p.clone())
                             .await;
@@ -122,27 +614,51 @@ impl FilePreviewer {
                     }
                 }
             }
+            FileType::Archive => {
+                let format = ArchiveFormat::from_path(&path_buf);
+                let preview = format.map_or_else(
+                    || meta::not_supported(&entry.name),
+                    |_| meta::loading(&entry.name),
+                );
+                self.cache_preview(entry.name.clone(), preview.clone())
+                    .await;
+                if let Some(format) = format {
+                    self.compute_archive_preview(entry, format);
+                }
+                preview
+            }
+            FileType::Directory => {
+                debug!("Previewing directory: {:?}", entry.name);
+                let preview =
+                    directory_listing_preview(&entry.name, &path_buf);
+                self.cache_preview(entry.name.clone(), preview.clone())
+                    .await;
+                preview
+            }
             FileType::Image => {
                 debug!("Previewing image file: {:?}", entry.name);
-                // insert a loading preview into the cache
-                //let preview = loading(&entry.name);
-                let preview = meta::not_supported(&entry.name);
+                let preview = if self.render_images {
+                    meta::loading(&entry.name)
+                } else {
+                    image_metadata_preview(&entry.name, &path_buf)
+                };
                 self.cache_preview(entry.name.clone(), preview.clone())
                     .await;
-                //// compute the image preview in the background
-                //self.compute_image_preview(entry).await;
+                if self.render_images {
+                    self.compute_image_preview(entry);
+                }
                 preview
             }
             FileType::Other => {
                 debug!("Previewing other file: {:?}", entry.name);
-                let preview = meta::not_supported(&entry.name);
+                let preview = self.compute_hex_preview(entry);
                 self.cache_preview(entry.name.clone(), preview.clone())
                     .await;
                 preview
             }
             FileType::Unknown => {
                 debug!("Unknown file type: {:?}", entry.name);
-                let preview = meta::not_supported(&entry.name);
+                let preview = self.compute_hex_preview(entry);
                 self.cache_preview(entry.name.clone(), preview.clone())
                     .await;
                 preview
@@ -150,87 +666,525 @@ impl FilePreviewer {
         }
     }
 
-    //async fn compute_image_preview(&self, entry: &entry::Entry) {
-    //    let cache = self.cache.clone();
-    //    let picker = self.image_picker.clone();
-    //    let entry_c = entry.clone();
-    //    tokio::spawn(async move {
-    //        info!("Loading image: {:?}", entry_c.name);
-    //        if let Ok(dyn_image) =
-    //            ImageReader::open(entry_c.name.clone()).unwrap().decode()
-    //        {
-    //            let image = picker.lock().await.new_resize_protocol(dyn_image);
-    //            let preview = Arc::new(Preview::new(
-    //                entry_c.name.clone(),
-    //                PreviewContent::Image(image),
-    //            ));
-    //            cache
-    //                .lock()
-    //                .await
-    //                .insert(entry_c.name.clone(), preview.clone());
-    //        }
-    //    });
-    //}
-
-    async fn compute_highlighted_text_preview(
+    /// Decode an image in the background and store it in the cache.
+    ///
+    /// Images that can't be decoded (unsupported formats, corrupted files)
+    /// are described by a metadata preview instead.
+    fn compute_image_preview(&self, entry: &entry::Entry) {
+        let name = entry.name.clone();
+        let background = self.image_background;
+        self.spawn_preview(entry, move |cancelled| {
+            let path = Path::new(&name);
+            match File::open(path)
+                .and_then(|file| {
+                    read_unless_cancelled(
+                        file.take(Self::MAX_FILE_SIZE),
+                        cancelled,
+                    )
+                })
+                .and_then(|bytes| decode_image(&bytes, background, cancelled))
+            {
+                Ok(image) => Arc::new(Preview::new(
+                    name.clone(),
+                    PreviewContent::Image(Arc::new(image)),
+                )),
+                Err(e) => {
+                    debug!("Error decoding image {:?}: {:?}", name, e);
+                    image_metadata_preview(&name, path)
+                }
+            }
+        });
+    }
+
+    /// Build a hex dump preview of the first `hex_preview_max_bytes` bytes of
+    /// the file.
+    fn compute_hex_preview(&self, entry: &entry::Entry) -> Arc<Preview> {
+        hex_dump_preview(&entry.name, self.hex_preview_max_bytes)
+    }
+
+    /// Decompress a gzip file in the background and store the preview of
+    /// its content in the cache.
+    ///
+    /// Text is highlighted using the syntax of the decompressed file name,
+    /// anything else is previewed as a hex dump of the compressed file. The
+    /// content of partial previews is kept for `load_more`.
+    fn compute_gzip_preview(&self, entry: &entry::Entry, max_lines: usize) {
+        let name = entry.name.clone();
+        let path = PathBuf::from(&name);
+        let highlight = self.highlighted_text_task(
+            entry,
+            decompressed_path(&path),
+            max_lines,
+        );
+        let decompressed = self.decompressed.clone();
+        let hex_preview_max_bytes = self.hex_preview_max_bytes;
+        self.spawn_preview(entry, move |cancelled| {
+            let kept = decompressed
+                .lock()
+                .take()
+                .filter(|(kept_name, _)| *kept_name == name);
+            let content = if let Some((_, content)) = kept {
+                debug!("Reusing the decompressed content of {:?}", name);
+                content
+            } else {
+                match read_gzip_text(&path, Self::MAX_FILE_SIZE, cancelled) {
+                    Ok(GzipContent::Text(content)) => Arc::from(content),
+                    Ok(GzipContent::TooLarge) => {
+                        debug!("Decompressed file too large: {:?}", name);
+                        return meta::file_too_large(&name);
+                    }
+                    Ok(GzipContent::Binary) => {
+                        debug!("Gzip file isn't text: {:?}", name);
+                        return hex_dump_preview(&name, hex_preview_max_bytes);
+                    }
+                    Err(e) => {
+                        debug!("Error decompressing {:?}: {:?}", name, e);
+                        return hex_dump_preview(&name, hex_preview_max_bytes);
+                    }
+                }
+            };
+            let preview = highlight(Cursor::new(content.clone()), cancelled);
+            if preview.partial {
+                *decompressed.lock() = Some((name, content));
+            }
+            preview
+        });
+    }
+
+    /// List the contents of an archive in the background and store the
+    /// resulting preview in the cache.
+    fn compute_archive_preview(
+        &self,
+        entry: &entry::Entry,
+        format: ArchiveFormat,
+    ) {
+        let name = entry.name.clone();
+        let max_entries = self.archive_max_entries;
+        self.spawn_preview(entry, move |cancelled| {
+            match archive_listing(
+                Path::new(&name),
+                format,
+                max_entries,
+                cancelled,
+            ) {
+                Ok(lines) => Arc::new(Preview::new(
+                    name,
+                    PreviewContent::PlainText(lines),
+                )),
+                Err(e) => {
+                    warn!("Error reading archive: {:?}", e);
+                    meta::not_supported(&name)
+                }
+            }
+        });
+    }
+
+    /// List the tables of a SQLite database in the background and store the
+    /// resulting preview in the cache.
+    fn compute_sqlite_preview(&self, entry: &entry::Entry) {
+        let name = entry.name.clone();
+        self.spawn_preview(entry, move |cancelled| {
+            match sqlite_schema(Path::new(&name), SQLITE_MAX_TABLES, cancelled)
+            {
+                Ok(lines) => Arc::new(Preview::new(
+                    name,
+                    PreviewContent::PlainText(lines),
+                )),
+                Err(e) => {
+                    warn!("Error reading database: {:?}", e);
+                    meta::not_supported(&name)
+                }
+            }
+        });
+    }
+
+    /// Render a markdown file in the background and store the resulting
+    /// preview in the cache.
+    fn compute_markdown_preview(&self, entry: &entry::Entry) {
+        let name = entry.name.clone();
+        let tab_width = self.tab_width;
+        self.spawn_preview_or_git_diff(entry, move |cancelled| {
+            match File::open(&name)
+                .and_then(|file| read_unless_cancelled(file, cancelled))
+            {
+                Ok(bytes) => Arc::new(Preview::new(
+                    name,
+                    PreviewContent::AnsiText(render_markdown(
+                        &decode_to_utf8(&bytes),
+                        tab_width,
+                    )),
+                )),
+                Err(e) => {
+                    warn!("Error reading file: {:?}", e);
+                    meta::not_supported(&name)
+                }
+            }
+        });
+    }
+
+    /// Render the first rows of a table in the background and store the
+    /// resulting preview in the cache.
+    fn compute_table_preview(
         &self,
         entry: &entry::Entry,
-        reader: BufReader<File>,
+        format: TableFormat,
     ) {
+        let name = entry.name.clone();
+        let max_rows = self.table_max_rows;
+        self.spawn_preview(entry, move |cancelled| match File::open(&name) {
+            Ok(file) => Arc::new(Preview::new(
+                name,
+                PreviewContent::PlainText(table_preview_lines(
+                    BufReader::new(file),
+                    format,
+                    max_rows,
+                    cancelled,
+                )),
+            )),
+            Err(e) => {
+                warn!("Error opening file: {:?}", e);
+                meta::not_supported(&name)
+            }
+        });
+    }
+
+    /// Load the remaining lines of a partial preview.
+    ///
+    /// They are loaded in one go rather than a chunk at a time: extending a
+    /// preview means reading and highlighting it from the start again, since
+    /// the state of the highlighting can't be kept around, and text previews
+    /// are bounded by `MAX_FILE_SIZE` anyway.
+    ///
+    /// This is a no-op if the entry's preview isn't cached, isn't partial, or
+    /// if more lines are already being loaded.
+    pub async fn load_more(&mut self, entry: &entry::Entry) {
+        let Some(preview) = self.cache.lock().get(&entry.name) else {
+            return;
+        };
+        if !preview.partial
+            || !self.loading_more.lock().insert(entry.name.clone())
+        {
+            return;
+        }
+        debug!("Loading the remaining lines of {:?}", entry.name);
+        let max_lines = usize::MAX;
+        let path = PathBuf::from(&entry.name);
+        if is_gzip_path(&path) {
+            self.compute_gzip_preview(entry, max_lines);
+            return;
+        }
+        match File::open(&path)
+            .map_err(TextReaderError::Io)
+            .and_then(|file| bounded_reader(file, Self::MAX_FILE_SIZE))
+        {
+            Ok(reader) => {
+                self.compute_highlighted_text_preview(
+                    entry, reader, path, max_lines,
+                )
+                .await;
+            }
+            Err(e) => {
+                warn!("Error reading file: {:?}", e);
+                self.loading_more.lock().remove(&entry.name);
+            }
+        }
+    }
+
+    /// Highlight the lines of `reader` in the background and store the
+    /// resulting preview in the cache.
+    ///
+    /// `syntax_path` is the path used to pick the syntax of the lines, it
+    /// differs from the entry's path when previewing decompressed content.
+    async fn compute_highlighted_text_preview<R>(
+        &self,
+        entry: &entry::Entry,
+        reader: R,
+        syntax_path: PathBuf,
+        max_lines: usize,
+    ) where
+        R: BufRead + Send + 'static,
+    {
+        let highlight =
+            self.highlighted_text_task(entry, syntax_path, max_lines);
+        self.spawn_preview(entry, move |cancelled| {
+            highlight(reader, cancelled)
+        });
+    }
+
+    /// Build the blocking work highlighting the lines of a reader for the
+    /// preview of `entry`, see `compute_highlighted_text_preview`.
+    fn highlighted_text_task<R>(
+        &self,
+        entry: &entry::Entry,
+        syntax_path: PathBuf,
+        max_lines: usize,
+    ) -> impl FnOnce(R, &AtomicBool) -> Arc<Preview> + Send + 'static
+    where
+        R: BufRead + Send + 'static,
+    {
         let cache = self.cache.clone();
+        let persistent_cache = self.persistent_cache.clone();
         let syntax_set = self.syntax_set.clone();
         let syntax_theme = self.syntax_theme.clone();
-        let entry_c = entry.clone();
-        tokio::spawn(async move {
-            debug!(
-                "Computing highlights in the background for {:?}",
-                entry_c.name
-            );
-            let lines: Vec<String> = reader
-                .lines()
-                .map_while(Result::ok)
-                // we need to add a newline here because sublime syntaxes expect one
-                // to be present at the end of each line
-                .map(|line| preprocess_line(&line) + "\n")
-                .collect();
-
-            match syntax::compute_highlights_for_path(
-                &PathBuf::from(&entry_c.name),
-                lines,
-                &syntax_set,
-                &syntax_theme,
+        let tab_width = self.tab_width;
+        let page_breaks = self.page_breaks;
+        let max_line_length = self.max_line_length;
+        let max_highlighted_line_length = self.max_highlighted_line_length;
+        let pretty_print_json =
+            self.pretty_print_json && is_json_path(&syntax_path);
+        // pretty-printed, decompressed and page broken lines don't match the
+        // lines of the file
+        let show_git_blame = self.show_git_blame
+            && !pretty_print_json
+            && !page_breaks
+            && syntax_path == Path::new(&entry.name);
+        // everything that affects the highlighted lines besides the file
+        let settings = format!(
+            "{}:{}:{}:{}:{}:{}",
+            self.theme_name,
+            tab_width,
+            max_line_length,
+            pretty_print_json,
+            max_highlighted_line_length,
+            page_breaks
+        );
+        let name = entry.name.clone();
+        move |mut reader: R, cancelled: &AtomicBool| {
+            let path = PathBuf::from(&name);
+            let blame = if show_git_blame {
+                // reuse the blame of previously loaded lines, if any
+                let cached_blame = cache
+                    .lock()
+                    .get(&name)
+                    .and_then(|preview| preview.blame.clone());
+                cached_blame.or_else(|| git_blame(&path))
+            } else {
+                None
+            };
+
+            if let Some(cached) = persistent_cache
+                .as_ref()
+                .and_then(|c| c.get(&path, &settings))
+                .filter(|c| !c.partial || c.lines.len() >= max_lines)
+            {
+                debug!("Using persisted highlights for {:?}", name);
+                return Arc::new(
+                    Preview::new(
+                        name,
+                        PreviewContent::SyntectHighlightedText(cached.lines),
+                    )
+                    .with_partial(cached.partial)
+                    .with_blame(blame),
+                );
+            }
+
+            debug!("Computing highlights in the background for {:?}", name);
+            let encoding = reader.fill_buf().map_or(UTF_8, detect_encoding);
+            let (lines, partial, longest_line) =
+                if pretty_print_json || encoding != UTF_8 {
+                    debug!("Decoding {:?} from {}", name, encoding.name());
+                    let mut raw = Vec::new();
+                    if let Err(e) = reader.read_to_end(&mut raw) {
+                        warn!("Error reading file: {:?}", e);
+                    }
+                    let mut content = decode_to_utf8(&raw);
+                    if pretty_print_json {
+                        content =
+                            pretty_print_json_str(&content).unwrap_or(content);
+                    }
+                    read_preview_lines(
+                        Cursor::new(content),
+                        max_lines,
+                        tab_width,
+                        page_breaks,
+                        max_line_length,
+                        cancelled,
+                    )
+                } else {
+                    read_preview_lines(
+                        reader,
+                        max_lines,
+                        tab_width,
+                        page_breaks,
+                        max_line_length,
+                        cancelled,
+                    )
+                };
+
+            let syntax_set = syntax_set.get();
+            let syntax = if longest_line > max_highlighted_line_length {
+                debug!(
+                    "Lines of {:?} are too long, not highlighting them",
+                    name
+                );
+                syntax_set.find_syntax_plain_text()
+            } else {
+                syntax::find_syntax_for_path(
+                    &syntax_path,
+                    lines.first().map(String::as_str),
+                    syntax_set,
+                )
+            };
+            match highlight_preview_lines(
+                &mut LineHighlighter::new(syntax, syntax_theme.get()),
+                &lines,
+                syntax_set,
+                syntax_theme.get(),
+                cancelled,
             ) {
                 Ok(highlighted_lines) => {
-                    debug!(
-                        "Successfully computed highlights for {:?}",
-                        entry_c.name
-                    );
-                    cache.lock().insert(
-                        entry_c.name.clone(),
-                        Arc::new(Preview::new(
-                            entry_c.name,
+                    debug!("Successfully computed highlights for {:?}", name);
+                    // the lines of cancelled previews may be incomplete
+                    if !cancelled.load(Ordering::Relaxed) {
+                        if let Some(persistent_cache) = &persistent_cache {
+                            persistent_cache.insert(
+                                &path,
+                                &settings,
+                                &highlighted_lines,
+                                partial,
+                            );
+                        }
+                    }
+                    Arc::new(
+                        Preview::new(
+                            name,
                             PreviewContent::SyntectHighlightedText(
                                 highlighted_lines,
                             ),
-                        )),
-                    );
-                    debug!("Inserted highlighted preview into cache");
+                        )
+                        .with_partial(partial)
+                        .with_blame(blame),
+                    )
                 }
                 Err(e) => {
                     warn!("Error computing highlights: {:?}", e);
-                    let preview = meta::not_supported(&entry_c.name);
-                    cache.lock().insert(entry_c.name.clone(), preview);
+                    meta::not_supported(&name)
                 }
-            };
+            }
+        }
+    }
+
+    /// Like `spawn_preview`, but previewing the uncommitted changes made to
+    /// the file instead when `prefer_git_diff` is set and it has some.
+    fn spawn_preview_or_git_diff<F>(&self, entry: &entry::Entry, compute: F)
+    where
+        F: FnOnce(&AtomicBool) -> Arc<Preview> + Send + 'static,
+    {
+        if !self.prefer_git_diff {
+            return self.spawn_preview(entry, compute);
+        }
+        let name = entry.name.clone();
+        let tab_width = self.tab_width;
+        self.spawn_preview(entry, move |cancelled| {
+            match git_diff(Path::new(&name), cancelled) {
+                Some(diff) => {
+                    debug!("Previewing git diff: {:?}", name);
+                    Arc::new(Preview::new(
+                        name,
+                        PreviewContent::AnsiText(diff_to_lines(
+                            &diff, tab_width,
+                        )),
+                    ))
+                }
+                None => compute(cancelled),
+            }
         });
     }
 
+    /// Compute the preview of `entry` on a blocking thread and store it in
+    /// the cache.
+    ///
+    /// Any preview still being computed for another entry is aborted: the
+    /// user has moved on and its result isn't needed anymore. Previews taking
+    /// longer than `preview_timeout` are replaced with a "timed out" preview.
+    /// Blocking work can't be interrupted, so `compute` is given a flag that
+    /// gets set in both cases and which it should check regularly to stop
+    /// early. Whatever it returns once cancelled is discarded.
+    fn spawn_preview<F>(&self, entry: &entry::Entry, compute: F)
+    where
+        F: FnOnce(&AtomicBool) -> Arc<Preview> + Send + 'static,
+    {
+        let cache = self.cache.clone();
+        let loading_more = self.loading_more.clone();
+        let in_flight = self.in_flight.clone();
+        let preview_timeout = self.preview_timeout;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.abort_in_flight_previews();
+        let name = entry.name.clone();
+        let cancelled_c = cancelled.clone();
+        let handle = tokio::spawn(async move {
+            let cancelled_w = cancelled_c.clone();
+            // running the work on a dedicated thread lets the timeout fire
+            // even if it never yields
+            let work =
+                tokio::task::spawn_blocking(move || compute(&cancelled_w));
+            match tokio::time::timeout(preview_timeout, work).await {
+                Ok(Ok(preview)) => {
+                    if !cancelled_c.load(Ordering::Relaxed) {
+                        cache.lock().insert(name.clone(), preview);
+                        debug!("Inserted preview of {:?} into cache", name);
+                    }
+                }
+                Ok(Err(e)) => {
+                    warn!("Error computing preview: {:?}", e);
+                    cache
+                        .lock()
+                        .insert(name.clone(), meta::not_supported(&name));
+                }
+                Err(_) => {
+                    warn!("Preview of {:?} timed out", name);
+                    cancelled_c.store(true, Ordering::Relaxed);
+                    let mut cache = cache.lock();
+                    // keep the lines that were already loaded, if any
+                    if cache.get(&name).map_or(true, |p| {
+                        matches!(p.content, PreviewContent::Loading)
+                    }) {
+                        cache.insert(name.clone(), meta::timed_out(&name));
+                    }
+                }
+            }
+            loading_more.lock().remove(&name);
+            let mut in_flight = in_flight.lock();
+            if in_flight.get(&name).map_or(false, |in_flight| {
+                in_flight.handle.id() == tokio::task::id()
+            }) {
+                in_flight.remove(&name);
+            }
+        });
+        self.in_flight.lock().insert(
+            entry.name.clone(),
+            InFlight {
+                handle: handle.abort_handle(),
+                cancelled,
+            },
+        );
+    }
+
+    /// Abort the previews currently being computed.
+    ///
+    /// Their entries are left with a loading preview, which gets computed
+    /// again the next time they are previewed.
+    fn abort_in_flight_previews(&self) {
+        for (name, in_flight) in self.in_flight.lock().drain() {
+            debug!("Aborting the preview of {:?}", name);
+            in_flight.cancelled.store(true, Ordering::Relaxed);
+            in_flight.handle.abort();
+            self.loading_more.lock().remove(&name);
+        }
+    }
+
     /// The maximum file size that we will try to preview.
     /// 4 MB
     const MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
 
     fn get_file_type(&self, path: &Path) -> FileType {
         debug!("Getting file type for {:?}", path);
+        if path.is_dir() {
+            return FileType::Directory;
+        }
+        if ArchiveFormat::from_path(path).is_some() {
+            return FileType::Archive;
+        }
         let mut file_type = match infer::get_from_path(path) {
             Ok(Some(t)) => {
                 let mime_type = t.mime_type();
@@ -247,15 +1201,15 @@ impl FilePreviewer {
 
         // if the file type is unknown, try to determine it from the extension or the content
         if matches!(file_type, FileType::Unknown) {
-            if is_known_text_extension(path) {
+            if self.is_text_extension(path) {
                 file_type = FileType::Text;
             } else if let Ok(mut f) = File::open(path) {
                 let mut buffer = [0u8; 256];
                 if let Ok(bytes_read) = f.read(&mut buffer) {
                     if bytes_read > 0
-                        && proportion_of_printable_ascii_characters(
-                            &buffer[..bytes_read],
-                        ) > PRINTABLE_ASCII_THRESHOLD
+                        && (buffer.starts_with(b"#!")
+                            || is_utf16(&buffer[..bytes_read])
+                            || !is_likely_binary(&buffer[..bytes_read]))
                     {
                         file_type = FileType::Text;
                     }
@@ -267,33 +1221,206 @@ impl FilePreviewer {
         file_type
     }
 
+    /// Whether the extension of `path` is one of the built-in or configured
+    /// extensions of text files.
+    fn is_text_extension(&self, path: &Path) -> bool {
+        if is_known_text_extension(path) {
+            return true;
+        }
+        let Some(file_name) = path.file_name() else {
+            return false;
+        };
+        let file_name = file_name.to_string_lossy().to_lowercase();
+        // extensions can have several components (e.g. `conf.d`)
+        self.text_extensions.iter().any(|extension| {
+            file_name
+                .strip_suffix(extension.as_str())
+                .is_some_and(|stem| stem.len() > 1 && stem.ends_with('.'))
+        })
+    }
+
     async fn cache_preview(&mut self, key: String, preview: Arc<Preview>) {
         self.cache.lock().insert(key, preview);
     }
 }
 
-//fn get_image_picker() -> Picker {
-//    let mut picker = match Picker::from_termios() {
-//        Ok(p) => p,
-//        Err(_) => Picker::new((7, 14)),
-//    };
-//    picker.guess_protocol();
-//    picker.background_color = Some(Rgb::<u8>([255, 0, 255]));
-//    picker
-//}
+/// Why a text file can't be read for its preview.
+#[derive(Debug)]
+enum TextReaderError {
+    /// The file grew larger than allowed since its size was checked.
+    TooLarge,
+    Io(io::Error),
+}
 
-/// This should be enough to most standard terminal sizes
-const TEMP_PLAIN_TEXT_PREVIEW_HEIGHT: usize = 200;
+/// Get a reader over the whole of `file`, which can't read past `max_size`
+/// bytes.
+///
+/// The size is checked again since the file may have grown since it was
+/// first checked, and files that can't be sought (e.g. pipes) are refused
+/// rather than read indefinitely.
+fn bounded_reader<R: Read + Seek>(
+    mut file: R,
+    max_size: u64,
+) -> Result<BufReader<Take<R>>, TextReaderError> {
+    let size = file.seek(SeekFrom::End(0)).map_err(TextReaderError::Io)?;
+    if size > max_size {
+        return Err(TextReaderError::TooLarge);
+    }
+    file.seek(SeekFrom::Start(0)).map_err(TextReaderError::Io)?;
+    Ok(BufReader::new(file.take(max_size)))
+}
 
-#[allow(dead_code)]
-fn plain_text_preview(title: &str, reader: BufReader<&File>) -> Arc<Preview> {
-    debug!("Creating plain text preview for {:?}", title);
-    let mut lines = Vec::with_capacity(TEMP_PLAIN_TEXT_PREVIEW_HEIGHT);
+/// The number of lines initially loaded when previewing text files.
+///
+/// The remaining lines are loaded once the user scrolls past them.
+const PARTIAL_PREVIEW_LINES: usize = 500;
+
+/// Build a hex dump preview of the first `max_bytes` bytes of the file
+/// named `name`.
+fn hex_dump_preview(name: &str, max_bytes: usize) -> Arc<Preview> {
+    let mut bytes = Vec::with_capacity(max_bytes);
+    if let Err(e) = File::open(name)
+        .and_then(|f| f.take(max_bytes as u64).read_to_end(&mut bytes))
+    {
+        warn!("Error reading file: {:?}", e);
+        return meta::not_supported(name);
+    }
+    let mut lines = format_hex_dump(&bytes);
+    if let Some(size) = get_file_size(Path::new(name)) {
+        if size > bytes.len() as u64 {
+            lines.push(String::new());
+            lines.push(format!(
+                "... showing the first {} of {} bytes",
+                bytes.len(),
+                size
+            ));
+        }
+    }
+    Arc::new(Preview::new(
+        name.to_string(),
+        PreviewContent::PlainText(lines),
+    ))
+}
+
+/// The size of the chunks in which whole files are read, checking for
+/// cancellation in between.
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Read `reader` to the end, failing with `Interrupted` once `cancelled` is
+/// set.
+pub(crate) fn read_unless_cancelled(
+    mut reader: impl Read,
+    cancelled: &AtomicBool,
+) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(io::Error::from(io::ErrorKind::Interrupted));
+        }
+        let read = reader
+            .by_ref()
+            .take(READ_CHUNK_SIZE as u64)
+            .read_to_end(&mut bytes)?;
+        if read == 0 {
+            return Ok(bytes);
+        }
+    }
+}
+
+/// Read and preprocess up to `max_lines` lines from `reader`.
+///
+/// Returns the lines (each terminated by a newline as expected by sublime
+/// syntaxes), whether there were more lines left to read and the length of
+/// the longest line read, in bytes, before truncation.
+/// Invalid UTF-8 sequences are replaced instead of truncating the preview.
+/// With `page_breaks`, lines containing form feeds or vertical tabs are
+/// split into several preview lines.
+/// Reading stops early once `cancelled` is set.
+fn read_preview_lines<R: BufRead>(
+    reader: R,
+    max_lines: usize,
+    tab_width: usize,
+    page_breaks: bool,
+    max_line_length: usize,
+    cancelled: &AtomicBool,
+) -> (Vec<String>, bool, usize) {
+    let config = ReplaceNonPrintableConfig::default()
+        .tab_width(tab_width)
+        .page_breaks(page_breaks);
+    let mut lines = reader
+        .split(b'\n')
+        .map_while(Result::ok)
+        .take_while(|_| !cancelled.load(Ordering::Relaxed));
+    let mut longest_line = 0;
+    let preview_lines: Vec<String> = lines
+        .by_ref()
+        .take(max_lines)
+        .flat_map(|line| {
+            longest_line = longest_line.max(line.len());
+            let line = preprocess_line_with_config(
+                &String::from_utf8_lossy(&line),
+                max_line_length,
+                &config,
+            );
+            line.split('\n')
+                .map(|line| line.to_string() + "\n")
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let partial = lines.next().is_some();
+    (preview_lines, partial, longest_line)
+}
+
+/// The number of lines highlighted between two checks of whether the
+/// preview was cancelled.
+const HIGHLIGHT_CHUNK_LINES: usize = 50;
+
+/// Highlight `lines` with `highlighter`, a chunk at a time, stopping early
+/// once `cancelled` is set.
+fn highlight_preview_lines(
+    highlighter: &mut LineHighlighter,
+    lines: &[String],
+    syntax_set: &SyntaxSet,
+    syntax_theme: &Theme,
+    cancelled: &AtomicBool,
+) -> Result<Vec<Vec<(Style, String)>>> {
+    let mut highlighted_lines = Vec::with_capacity(lines.len());
+    for chunk in lines.chunks(HIGHLIGHT_CHUNK_LINES) {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        highlighted_lines.extend(highlighter.highlight_lines(
+            chunk,
+            syntax_set,
+            syntax_theme,
+        )?);
+    }
+    Ok(highlighted_lines)
+}
+
+/// This should be enough to most standard terminal sizes
+const TEMP_PLAIN_TEXT_PREVIEW_HEIGHT: usize = 200;
+
+#[allow(dead_code)]
+fn plain_text_preview(
+    title: &str,
+    reader: BufReader<&File>,
+    tab_width: usize,
+    max_line_length: usize,
+) -> Arc<Preview> {
+    debug!("Creating plain text preview for {:?}", title);
+    let mut lines = Vec::with_capacity(TEMP_PLAIN_TEXT_PREVIEW_HEIGHT);
     // PERF: instead of using lines(), maybe check for the length of the first line instead and
     // truncate accordingly (since this is just a temp preview)
     for maybe_line in reader.lines() {
         match maybe_line {
-            Ok(line) => lines.push(preprocess_line(&line)),
+            Ok(line) => {
+                lines.push(preprocess_line_with_config(
+                    &line,
+                    max_line_length,
+                    &ReplaceNonPrintableConfig::default().tab_width(tab_width),
+                ));
+            }
             Err(e) => {
                 warn!("Error reading file: {:?}", e);
                 return meta::not_supported(title);
@@ -308,3 +1435,1433 @@ fn plain_text_preview(title: &str, reader: BufReader<&File>) -> Arc<Preview> {
         PreviewContent::PlainText(lines),
     ))
 }
+
+/// The target of the given path if it is a symlink.
+fn symlink_target(path: &Path) -> Option<PathBuf> {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    if !metadata.file_type().is_symlink() {
+        return None;
+    }
+    std::fs::read_link(path).ok()
+}
+
+/// Normalize a configured extension so that e.g. `.HCL` matches `hcl`.
+fn normalize_extension(extension: &str) -> String {
+    extension.trim_start_matches('.').to_lowercase()
+}
+
+/// The kind of special file (fifo, socket, device) found at `path`, if any,
+/// symlinks being followed.
+#[cfg(unix)]
+fn special_file_kind(path: &Path) -> Option<&'static str> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let file_type = std::fs::metadata(path).ok()?.file_type();
+    if file_type.is_fifo() {
+        Some("fifo")
+    } else if file_type.is_socket() {
+        Some("socket")
+    } else if file_type.is_char_device() || file_type.is_block_device() {
+        Some("device")
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn special_file_kind(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+fn is_json_path(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Pretty-print a JSON document using 2-space indentation.
+///
+/// Returns `None` if the input isn't valid JSON. The order of object keys is
+/// preserved.
+fn pretty_print_json_str(raw: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(raw).ok()?;
+    serde_json::to_string_pretty(&value).ok()
+}
+
+/// The default time after which a preview still being computed is given
+/// up on: 2 seconds.
+pub const DEFAULT_PREVIEW_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The default length (in bytes) of lines above which files are shown as
+/// plain text, e.g. minified bundles: 10 KB.
+pub const DEFAULT_MAX_HIGHLIGHTED_LINE_LENGTH: usize = 10 * 1024;
+
+/// The default maximum number of bytes shown in hex dump previews: 2 KB.
+pub const DEFAULT_HEX_PREVIEW_MAX_BYTES: usize = 2 * 1024;
+
+/// The number of bytes shown on each row of a hex dump.
+const HEX_DUMP_ROW_SIZE: usize = 16;
+
+/// Format bytes as a hex dump with an offset column, 16 bytes per row and an
+/// ASCII gutter.
+///
+/// Non-printable bytes are shown using the default replacement symbol in the
+/// ASCII gutter.
+fn format_hex_dump(bytes: &[u8]) -> Vec<String> {
+    let replacement_symbol =
+        ReplaceNonPrintableConfig::default().replacement_symbol;
+    bytes
+        .chunks(HEX_DUMP_ROW_SIZE)
+        .enumerate()
+        .map(|(i, row)| {
+            let mut line = format!("{:08x} ", i * HEX_DUMP_ROW_SIZE);
+            for j in 0..HEX_DUMP_ROW_SIZE {
+                if j % 8 == 0 {
+                    line.push(' ');
+                }
+                match row.get(j) {
+                    Some(b) => line.push_str(&format!("{b:02x} ")),
+                    None => line.push_str("   "),
+                }
+            }
+            line.push(' ');
+            line.push('|');
+            line.extend(row.iter().map(|b| {
+                if b.is_ascii_graphic() || *b == b' ' {
+                    char::from(*b)
+                } else {
+                    replacement_symbol
+                }
+            }));
+            line.push('|');
+            line
+        })
+        .collect()
+}
+
+/// The maximum number of entries listed in a directory preview.
+const MAX_DIRECTORY_LISTING_ENTRIES: usize = 500;
+
+/// An immediate child of a directory, as shown in a directory preview.
+struct DirectoryListingEntry {
+    name: String,
+    is_dir: bool,
+    size: Option<u64>,
+}
+
+fn directory_listing_preview(title: &str, path: &Path) -> Arc<Preview> {
+    let read_dir = match std::fs::read_dir(path) {
+        Ok(read_dir) => read_dir,
+        Err(e) => {
+            warn!("Error reading directory: {:?}", e);
+            return meta::not_supported(title);
+        }
+    };
+    let entries = read_dir
+        .map_while(Result::ok)
+        .map(|e| {
+            let metadata = e.metadata().ok();
+            let is_dir = metadata.as_ref().map_or(false, |m| m.is_dir());
+            DirectoryListingEntry {
+                name: e.file_name().to_string_lossy().into_owned(),
+                is_dir,
+                size: metadata.filter(|_| !is_dir).map(|m| m.len()),
+            }
+        })
+        .collect();
+    Arc::new(Preview::new(
+        title.to_string(),
+        PreviewContent::PlainText(format_directory_listing(
+            entries,
+            MAX_DIRECTORY_LISTING_ENTRIES,
+        )),
+    ))
+}
+
+/// Format directory entries as a sorted listing with sizes aligned on the left.
+///
+/// Hidden entries are listed after the other ones and subdirectories get a
+/// trailing `/`. At most `max_entries` entries are listed.
+fn format_directory_listing(
+    mut entries: Vec<DirectoryListingEntry>,
+    max_entries: usize,
+) -> Vec<String> {
+    entries.sort_by(|a, b| {
+        a.name
+            .starts_with('.')
+            .cmp(&b.name.starts_with('.'))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    let total = entries.len();
+    let mut lines: Vec<String> = entries
+        .into_iter()
+        .take(max_entries)
+        .map(|e| {
+            let size = e.size.map(human_readable_size).unwrap_or_default();
+            let name = preprocess_line(&e.name);
+            if e.is_dir {
+                format!("{size:>6}  {name}/")
+            } else {
+                format!("{size:>6}  {name}")
+            }
+        })
+        .collect();
+    if total > max_entries {
+        lines.push(String::new());
+        lines.push(format!("... and {} more entries", total - max_entries));
+    }
+    lines
+}
+
+/// Format a size in bytes using binary units (e.g. `1.5K`, `12M`).
+pub(crate) fn human_readable_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+    if size < 1024 {
+        return format!("{size}B");
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if value < 10.0 {
+        format!("{value:.1}{}", UNITS[unit])
+    } else {
+        format!("{value:.0}{}", UNITS[unit])
+    }
+}
+
+/// The number of bytes to read when looking for image dimensions.
+const IMAGE_HEADER_SIZE: u64 = 64 * 1024;
+
+/// Build a preview describing an image file (format, dimensions, size and
+/// EXIF metadata) for when the image itself can't be rendered.
+fn image_metadata_preview(title: &str, path: &Path) -> Arc<Preview> {
+    let mut header = Vec::new();
+    if let Err(e) = File::open(path)
+        .and_then(|f| f.take(IMAGE_HEADER_SIZE).read_to_end(&mut header))
+    {
+        warn!("Error reading image file: {:?}", e);
+        return meta::not_supported(title);
+    }
+    let mut lines = vec![String::from("This image can't be rendered")];
+    lines.push(String::new());
+    if let Some(kind) = infer::get(&header) {
+        lines.push(format!("Format:     {}", kind.mime_type()));
+    }
+    if let Some((width, height)) = image_dimensions(&header) {
+        lines.push(format!("Dimensions: {width}x{height}"));
+    }
+    if let Some(size) = get_file_size(path) {
+        lines.push(format!("Size:       {size} bytes"));
+    }
+    if let Some(exif) = jpeg_exif(&header) {
+        lines.extend(exif.lines());
+    }
+    Arc::new(Preview::new(
+        title.to_string(),
+        PreviewContent::PlainText(lines),
+    ))
+}
+
+/// Try to read the dimensions of an image from its header.
+///
+/// Supports PNG, GIF, BMP and JPEG.
+fn image_dimensions(header: &[u8]) -> Option<(u32, u32)> {
+    let be_u16 = |i: usize| {
+        header
+            .get(i..i + 2)
+            .map(|b| u32::from(u16::from_be_bytes([b[0], b[1]])))
+    };
+    let le_u16 = |i: usize| {
+        header
+            .get(i..i + 2)
+            .map(|b| u32::from(u16::from_le_bytes([b[0], b[1]])))
+    };
+    let be_u32 = |i: usize| {
+        header
+            .get(i..i + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let le_i32 = |i: usize| {
+        header
+            .get(i..i + 4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some((be_u32(16)?, be_u32(20)?));
+    }
+    if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        return Some((le_u16(6)?, le_u16(8)?));
+    }
+    if header.starts_with(b"BM") {
+        // the height is negative for top-down bitmaps
+        return Some((le_i32(18)?.unsigned_abs(), le_i32(22)?.unsigned_abs()));
+    }
+    if header.starts_with(&[0xFF, 0xD8]) {
+        let mut i = 2;
+        while i + 4 <= header.len() {
+            if header[i] != 0xFF {
+                return None;
+            }
+            let marker = header[i + 1];
+            // start of frame markers (excluding DHT, JPG and DAC)
+            if (0xC0..=0xCF).contains(&marker)
+                && !matches!(marker, 0xC4 | 0xC8 | 0xCC)
+            {
+                return Some((be_u16(i + 7)?, be_u16(i + 5)?));
+            }
+            i += 2 + usize::try_from(be_u16(i + 2)?).ok()?;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use television_utils::strings::PAGE_BREAK_RULE;
+
+    fn listing_entry(
+        name: &str,
+        is_dir: bool,
+        size: Option<u64>,
+    ) -> DirectoryListingEntry {
+        DirectoryListingEntry {
+            name: name.to_string(),
+            is_dir,
+            size,
+        }
+    }
+
+    #[test]
+    fn test_format_directory_listing() {
+        let entries = vec![
+            listing_entry("src", true, None),
+            listing_entry(".gitignore", false, Some(12)),
+            listing_entry("Cargo.toml", false, Some(2048)),
+            listing_entry(".github", true, None),
+            listing_entry("README.md", false, Some(15 * 1024 * 1024)),
+        ];
+        assert_eq!(
+            format_directory_listing(entries, 500),
+            vec![
+                "  2.0K  Cargo.toml",
+                "   15M  README.md",
+                "        src/",
+                "        .github/",
+                "   12B  .gitignore",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_directory_listing_truncation() {
+        let entries = (0..10)
+            .map(|i| listing_entry(&format!("file{i}"), false, Some(1)))
+            .collect();
+        let lines = format_directory_listing(entries, 3);
+        assert_eq!(
+            lines,
+            vec![
+                "    1B  file0",
+                "    1B  file1",
+                "    1B  file2",
+                "",
+                "... and 7 more entries",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_human_readable_size() {
+        assert_eq!(human_readable_size(0), "0B");
+        assert_eq!(human_readable_size(1023), "1023B");
+        assert_eq!(human_readable_size(1536), "1.5K");
+        assert_eq!(human_readable_size(200 * 1024), "200K");
+        assert_eq!(human_readable_size(3 * 1024 * 1024 * 1024), "3.0G");
+    }
+
+    fn numbered_lines(count: usize) -> String {
+        (0..count)
+            .map(|i| format!("line {i}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_format_hex_dump() {
+        let bytes = b"Hello, World!\x00\x01\x7f\xffabc";
+        assert_eq!(
+            format_hex_dump(bytes),
+            vec![
+                "00000000  48 65 6c 6c 6f 2c 20 57  6f 72 6c 64 21 00 01 7f  |Hello, World!␀␀␀|",
+                "00000010  ff 61 62 63                                       |␀abc|",
+            ]
+        );
+        assert!(format_hex_dump(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_pretty_print_minified_json() {
+        let raw =
+            r#"{"name":"tv","tags":["a","b"],"nested":{"z":1,"a":null}}"#;
+        assert_eq!(
+            pretty_print_json_str(raw).unwrap(),
+            r#"{
+  "name": "tv",
+  "tags": [
+    "a",
+    "b"
+  ],
+  "nested": {
+    "z": 1,
+    "a": null
+  }
+}"#
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_invalid_json() {
+        assert_eq!(pretty_print_json_str(r#"{"name": "tv""#), None);
+        assert_eq!(pretty_print_json_str("not json at all"), None);
+    }
+
+    #[test]
+    fn test_is_json_path() {
+        assert!(is_json_path(Path::new("package.json")));
+        assert!(is_json_path(Path::new("dir/DATA.JSON")));
+        assert!(!is_json_path(Path::new("config.toml")));
+        assert!(!is_json_path(Path::new("json")));
+    }
+
+    #[test]
+    fn test_read_preview_lines_is_bounded() {
+        let content = numbered_lines(10_000);
+        let (lines, partial, _) = read_preview_lines(
+            Cursor::new(content),
+            PARTIAL_PREVIEW_LINES,
+            TAB_WIDTH,
+            false,
+            MAX_LINE_LENGTH,
+            &AtomicBool::new(false),
+        );
+        assert_eq!(lines.len(), PARTIAL_PREVIEW_LINES);
+        assert_eq!(lines[0], "line 0\n");
+        assert_eq!(lines[PARTIAL_PREVIEW_LINES - 1], "line 499\n");
+        assert!(partial);
+    }
+
+    #[test]
+    fn test_read_preview_lines_invalid_utf8() {
+        let (lines, partial, _) = read_preview_lines(
+            Cursor::new(b"first\nsec\xFFond\nthird".to_vec()),
+            PARTIAL_PREVIEW_LINES,
+            TAB_WIDTH,
+            false,
+            MAX_LINE_LENGTH,
+            &AtomicBool::new(false),
+        );
+        assert_eq!(lines, vec!["first\n", "sec␀ond\n", "third\n"]);
+        assert!(!partial);
+    }
+
+    #[test]
+    fn test_read_preview_lines_utf16_fixture() {
+        let mut fixture = vec![0xFF, 0xFE];
+        fixture.extend(
+            "# Título\nçà et là\n"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        assert!(is_utf16(&fixture));
+        let (lines, _, _) = read_preview_lines(
+            Cursor::new(decode_to_utf8(&fixture)),
+            PARTIAL_PREVIEW_LINES,
+            TAB_WIDTH,
+            false,
+            MAX_LINE_LENGTH,
+            &AtomicBool::new(false),
+        );
+        assert_eq!(lines, vec!["# Título\n", "çà et là\n"]);
+    }
+
+    #[test]
+    fn test_read_preview_lines_latin1_fixture() {
+        let fixture = b"na\xEFve caf\xE9\n\xC0 bient\xF4t\n";
+        assert_ne!(detect_encoding(fixture), UTF_8);
+        let (lines, _, _) = read_preview_lines(
+            Cursor::new(decode_to_utf8(fixture)),
+            PARTIAL_PREVIEW_LINES,
+            TAB_WIDTH,
+            false,
+            MAX_LINE_LENGTH,
+            &AtomicBool::new(false),
+        );
+        assert_eq!(lines, vec!["naïve café\n", "À bientôt\n"]);
+    }
+
+    #[test]
+    fn test_read_preview_lines_whole_file() {
+        let content = numbered_lines(10);
+        let (lines, partial, _) = read_preview_lines(
+            Cursor::new(content.clone()),
+            10,
+            TAB_WIDTH,
+            false,
+            MAX_LINE_LENGTH,
+            &AtomicBool::new(false),
+        );
+        assert_eq!(lines.len(), 10);
+        assert!(!partial);
+
+        let (lines, partial, _) = read_preview_lines(
+            Cursor::new(content),
+            PARTIAL_PREVIEW_LINES,
+            TAB_WIDTH,
+            false,
+            MAX_LINE_LENGTH,
+            &AtomicBool::new(false),
+        );
+        assert_eq!(lines.len(), 10);
+        assert!(!partial);
+    }
+
+    #[test]
+    fn test_png_dimensions() {
+        let mut header = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        header.extend_from_slice(&640u32.to_be_bytes());
+        header.extend_from_slice(&480u32.to_be_bytes());
+        assert_eq!(image_dimensions(&header), Some((640, 480)));
+    }
+
+    #[test]
+    fn test_gif_dimensions() {
+        let mut header = b"GIF89a".to_vec();
+        header.extend_from_slice(&32u16.to_le_bytes());
+        header.extend_from_slice(&16u16.to_le_bytes());
+        assert_eq!(image_dimensions(&header), Some((32, 16)));
+    }
+
+    #[test]
+    fn test_bmp_dimensions() {
+        let mut header = b"BM".to_vec();
+        header.resize(18, 0);
+        header.extend_from_slice(&100i32.to_le_bytes());
+        header.extend_from_slice(&(-50i32).to_le_bytes());
+        assert_eq!(image_dimensions(&header), Some((100, 50)));
+    }
+
+    #[test]
+    fn test_jpeg_dimensions() {
+        let header = [
+            0xFF, 0xD8, // SOI
+            0xFF, 0xE0, 0x00, 0x04, 0x00,
+            0x00, // APP0 with 2 bytes of data
+            0xFF, 0xC0, 0x00, 0x11, 0x08, // SOF0, length, precision
+            0x01, 0x2C, // height: 300
+            0x01, 0x90, // width: 400
+        ];
+        assert_eq!(image_dimensions(&header), Some((400, 300)));
+    }
+
+    #[test]
+    fn test_image_metadata_preview() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/previewers/fixtures/exif.jpg");
+        let preview = image_metadata_preview("exif.jpg", &path);
+        let PreviewContent::PlainText(lines) = &preview.content else {
+            panic!("expected a plain text preview");
+        };
+        assert!(lines.contains(&String::from("Dimensions: 64x48")));
+        assert!(lines.contains(&String::from("Camera:     Canon EOS 5D")));
+        assert!(
+            lines.contains(&String::from("Date:       2023-06-15 14:30:00"))
+        );
+    }
+
+    #[test]
+    fn test_truncated_headers() {
+        assert_eq!(image_dimensions(b"\x89PNG\r\n\x1a\n"), None);
+        assert_eq!(image_dimensions(&[0xFF, 0xD8, 0xFF, 0xE0]), None);
+        assert_eq!(image_dimensions(b"not an image"), None);
+    }
+
+    /// Wait for the highlighted preview of the given entry.
+    async fn highlighted_preview(
+        previewer: &mut FilePreviewer,
+        entry: &entry::Entry,
+    ) -> Vec<Vec<(syntect::highlighting::Style, String)>> {
+        for _ in 0..500 {
+            let preview = previewer.preview(entry).await;
+            if let PreviewContent::SyntectHighlightedText(lines) =
+                &preview.content
+            {
+                return lines.clone();
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("No highlighted preview for {:?}", entry.name);
+    }
+
+    /// A reader taking `delay` to return its first bytes.
+    struct SlowReader {
+        delay: Duration,
+        content: Cursor<&'static str>,
+    }
+
+    impl Read for SlowReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            std::thread::sleep(std::mem::take(&mut self.delay));
+            self.content.read(buf)
+        }
+    }
+
+    fn slow_reader(delay_ms: u64) -> BufReader<SlowReader> {
+        BufReader::new(SlowReader {
+            delay: Duration::from_millis(delay_ms),
+            content: Cursor::new("fn main() {}\n"),
+        })
+    }
+
+    /// A reader returning the same line forever, taking `delay` for each
+    /// read and counting them.
+    struct EndlessReader {
+        delay: Duration,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl Read for EndlessReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            std::thread::sleep(self.delay);
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            Cursor::new("fn main() {}\n").read(buf)
+        }
+    }
+
+    fn endless_reader(reads: &Arc<AtomicUsize>) -> BufReader<EndlessReader> {
+        BufReader::new(EndlessReader {
+            delay: Duration::from_millis(5),
+            reads: reads.clone(),
+        })
+    }
+
+    /// Assert that the given reader isn't read from anymore.
+    async fn assert_stops_reading(reads: &AtomicUsize) {
+        // the read in progress, if any, may still complete
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let count = reads.load(Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(reads.load(Ordering::Relaxed), count);
+    }
+
+    /// Wait for the computation of the given entry's preview to finish.
+    async fn wait_for_preview(
+        previewer: &FilePreviewer,
+        entry: &entry::Entry,
+    ) -> Arc<Preview> {
+        for _ in 0..500 {
+            if !previewer.in_flight.lock().contains_key(&entry.name) {
+                if let Some(preview) = previewer.cache.lock().get(&entry.name)
+                {
+                    return preview;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("No preview for {:?}", entry.name);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_preview_timeout() {
+        let entry = entry::Entry::new(
+            String::from("slow.rs"),
+            entry::PreviewType::Files,
+        );
+        let previewer = FilePreviewer::new(Some(
+            FilePreviewerConfig::default()
+                .preview_timeout(Duration::from_millis(50)),
+        ));
+        previewer
+            .compute_highlighted_text_preview(
+                &entry,
+                slow_reader(500),
+                PathBuf::from(&entry.name),
+                10,
+            )
+            .await;
+        let preview = wait_for_preview(&previewer, &entry).await;
+        assert!(matches!(preview.content, PreviewContent::TimedOut));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cancelled_previews_stop_reading() {
+        let first = entry::Entry::new(
+            String::from("first.rs"),
+            entry::PreviewType::Files,
+        );
+        let second = entry::Entry::new(
+            String::from("second.rs"),
+            entry::PreviewType::Files,
+        );
+        let previewer = FilePreviewer::new(Some(
+            FilePreviewerConfig::default()
+                .preview_timeout(Duration::from_millis(100)),
+        ));
+
+        // timed out
+        let reads = Arc::new(AtomicUsize::new(0));
+        previewer
+            .compute_highlighted_text_preview(
+                &first,
+                endless_reader(&reads),
+                PathBuf::from(&first.name),
+                usize::MAX,
+            )
+            .await;
+        let preview = wait_for_preview(&previewer, &first).await;
+        assert!(matches!(preview.content, PreviewContent::TimedOut));
+        assert_stops_reading(&reads).await;
+
+        // aborted
+        let reads = Arc::new(AtomicUsize::new(0));
+        previewer
+            .compute_highlighted_text_preview(
+                &first,
+                endless_reader(&reads),
+                PathBuf::from(&first.name),
+                usize::MAX,
+            )
+            .await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        previewer
+            .compute_highlighted_text_preview(
+                &second,
+                slow_reader(0),
+                PathBuf::from(&second.name),
+                10,
+            )
+            .await;
+        assert!(reads.load(Ordering::Relaxed) > 0);
+        assert_stops_reading(&reads).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_superseded_preview_is_aborted() {
+        let first = entry::Entry::new(
+            String::from("first.rs"),
+            entry::PreviewType::Files,
+        );
+        let second = entry::Entry::new(
+            String::from("second.rs"),
+            entry::PreviewType::Files,
+        );
+        let previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        previewer
+            .compute_highlighted_text_preview(
+                &first,
+                slow_reader(200),
+                PathBuf::from(&first.name),
+                10,
+            )
+            .await;
+        previewer
+            .compute_highlighted_text_preview(
+                &second,
+                slow_reader(0),
+                PathBuf::from(&second.name),
+                10,
+            )
+            .await;
+        assert!(!previewer.in_flight.lock().contains_key(&first.name));
+
+        wait_for_preview(&previewer, &second).await;
+        // give the aborted computation time to complete
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(previewer.cache.lock().get(&first.name).is_none());
+    }
+
+    /// A reader that can't be sought, like a pipe.
+    struct Pipe(Cursor<&'static str>);
+
+    impl Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    impl Seek for Pipe {
+        fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+            Err(io::Error::from(io::ErrorKind::Unsupported))
+        }
+    }
+
+    #[test]
+    fn test_bounded_reader() {
+        assert!(matches!(
+            bounded_reader(Pipe(Cursor::new("a\nb\n")), 1024),
+            Err(TextReaderError::Io(_))
+        ));
+        // the file grew past the limit after its size was checked
+        assert!(matches!(
+            bounded_reader(Cursor::new("a".repeat(2048)), 1024),
+            Err(TextReaderError::TooLarge)
+        ));
+
+        let mut file = Cursor::new("first\nsecond\n");
+        file.set_position(6);
+        let mut content = String::new();
+        bounded_reader(file, 1024)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "first\nsecond\n");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_long_lines_are_not_highlighted() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-long-lines-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let minified = directory.join("bundle.min.js");
+        std::fs::write(
+            &minified,
+            "var a=function(b){return b+1};".repeat(35_000) + "\n",
+        )
+        .unwrap();
+        let short = directory.join("main.js");
+        std::fs::write(&short, "var a=function(b){return b+1};\n").unwrap();
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        let to_entry = |path: &Path| {
+            entry::Entry::new(
+                path.to_string_lossy().to_string(),
+                entry::PreviewType::Files,
+            )
+        };
+        let start = std::time::Instant::now();
+        let lines =
+            highlighted_preview(&mut previewer, &to_entry(&minified)).await;
+        assert!(start.elapsed() < Duration::from_secs(5));
+        // a single plain text span
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].len(), 1);
+
+        let lines =
+            highlighted_preview(&mut previewer, &to_entry(&short)).await;
+        assert!(lines[0].len() > 1);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_lazy_highlighting_assets() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-lazy-assets-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let binary = directory.join("data.bin");
+        std::fs::write(&binary, [0u8, 159, 146, 150, 0, 1, 2, 3]).unwrap();
+        let text = directory.join("main.rs");
+        std::fs::write(&text, "fn main() {}\n").unwrap();
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        assert!(!previewer.syntax_set.is_loaded());
+        assert!(!previewer.syntax_theme.is_loaded());
+        previewer
+            .preview(&entry::Entry::new(
+                binary.to_string_lossy().to_string(),
+                entry::PreviewType::Files,
+            ))
+            .await;
+        assert!(!previewer.syntax_set.is_loaded());
+
+        highlighted_preview(
+            &mut previewer,
+            &entry::Entry::new(
+                text.to_string_lossy().to_string(),
+                entry::PreviewType::Files,
+            ),
+        )
+        .await;
+        assert!(previewer.syntax_set.is_loaded());
+        assert!(previewer.syntax_theme.is_loaded());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cycle_theme() {
+        const CONTENT: &str = "fn main() {\n    let answer = 42;\n}\n";
+        let path = std::env::temp_dir()
+            .join(format!("tv-cycle-theme-{}.rs", std::process::id()));
+        std::fs::write(&path, CONTENT).unwrap();
+        let entry = entry::Entry::new(
+            path.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        let initial_theme = previewer.syntax_theme.clone();
+        highlighted_preview(&mut previewer, &entry).await;
+
+        let first = previewer.cycle_theme().to_string();
+        let second = previewer.cycle_theme().to_string();
+        assert_ne!(first, second);
+        assert_eq!(previewer.theme_name, second);
+        assert!(!Arc::ptr_eq(&initial_theme, &previewer.syntax_theme));
+        assert!(previewer.cache.lock().is_empty());
+
+        let (lines, _, _) = read_preview_lines(
+            Cursor::new(CONTENT),
+            PARTIAL_PREVIEW_LINES,
+            TAB_WIDTH,
+            false,
+            MAX_LINE_LENGTH,
+            &AtomicBool::new(false),
+        );
+        let expected = syntax::compute_highlights_for_path(
+            &path,
+            lines,
+            previewer.syntax_set.get(),
+            previewer.syntax_theme.get(),
+        )
+        .unwrap();
+        assert_eq!(
+            highlighted_preview(&mut previewer, &entry).await,
+            expected
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_archive_preview_is_computed_in_the_background() {
+        let path = std::env::temp_dir()
+            .join(format!("tv-archive-{}.tar", std::process::id()));
+        // an empty archive
+        std::fs::write(&path, [0u8; 1024]).unwrap();
+        let entry = entry::Entry::new(
+            path.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        let preview = previewer.preview(&entry).await;
+        assert!(matches!(preview.content, PreviewContent::Loading));
+        let preview = wait_for_preview(&previewer, &entry).await;
+        assert!(matches!(
+            &preview.content,
+            PreviewContent::PlainText(lines) if lines.is_empty()
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_read_unless_cancelled() {
+        let content = "a".repeat(3 * READ_CHUNK_SIZE + 1);
+        assert_eq!(
+            read_unless_cancelled(content.as_bytes(), &AtomicBool::new(false))
+                .unwrap(),
+            content.as_bytes()
+        );
+        let reads = Arc::new(AtomicUsize::new(0));
+        let error = read_unless_cancelled(
+            endless_reader(&reads),
+            &AtomicBool::new(true),
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+        assert_eq!(reads.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_markdown_preview_is_computed_in_the_background() {
+        let path = std::env::temp_dir()
+            .join(format!("tv-markdown-{}.md", std::process::id()));
+        std::fs::write(&path, "# Title\n\nSome text\n").unwrap();
+        let entry = entry::Entry::new(
+            path.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        let preview = previewer.preview(&entry).await;
+        assert!(matches!(preview.content, PreviewContent::Loading));
+        let preview = wait_for_preview(&previewer, &entry).await;
+        let PreviewContent::AnsiText(lines) = &preview.content else {
+            panic!("expected rendered markdown");
+        };
+        assert_eq!(lines[0].to_string(), "Title");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_table_preview_is_computed_in_the_background() {
+        let path = std::env::temp_dir()
+            .join(format!("tv-table-{}.csv", std::process::id()));
+        std::fs::write(&path, "a,b\n1,2\n").unwrap();
+        let entry = entry::Entry::new(
+            path.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        let preview = previewer.preview(&entry).await;
+        assert!(matches!(preview.content, PreviewContent::Loading));
+        let preview = wait_for_preview(&previewer, &entry).await;
+        assert!(matches!(
+            &preview.content,
+            PreviewContent::PlainText(lines)
+                if lines == &["a │ b", "1 │ 2"]
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_git_diff_preview_is_computed_in_the_background() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-git-diff-preview-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&directory)
+                .args([
+                    "-c",
+                    "user.name=tv",
+                    "-c",
+                    "user.email=tv@example.com",
+                ])
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "--quiet"]);
+        let modified = directory.join("modified.txt");
+        let unchanged = directory.join("unchanged.txt");
+        std::fs::write(&modified, "one\n").unwrap();
+        std::fs::write(&unchanged, "one\n").unwrap();
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "--message", "initial commit"]);
+        std::fs::write(&modified, "two\n").unwrap();
+        let entry = |path: &Path| {
+            entry::Entry::new(
+                path.to_string_lossy().to_string(),
+                entry::PreviewType::Files,
+            )
+        };
+
+        let mut previewer = FilePreviewer::new(Some(
+            FilePreviewerConfig::default().prefer_git_diff(true),
+        ));
+        let modified = entry(&modified);
+        let preview = previewer.preview(&modified).await;
+        assert!(matches!(preview.content, PreviewContent::Loading));
+        let preview = wait_for_preview(&previewer, &modified).await;
+        let PreviewContent::AnsiText(lines) = &preview.content else {
+            panic!("expected a diff");
+        };
+        assert!(lines.iter().any(|line| line.to_string() == "+two"));
+
+        // files without changes are highlighted as usual
+        let lines =
+            highlighted_preview(&mut previewer, &entry(&unchanged)).await;
+        assert_eq!(lines[0][0].1, "one\n");
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_set_max_line_length() {
+        let path = std::env::temp_dir()
+            .join(format!("tv-max-line-length-{}.txt", std::process::id()));
+        std::fs::write(&path, "a".repeat(100)).unwrap();
+        let entry = entry::Entry::new(
+            path.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        let lines = highlighted_preview(&mut previewer, &entry).await;
+        assert_eq!(lines[0][0].1, "a".repeat(100) + "\n");
+
+        // the cached preview is computed again with the new length
+        previewer.set_max_line_length(10);
+        let lines = highlighted_preview(&mut previewer, &entry).await;
+        assert_eq!(lines[0][0].1, "a".repeat(10) + "\n");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_page_breaks_preview() {
+        let path = std::env::temp_dir()
+            .join(format!("tv-page-breaks-{}.txt", std::process::id()));
+        std::fs::write(&path, "page 1\x0cpage 2\nline\x0bbreak\n").unwrap();
+        let entry = entry::Entry::new(
+            path.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer = FilePreviewer::new(Some(
+            FilePreviewerConfig::default().page_breaks(true),
+        ));
+        let lines: Vec<String> = highlighted_preview(&mut previewer, &entry)
+            .await
+            .iter()
+            .map(|line| line.iter().map(|(_, text)| text.as_str()).collect())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "page 1\n".to_string(),
+                format!("{PAGE_BREAK_RULE}\n"),
+                "page 2\n".to_string(),
+                "line\n".to_string(),
+                "break\n".to_string(),
+            ]
+        );
+
+        // form feeds are replaced like other control characters by default
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        let lines = highlighted_preview(&mut previewer, &entry).await;
+        assert_eq!(lines.len(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_load_more() {
+        let path = std::env::temp_dir()
+            .join(format!("tv-load-more-{}.txt", std::process::id()));
+        std::fs::write(&path, numbered_lines(2 * PARTIAL_PREVIEW_LINES + 1))
+            .unwrap();
+        let entry = entry::Entry::new(
+            path.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        highlighted_preview(&mut previewer, &entry).await;
+        let preview = wait_for_preview(&previewer, &entry).await;
+        assert!(preview.partial);
+        assert_eq!(preview.total_lines(), PARTIAL_PREVIEW_LINES);
+
+        previewer.load_more(&entry).await;
+        let preview = wait_for_preview(&previewer, &entry).await;
+        assert!(!preview.partial);
+        assert_eq!(preview.total_lines(), 2 * PARTIAL_PREVIEW_LINES + 1);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_gzip_preview() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        const CONTENT: &str = "fn main() {\n    let answer = 42;\n}\n";
+        let path = std::env::temp_dir()
+            .join(format!("tv-gzip-{}.rs.gz", std::process::id()));
+        let mut encoder = GzEncoder::new(
+            File::create(&path).unwrap(),
+            Compression::default(),
+        );
+        encoder.write_all(CONTENT.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        let entry = entry::Entry::new(
+            path.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        let lines = highlighted_preview(&mut previewer, &entry).await;
+
+        // highlighted as rust, the extension of the decompressed file
+        let (expected, _, _) = read_preview_lines(
+            Cursor::new(CONTENT),
+            PARTIAL_PREVIEW_LINES,
+            TAB_WIDTH,
+            false,
+            MAX_LINE_LENGTH,
+            &AtomicBool::new(false),
+        );
+        let expected = syntax::compute_highlights_for_path(
+            Path::new("main.rs"),
+            expected,
+            previewer.syntax_set.get(),
+            previewer.syntax_theme.get(),
+        )
+        .unwrap();
+        assert_eq!(lines, expected);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_gzip_load_more_reuses_decompressed_content() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let path = std::env::temp_dir()
+            .join(format!("tv-gzip-load-more-{}.txt.gz", std::process::id()));
+        let mut encoder = GzEncoder::new(
+            File::create(&path).unwrap(),
+            Compression::default(),
+        );
+        encoder
+            .write_all(numbered_lines(2 * PARTIAL_PREVIEW_LINES).as_bytes())
+            .unwrap();
+        encoder.finish().unwrap();
+        let entry = entry::Entry::new(
+            path.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        let preview = previewer.preview(&entry).await;
+        assert!(matches!(preview.content, PreviewContent::Loading));
+        let preview = wait_for_preview(&previewer, &entry).await;
+        assert!(preview.partial);
+        assert_eq!(preview.total_lines(), PARTIAL_PREVIEW_LINES);
+
+        // the remaining lines come from the content decompressed earlier
+        std::fs::remove_file(&path).unwrap();
+        previewer.load_more(&entry).await;
+        let preview = wait_for_preview(&previewer, &entry).await;
+        assert!(!preview.partial);
+        assert_eq!(preview.total_lines(), 2 * PARTIAL_PREVIEW_LINES);
+    }
+
+    fn fixture_entry(name: &str) -> entry::Entry {
+        entry::Entry::new(
+            format!(
+                "{}/src/previewers/fixtures/{name}",
+                env!("CARGO_MANIFEST_DIR")
+            ),
+            entry::PreviewType::Files,
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_image_preview() {
+        let entry = fixture_entry("tiny.png");
+        let mut previewer = FilePreviewer::new(Some(
+            FilePreviewerConfig::default().image_background([0, 0, 255]),
+        ));
+        let preview = previewer.preview(&entry).await;
+        assert!(matches!(preview.content, PreviewContent::Loading));
+        let preview = wait_for_preview(&previewer, &entry).await;
+        let PreviewContent::Image(image) = &preview.content else {
+            panic!("expected an image preview");
+        };
+        assert_eq!((image.width, image.height), (3, 2));
+        // the transparent pixel is blended with the background
+        assert_eq!(
+            super::super::image::halfblocks(image, 3, 1)[0].spans[2]
+                .style
+                .bg,
+            Some(ratatui::style::Color::Rgb(0, 0, 255))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_undecodable_image_preview() {
+        // JPEGs can't be decoded, they are described instead
+        let entry = fixture_entry("exif.jpg");
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        previewer.preview(&entry).await;
+        let preview = wait_for_preview(&previewer, &entry).await;
+        let PreviewContent::PlainText(lines) = &preview.content else {
+            panic!("expected a metadata preview");
+        };
+        assert!(lines.contains(&String::from("Dimensions: 64x48")));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_disabled_image_preview() {
+        let entry = fixture_entry("tiny.png");
+        let mut previewer = FilePreviewer::new(Some(
+            FilePreviewerConfig::default().render_images(false),
+        ));
+        let preview = previewer.preview(&entry).await;
+        let PreviewContent::PlainText(lines) = &preview.content else {
+            panic!("expected a metadata preview");
+        };
+        assert!(lines.contains(&String::from("Dimensions: 3x2")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_symlink_preview() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-symlink-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let target = directory.join("main.rs");
+        std::fs::write(&target, "fn main() {}\n").unwrap();
+        let link = directory.join("link.rs");
+        std::os::unix::fs::symlink("main.rs", &link).unwrap();
+        let entry = entry::Entry::new(
+            link.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        highlighted_preview(&mut previewer, &entry).await;
+        let preview = previewer.preview(&entry).await;
+        assert_eq!(
+            preview.title,
+            format!(
+                "{} -> {}",
+                link.display(),
+                target.canonicalize().unwrap().display()
+            )
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_broken_symlink_preview() {
+        let link = std::env::temp_dir()
+            .join(format!("tv-broken-symlink-{}", std::process::id()));
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink("missing.rs", &link).unwrap();
+        let entry = entry::Entry::new(
+            link.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        let preview = previewer.preview(&entry).await;
+        assert!(matches!(
+            &preview.content,
+            PreviewContent::BrokenSymlink(target) if target == "missing.rs"
+        ));
+
+        std::fs::remove_file(&link).unwrap();
+    }
+
+    #[test]
+    fn test_light_theme() {
+        let config =
+            FilePreviewerConfig::new(String::from("base16-ocean.dark"))
+                .light_theme("InspiredGitHub");
+        let previewer = FilePreviewer::new(Some(
+            config
+                .clone()
+                .terminal_background(TerminalBackground::Light),
+        ));
+        assert_eq!(previewer.theme_name, "InspiredGitHub");
+        let previewer = FilePreviewer::new(Some(
+            config.terminal_background(TerminalBackground::Dark),
+        ));
+        assert_eq!(previewer.theme_name, "base16-ocean.dark");
+        // without a light theme, the theme is used whatever the background
+        let previewer = FilePreviewer::new(Some(
+            FilePreviewerConfig::new(String::from("base16-ocean.dark"))
+                .terminal_background(TerminalBackground::Light),
+        ));
+        assert_eq!(previewer.theme_name, "base16-ocean.dark");
+    }
+
+    #[test]
+    fn test_configured_text_extensions() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-text-extensions-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        // the NUL byte makes the content look binary when sniffed
+        let content = "key = \"value\"\0\n";
+        let hcl = directory.join("main.hcl");
+        let conf = directory.join("defaults.conf.d");
+        let other = directory.join("main.other");
+        for path in [&hcl, &conf, &other] {
+            std::fs::write(path, content).unwrap();
+        }
+
+        let previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        assert!(!matches!(previewer.get_file_type(&hcl), FileType::Text));
+
+        let previewer = FilePreviewer::new(Some(
+            FilePreviewerConfig::default().text_extensions(vec![
+                String::from(".HCL"),
+                String::from("conf.d"),
+            ]),
+        ));
+        assert!(matches!(previewer.get_file_type(&hcl), FileType::Text));
+        assert!(matches!(previewer.get_file_type(&conf), FileType::Text));
+        assert!(!matches!(previewer.get_file_type(&other), FileType::Text));
+        // built-in extensions are still known
+        assert!(previewer.is_text_extension(Path::new("main.rs")));
+        assert!(!previewer.is_text_extension(Path::new("hcl")));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_fifo_preview() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-special-files-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        // named like a text file to make sure the extension isn't trusted
+        let fifo = directory.join("fifo.txt");
+        let status = std::process::Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(special_file_kind(&fifo), Some("fifo"));
+        assert_eq!(special_file_kind(&directory), None);
+        assert_eq!(special_file_kind(Path::new("/dev/null")), Some("device"));
+
+        let entry = entry::Entry::new(
+            fifo.to_string_lossy().to_string(),
+            entry::PreviewType::Files,
+        );
+        let mut previewer =
+            FilePreviewer::new(Some(FilePreviewerConfig::default()));
+        // nothing ever writes to the fifo, so this would hang if it got
+        // opened
+        let preview = tokio::time::timeout(
+            Duration::from_secs(5),
+            previewer.preview(&entry),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            &preview.content,
+            PreviewContent::SpecialFile(kind) if kind == "fifo"
+        ));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
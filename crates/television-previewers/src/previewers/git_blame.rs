@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::git_diff::git_command;
+
+/// The number of characters of the commit hashes shown in the gutter.
+const SHORT_HASH_LENGTH: usize = 7;
+
+/// Get a blame annotation (short commit hash and age) for each line of the
+/// given file, as reported by `git blame`.
+///
+/// Returns `None` if the file isn't tracked by git or if git couldn't be run.
+pub fn git_blame(path: &Path) -> Option<Vec<String>> {
+    let output = git_command(path)
+        .args(["blame", "--porcelain", "--"])
+        .arg(path.file_name()?)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    Some(parse_porcelain_blame(
+        &String::from_utf8_lossy(&output.stdout),
+        now,
+    ))
+}
+
+/// Parse the output of `git blame --porcelain` into one annotation per line
+/// of the file.
+///
+/// Each line is introduced by a header (`<hash> <original line> <final line>
+/// [<group size>]`), followed by the commit's metadata the first time that
+/// commit appears, and by the content of the line prefixed with a tab.
+fn parse_porcelain_blame(porcelain: &str, now: u64) -> Vec<String> {
+    let mut author_times: HashMap<&str, u64> = HashMap::new();
+    let mut annotations = Vec::new();
+    let mut current_hash = None;
+    for line in porcelain.lines() {
+        if line.starts_with('\t') {
+            if let Some(hash) = current_hash.take() {
+                annotations.push(annotation(
+                    hash,
+                    author_times.get(hash).copied(),
+                    now,
+                ));
+            }
+        } else if let Some(time) = line.strip_prefix("author-time ") {
+            if let (Some(hash), Ok(time)) = (current_hash, time.parse()) {
+                author_times.insert(hash, time);
+            }
+        } else if let Some(hash) = line.split(' ').next() {
+            if hash.len() >= SHORT_HASH_LENGTH
+                && hash.chars().all(|c| c.is_ascii_hexdigit())
+            {
+                current_hash = Some(hash);
+            }
+        }
+    }
+    annotations
+}
+
+fn annotation(hash: &str, author_time: Option<u64>, now: u64) -> String {
+    if hash.chars().all(|c| c == '0') {
+        // lines that haven't been committed yet
+        return format!("{:<SHORT_HASH_LENGTH$} {:>4}", "-------", "now");
+    }
+    let age = author_time
+        .map(|time| relative_age(now.saturating_sub(time)))
+        .unwrap_or_default();
+    format!("{} {age:>4}", &hash[..SHORT_HASH_LENGTH])
+}
+
+/// Format a duration in seconds as a compact relative age (e.g. `5m`, `3d`,
+/// `2y`).
+fn relative_age(seconds: u64) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+    const DAY: u64 = 24 * HOUR;
+    const MONTH: u64 = 30 * DAY;
+    const YEAR: u64 = 365 * DAY;
+    match seconds {
+        s if s < MINUTE => "now".to_string(),
+        s if s < HOUR => format!("{}m", s / MINUTE),
+        s if s < DAY => format!("{}h", s / HOUR),
+        s if s < MONTH => format!("{}d", s / DAY),
+        s if s < YEAR => format!("{}mo", s / MONTH),
+        s => format!("{}y", s / YEAR),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn test_relative_age() {
+        assert_eq!(relative_age(30), "now");
+        assert_eq!(relative_age(5 * 60), "5m");
+        assert_eq!(relative_age(3 * 3600), "3h");
+        assert_eq!(relative_age(3 * 86400), "3d");
+        assert_eq!(relative_age(65 * 86400), "2mo");
+        assert_eq!(relative_age(800 * 86400), "2y");
+    }
+
+    #[test]
+    fn test_parse_porcelain_blame() {
+        let hash_a = "a".repeat(40);
+        let hash_b = "b".repeat(40);
+        let porcelain = format!(
+            "{hash_a} 1 1 2\n\
+             author someone\n\
+             author-time {}\n\
+             filename file.txt\n\
+             \tfirst\n\
+             {hash_a} 2 2\n\
+             \tsecond\n\
+             {hash_b} 1 3 1\n\
+             author someone else\n\
+             author-time {}\n\
+             filename file.txt\n\
+             \tthird\n\
+             {} 4 4 1\n\
+             author Not Committed Yet\n\
+             author-time {NOW}\n\
+             \tfourth\n",
+            NOW - 3 * 86400,
+            NOW - 2 * 3600,
+            "0".repeat(40),
+        );
+        assert_eq!(
+            parse_porcelain_blame(&porcelain, NOW),
+            vec![
+                "aaaaaaa   3d",
+                "aaaaaaa   3d",
+                "bbbbbbb   2h",
+                "-------  now",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_blame_of_fixture_repo() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-git-blame-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&directory)
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        let file = directory.join("file.txt");
+        git(&["init", "--quiet"]);
+        std::fs::write(&file, "one\ntwo\n").unwrap();
+        git(&["add", "file.txt"]);
+        git(&[
+            "-c",
+            "user.name=tv",
+            "-c",
+            "user.email=tv@example.com",
+            "commit",
+            "--quiet",
+            "--message",
+            "initial commit",
+        ]);
+        std::fs::write(&file, "one\ntwo\nthree\n").unwrap();
+
+        let blame = git_blame(&file).unwrap();
+        assert_eq!(blame.len(), 3);
+        assert!(blame[0].ends_with("now"));
+        assert_ne!(blame[0], "-------  now");
+        assert_eq!(blame[0], blame[1]);
+        assert_eq!(blame[2], "-------  now");
+
+        let untracked = directory.join("untracked.txt");
+        std::fs::write(&untracked, "hello\n").unwrap();
+        assert_eq!(git_blame(&untracked), None);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
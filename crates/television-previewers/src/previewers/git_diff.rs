@@ -0,0 +1,170 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use television_utils::strings::preprocess_line_with_tab_width;
+
+use super::command::wait_for_output;
+
+/// Get the uncommitted changes made to the given file, as reported by
+/// `git diff`.
+///
+/// Returns `None` if the file isn't inside a git working tree, if it has no
+/// uncommitted changes or if git couldn't be run. git is killed once
+/// `cancelled` is set.
+pub fn git_diff(path: &Path, cancelled: &AtomicBool) -> Option<String> {
+    let child = git_command(path)
+        .args(["diff", "--no-color", "--no-ext-diff", "--"])
+        .arg(path.file_name()?)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let output = wait_for_output(
+        child,
+        || cancelled.load(Ordering::Relaxed),
+        |child| {
+            let _ = child.kill();
+        },
+    )
+    .ok()??;
+    if !output.status.success() || output.stdout.is_empty() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Build a git command running in the directory containing `path`.
+pub(crate) fn git_command(path: &Path) -> Command {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut command = Command::new("git");
+    command.arg("-C").arg(directory);
+    command
+}
+
+/// Turn the output of `git diff` into styled lines, with added lines in
+/// green and removed lines in red.
+pub fn diff_to_lines(diff: &str, tab_width: usize) -> Vec<Line<'static>> {
+    diff.lines()
+        .map(|line| {
+            let style = if line.starts_with("+++")
+                || line.starts_with("---")
+                || line.starts_with("diff ")
+                || line.starts_with("index ")
+            {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else if line.starts_with("@@") {
+                Style::default().fg(Color::Cyan)
+            } else if line.starts_with('+') {
+                Style::default().fg(Color::Green)
+            } else if line.starts_with('-') {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            Line::styled(
+                preprocess_line_with_tab_width(line, tab_width),
+                style,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A git repository with a single committed file, removed on drop.
+    struct FixtureRepo {
+        directory: PathBuf,
+    }
+
+    impl FixtureRepo {
+        fn new(name: &str) -> Self {
+            let directory = std::env::temp_dir()
+                .join(format!("tv-git-diff-{name}-{}", std::process::id()));
+            std::fs::create_dir_all(&directory).unwrap();
+            let repo = Self { directory };
+            repo.git(&["init", "--quiet"]);
+            std::fs::write(repo.file(), "one\ntwo\nthree\n").unwrap();
+            repo.git(&["add", "file.txt"]);
+            repo.git(&[
+                "-c",
+                "user.name=tv",
+                "-c",
+                "user.email=tv@example.com",
+                "commit",
+                "--quiet",
+                "--message",
+                "initial commit",
+            ]);
+            repo
+        }
+
+        fn git(&self, args: &[&str]) {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&self.directory)
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+
+        fn file(&self) -> PathBuf {
+            self.directory.join("file.txt")
+        }
+    }
+
+    impl Drop for FixtureRepo {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    #[test]
+    fn test_git_diff_of_modified_file() {
+        let repo = FixtureRepo::new("modified");
+        std::fs::write(repo.file(), "one\n2\nthree\n").unwrap();
+
+        let diff = git_diff(&repo.file(), &AtomicBool::new(false)).unwrap();
+        let lines = diff_to_lines(&diff, 4);
+        let removed = lines.iter().find(|l| l.to_string() == "-two").unwrap();
+        assert_eq!(removed.style, Style::default().fg(Color::Red));
+        let added = lines.iter().find(|l| l.to_string() == "+2").unwrap();
+        assert_eq!(added.style, Style::default().fg(Color::Green));
+        let context = lines.iter().find(|l| l.to_string() == " one").unwrap();
+        assert_eq!(context.style, Style::default());
+    }
+
+    #[test]
+    fn test_git_diff_of_unchanged_file() {
+        let repo = FixtureRepo::new("unchanged");
+        assert_eq!(git_diff(&repo.file(), &AtomicBool::new(false)), None);
+    }
+
+    #[test]
+    fn test_cancelled_git_diff() {
+        let repo = FixtureRepo::new("cancelled");
+        std::fs::write(repo.file(), "one\n2\nthree\n").unwrap();
+        assert_eq!(git_diff(&repo.file(), &AtomicBool::new(true)), None);
+    }
+
+    #[test]
+    fn test_git_diff_outside_of_repository() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-git-diff-no-repo-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("file.txt");
+        std::fs::write(&file, "one\n").unwrap();
+        assert_eq!(git_diff(&file, &AtomicBool::new(false)), None);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use flate2::read::GzDecoder;
+use television_utils::encoding::is_utf16;
+use television_utils::strings::is_likely_binary;
+
+use super::archive::ArchiveFormat;
+use super::files::read_unless_cancelled;
+
+/// The number of decompressed bytes looked at to tell text from binary data.
+const SAMPLE_SIZE: u64 = 1024;
+
+/// The decompressed content of a gzip file.
+#[derive(Debug, PartialEq)]
+pub enum GzipContent {
+    Text(Vec<u8>),
+    Binary,
+    /// The decompressed content is larger than the allowed size.
+    TooLarge,
+}
+
+/// Whether the given path looks like a gzip-compressed file, compressed
+/// tarballs excluded.
+pub fn is_gzip_path(path: &Path) -> bool {
+    ArchiveFormat::from_path(path).is_none()
+        && path
+            .extension()
+            .map_or(false, |extension| extension.eq_ignore_ascii_case("gz"))
+}
+
+/// Whether the file at `path` starts with the gzip magic number.
+pub fn has_gzip_magic(path: &Path) -> bool {
+    let mut magic = [0u8; 2];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .map_or(false, |()| magic == [0x1f, 0x8b])
+}
+
+/// The path of the decompressed file, e.g. `app.log` for `app.log.gz`.
+///
+/// This is used to pick the syntax of the decompressed content.
+pub fn decompressed_path(path: &Path) -> PathBuf {
+    path.with_extension("")
+}
+
+/// Decompress the gzip file at `path` if its content looks like text.
+///
+/// At most `max_size` decompressed bytes are read so that decompression
+/// bombs don't exhaust memory. Decompression stops with an `Interrupted`
+/// error once `cancelled` is set.
+pub fn read_gzip_text(
+    path: &Path,
+    max_size: u64,
+    cancelled: &AtomicBool,
+) -> io::Result<GzipContent> {
+    let file = File::open(path)?;
+    decompress_text(GzDecoder::new(BufReader::new(file)), max_size, cancelled)
+}
+
+fn decompress_text(
+    reader: impl Read,
+    max_size: u64,
+    cancelled: &AtomicBool,
+) -> io::Result<GzipContent> {
+    let mut reader = reader.take(max_size.saturating_add(1));
+    let mut content = Vec::new();
+    reader
+        .by_ref()
+        .take(SAMPLE_SIZE)
+        .read_to_end(&mut content)?;
+    if !is_utf16(&content) && is_likely_binary(&content) {
+        return Ok(GzipContent::Binary);
+    }
+    content.extend(read_unless_cancelled(reader, cancelled)?);
+    if content.len() as u64 > max_size {
+        return Ok(GzipContent::TooLarge);
+    }
+    Ok(GzipContent::Text(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::{Cursor, Write};
+
+    fn gzip(content: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn decompress(compressed: Vec<u8>, max_size: u64) -> GzipContent {
+        decompress_text(
+            GzDecoder::new(Cursor::new(compressed)),
+            max_size,
+            &AtomicBool::new(false),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_gzipped_text() {
+        let log = "2024-01-01 12:00:00 INFO started\n".repeat(100);
+        assert_eq!(
+            decompress(gzip(log.as_bytes()), 1024 * 1024),
+            GzipContent::Text(log.into_bytes())
+        );
+        assert_eq!(decompress(gzip(b""), 1024), GzipContent::Text(Vec::new()));
+    }
+
+    #[test]
+    fn test_gzipped_binary() {
+        let binary: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        assert_eq!(
+            decompress(gzip(&binary), 1024 * 1024),
+            GzipContent::Binary
+        );
+    }
+
+    #[test]
+    fn test_decompression_bomb() {
+        // a few KB that decompress to 10 MB
+        let compressed = gzip(&b"a".repeat(10 * 1024 * 1024));
+        assert!(compressed.len() < 64 * 1024);
+        assert_eq!(decompress(compressed, 1024 * 1024), GzipContent::TooLarge);
+    }
+
+    #[test]
+    fn test_not_gzip() {
+        assert!(decompress_text(
+            GzDecoder::new(Cursor::new(b"plain text".to_vec())),
+            1024,
+            &AtomicBool::new(false),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_cancelled_decompression() {
+        let log = "2024-01-01 12:00:00 INFO started\n".repeat(100);
+        let error = decompress_text(
+            GzDecoder::new(Cursor::new(gzip(log.as_bytes()))),
+            1024 * 1024,
+            &AtomicBool::new(true),
+        )
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+    }
+
+    #[test]
+    fn test_gzip_magic() {
+        let path = std::env::temp_dir()
+            .join(format!("tv-gzip-magic-{}.gz", std::process::id()));
+        std::fs::write(&path, gzip(b"text")).unwrap();
+        assert!(has_gzip_magic(&path));
+        std::fs::write(&path, b"text").unwrap();
+        assert!(!has_gzip_magic(&path));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_gzip_paths() {
+        assert!(is_gzip_path(Path::new("logs/app.log.gz")));
+        assert!(is_gzip_path(Path::new("DATA.JSON.GZ")));
+        assert!(!is_gzip_path(Path::new("archive.tar.gz")));
+        assert!(!is_gzip_path(Path::new("app.log")));
+        assert_eq!(
+            decompressed_path(Path::new("logs/app.log.gz")),
+            PathBuf::from("logs/app.log")
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use syntect::{highlighting::Theme, parsing::SyntaxSet};
+use tracing::{debug, warn};
+
+use television_utils::syntax::{
+    self, load_highlighting_assets, HighlightingAssetsExt,
+};
+
+/// The syntaxes used to highlight text previews, only loaded the first time
+/// they are needed since deserializing them takes a while.
+#[derive(Debug, Default)]
+pub struct LazySyntaxSet {
+    /// A directory containing additional `.sublime-syntax` definitions.
+    directory: Option<PathBuf>,
+    syntax_set: OnceLock<SyntaxSet>,
+}
+
+impl LazySyntaxSet {
+    pub fn new(directory: Option<PathBuf>) -> Self {
+        LazySyntaxSet {
+            directory,
+            syntax_set: OnceLock::new(),
+        }
+    }
+
+    /// The syntaxes, loading them if they haven't been yet.
+    pub fn get(&self) -> &SyntaxSet {
+        self.syntax_set.get_or_init(|| {
+            debug!("Loading the syntax set");
+            let hl_assets = load_highlighting_assets();
+            let bundled_syntax_set = hl_assets.get_syntax_set().unwrap();
+            match &self.directory {
+                Some(directory) => {
+                    syntax::load_user_syntaxes(bundled_syntax_set, directory)
+                        .unwrap_or_else(|e| {
+                            warn!(
+                                "Could not load syntaxes from {:?}: {}",
+                                directory, e
+                            );
+                            bundled_syntax_set.clone()
+                        })
+                }
+                None => bundled_syntax_set.clone(),
+            }
+        })
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.syntax_set.get().is_some()
+    }
+}
+
+/// The theme used to highlight text previews, only loaded the first time it
+/// is needed.
+#[derive(Debug, Default)]
+pub struct LazyTheme {
+    /// The name of a bundled theme or the path to a `.tmTheme` file, the
+    /// default theme being used if unset.
+    name: Option<String>,
+    theme: OnceLock<Theme>,
+}
+
+impl LazyTheme {
+    pub fn new(name: Option<String>) -> Self {
+        LazyTheme {
+            name,
+            theme: OnceLock::new(),
+        }
+    }
+
+    /// The theme, loading it if it hasn't been yet.
+    pub fn get(&self) -> &Theme {
+        self.theme.get_or_init(|| match &self.name {
+            Some(name) => load_highlighting_assets().load_theme(name),
+            None => syntax::default_theme(),
+        })
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.theme.get().is_some()
+    }
+}
+
+impl From<Theme> for LazyTheme {
+    fn from(theme: Theme) -> Self {
+        LazyTheme {
+            name: None,
+            theme: OnceLock::from(theme),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loaded_on_first_use() {
+        let syntax_set = LazySyntaxSet::new(None);
+        assert!(!syntax_set.is_loaded());
+        assert!(syntax_set.get().find_syntax_by_extension("rs").is_some());
+        assert!(syntax_set.is_loaded());
+
+        let theme = LazyTheme::new(None);
+        assert!(!theme.is_loaded());
+        theme.get();
+        assert!(theme.is_loaded());
+        assert!(LazyTheme::from(syntax::default_theme()).is_loaded());
+    }
+}
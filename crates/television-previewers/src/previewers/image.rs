@@ -0,0 +1,1049 @@
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression, Crc};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+/// The color transparent pixels are blended with by default.
+pub const DEFAULT_IMAGE_BACKGROUND: [u8; 3] = [255, 0, 255];
+
+/// Decoded images are downscaled to fit within this many pixels on each
+/// side, which is plenty for a preview pane and bounds the memory they use.
+const MAX_IMAGE_SIDE: u32 = 1024;
+
+/// Images with more pixels than this aren't decoded at all.
+const MAX_SOURCE_PIXELS: u64 = 8192 * 8192;
+
+/// The number of rows decoded between two checks of whether the preview was
+/// cancelled.
+const CANCELLATION_CHECK_ROWS: u32 = 64;
+
+/// The size of the chunks Kitty images are transmitted in.
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Delete every image drawn with the Kitty graphics protocol, which unlike
+/// other protocols aren't cleared by drawing text over them.
+pub const KITTY_DELETE_IMAGES: &str = "\x1b_Ga=d,d=A,q=2\x1b\\";
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// How images are drawn in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageProtocol {
+    /// The Kitty graphics protocol (Kitty, Ghostty).
+    Kitty,
+    /// The inline images protocol of iTerm2 (iTerm2, WezTerm).
+    Iterm2,
+    /// Sixel graphics (foot, mlterm, contour, ...).
+    Sixel,
+    /// Unicode half blocks colored with the pixels, two pixels per cell,
+    /// which works in any terminal supporting true colors.
+    Halfblocks,
+}
+
+impl ImageProtocol {
+    /// Guess the protocol supported by the terminal from the environment,
+    /// falling back to half blocks.
+    pub fn detect() -> Self {
+        Self::from_env(|name| std::env::var(name).ok())
+    }
+
+    /// Guess the protocol supported by the terminal from the environment
+    /// variables returned by `var`.
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+        // multiplexers don't pass graphics through by default
+        if var("TMUX").is_some() || term.starts_with("screen") {
+            return ImageProtocol::Halfblocks;
+        }
+        if var("KITTY_WINDOW_ID").is_some()
+            || term == "xterm-kitty"
+            || term == "xterm-ghostty"
+            || program == "ghostty"
+        {
+            ImageProtocol::Kitty
+        } else if program == "iTerm.app"
+            || program == "WezTerm"
+            || var("LC_TERMINAL").is_some_and(|t| t == "iTerm2")
+        {
+            ImageProtocol::Iterm2
+        } else if ["foot", "mlterm", "contour", "yaft"]
+            .iter()
+            .any(|sixel_term| term.starts_with(sixel_term))
+        {
+            ImageProtocol::Sixel
+        } else {
+            ImageProtocol::Halfblocks
+        }
+    }
+}
+
+impl FromStr for ImageProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kitty" => Ok(ImageProtocol::Kitty),
+            "iterm2" => Ok(ImageProtocol::Iterm2),
+            "sixel" => Ok(ImageProtocol::Sixel),
+            "halfblocks" => Ok(ImageProtocol::Halfblocks),
+            _ => Err(format!("unknown image protocol `{s}`")),
+        }
+    }
+}
+
+/// A decoded image, with transparent pixels blended with a background.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// The RGB pixels, row by row.
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    /// The size of the largest image fitting within `max_width` by
+    /// `max_height` pixels with the same aspect ratio, images are never
+    /// scaled up.
+    pub fn fit(&self, max_width: u32, max_height: u32) -> (u32, u32) {
+        fit(self.width, self.height, max_width, max_height)
+    }
+
+    /// Resize the image by averaging the pixels covered by each new pixel.
+    pub fn resize(&self, width: u32, height: u32) -> Image {
+        let width = width.max(1);
+        let height = height.max(1);
+        let span = |i: u32, size: u32, new_size: u32| {
+            let start = scale(i, size, new_size);
+            let end = scale(i + 1, size, new_size).max(start + 1);
+            start..end.min(size)
+        };
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            let rows = span(y, self.height, height);
+            for x in 0..width {
+                let columns = span(x, self.width, width);
+                let mut sum = [0u32; 4];
+                for row in rows.clone() {
+                    for column in columns.clone() {
+                        let pixel = self.pixel(column, row);
+                        sum[0] += u32::from(pixel[0]);
+                        sum[1] += u32::from(pixel[1]);
+                        sum[2] += u32::from(pixel[2]);
+                        sum[3] += 1;
+                    }
+                }
+                pixels.push(average(sum));
+            }
+        }
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// The memory used by the pixels of the image, in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.pixels.len() * 3
+    }
+
+    /// The raw RGB bytes of the image.
+    fn rgb_bytes(&self) -> Vec<u8> {
+        self.pixels.iter().flatten().copied().collect()
+    }
+}
+
+/// `i * new_size / size`, without overflowing.
+fn scale(i: u32, new_size: u32, size: u32) -> u32 {
+    (u64::from(i) * u64::from(new_size) / u64::from(size)) as u32
+}
+
+fn average([r, g, b, count]: [u32; 4]) -> [u8; 3] {
+    if count == 0 {
+        return [0; 3];
+    }
+    let channel = |sum: u32| ((sum + count / 2) / count) as u8;
+    [channel(r), channel(g), channel(b)]
+}
+
+fn fit(
+    width: u32,
+    height: u32,
+    max_width: u32,
+    max_height: u32,
+) -> (u32, u32) {
+    if width <= max_width && height <= max_height {
+        return (width, height);
+    }
+    let (width, height) = (u64::from(width), u64::from(height));
+    let (max_width, max_height) =
+        (u64::from(max_width), u64::from(max_height));
+    let (width, height) = if width * max_height > height * max_width {
+        (max_width, height * max_width / width)
+    } else {
+        (width * max_height / height, max_height)
+    };
+    (width.max(1) as u32, height.max(1) as u32)
+}
+
+/// Builds an image of at most `MAX_IMAGE_SIDE` pixels on each side out of
+/// the pixels of a larger one, one pixel at a time.
+struct Downscaler {
+    source_width: u32,
+    source_height: u32,
+    width: u32,
+    height: u32,
+    background: [u8; 3],
+    sums: Vec<[u32; 4]>,
+}
+
+impl Downscaler {
+    fn new(
+        source_width: u32,
+        source_height: u32,
+        background: [u8; 3],
+    ) -> io::Result<Self> {
+        if source_width == 0
+            || source_height == 0
+            || u64::from(source_width) * u64::from(source_height)
+                > MAX_SOURCE_PIXELS
+        {
+            return Err(invalid_data("invalid image size"));
+        }
+        let (width, height) =
+            fit(source_width, source_height, MAX_IMAGE_SIDE, MAX_IMAGE_SIDE);
+        Ok(Self {
+            source_width,
+            source_height,
+            width,
+            height,
+            background,
+            sums: vec![[0; 4]; (width * height) as usize],
+        })
+    }
+
+    /// Add a pixel, blending it with the background using its alpha.
+    fn add(&mut self, x: u32, y: u32, [r, g, b, a]: [u8; 4]) {
+        let blend = |channel: u8, background: u8| {
+            (u32::from(channel) * u32::from(a)
+                + u32::from(background) * (255 - u32::from(a))
+                + 127)
+                / 255
+        };
+        let column = scale(x, self.width, self.source_width);
+        let row = scale(y, self.height, self.source_height);
+        let sum = &mut self.sums[(row * self.width + column) as usize];
+        sum[0] += blend(r, self.background[0]);
+        sum[1] += blend(g, self.background[1]);
+        sum[2] += blend(b, self.background[2]);
+        sum[3] += 1;
+    }
+
+    fn finish(self) -> Image {
+        Image {
+            width: self.width,
+            height: self.height,
+            pixels: self.sums.into_iter().map(average).collect(),
+        }
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn check_cancelled(row: u32, cancelled: &AtomicBool) -> io::Result<()> {
+    if row % CANCELLATION_CHECK_ROWS == 0 && cancelled.load(Ordering::Relaxed)
+    {
+        return Err(io::ErrorKind::Interrupted.into());
+    }
+    Ok(())
+}
+
+/// Decode a PNG or BMP image, blending its transparent pixels with
+/// `background`.
+///
+/// Other formats (and interlaced PNGs) fail with `Unsupported`, decoding
+/// stops with `Interrupted` once `cancelled` is set.
+pub fn decode_image(
+    bytes: &[u8],
+    background: [u8; 3],
+    cancelled: &AtomicBool,
+) -> io::Result<Image> {
+    if bytes.starts_with(PNG_SIGNATURE) {
+        decode_png(bytes, background, cancelled)
+    } else if bytes.starts_with(b"BM") {
+        decode_bmp(bytes, background, cancelled)
+    } else {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// The color information of a PNG image.
+struct PngInfo {
+    bit_depth: u8,
+    color_type: u8,
+    palette: Vec<[u8; 3]>,
+    transparency: Vec<u8>,
+}
+
+impl PngInfo {
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    /// Read the pixel at column `x` of an unfiltered row.
+    fn pixel(&self, row: &[u8], x: usize) -> [u8; 4] {
+        let channels = self.channels();
+        let sample = |channel: usize| {
+            png_sample(row, x * channels + channel, self.bit_depth)
+        };
+        let to_u8 = |value: u16| match self.bit_depth {
+            16 => (value >> 8) as u8,
+            8 => value as u8,
+            depth => (u32::from(value) * 255 / ((1 << depth) - 1)) as u8,
+        };
+        // the transparent color of images without alpha channel
+        let key = |channel: usize| {
+            self.transparency
+                .get(2 * channel..2 * channel + 2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        };
+        match self.color_type {
+            0 => {
+                let gray = sample(0);
+                let alpha = if key(0) == Some(gray) { 0 } else { 255 };
+                let gray = to_u8(gray);
+                [gray, gray, gray, alpha]
+            }
+            2 => {
+                let rgb = [sample(0), sample(1), sample(2)];
+                let transparent = (0..3).all(|c| key(c) == Some(rgb[c]));
+                let alpha = if transparent { 0 } else { 255 };
+                [to_u8(rgb[0]), to_u8(rgb[1]), to_u8(rgb[2]), alpha]
+            }
+            3 => {
+                let index = usize::from(sample(0));
+                let [r, g, b] =
+                    self.palette.get(index).copied().unwrap_or_default();
+                let alpha =
+                    self.transparency.get(index).copied().unwrap_or(255);
+                [r, g, b, alpha]
+            }
+            4 => {
+                let gray = to_u8(sample(0));
+                [gray, gray, gray, to_u8(sample(1))]
+            }
+            _ => [
+                to_u8(sample(0)),
+                to_u8(sample(1)),
+                to_u8(sample(2)),
+                to_u8(sample(3)),
+            ],
+        }
+    }
+}
+
+/// Read the `index`-th sample of a row of samples of `bit_depth` bits.
+fn png_sample(row: &[u8], index: usize, bit_depth: u8) -> u16 {
+    match bit_depth {
+        16 => u16::from_be_bytes([row[2 * index], row[2 * index + 1]]),
+        8 => u16::from(row[index]),
+        depth => {
+            let per_byte = usize::from(8 / depth);
+            let shift = 8 - depth * (index % per_byte + 1) as u8;
+            u16::from((row[index / per_byte] >> shift) & ((1 << depth) - 1))
+        }
+    }
+}
+
+fn decode_png(
+    bytes: &[u8],
+    background: [u8; 3],
+    cancelled: &AtomicBool,
+) -> io::Result<Image> {
+    let mut info = None;
+    let mut palette = Vec::new();
+    let mut transparency = Vec::new();
+    let mut data = Vec::new();
+    let mut position = PNG_SIGNATURE.len();
+    loop {
+        let header = bytes
+            .get(position..position + 8)
+            .ok_or_else(|| invalid_data("truncated PNG"))?;
+        let length =
+            u32::from_be_bytes([header[0], header[1], header[2], header[3]])
+                as usize;
+        let chunk = bytes
+            .get(position + 8..)
+            .and_then(|rest| rest.get(..length))
+            .ok_or_else(|| invalid_data("truncated PNG"))?;
+        match &header[4..8] {
+            b"IHDR" if chunk.len() >= 13 => {
+                let be_u32 = |i: usize| {
+                    u32::from_be_bytes([
+                        chunk[i],
+                        chunk[i + 1],
+                        chunk[i + 2],
+                        chunk[i + 3],
+                    ])
+                };
+                info = Some((be_u32(0), be_u32(4), chunk[8], chunk[9]));
+                if chunk[12] != 0 {
+                    return Err(io::ErrorKind::Unsupported.into());
+                }
+            }
+            b"PLTE" => {
+                palette = chunk
+                    .chunks_exact(3)
+                    .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+                    .collect();
+            }
+            b"tRNS" => transparency = chunk.to_vec(),
+            b"IDAT" => data.extend_from_slice(chunk),
+            b"IEND" => break,
+            _ => {}
+        }
+        // the length, type and CRC of the chunk
+        position += length + 12;
+    }
+    let (width, height, bit_depth, color_type) =
+        info.ok_or_else(|| invalid_data("missing PNG header"))?;
+    let valid_depth = match color_type {
+        0 => matches!(bit_depth, 1 | 2 | 4 | 8 | 16),
+        3 => matches!(bit_depth, 1 | 2 | 4 | 8),
+        2 | 4 | 6 => matches!(bit_depth, 8 | 16),
+        _ => false,
+    };
+    if !valid_depth {
+        return Err(invalid_data("invalid PNG color type or bit depth"));
+    }
+    let info = PngInfo {
+        bit_depth,
+        color_type,
+        palette,
+        transparency,
+    };
+    let mut downscaler = Downscaler::new(width, height, background)?;
+
+    let bits_per_pixel = info.channels() * usize::from(bit_depth);
+    let stride = (width as usize * bits_per_pixel).div_ceil(8);
+    // the distance to the corresponding byte of the previous pixel
+    let distance = bits_per_pixel.div_ceil(8);
+    let mut decoder = ZlibDecoder::new(data.as_slice());
+    let mut previous = vec![0u8; stride];
+    let mut row = vec![0u8; stride + 1];
+    for y in 0..height {
+        check_cancelled(y, cancelled)?;
+        decoder
+            .read_exact(&mut row)
+            .map_err(|_| invalid_data("truncated PNG data"))?;
+        unfilter(row[0], &mut row[1..], &previous, distance)?;
+        for x in 0..width {
+            downscaler.add(x, y, info.pixel(&row[1..], x as usize));
+        }
+        previous.copy_from_slice(&row[1..]);
+    }
+    Ok(downscaler.finish())
+}
+
+/// Reverse the filter applied to a row of a PNG image.
+fn unfilter(
+    filter: u8,
+    row: &mut [u8],
+    previous: &[u8],
+    distance: usize,
+) -> io::Result<()> {
+    for i in 0..row.len() {
+        let left = if i >= distance { row[i - distance] } else { 0 };
+        let up = previous[i];
+        let up_left = if i >= distance {
+            previous[i - distance]
+        } else {
+            0
+        };
+        let predictor = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => return Err(invalid_data("invalid PNG filter")),
+        };
+        row[i] = row[i].wrapping_add(predictor);
+    }
+    Ok(())
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = i16::from(left) + i16::from(up) - i16::from(up_left);
+    let distance = |value: u8| (estimate - i16::from(value)).abs();
+    if distance(left) <= distance(up) && distance(left) <= distance(up_left) {
+        left
+    } else if distance(up) <= distance(up_left) {
+        up
+    } else {
+        up_left
+    }
+}
+
+fn decode_bmp(
+    bytes: &[u8],
+    background: [u8; 3],
+    cancelled: &AtomicBool,
+) -> io::Result<Image> {
+    let le_u16 = |i: usize| {
+        bytes
+            .get(i..i + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or_else(|| invalid_data("truncated BMP"))
+    };
+    let le_u32 = |i: usize| {
+        bytes
+            .get(i..i + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| invalid_data("truncated BMP"))
+    };
+    let pixels_offset = le_u32(10)? as usize;
+    let header_size = le_u32(14)? as usize;
+    if header_size < 40 {
+        return Err(io::ErrorKind::Unsupported.into());
+    }
+    let width = le_u32(18)? as i32;
+    // the height is negative for top-down bitmaps
+    let height = le_u32(22)? as i32;
+    let bits_per_pixel = le_u16(28)?;
+    let compression = le_u32(30)?;
+    let uncompressed =
+        compression == 0 || (compression == 3 && bits_per_pixel == 32);
+    if width <= 0 || !uncompressed || !matches!(bits_per_pixel, 8 | 24 | 32) {
+        return Err(io::ErrorKind::Unsupported.into());
+    }
+    let (width, top_down) = (width.unsigned_abs(), height < 0);
+    let height = height.unsigned_abs();
+    let palette = if bits_per_pixel == 8 {
+        let colors = match le_u32(46)? {
+            0 => 256,
+            colors => colors.min(256) as usize,
+        };
+        let start = 14 + header_size;
+        bytes
+            .get(start..start + 4 * colors)
+            .ok_or_else(|| invalid_data("truncated BMP palette"))?
+            .chunks_exact(4)
+            .map(|bgr| [bgr[2], bgr[1], bgr[0], 255])
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let mut downscaler = Downscaler::new(width, height, background)?;
+
+    let bytes_per_pixel = usize::from(bits_per_pixel / 8);
+    // rows are padded to multiples of 4 bytes
+    let stride = (width as usize * bytes_per_pixel).div_ceil(4) * 4;
+    for (i, y) in (0..height).enumerate() {
+        check_cancelled(y, cancelled)?;
+        let start = pixels_offset + i * stride;
+        let row = bytes
+            .get(start..start + width as usize * bytes_per_pixel)
+            .ok_or_else(|| invalid_data("truncated BMP pixels"))?;
+        let y = if top_down { y } else { height - 1 - y };
+        for (x, pixel) in row.chunks_exact(bytes_per_pixel).enumerate() {
+            let pixel = match pixel {
+                [index] => palette
+                    .get(usize::from(*index))
+                    .copied()
+                    .unwrap_or([0, 0, 0, 255]),
+                // the alpha of 32 bits bitmaps is rarely meaningful
+                [b, g, r, ..] => [*r, *g, *b, 255],
+                _ => unreachable!(),
+            };
+            downscaler.add(x as u32, y, pixel);
+        }
+    }
+    Ok(downscaler.finish())
+}
+
+/// Encode an image as an RGB PNG.
+fn encode_png(image: &Image) -> Vec<u8> {
+    let mut rows =
+        Vec::with_capacity(image.pixels.len() * 3 + image.height as usize);
+    for row in image.pixels.chunks(image.width as usize) {
+        // no filter
+        rows.push(0);
+        rows.extend(row.iter().flatten());
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+    // writing to a vector can't fail
+    let _ = encoder.write_all(&rows);
+    let data = encoder.finish().unwrap_or_default();
+
+    let mut header = Vec::with_capacity(13);
+    header.extend(image.width.to_be_bytes());
+    header.extend(image.height.to_be_bytes());
+    // 8 bits RGB, default compression and filters, not interlaced
+    header.extend([8, 2, 0, 0, 0]);
+
+    let mut png = PNG_SIGNATURE.to_vec();
+    for (kind, chunk) in [
+        (b"IHDR", header.as_slice()),
+        (b"IDAT", &data),
+        (b"IEND", &[]),
+    ] {
+        png.extend((chunk.len() as u32).to_be_bytes());
+        png.extend(kind);
+        png.extend(chunk);
+        let mut crc = Crc::new();
+        crc.update(kind);
+        crc.update(chunk);
+        png.extend(crc.sum().to_be_bytes());
+    }
+    png
+}
+
+/// Render an image as lines of half blocks fitting within `columns` by
+/// `rows` cells, each cell showing two pixels on top of each other.
+pub fn halfblocks(
+    image: &Image,
+    columns: u16,
+    rows: u16,
+) -> Vec<Line<'static>> {
+    let (width, height) = image.fit(u32::from(columns), 2 * u32::from(rows));
+    let image = image.resize(width, height);
+    let color = |[r, g, b]: [u8; 3]| Color::Rgb(r, g, b);
+    (0..height)
+        .step_by(2)
+        .map(|y| {
+            Line::from(
+                (0..width)
+                    .map(|x| {
+                        let mut style =
+                            Style::default().fg(color(image.pixel(x, y)));
+                        if y + 1 < height {
+                            style = style.bg(color(image.pixel(x, y + 1)));
+                        }
+                        Span::styled("▀", style)
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+/// Build the escape sequence drawing an image with a graphics protocol at
+/// the position of the cursor, fitting within `columns` by `rows` cells of
+/// `cell_size` pixels (width, height).
+///
+/// Returns `None` for `ImageProtocol::Halfblocks`, see `halfblocks`.
+pub fn graphics_sequence(
+    protocol: ImageProtocol,
+    image: &Image,
+    columns: u16,
+    rows: u16,
+    cell_size: (u16, u16),
+) -> Option<String> {
+    let (cell_width, cell_height) =
+        (u32::from(cell_size.0.max(1)), u32::from(cell_size.1.max(1)));
+    let (width, height) = image.fit(
+        u32::from(columns) * cell_width,
+        u32::from(rows) * cell_height,
+    );
+    let image = image.resize(width, height);
+    let columns = width.div_ceil(cell_width);
+    let rows = height.div_ceil(cell_height);
+    match protocol {
+        ImageProtocol::Kitty => Some(kitty_sequence(&image, columns, rows)),
+        ImageProtocol::Iterm2 => Some(iterm2_sequence(&image, columns, rows)),
+        ImageProtocol::Sixel => Some(sixel_sequence(&image)),
+        ImageProtocol::Halfblocks => None,
+    }
+}
+
+fn kitty_sequence(image: &Image, columns: u32, rows: u32) -> String {
+    let payload = STANDARD.encode(image.rgb_bytes());
+    let chunks: Vec<&str> = payload
+        .as_bytes()
+        .chunks(KITTY_CHUNK_SIZE)
+        // base64 is ASCII
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+    let mut sequence = String::with_capacity(payload.len() + 64);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            // transmit and display 24 bits pixels, without moving the
+            // cursor or getting a response
+            let _ = write!(
+                sequence,
+                "\x1b_Ga=T,f=24,s={},v={},c={columns},r={rows},C=1,q=2,m={more};{chunk}\x1b\\",
+                image.width, image.height
+            );
+        } else {
+            let _ = write!(sequence, "\x1b_Gm={more};{chunk}\x1b\\");
+        }
+    }
+    sequence
+}
+
+fn iterm2_sequence(image: &Image, columns: u32, rows: u32) -> String {
+    let png = encode_png(image);
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={columns};height={rows};preserveAspectRatio=1;doNotMoveCursor=1:{}\x07",
+        png.len(),
+        STANDARD.encode(&png)
+    )
+}
+
+/// Map a color to the closest one of a 6x6x6 color cube.
+fn sixel_color_index([r, g, b]: [u8; 3]) -> usize {
+    let level = |channel: u8| (usize::from(channel) * 5 + 127) / 255;
+    level(r) * 36 + level(g) * 6 + level(b)
+}
+
+fn sixel_sequence(image: &Image) -> String {
+    let mut sequence = format!("\x1bPq\"1;1;{};{}", image.width, image.height);
+    // a 6x6x6 color cube, with components in percents
+    for index in 0..216 {
+        let percent = |level: usize| level * 100 / 5;
+        let _ = write!(
+            sequence,
+            "#{index};2;{};{};{}",
+            percent(index / 36),
+            percent(index / 6 % 6),
+            percent(index % 6)
+        );
+    }
+    for band in (0..image.height).step_by(6) {
+        // the sixels of each color used in the band, a bit per row
+        let mut colors: Vec<Option<Vec<u8>>> = vec![None; 216];
+        for row in band..(band + 6).min(image.height) {
+            for x in 0..image.width {
+                let index = sixel_color_index(image.pixel(x, row));
+                let sixels = colors[index]
+                    .get_or_insert_with(|| vec![0; image.width as usize]);
+                sixels[x as usize] |= 1 << (row - band);
+            }
+        }
+        for (index, sixels) in colors.iter().enumerate() {
+            let Some(sixels) = sixels else { continue };
+            let _ = write!(sequence, "#{index}");
+            let mut x = 0;
+            while x < sixels.len() {
+                let run = sixels[x..]
+                    .iter()
+                    .take_while(|&&sixel| sixel == sixels[x])
+                    .count();
+                let character = char::from(63 + sixels[x]);
+                if run > 3 {
+                    let _ = write!(sequence, "!{run}{character}");
+                } else {
+                    sequence.extend(std::iter::repeat(character).take(run));
+                }
+                x += run;
+            }
+            // back to the start of the band
+            sequence.push('$');
+        }
+        sequence.push('-');
+    }
+    sequence.push_str("\x1b\\");
+    sequence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static NOT_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/previewers/fixtures/tiny.png"
+    );
+
+    fn image(width: u32, height: u32, pixels: Vec<[u8; 3]>) -> Image {
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    #[test]
+    fn test_decode_png_fixture() {
+        // a 3x2 RGBA image using the sub and paeth filters, the last pixel
+        // transparent
+        let bytes = std::fs::read(FIXTURE).unwrap();
+        let image = decode_image(&bytes, [0, 0, 255], &NOT_CANCELLED).unwrap();
+        assert_eq!(
+            image,
+            Image {
+                width: 3,
+                height: 2,
+                pixels: vec![
+                    [255, 0, 0],
+                    [0, 255, 0],
+                    [0, 0, 255],
+                    [255, 255, 255],
+                    [128, 128, 128],
+                    [0, 0, 255],
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_unfilter() {
+        let previous = [10, 20, 30, 40];
+        let mut up = [1, 2, 3, 4];
+        unfilter(2, &mut up, &previous, 2).unwrap();
+        assert_eq!(up, [11, 22, 33, 44]);
+        let mut avg = [1, 2, 3, 4];
+        unfilter(3, &mut avg, &previous, 2).unwrap();
+        assert_eq!(avg, [6, 12, 21, 30]);
+        assert!(unfilter(5, &mut avg, &previous, 2).is_err());
+    }
+
+    #[test]
+    fn test_png_round_trip() {
+        let original =
+            image(2, 2, vec![[1, 2, 3], [4, 5, 6], [7, 8, 9], [10, 11, 12]]);
+        let decoded =
+            decode_image(&encode_png(&original), [0; 3], &NOT_CANCELLED)
+                .unwrap();
+        assert_eq!(decoded, original);
+    }
+
+    #[test]
+    fn test_png_samples() {
+        assert_eq!(png_sample(&[0b1011_0010], 0, 1), 1);
+        assert_eq!(png_sample(&[0b1011_0010], 1, 1), 0);
+        assert_eq!(png_sample(&[0b1011_0010], 1, 2), 0b11);
+        assert_eq!(png_sample(&[0b1011_0010], 1, 4), 0b0010);
+        assert_eq!(png_sample(&[0x12, 0x34], 0, 16), 0x1234);
+    }
+
+    /// Build a 24 bits bitmap from its rows, bottom row first.
+    fn bmp(width: u32, rows: &[&[[u8; 3]]]) -> Vec<u8> {
+        let stride = (width as usize * 3).div_ceil(4) * 4;
+        let mut bytes = b"BM".to_vec();
+        bytes.extend([0; 8]);
+        bytes.extend(54u32.to_le_bytes());
+        bytes.extend(40u32.to_le_bytes());
+        bytes.extend(width.to_le_bytes());
+        bytes.extend((rows.len() as u32).to_le_bytes());
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(24u16.to_le_bytes());
+        bytes.extend([0; 24]);
+        for row in rows {
+            let start = bytes.len();
+            for [r, g, b] in row.iter() {
+                bytes.extend([*b, *g, *r]);
+            }
+            bytes.resize(start + stride, 0);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_decode_bmp() {
+        let bytes =
+            bmp(2, &[&[[1, 2, 3], [4, 5, 6]], &[[7, 8, 9], [10, 11, 12]]]);
+        let image = decode_image(&bytes, [0; 3], &NOT_CANCELLED).unwrap();
+        // bottom-up
+        assert_eq!(
+            image,
+            Image {
+                width: 2,
+                height: 2,
+                pixels: vec![[7, 8, 9], [10, 11, 12], [1, 2, 3], [4, 5, 6]],
+            }
+        );
+    }
+
+    #[test]
+    fn test_unsupported_images() {
+        let jpeg = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/previewers/fixtures/exif.jpg"
+        ))
+        .unwrap();
+        assert_eq!(
+            decode_image(&jpeg, [0; 3], &NOT_CANCELLED)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::Unsupported
+        );
+        let truncated = &std::fs::read(FIXTURE).unwrap()[..40];
+        assert_eq!(
+            decode_image(truncated, [0; 3], &NOT_CANCELLED)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_cancelled_decoding() {
+        let bytes = std::fs::read(FIXTURE).unwrap();
+        assert_eq!(
+            decode_image(&bytes, [0; 3], &AtomicBool::new(true))
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::Interrupted
+        );
+    }
+
+    #[test]
+    fn test_large_images_are_downscaled() {
+        let mut downscaler = Downscaler::new(4096, 2048, [0; 3]).unwrap();
+        downscaler.add(0, 0, [255, 255, 255, 255]);
+        let image = downscaler.finish();
+        assert_eq!((image.width, image.height), (1024, 512));
+        // the average of the 4x4 pixels of the first pixel
+        assert_eq!(image.pixel(0, 0), [255; 3]);
+        assert!(Downscaler::new(100_000, 100_000, [0; 3]).is_err());
+    }
+
+    #[test]
+    fn test_fit() {
+        let image = image(200, 100, vec![[0; 3]; 200 * 100]);
+        assert_eq!(image.fit(100, 100), (100, 50));
+        assert_eq!(image.fit(400, 20), (40, 20));
+        // never scaled up
+        assert_eq!(image.fit(1000, 1000), (200, 100));
+    }
+
+    #[test]
+    fn test_resize() {
+        let original = image(2, 2, vec![[0; 3], [100; 3], [200; 3], [40; 3]]);
+        assert_eq!(original.resize(1, 1), image(1, 1, vec![[85; 3]]));
+        assert_eq!(
+            original.resize(2, 1),
+            image(2, 1, vec![[100; 3], [70; 3]])
+        );
+    }
+
+    #[test]
+    fn test_halfblocks() {
+        let image =
+            image(2, 3, vec![[1; 3], [2; 3], [3; 3], [4; 3], [5; 3], [6; 3]]);
+        let lines = halfblocks(&image, 10, 10);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].to_string(), "▀▀");
+        assert_eq!(
+            lines[0].spans[1].style,
+            Style::default()
+                .fg(Color::Rgb(2, 2, 2))
+                .bg(Color::Rgb(4, 4, 4))
+        );
+        // the last row of pixels has no pixels below it
+        assert_eq!(
+            lines[1].spans[0].style,
+            Style::default().fg(Color::Rgb(5, 5, 5))
+        );
+        // downscaled to fit
+        assert_eq!(halfblocks(&image, 1, 1)[0].spans.len(), 1);
+    }
+
+    #[test]
+    fn test_detect_protocol() {
+        let detect = |vars: &[(&str, &str)]| {
+            ImageProtocol::from_env(|name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]), ImageProtocol::Kitty);
+        assert_eq!(detect(&[("KITTY_WINDOW_ID", "1")]), ImageProtocol::Kitty);
+        assert_eq!(
+            detect(&[("TERM_PROGRAM", "iTerm.app")]),
+            ImageProtocol::Iterm2
+        );
+        assert_eq!(
+            detect(&[("TERM_PROGRAM", "WezTerm")]),
+            ImageProtocol::Iterm2
+        );
+        assert_eq!(detect(&[("TERM", "foot")]), ImageProtocol::Sixel);
+        assert_eq!(
+            detect(&[("TERM", "xterm-256color")]),
+            ImageProtocol::Halfblocks
+        );
+        assert_eq!(
+            detect(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux")]),
+            ImageProtocol::Halfblocks
+        );
+        assert_eq!("sixel".parse(), Ok(ImageProtocol::Sixel));
+        assert!("png".parse::<ImageProtocol>().is_err());
+    }
+
+    #[test]
+    fn test_kitty_sequence() {
+        let image = image(100, 100, vec![[7; 3]; 100 * 100]);
+        let sequence =
+            graphics_sequence(ImageProtocol::Kitty, &image, 10, 5, (10, 20))
+                .unwrap();
+        // fitted within 100x100 pixels, i.e. 10 by 5 cells
+        assert!(sequence
+            .starts_with("\x1b_Ga=T,f=24,s=100,v=100,c=10,r=5,C=1,q=2,m=1;"));
+        assert!(sequence.ends_with("\x1b\\"));
+        // 30000 bytes take 40000 base64 characters, sent in 10 chunks
+        assert_eq!(sequence.matches("\x1b_G").count(), 10);
+        assert_eq!(sequence.matches("m=0;").count(), 1);
+        assert_eq!(
+            graphics_sequence(
+                ImageProtocol::Halfblocks,
+                &image,
+                10,
+                5,
+                (10, 20)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_iterm2_sequence() {
+        let image = image(2, 2, vec![[7; 3]; 4]);
+        let sequence =
+            graphics_sequence(ImageProtocol::Iterm2, &image, 10, 10, (8, 16))
+                .unwrap();
+        let payload = sequence
+            .strip_prefix("\x1b]1337;File=inline=1;size=")
+            .and_then(|s| s.split_once(':'))
+            .and_then(|(_, s)| s.strip_suffix('\x07'))
+            .unwrap();
+        let png = STANDARD.decode(payload).unwrap();
+        assert_eq!(decode_image(&png, [0; 3], &NOT_CANCELLED).unwrap(), image);
+    }
+
+    #[test]
+    fn test_sixel_sequence() {
+        let image = image(5, 2, vec![[255, 0, 0]; 10]);
+        let sequence =
+            graphics_sequence(ImageProtocol::Sixel, &image, 10, 10, (8, 16))
+                .unwrap();
+        assert!(sequence.starts_with("\x1bPq\"1;1;5;2#0;2;0;0;0"));
+        // red is the 181st color of the cube, each sixel has its two top
+        // rows set
+        assert!(sequence.ends_with("#180!5B$-\x1b\\"));
+        assert_eq!(sixel_color_index([255, 255, 255]), 215);
+        assert_eq!(sixel_color_index([0, 0, 0]), 0);
+    }
+}
@@ -0,0 +1,245 @@
+use std::path::Path;
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use television_utils::strings::preprocess_line_with_tab_width;
+
+const HEADING_COLORS: [Color; 3] = [Color::Magenta, Color::Cyan, Color::Blue];
+const CODE_STYLE: Style = Style::new().fg(Color::Yellow);
+const QUOTE_STYLE: Style = Style::new()
+    .fg(Color::DarkGray)
+    .add_modifier(Modifier::ITALIC);
+const BULLET: &str = "•";
+const RULE_WIDTH: usize = 40;
+
+/// Whether the given path looks like a markdown file.
+pub fn is_markdown_path(path: &Path) -> bool {
+    path.extension().map_or(false, |extension| {
+        let extension = extension.to_string_lossy().to_lowercase();
+        matches!(extension.as_str(), "md" | "markdown" | "mdown" | "mkd")
+    })
+}
+
+/// Render markdown into lightly styled lines.
+///
+/// This isn't a complete CommonMark implementation but covers what makes a
+/// document easier to read in a preview:
+/// - headings are emphasized (and their `#` markers removed)
+/// - list bullets are normalized
+/// - fenced code blocks are shown in a distinct style
+/// - block quotes and horizontal rules are drawn with box characters
+/// - inline code, bold and italic spans are styled
+pub fn render_markdown(content: &str, tab_width: usize) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut fence: Option<String> = None;
+    for raw_line in content.lines() {
+        let line = preprocess_line_with_tab_width(raw_line, tab_width);
+        let trimmed = line.trim_start();
+
+        if let Some(marker) = &fence {
+            if trimmed.starts_with(marker.as_str()) {
+                fence = None;
+            } else {
+                lines.push(Line::styled(format!("  {line}"), CODE_STYLE));
+            }
+            continue;
+        }
+        if let Some(marker) = fence_marker(trimmed) {
+            fence = Some(marker);
+            continue;
+        }
+
+        if let Some((level, title)) = heading(trimmed) {
+            let color =
+                HEADING_COLORS[(level - 1).min(HEADING_COLORS.len() - 1)];
+            let mut style =
+                Style::default().fg(color).add_modifier(Modifier::BOLD);
+            if level == 1 {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            lines.push(Line::styled(title.to_string(), style));
+        } else if is_rule(trimmed) {
+            lines.push(Line::styled(
+                "─".repeat(RULE_WIDTH),
+                Style::default().fg(Color::DarkGray),
+            ));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            lines.push(Line::styled(
+                format!("│ {}", quote.trim_start()),
+                QUOTE_STYLE,
+            ));
+        } else if let Some(item) = bullet_item(trimmed) {
+            let indent = &line[..line.len() - trimmed.len()];
+            let mut spans = vec![Span::raw(format!("{indent}{BULLET} "))];
+            spans.extend(inline_spans(item));
+            lines.push(Line::from(spans));
+        } else {
+            lines.push(Line::from(inline_spans(&line)));
+        }
+    }
+    lines
+}
+
+/// The marker opening a fenced code block (e.g. "```" or "~~~~").
+fn fence_marker(line: &str) -> Option<String> {
+    ['`', '~'].into_iter().find_map(|c| {
+        let count = line.chars().take_while(|&l| l == c).count();
+        (count >= 3).then(|| c.to_string().repeat(count))
+    })
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if rest.is_empty() || rest.starts_with(' ') {
+        Some((level, rest.trim().trim_end_matches('#').trim_end()))
+    } else {
+        None
+    }
+}
+
+fn is_rule(line: &str) -> bool {
+    let line: String = line.chars().filter(|c| *c != ' ').collect();
+    line.len() >= 3
+        && ["-", "*", "_"]
+            .iter()
+            .any(|c| line.chars().all(|l| l.to_string() == *c))
+}
+
+fn bullet_item(line: &str) -> Option<&str> {
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+}
+
+/// Style inline code (`` `code` ``), bold (`**bold**`) and italic
+/// (`*italic*`) spans.
+fn inline_spans(text: &str) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let next = [("`", CODE_STYLE), ("**", bold()), ("*", italic())]
+            .into_iter()
+            .filter_map(|(delimiter, style)| {
+                let start = rest.find(delimiter)?;
+                let end = rest[start + delimiter.len()..].find(delimiter)?;
+                Some((start, end, delimiter, style))
+            })
+            // the earliest delimiter wins, `**` over `*` at the same position
+            .min_by_key(|(start, _, delimiter, _)| {
+                (*start, usize::MAX - delimiter.len())
+            });
+        let Some((start, end, delimiter, style)) = next else {
+            spans.push(Span::raw(rest.to_string()));
+            break;
+        };
+        if start > 0 {
+            spans.push(Span::raw(rest[..start].to_string()));
+        }
+        let content_start = start + delimiter.len();
+        let content_end = content_start + end;
+        spans.push(Span::styled(
+            rest[content_start..content_end].to_string(),
+            style,
+        ));
+        rest = &rest[content_end + delimiter.len()..];
+    }
+    spans
+}
+
+fn bold() -> Style {
+    Style::default().add_modifier(Modifier::BOLD)
+}
+
+fn italic() -> Style {
+    Style::default().add_modifier(Modifier::ITALIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(content: &str) -> Vec<Line<'static>> {
+        render_markdown(content, 4)
+    }
+
+    #[test]
+    fn test_headings() {
+        let lines = render("# Title\n## Section ##\n#### Deep\n#hashtag\n");
+        assert_eq!(lines[0].to_string(), "Title");
+        assert_eq!(
+            lines[0].style,
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+        );
+        assert_eq!(lines[1].to_string(), "Section");
+        assert_eq!(
+            lines[1].style,
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            lines[2].style,
+            Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD)
+        );
+        // not a heading
+        assert_eq!(lines[3].to_string(), "#hashtag");
+        assert_eq!(lines[3].style, Style::default());
+    }
+
+    #[test]
+    fn test_code_fences() {
+        let lines = render(
+            "text\n```rust\nfn main() {}\n# not a heading\n```\nafter\n",
+        );
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1].to_string(), "  fn main() {}");
+        assert_eq!(lines[1].style, CODE_STYLE);
+        assert_eq!(lines[2].to_string(), "  # not a heading");
+        assert_eq!(lines[2].style, CODE_STYLE);
+        assert_eq!(lines[3].to_string(), "after");
+        assert_eq!(lines[3].style, Style::default());
+    }
+
+    #[test]
+    fn test_lists_quotes_and_rules() {
+        let lines = render("- one\n  * two\n+ three\n> quoted\n---\n");
+        assert_eq!(lines[0].to_string(), "• one");
+        assert_eq!(lines[1].to_string(), "  • two");
+        assert_eq!(lines[2].to_string(), "• three");
+        assert_eq!(lines[3].to_string(), "│ quoted");
+        assert_eq!(lines[3].style, QUOTE_STYLE);
+        assert_eq!(lines[4].to_string(), "─".repeat(RULE_WIDTH));
+    }
+
+    #[test]
+    fn test_inline_spans() {
+        let spans = inline_spans("run `tv` **now** or *later*");
+        assert_eq!(
+            spans,
+            vec![
+                Span::raw("run "),
+                Span::styled("tv", CODE_STYLE),
+                Span::raw(" "),
+                Span::styled("now", bold()),
+                Span::raw(" or "),
+                Span::styled("later", italic()),
+            ]
+        );
+        assert_eq!(inline_spans("2 * 3"), vec![Span::raw("2 * 3")]);
+    }
+
+    #[test]
+    fn test_is_markdown_path() {
+        assert!(is_markdown_path(Path::new("README.md")));
+        assert!(is_markdown_path(Path::new("notes.Markdown")));
+        assert!(!is_markdown_path(Path::new("main.rs")));
+    }
+}
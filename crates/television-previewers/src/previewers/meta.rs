@@ -1,4 +1,5 @@
 use crate::previewers::{Preview, PreviewContent};
+use std::path::Path;
 use std::sync::Arc;
 
 pub fn not_supported(title: &str) -> Arc<Preview> {
@@ -15,6 +16,24 @@ pub fn file_too_large(title: &str) -> Arc<Preview> {
     ))
 }
 
+pub fn timed_out(title: &str) -> Arc<Preview> {
+    Arc::new(Preview::new(title.to_string(), PreviewContent::TimedOut))
+}
+
+pub fn broken_symlink(title: &str, target: &Path) -> Arc<Preview> {
+    Arc::new(Preview::new(
+        title.to_string(),
+        PreviewContent::BrokenSymlink(target.to_string_lossy().to_string()),
+    ))
+}
+
+pub fn special_file(title: &str, kind: &str) -> Arc<Preview> {
+    Arc::new(Preview::new(
+        title.to_string(),
+        PreviewContent::SpecialFile(kind.to_string()),
+    ))
+}
+
 #[allow(dead_code)]
 pub fn loading(title: &str) -> Arc<Preview> {
     Arc::new(Preview::new(title.to_string(), PreviewContent::Loading))
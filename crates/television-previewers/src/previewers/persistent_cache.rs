@@ -0,0 +1,215 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde_json::{json, Value};
+use syntect::highlighting::Style;
+use tracing::debug;
+
+/// Bumped whenever the format of cache files changes so that older files
+/// are ignored.
+const CACHE_FORMAT_VERSION: u64 = 1;
+
+/// Highlighted lines loaded from the persistent cache.
+#[derive(Debug, PartialEq)]
+pub struct CachedHighlights {
+    pub lines: Vec<Vec<(Style, String)>>,
+    pub partial: bool,
+}
+
+/// An on-disk cache of highlighted previews which outlives the process.
+///
+/// Each file gets its own cache file, named after a hash of its path, which
+/// records the modification time and size of the file at the time it was
+/// highlighted. Cached highlights are only used if both still match, as well
+/// as the `settings` they were computed with (theme, tab width...).
+#[derive(Debug)]
+pub struct PersistentCache {
+    directory: PathBuf,
+}
+
+impl PersistentCache {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        PersistentCache {
+            directory: directory.into(),
+        }
+    }
+
+    /// Get the cached highlights of the file at `path`, if it hasn't changed
+    /// since they were computed.
+    pub fn get(
+        &self,
+        path: &Path,
+        settings: &str,
+    ) -> Option<CachedHighlights> {
+        let key = CacheKey::new(path)?;
+        let content = fs::read(self.cache_file(&key)).ok()?;
+        let value: Value = serde_json::from_slice(&content).ok()?;
+        if value.get("key")? != &key.to_value(settings) {
+            debug!("Stale persistent cache entry for {:?}", path);
+            return None;
+        }
+        Some(CachedHighlights {
+            lines: serde_json::from_value(value.get("lines")?.clone()).ok()?,
+            partial: value.get("partial")?.as_bool()?,
+        })
+    }
+
+    /// Store the highlighted lines of the file at `path`.
+    ///
+    /// Errors are only logged since the cache is merely an optimization.
+    pub fn insert(
+        &self,
+        path: &Path,
+        settings: &str,
+        lines: &[Vec<(Style, String)>],
+        partial: bool,
+    ) {
+        let Some(key) = CacheKey::new(path) else {
+            return;
+        };
+        let value = json!({
+            "key": key.to_value(settings),
+            "partial": partial,
+            "lines": lines,
+        });
+        if let Err(e) = fs::create_dir_all(&self.directory)
+            .and_then(|()| fs::write(self.cache_file(&key), value.to_string()))
+        {
+            debug!("Could not write persistent cache entry: {:?}", e);
+        }
+    }
+
+    fn cache_file(&self, key: &CacheKey) -> PathBuf {
+        self.directory
+            .join(format!("{:016x}.json", key.path_hash()))
+    }
+}
+
+/// What identifies a given version of a file.
+struct CacheKey {
+    path: String,
+    modified_secs: u64,
+    modified_nanos: u32,
+    size: u64,
+}
+
+impl CacheKey {
+    fn new(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH);
+        let modified = modified.ok()?;
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        Some(CacheKey {
+            path: path.to_string_lossy().to_string(),
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+            size: metadata.len(),
+        })
+    }
+
+    fn to_value(&self, settings: &str) -> Value {
+        json!({
+            "version": CACHE_FORMAT_VERSION,
+            "path": self.path,
+            "modified_secs": self.modified_secs,
+            "modified_nanos": self.modified_nanos,
+            "size": self.size,
+            "settings": settings,
+        })
+    }
+
+    /// A stable (FNV-1a) hash of the path, used to name cache files.
+    fn path_hash(&self) -> u64 {
+        self.path.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+
+    fn lines() -> Vec<Vec<(Style, String)>> {
+        vec![
+            vec![(Style::default(), String::from("fn main() {"))],
+            vec![(Style::default(), String::from("}"))],
+        ]
+    }
+
+    struct Fixture {
+        directory: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let directory = std::env::temp_dir().join(format!(
+                "tv-persistent-cache-{name}-{}",
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&directory);
+            fs::create_dir_all(&directory).unwrap();
+            fs::write(directory.join("main.rs"), "fn main() {\n}\n").unwrap();
+            Fixture { directory }
+        }
+
+        fn file(&self) -> PathBuf {
+            self.directory.join("main.rs")
+        }
+
+        fn cache(&self) -> PersistentCache {
+            PersistentCache::new(self.directory.join("cache"))
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.directory);
+        }
+    }
+
+    #[test]
+    fn test_hit_on_unchanged_file() {
+        let fixture = Fixture::new("hit");
+        let cache = fixture.cache();
+        assert_eq!(cache.get(&fixture.file(), "theme"), None);
+
+        cache.insert(&fixture.file(), "theme", &lines(), true);
+        // a new cache simulates restarting the application
+        assert_eq!(
+            fixture.cache().get(&fixture.file(), "theme"),
+            Some(CachedHighlights {
+                lines: lines(),
+                partial: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_miss_after_mtime_bump() {
+        let fixture = Fixture::new("mtime");
+        let cache = fixture.cache();
+        cache.insert(&fixture.file(), "theme", &lines(), false);
+        assert!(cache.get(&fixture.file(), "theme").is_some());
+
+        let modified = SystemTime::now() + Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(fixture.file())
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(cache.get(&fixture.file(), "theme"), None);
+    }
+
+    #[test]
+    fn test_miss_with_other_settings() {
+        let fixture = Fixture::new("settings");
+        let cache = fixture.cache();
+        cache.insert(&fixture.file(), "theme", &lines(), false);
+        assert_eq!(cache.get(&fixture.file(), "other theme"), None);
+    }
+}
@@ -0,0 +1,667 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The maximum number of tables listed in SQLite previews.
+pub const SQLITE_MAX_TABLES: usize = 100;
+
+/// The maximum number of pages read to count the rows of a single table,
+/// larger tables get an approximate (lower bound) count.
+const MAX_PAGES_PER_TABLE: usize = 1_000;
+
+const SQLITE_MAGIC: &[u8; 16] = b"SQLite format 3\0";
+const DATABASE_HEADER_SIZE: usize = 100;
+
+const INTERIOR_INDEX_PAGE: u8 = 0x02;
+const INTERIOR_TABLE_PAGE: u8 = 0x05;
+const LEAF_INDEX_PAGE: u8 = 0x0a;
+const LEAF_TABLE_PAGE: u8 = 0x0d;
+
+/// Whether the given path looks like a SQLite database based on its
+/// extension.
+pub fn is_sqlite_path(path: &Path) -> bool {
+    path.extension().map_or(false, |extension| {
+        let extension = extension.to_string_lossy().to_lowercase();
+        matches!(extension.as_str(), "db" | "sqlite" | "sqlite3" | "db3")
+    })
+}
+
+/// Whether the file at the given path starts with the header of a SQLite
+/// database.
+pub fn is_sqlite_database(path: &Path) -> bool {
+    let mut magic = [0u8; SQLITE_MAGIC.len()];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|()| &magic == SQLITE_MAGIC)
+}
+
+/// List the tables of a SQLite database with their columns and row counts.
+///
+/// The database file is parsed directly (read-only) and at most `max_tables`
+/// tables are listed. An error is returned if the file isn't a valid SQLite
+/// database, or once `cancelled` is set.
+///
+/// The write-ahead log of databases in WAL mode isn't read, so the changes
+/// it holds (that haven't been checkpointed into the database yet) are
+/// missing and the row counts can be stale. A note is added at the bottom
+/// when the database has a non-empty write-ahead log.
+pub fn sqlite_schema(
+    path: &Path,
+    max_tables: usize,
+    cancelled: &AtomicBool,
+) -> io::Result<Vec<String>> {
+    let mut database = Database::open(path)?;
+    let mut tables = Vec::new();
+    for record in database.table_payloads(1)? {
+        let values = parse_record(&record, database.text_encoding)
+            .ok_or_else(|| invalid_data("malformed schema record"))?;
+        if let [Value::Text(kind), Value::Text(name), _, root_page, sql, ..] =
+            values.as_slice()
+        {
+            if kind == "table" {
+                let root_page = match root_page {
+                    Value::Integer(page) => u32::try_from(*page).ok(),
+                    _ => None,
+                };
+                let sql = match sql {
+                    Value::Text(sql) => sql.clone(),
+                    _ => String::new(),
+                };
+                tables.push((name.clone(), root_page, sql));
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    let table_count = tables.len();
+    for (name, root_page, sql) in tables.into_iter().take(max_tables) {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(io::Error::from(io::ErrorKind::Interrupted));
+        }
+        let rows = match root_page {
+            // virtual tables don't have any storage
+            None | Some(0) => String::from("virtual"),
+            Some(root_page) => match database.count_rows(root_page)? {
+                (count, true) => format!("{} rows", count),
+                (count, false) => format!("{}+ rows", count),
+            },
+        };
+        lines.push(format!("table {name} ({rows})"));
+        lines.push(format!("  {}", column_names(&sql).join(", ")));
+    }
+    if table_count > max_tables {
+        lines
+            .push(format!("... and {} more tables", table_count - max_tables));
+    }
+    if lines.is_empty() {
+        lines.push(String::from("(no tables)"));
+    }
+    if std::fs::metadata(wal_path(path)).is_ok_and(|m| m.len() > 0) {
+        lines.push(String::new());
+        lines.push(String::from(
+            "(the write-ahead log isn't read, row counts may be stale)",
+        ));
+    }
+    Ok(lines)
+}
+
+/// The path of the write-ahead log of the database at the given path.
+fn wal_path(path: &Path) -> PathBuf {
+    let mut wal_path = path.as_os_str().to_owned();
+    wal_path.push("-wal");
+    PathBuf::from(wal_path)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// A b-tree page along with its decoded header.
+struct BTreePage {
+    page_type: u8,
+    data: Vec<u8>,
+    /// The offsets of the cells within the page.
+    cells: Vec<usize>,
+    /// The right-most child, for interior pages.
+    right_most: Option<u32>,
+}
+
+struct Database {
+    file: File,
+    page_size: usize,
+    usable_size: usize,
+    page_count: u32,
+    text_encoding: TextEncoding,
+}
+
+impl Database {
+    fn open(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut header = [0u8; DATABASE_HEADER_SIZE];
+        file.read_exact(&mut header)?;
+        if &header[..16] != SQLITE_MAGIC {
+            return Err(invalid_data("not a SQLite database"));
+        }
+        let page_size = match u16::from_be_bytes([header[16], header[17]]) {
+            1 => 65536,
+            size if size >= 512 && size.is_power_of_two() => usize::from(size),
+            _ => return Err(invalid_data("invalid page size")),
+        };
+        let usable_size = page_size - usize::from(header[20]);
+        let text_encoding = match read_u32(&header, 56) {
+            2 => TextEncoding::Utf16Le,
+            3 => TextEncoding::Utf16Be,
+            _ => TextEncoding::Utf8,
+        };
+        let file_size = file.metadata()?.len();
+        let page_count =
+            u32::try_from(file_size / page_size as u64).unwrap_or(u32::MAX);
+        Ok(Self {
+            file,
+            page_size,
+            usable_size,
+            page_count,
+            text_encoding,
+        })
+    }
+
+    fn read_page(&mut self, page_number: u32) -> io::Result<Vec<u8>> {
+        if page_number == 0 || page_number > self.page_count {
+            return Err(invalid_data("page out of bounds"));
+        }
+        let mut page = vec![0u8; self.page_size];
+        self.file.seek(SeekFrom::Start(
+            u64::from(page_number - 1) * self.page_size as u64,
+        ))?;
+        self.file.read_exact(&mut page)?;
+        Ok(page)
+    }
+
+    /// Read a b-tree page and decode its header.
+    fn btree_page(&mut self, page_number: u32) -> io::Result<BTreePage> {
+        let page = self.read_page(page_number)?;
+        let offset = if page_number == 1 {
+            DATABASE_HEADER_SIZE
+        } else {
+            0
+        };
+        let page_type = page[offset];
+        let is_interior = match page_type {
+            INTERIOR_INDEX_PAGE | INTERIOR_TABLE_PAGE => true,
+            LEAF_INDEX_PAGE | LEAF_TABLE_PAGE => false,
+            _ => return Err(invalid_data("invalid b-tree page")),
+        };
+        let cell_count = usize::from(read_u16(&page, offset + 3));
+        let header_size = if is_interior { 12 } else { 8 };
+        let right_most = is_interior.then(|| read_u32(&page, offset + 8));
+        let pointers_start = offset + header_size;
+        if pointers_start + 2 * cell_count > page.len() {
+            return Err(invalid_data("invalid cell count"));
+        }
+        let cells = (0..cell_count)
+            .map(|i| usize::from(read_u16(&page, pointers_start + 2 * i)))
+            .collect();
+        Ok(BTreePage {
+            page_type,
+            data: page,
+            cells,
+            right_most,
+        })
+    }
+
+    /// Collect the payloads of all the rows of the table b-tree rooted at the
+    /// given page.
+    fn table_payloads(&mut self, root_page: u32) -> io::Result<Vec<Vec<u8>>> {
+        let mut payloads = Vec::new();
+        let mut pages = vec![root_page];
+        let mut visited = HashSet::new();
+        while let Some(page_number) = pages.pop() {
+            if !visited.insert(page_number) {
+                return Err(invalid_data("b-tree cycle"));
+            }
+            let BTreePage {
+                page_type,
+                data: page,
+                cells,
+                right_most,
+            } = self.btree_page(page_number)?;
+            match page_type {
+                INTERIOR_TABLE_PAGE => {
+                    pages.extend(right_most);
+                    for &cell in cells.iter().rev() {
+                        pages.push(read_u32(&page, cell));
+                    }
+                }
+                LEAF_TABLE_PAGE => {
+                    for cell in cells {
+                        payloads.push(self.leaf_table_payload(&page, cell)?);
+                    }
+                }
+                _ => return Err(invalid_data("unexpected b-tree page")),
+            }
+        }
+        Ok(payloads)
+    }
+
+    fn leaf_table_payload(
+        &mut self,
+        page: &[u8],
+        cell: usize,
+    ) -> io::Result<Vec<u8>> {
+        let (payload_size, n) = read_varint(page, cell)
+            .ok_or_else(|| invalid_data("invalid cell"))?;
+        let (_rowid, m) = read_varint(page, cell + n)
+            .ok_or_else(|| invalid_data("invalid cell"))?;
+        // a payload can't be larger than the whole database
+        let max_payload_size =
+            u64::from(self.page_count) * self.usable_size as u64;
+        let payload_size = usize::try_from(payload_size)
+            .ok()
+            .filter(|&size| size as u64 <= max_payload_size)
+            .ok_or_else(|| invalid_data("invalid payload size"))?;
+        let start = cell + n + m;
+        let local_size = self.local_payload_size(payload_size);
+        let local = start
+            .checked_add(local_size)
+            .and_then(|end| page.get(start..end))
+            .ok_or_else(|| invalid_data("invalid cell"))?;
+        let mut payload = local.to_vec();
+        if local_size < payload_size {
+            let mut overflow_page = read_u32(page, start + local_size);
+            let mut visited = HashSet::new();
+            while payload.len() < payload_size && overflow_page != 0 {
+                if !visited.insert(overflow_page) {
+                    return Err(invalid_data("overflow cycle"));
+                }
+                let page = self.read_page(overflow_page)?;
+                overflow_page = read_u32(&page, 0);
+                let remaining = payload_size - payload.len();
+                let end = remaining
+                    .checked_add(4)
+                    .map_or(self.usable_size, |end| end.min(self.usable_size));
+                let content = page
+                    .get(4..end)
+                    .ok_or_else(|| invalid_data("invalid overflow page"))?;
+                payload.extend_from_slice(content);
+            }
+        }
+        Ok(payload)
+    }
+
+    /// The number of bytes of a table leaf cell's payload stored on the page
+    /// itself, the rest being stored on overflow pages.
+    fn local_payload_size(&self, payload_size: usize) -> usize {
+        let max_local = self.usable_size - 35;
+        if payload_size <= max_local {
+            return payload_size;
+        }
+        let min_local = (self.usable_size - 12) * 32 / 255 - 23;
+        let local =
+            min_local + (payload_size - min_local) % (self.usable_size - 4);
+        if local <= max_local {
+            local
+        } else {
+            min_local
+        }
+    }
+
+    /// Count the rows of the b-tree rooted at the given page, returning
+    /// whether the count is exact (i.e. the whole b-tree was visited).
+    fn count_rows(&mut self, root_page: u32) -> io::Result<(u64, bool)> {
+        let mut count = 0;
+        let mut pages = vec![root_page];
+        let mut visited = HashSet::new();
+        while let Some(page_number) = pages.pop() {
+            if visited.len() >= MAX_PAGES_PER_TABLE {
+                return Ok((count, false));
+            }
+            if !visited.insert(page_number) {
+                return Err(invalid_data("b-tree cycle"));
+            }
+            let BTreePage {
+                page_type,
+                data: page,
+                cells,
+                right_most,
+            } = self.btree_page(page_number)?;
+            pages.extend(right_most);
+            match page_type {
+                INTERIOR_TABLE_PAGE => {
+                    pages.extend(cells.iter().map(|&c| read_u32(&page, c)));
+                }
+                // entries of interior index pages are rows of their own
+                INTERIOR_INDEX_PAGE => {
+                    pages.extend(cells.iter().map(|&c| read_u32(&page, c)));
+                    count += cells.len() as u64;
+                }
+                _ => count += cells.len() as u64,
+            }
+        }
+        Ok((count, true))
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    bytes
+        .get(offset..offset + 2)
+        .map_or(0, |b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    bytes
+        .get(offset..offset + 4)
+        .map_or(0, |b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Read a SQLite variable-length integer, returning it along with the number
+/// of bytes it takes.
+fn read_varint(bytes: &[u8], offset: usize) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let byte = *bytes.get(offset + i)?;
+        if i == 8 {
+            return Some(((value << 8) | u64::from(byte), 9));
+        }
+        value = (value << 7) | u64::from(byte & 0x7f);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+#[derive(Debug, PartialEq)]
+enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+/// Parse a record (the payload of a row) into its values.
+fn parse_record(record: &[u8], encoding: TextEncoding) -> Option<Vec<Value>> {
+    let (header_size, mut offset) = read_varint(record, 0)?;
+    let header_size = usize::try_from(header_size).ok()?;
+    let mut serial_types = Vec::new();
+    while offset < header_size {
+        let (serial_type, n) = read_varint(record, offset)?;
+        serial_types.push(serial_type);
+        offset += n;
+    }
+    let mut body = record.get(header_size..)?;
+    let mut values = Vec::new();
+    for serial_type in serial_types {
+        let size = match serial_type {
+            0 | 8 | 9 => 0,
+            1..=4 => usize::try_from(serial_type).ok()?,
+            5 => 6,
+            6 | 7 => 8,
+            n if n >= 12 => usize::try_from((n - 12) / 2).ok()?,
+            _ => return None,
+        };
+        let bytes = body.get(..size)?;
+        body = &body[size..];
+        values.push(match serial_type {
+            0 => Value::Null,
+            8 => Value::Integer(0),
+            9 => Value::Integer(1),
+            1..=6 => {
+                // sign-extend the big-endian integer
+                let mut value = if bytes[0] & 0x80 == 0 { 0 } else { -1i64 };
+                for &byte in bytes {
+                    value = (value << 8) | i64::from(byte);
+                }
+                Value::Integer(value)
+            }
+            7 => Value::Real(f64::from_be_bytes(bytes.try_into().ok()?)),
+            n if n % 2 == 0 => Value::Blob(bytes.to_vec()),
+            _ => Value::Text(decode_text(bytes, encoding)),
+        });
+    }
+    Some(values)
+}
+
+fn decode_text(bytes: &[u8], encoding: TextEncoding) -> String {
+    let utf16 = |to_u16: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| to_u16([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    match encoding {
+        TextEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+        TextEncoding::Utf16Le => utf16(u16::from_le_bytes),
+        TextEncoding::Utf16Be => utf16(u16::from_be_bytes),
+    }
+}
+
+/// Extract the column names from a `CREATE TABLE` statement.
+fn column_names(sql: &str) -> Vec<String> {
+    let (Some(start), Some(end)) = (sql.find('('), sql.rfind(')')) else {
+        return Vec::new();
+    };
+    if end <= start {
+        return Vec::new();
+    }
+    split_top_level(&sql[start + 1..end])
+        .into_iter()
+        .filter(|definition| {
+            let upper = definition.to_uppercase();
+            !["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"]
+                .iter()
+                .any(|keyword| {
+                    upper.starts_with(keyword)
+                        && upper[keyword.len()..].starts_with(|c: char| {
+                            c.is_whitespace() || c == '('
+                        })
+                })
+        })
+        .filter_map(|definition| column_name(&definition))
+        .collect()
+}
+
+/// Split column definitions on the commas that aren't nested in parentheses
+/// or quotes.
+fn split_top_level(definitions: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    for c in definitions.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+fn column_name(definition: &str) -> Option<String> {
+    let mut chars = definition.chars();
+    let closing = match chars.next()? {
+        '"' => '"',
+        '`' => '`',
+        '[' => ']',
+        '\'' => '\'',
+        _ => {
+            return definition
+                .split_whitespace()
+                .next()
+                .map(ToString::to_string)
+        }
+    };
+    let rest = &definition[1..];
+    rest.find(closing).map(|end| rest[..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/previewers/fixtures/tiny.sqlite"
+    );
+
+    static NOT_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+    #[test]
+    fn test_sqlite_schema() {
+        let lines = sqlite_schema(
+            Path::new(FIXTURE),
+            SQLITE_MAX_TABLES,
+            &NOT_CANCELLED,
+        )
+        .unwrap();
+        let wide_columns: Vec<String> =
+            (0..40).map(|i| format!("column_number_{i:02}")).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "table users (3 rows)".to_string(),
+                "  id, name, e-mail".to_string(),
+                "table posts (200 rows)".to_string(),
+                "  id, user_id, title, body".to_string(),
+                "table kv (5 rows)".to_string(),
+                "  k, v".to_string(),
+                "table wide (1 rows)".to_string(),
+                format!("  {}", wide_columns.join(", ")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_tables() {
+        let lines =
+            sqlite_schema(Path::new(FIXTURE), 1, &NOT_CANCELLED).unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                "table users (3 rows)",
+                "  id, name, e-mail",
+                "... and 3 more tables"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_not_a_database() {
+        let path =
+            Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"));
+        assert_eq!(
+            sqlite_schema(path, SQLITE_MAX_TABLES, &NOT_CANCELLED)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_is_sqlite_database() {
+        assert!(is_sqlite_database(Path::new(FIXTURE)));
+        assert!(!is_sqlite_database(Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/Cargo.toml"
+        ))));
+    }
+
+    #[test]
+    fn test_stale_wal_note() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-sqlite-wal-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("tiny.sqlite");
+        std::fs::copy(FIXTURE, &path).unwrap();
+        std::fs::write(wal_path(&path), b"wal").unwrap();
+        let lines =
+            sqlite_schema(&path, SQLITE_MAX_TABLES, &NOT_CANCELLED).unwrap();
+        assert_eq!(
+            lines.last().unwrap(),
+            "(the write-ahead log isn't read, row counts may be stale)"
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_oversized_payload() {
+        let mut database = Database::open(Path::new(FIXTURE)).unwrap();
+        // a cell claiming a payload of u64::MAX bytes, with rowid 1
+        let mut page = vec![0xff; 9];
+        page.push(0x01);
+        page.resize(database.page_size, 0);
+        assert_eq!(
+            database.leaf_table_payload(&page, 0).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        // one byte larger than the whole database
+        let size =
+            u64::from(database.page_count) * database.usable_size as u64 + 1;
+        let mut page = varint(size);
+        page.push(0x01);
+        page.resize(database.page_size, 0);
+        assert_eq!(
+            database.leaf_table_payload(&page, 0).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    /// Encode a value as a SQLite variable-length integer of at most 8
+    /// bytes.
+    fn varint(mut value: u64) -> Vec<u8> {
+        let mut bytes = vec![(value & 0x7f) as u8];
+        value >>= 7;
+        while value != 0 {
+            bytes.insert(0, 0x80 | (value & 0x7f) as u8);
+            value >>= 7;
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_read_varint() {
+        assert_eq!(read_varint(&[0x05], 0), Some((5, 1)));
+        assert_eq!(read_varint(&[0x81, 0x00], 0), Some((128, 2)));
+        assert_eq!(read_varint(&[0xff; 9], 0), Some((u64::MAX, 9)));
+        assert_eq!(read_varint(&[0x81], 0), None);
+    }
+
+    #[test]
+    fn test_column_names() {
+        assert_eq!(
+            column_names(
+                "CREATE TABLE t (a INT, \"b c\" TEXT DEFAULT (1, 2), [d], \
+                 PRIMARY KEY (a), CONSTRAINT u UNIQUE (d))"
+            ),
+            vec!["a", "b c", "d"]
+        );
+        assert_eq!(
+            column_names("CREATE TABLE t (primary_color TEXT, uniqueness)"),
+            vec!["primary_color", "uniqueness"]
+        );
+    }
+}
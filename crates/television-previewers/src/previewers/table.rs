@@ -0,0 +1,272 @@
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use television_utils::strings::{preprocess_line, shrink_with_ellipsis};
+use unicode_width::UnicodeWidthStr;
+
+/// The default maximum number of rows shown in table previews.
+pub const DEFAULT_TABLE_MAX_ROWS: usize = 1000;
+
+/// The maximum display width of a column, wider cells are shrunk with an
+/// ellipsis.
+const MAX_COLUMN_WIDTH: usize = 32;
+
+const COLUMN_SEPARATOR: &str = " │ ";
+
+/// The supported delimited text formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    Tsv,
+}
+
+impl TableFormat {
+    /// Guess the table format of a file based on its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "csv" => Some(TableFormat::Csv),
+            "tsv" => Some(TableFormat::Tsv),
+            _ => None,
+        }
+    }
+
+    fn delimiter(self) -> char {
+        match self {
+            TableFormat::Csv => ',',
+            TableFormat::Tsv => '\t',
+        }
+    }
+}
+
+/// A line of the file, either parsed into fields or kept verbatim if it
+/// couldn't be parsed.
+#[derive(Debug, PartialEq, Eq)]
+enum Row {
+    Record(Vec<String>),
+    Verbatim(String),
+}
+
+/// Split a line into its fields.
+///
+/// Fields may be enclosed in double quotes, in which case they can contain
+/// the delimiter and escaped (doubled) quotes. Returns `None` if the line is
+/// malformed (e.g. an unterminated quoted field).
+fn parse_record(line: &str, delimiter: char) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '"' if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    '"' => break,
+                    c => field.push(c),
+                }
+            }
+            // a closing quote must end the field
+            match chars.next() {
+                Some(c) if c == delimiter => {
+                    fields.push(std::mem::take(&mut field));
+                }
+                Some(_) => return None,
+                None => {
+                    fields.push(field);
+                    return Some(fields);
+                }
+            }
+        } else {
+            match chars.next() {
+                Some(c) if c == delimiter => {
+                    fields.push(std::mem::take(&mut field));
+                }
+                Some(c) => field.push(c),
+                None => {
+                    fields.push(field);
+                    return Some(fields);
+                }
+            }
+        }
+    }
+}
+
+/// Render the first `max_rows` lines of a delimited text file as an aligned
+/// table.
+///
+/// Malformed lines are shown as is and don't count towards the column
+/// widths. Reading stops early once `cancelled` is set.
+pub fn table_preview_lines<R: BufRead>(
+    reader: R,
+    format: TableFormat,
+    max_rows: usize,
+    cancelled: &AtomicBool,
+) -> Vec<String> {
+    let mut lines = reader
+        .lines()
+        .map_while(Result::ok)
+        .take_while(|_| !cancelled.load(Ordering::Relaxed));
+    let rows: Vec<Row> = lines
+        .by_ref()
+        .take(max_rows)
+        .map(|line| {
+            let line = line.trim_end_matches('\r');
+            parse_record(line, format.delimiter()).map_or_else(
+                || Row::Verbatim(preprocess_line(line)),
+                |fields| {
+                    Row::Record(
+                        fields.iter().map(|f| preprocess_line(f)).collect(),
+                    )
+                },
+            )
+        })
+        .collect();
+    let truncated = lines.next().is_some();
+
+    let mut widths: Vec<usize> = Vec::new();
+    for row in &rows {
+        if let Row::Record(fields) = row {
+            for (i, field) in fields.iter().enumerate() {
+                let width = field.width().min(MAX_COLUMN_WIDTH);
+                match widths.get_mut(i) {
+                    Some(w) => *w = (*w).max(width),
+                    None => widths.push(width),
+                }
+            }
+        }
+    }
+
+    let mut output: Vec<String> = rows
+        .into_iter()
+        .map(|row| match row {
+            Row::Record(fields) => {
+                let cells: Vec<String> = fields
+                    .iter()
+                    .zip(&widths)
+                    .map(|(field, &width)| {
+                        let cell = shrink_with_ellipsis(field, width);
+                        let padding = width.saturating_sub(cell.width());
+                        format!("{cell}{}", " ".repeat(padding))
+                    })
+                    .collect();
+                cells.join(COLUMN_SEPARATOR).trim_end().to_string()
+            }
+            Row::Verbatim(line) => line,
+        })
+        .collect();
+    if truncated {
+        output.push(String::from("... and more rows"));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn preview(content: &str, format: TableFormat) -> Vec<String> {
+        table_preview_lines(
+            Cursor::new(content),
+            format,
+            100,
+            &AtomicBool::new(false),
+        )
+    }
+
+    #[test]
+    fn test_parse_record() {
+        assert_eq!(
+            parse_record("a,b,,c", ','),
+            Some(vec!["a".into(), "b".into(), String::new(), "c".into()])
+        );
+        assert_eq!(
+            parse_record(r#""a, b","say ""hi""",c"#, ','),
+            Some(vec!["a, b".into(), r#"say "hi""#.into(), "c".into()])
+        );
+        assert_eq!(parse_record(r#""unterminated,a"#, ','), None);
+        assert_eq!(parse_record(r#""a"b,c"#, ','), None);
+    }
+
+    #[test]
+    fn test_simple_csv() {
+        assert_eq!(
+            preview(
+                "name,age,city\nAlice,30,Paris\nBob,4,\"New York, NY\"\n",
+                TableFormat::Csv
+            ),
+            vec![
+                "name  │ age │ city",
+                "Alice │ 30  │ Paris",
+                "Bob   │ 4   │ New York, NY",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tsv() {
+        assert_eq!(
+            preview("a\tbb\nccc\td\n", TableFormat::Tsv),
+            vec!["a   │ bb", "ccc │ d"]
+        );
+    }
+
+    #[test]
+    fn test_ragged_csv() {
+        assert_eq!(
+            preview("a,b,c\n1\n1,2,3,4\n\"broken,row\n", TableFormat::Csv),
+            vec!["a │ b │ c", "1", "1 │ 2 │ 3 │ 4", "\"broken,row"]
+        );
+    }
+
+    #[test]
+    fn test_wide_columns_are_shrunk() {
+        let long = "x".repeat(50);
+        let lines = preview(&format!("{long},y\n"), TableFormat::Csv);
+        assert_eq!(
+            lines[0].width(),
+            MAX_COLUMN_WIDTH + COLUMN_SEPARATOR.width() + 1
+        );
+        assert!(lines[0].contains('…'));
+    }
+
+    #[test]
+    fn test_max_rows() {
+        let lines = table_preview_lines(
+            Cursor::new("1\n2\n3\n"),
+            TableFormat::Csv,
+            2,
+            &AtomicBool::new(false),
+        );
+        assert_eq!(lines, vec!["1", "2", "... and more rows"]);
+    }
+
+    #[test]
+    fn test_cancelled() {
+        let lines = table_preview_lines(
+            Cursor::new("1\n2\n3\n"),
+            TableFormat::Csv,
+            2,
+            &AtomicBool::new(true),
+        );
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            TableFormat::from_path(Path::new("data.CSV")),
+            Some(TableFormat::Csv)
+        );
+        assert_eq!(
+            TableFormat::from_path(Path::new("data.tsv")),
+            Some(TableFormat::Tsv)
+        );
+        assert_eq!(TableFormat::from_path(Path::new("data.txt")), None);
+    }
+}
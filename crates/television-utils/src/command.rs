@@ -0,0 +1,304 @@
+use std::process::{Child, Command};
+
+use directories::BaseDirs;
+use tracing::{debug, warn};
+
+/// The placeholder substituted with the entry in command templates.
+pub const ENTRY_PLACEHOLDER: &str = "{}";
+
+/// Build a command running the given command line through the user's shell
+/// (`sh -c` on Unix, `cmd /C` on Windows).
+///
+/// On Unix, the command runs in its own process group so that
+/// [`kill_shell_command`] also kills the processes spawned by the shell.
+#[cfg(not(windows))]
+pub fn shell_command(command_line: &str) -> Command {
+    use std::os::unix::process::CommandExt;
+
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(command_line).process_group(0);
+    command
+}
+
+/// Build a command running the given command line through the user's shell
+/// (`sh -c` on Unix, `cmd /C` on Windows).
+#[cfg(windows)]
+pub fn shell_command(command_line: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    // `cmd` doesn't parse its command line like other programs do, the
+    // command line is passed as is so that our quoting isn't escaped again
+    let mut command = Command::new("cmd");
+    command.arg("/C").raw_arg(command_line);
+    command
+}
+
+/// Kill a process spawned from [`shell_command`], along with the processes
+/// it spawned on Unix.
+///
+/// The process still needs to be waited for afterwards.
+#[cfg(unix)]
+pub fn kill_shell_command(child: &mut Child) {
+    let Ok(pgid) = libc::pid_t::try_from(child.id()) else {
+        return;
+    };
+    // SAFETY: `kill` has no memory safety requirements, the process group
+    // is the child's own since it hasn't been waited for yet
+    if unsafe { libc::kill(-pgid, libc::SIGKILL) } != 0 {
+        warn!(
+            "Failed to kill command: {:?}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Kill a process spawned from [`shell_command`], along with the processes
+/// it spawned on Unix.
+///
+/// The process still needs to be waited for afterwards.
+#[cfg(not(unix))]
+pub fn kill_shell_command(child: &mut Child) {
+    if let Err(e) = child.kill() {
+        warn!("Failed to kill command: {:?}", e);
+    }
+}
+
+/// Quote the given argument so that the shell passes it through as is.
+///
+/// # Examples
+/// ```
+/// use television_utils::command::shell_quote;
+///
+/// # #[cfg(not(windows))]
+/// # {
+/// assert_eq!(shell_quote("main"), "'main'");
+/// assert_eq!(shell_quote("it's"), "'it'\\''s'");
+/// # }
+/// ```
+pub fn shell_quote(argument: &str) -> String {
+    if cfg!(windows) {
+        cmd_quote(argument)
+    } else {
+        posix_quote(argument)
+    }
+}
+
+fn posix_quote(argument: &str) -> String {
+    format!("'{}'", argument.replace('\'', "'\\''"))
+}
+
+/// Quote an argument for `cmd /C`: `cmd` leaves what's inside double quotes
+/// alone except for `%` which is escaped outside of the quotes, while the
+/// double quotes and the backslashes before them are escaped the way
+/// programs split their command line.
+fn cmd_quote(argument: &str) -> String {
+    let mut quoted = String::with_capacity(argument.len() + 2);
+    quoted.push('"');
+    let mut backslashes = 0;
+    for c in argument.chars() {
+        match c {
+            '\\' => {
+                backslashes += 1;
+                continue;
+            }
+            '"' => {
+                quoted.extend(std::iter::repeat('\\').take(backslashes * 2));
+                quoted.push_str("\"\"");
+            }
+            '%' => {
+                quoted.extend(std::iter::repeat('\\').take(backslashes));
+                quoted.push_str("\"^%\"");
+            }
+            _ => {
+                quoted.extend(std::iter::repeat('\\').take(backslashes));
+                quoted.push(c);
+            }
+        }
+        backslashes = 0;
+    }
+    quoted.extend(std::iter::repeat('\\').take(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Substitute every occurrence of `{}` in the given template with the
+/// (quoted) entry.
+///
+/// # Examples
+/// ```
+/// use television_utils::command::format_command;
+///
+/// # #[cfg(not(windows))]
+/// assert_eq!(format_command("git show {}", "main"), "git show 'main'");
+/// ```
+pub fn format_command(template: &str, entry: &str) -> String {
+    template.replace(ENTRY_PLACEHOLDER, &shell_quote(entry))
+}
+
+/// Expand `~`, `$VAR` and `${VAR}` in the given command template.
+///
+/// A `~` is only expanded at the start of a word and nothing is expanded
+/// inside single quotes, e.g. in `awk '{print $1}'`. Undefined variables
+/// expand to an empty string.
+///
+/// # Examples
+/// ```
+/// use television_utils::command::expand_command;
+///
+/// std::env::set_var("TV_EXAMPLE_DIR", "/tmp/notes");
+/// assert_eq!(expand_command("ls $TV_EXAMPLE_DIR"), "ls /tmp/notes");
+/// assert_eq!(expand_command("ls ${TV_EXAMPLE_DIR}/a"), "ls /tmp/notes/a");
+/// assert_eq!(expand_command("echo '$TV_EXAMPLE_DIR'"), "echo '$TV_EXAMPLE_DIR'");
+/// ```
+pub fn expand_command(template: &str) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut chars = template.char_indices().peekable();
+    let mut in_single_quotes = false;
+    let mut previous = None;
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' => in_single_quotes = !in_single_quotes,
+            '~' if !in_single_quotes
+                && previous.map_or(true, char::is_whitespace)
+                && chars.peek().map_or(true, |&(_, next)| {
+                    next == '/' || next.is_whitespace()
+                }) =>
+            {
+                if let Some(home) = home_dir() {
+                    output.push_str(&home);
+                    previous = Some(c);
+                    continue;
+                }
+            }
+            '$' if !in_single_quotes && previous != Some('\\') => {
+                if let Some((name, len)) = variable_name(&template[i + 1..]) {
+                    output.push_str(&env_var(name));
+                    for _ in 0..len {
+                        chars.next();
+                    }
+                    previous = Some(c);
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        output.push(c);
+        previous = Some(c);
+    }
+    output
+}
+
+/// Parse the name of the variable referenced right after a `$`, as well as
+/// the length of the reference (braces included).
+fn variable_name(s: &str) -> Option<(&str, usize)> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    if let Some(braced) = s.strip_prefix('{') {
+        let end = braced.find('}')?;
+        let name = &braced[..end];
+        return (!name.is_empty() && name.chars().all(is_name_char))
+            .then_some((name, end + 2));
+    }
+    if !s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return None;
+    }
+    let end = s.find(|c: char| !is_name_char(c)).unwrap_or(s.len());
+    Some((&s[..end], end))
+}
+
+fn env_var(name: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| {
+        debug!("Undefined variable in command: {:?}", name);
+        String::new()
+    })
+}
+
+fn home_dir() -> Option<String> {
+    BaseDirs::new().map(|dirs| dirs.home_dir().to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = home_dir().unwrap();
+        assert_eq!(expand_command("ls ~/notes"), format!("ls {home}/notes"));
+        assert_eq!(expand_command("cd ~"), format!("cd {home}"));
+        assert_eq!(expand_command("ls a~/b ~user"), "ls a~/b ~user");
+        assert_eq!(expand_command("echo '~/notes'"), "echo '~/notes'");
+    }
+
+    #[test]
+    fn test_expand_variable() {
+        std::env::set_var("TV_TEST_SIMPLE_VAR", "/src");
+        assert_eq!(
+            expand_command("fd . $TV_TEST_SIMPLE_VAR/tv"),
+            "fd . /src/tv"
+        );
+        assert_eq!(expand_command("echo $TV_TEST_UNDEFINED_VAR!"), "echo !");
+        // not variables
+        assert_eq!(
+            expand_command("awk '{print $1}' | cut -c$ \\$HOME"),
+            "awk '{print $1}' | cut -c$ \\$HOME"
+        );
+        assert_eq!(expand_command("echo $1 $"), "echo $1 $");
+    }
+
+    #[test]
+    fn test_expand_braced_variable() {
+        std::env::set_var("TV_TEST_BRACED_VAR", "notes");
+        assert_eq!(
+            expand_command("ls ~/${TV_TEST_BRACED_VAR}_old"),
+            format!("ls {}/notes_old", home_dir().unwrap())
+        );
+        assert_eq!(expand_command("echo ${TV_TEST_UNDEFINED_VAR}."), "echo .");
+        assert_eq!(expand_command("echo ${unclosed"), "echo ${unclosed");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_format_command() {
+        assert_eq!(
+            format_command("git show {}", "feature/foo"),
+            "git show 'feature/foo'"
+        );
+        assert_eq!(
+            format_command("diff {} {}.orig", "a b.txt"),
+            "diff 'a b.txt' 'a b.txt'.orig"
+        );
+        assert_eq!(format_command("uptime", "ignored"), "uptime");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_format_command_escapes_shell_syntax() {
+        assert_eq!(
+            format_command("echo {}", "$(rm -rf ~)"),
+            "echo '$(rm -rf ~)'"
+        );
+        assert_eq!(format_command("echo {}", "don't"), "echo 'don'\\''t'");
+    }
+
+    #[test]
+    fn test_cmd_quote() {
+        assert_eq!(cmd_quote("a b.txt"), "\"a b.txt\"");
+        assert_eq!(cmd_quote("a & del *"), "\"a & del *\"");
+        assert_eq!(cmd_quote("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(cmd_quote("%PATH%"), "\"\"^%\"PATH\"^%\"\"");
+        // backslashes are only escaped before a double quote
+        assert_eq!(cmd_quote("C:\\dir\\"), "\"C:\\dir\\\\\"");
+        assert_eq!(cmd_quote("a\\\"b"), "\"a\\\\\"\"b\"");
+        assert_eq!(cmd_quote("a\\b"), "\"a\\b\"");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_command() {
+        let output =
+            shell_command(&format_command("printf %s {}", "it's $HOME"))
+                .output()
+                .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "it's $HOME");
+    }
+}
@@ -0,0 +1,156 @@
+pub use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// The minimum proportion of NUL bytes at odd (or even) positions for a
+/// buffer without a BOM to be considered UTF-16.
+const UTF16_NUL_BYTES_THRESHOLD: f32 = 0.4;
+
+/// Try to detect the encoding of the given buffer.
+///
+/// The detection goes as follows:
+/// - if the buffer starts with a BOM, the encoding it designates is used
+/// - if the buffer is valid UTF-8 (allowing for a truncated sequence at the
+///   end), UTF-8 is used
+/// - if the buffer looks like UTF-16 text (i.e. every other byte is NUL), the
+///   corresponding UTF-16 variant is used
+/// - otherwise, the buffer is assumed to be Latin-1 (more precisely, its
+///   superset Windows-1252)
+///
+/// # Examples
+/// ```
+/// use television_utils::encoding::{
+///     detect_encoding, UTF_16LE, UTF_8, WINDOWS_1252,
+/// };
+///
+/// assert_eq!(detect_encoding("héllo".as_bytes()), UTF_8);
+/// assert_eq!(detect_encoding(b"\xFF\xFEh\x00i\x00"), UTF_16LE);
+/// assert_eq!(detect_encoding(b"h\xE9llo"), WINDOWS_1252);
+/// ```
+pub fn detect_encoding(buffer: &[u8]) -> &'static Encoding {
+    detect(buffer, true)
+}
+
+fn detect(buffer: &[u8], may_be_truncated: bool) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(buffer) {
+        return encoding;
+    }
+    match std::str::from_utf8(buffer) {
+        Ok(_) => return UTF_8,
+        // the buffer may end in the middle of a multi-byte sequence
+        Err(e) if may_be_truncated && e.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+    if let Some(encoding) = detect_utf16_without_bom(buffer) {
+        return encoding;
+    }
+    WINDOWS_1252
+}
+
+fn detect_utf16_without_bom(buffer: &[u8]) -> Option<&'static Encoding> {
+    let pairs = buffer.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let (mut even_nuls, mut odd_nuls) = (0, 0);
+    for pair in buffer.chunks_exact(2) {
+        if pair[0] == 0 {
+            even_nuls += 1;
+        }
+        if pair[1] == 0 {
+            odd_nuls += 1;
+        }
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let proportion = |count: usize| count as f32 / pairs as f32;
+    if proportion(odd_nuls) > UTF16_NUL_BYTES_THRESHOLD && even_nuls == 0 {
+        Some(UTF_16LE)
+    } else if proportion(even_nuls) > UTF16_NUL_BYTES_THRESHOLD
+        && odd_nuls == 0
+    {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Whether the given buffer looks like UTF-16 text.
+pub fn is_utf16(buffer: &[u8]) -> bool {
+    let encoding = detect_encoding(buffer);
+    encoding == UTF_16LE || encoding == UTF_16BE
+}
+
+/// Decode the given bytes to UTF-8 using the detected encoding.
+///
+/// Unlike [`detect_encoding`], the bytes are expected to be complete, i.e. a
+/// trailing incomplete UTF-8 sequence disqualifies UTF-8.
+///
+/// BOMs are removed and malformed sequences are replaced with `U+FFFD`.
+///
+/// # Examples
+/// ```
+/// use television_utils::encoding::decode_to_utf8;
+///
+/// assert_eq!(decode_to_utf8(b"\xFE\xFF\x00h\x00i"), "hi");
+/// assert_eq!(decode_to_utf8(b"caf\xE9"), "café");
+/// ```
+pub fn decode_to_utf8(bytes: &[u8]) -> String {
+    let (decoded, _, _) = detect(bytes, false).decode(bytes);
+    decoded.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(s: &str, with_bom: bool) -> Vec<u8> {
+        let mut bytes = if with_bom { vec![0xFF, 0xFE] } else { vec![] };
+        bytes.extend(s.encode_utf16().flat_map(u16::to_le_bytes));
+        bytes
+    }
+
+    fn utf16be(s: &str, with_bom: bool) -> Vec<u8> {
+        let mut bytes = if with_bom { vec![0xFE, 0xFF] } else { vec![] };
+        bytes.extend(s.encode_utf16().flat_map(u16::to_be_bytes));
+        bytes
+    }
+
+    const TEXT: &str = "fn main() {\n    println!(\"héllo wörld\");\n}\n";
+
+    #[test]
+    fn test_utf16_with_bom() {
+        let le = utf16le(TEXT, true);
+        assert_eq!(detect_encoding(&le), UTF_16LE);
+        assert!(is_utf16(&le));
+        assert_eq!(decode_to_utf8(&le), TEXT);
+
+        let be = utf16be(TEXT, true);
+        assert_eq!(detect_encoding(&be), UTF_16BE);
+        assert!(is_utf16(&be));
+        assert_eq!(decode_to_utf8(&be), TEXT);
+    }
+
+    #[test]
+    fn test_utf16_without_bom() {
+        assert_eq!(detect_encoding(&utf16le(TEXT, false)), UTF_16LE);
+        assert_eq!(detect_encoding(&utf16be(TEXT, false)), UTF_16BE);
+        assert_eq!(decode_to_utf8(&utf16le(TEXT, false)), TEXT);
+    }
+
+    #[test]
+    fn test_latin1() {
+        // "Crème brûlée à la française" in Latin-1
+        let bytes = b"Cr\xE8me br\xFBl\xE9e \xE0 la fran\xE7aise\n";
+        assert_eq!(detect_encoding(bytes), WINDOWS_1252);
+        assert!(!is_utf16(bytes));
+        assert_eq!(decode_to_utf8(bytes), "Crème brûlée à la française\n");
+    }
+
+    #[test]
+    fn test_utf8() {
+        assert_eq!(detect_encoding(TEXT.as_bytes()), UTF_8);
+        assert_eq!(detect_encoding(b"\xEF\xBB\xBFhi"), UTF_8);
+        assert_eq!(decode_to_utf8(b"\xEF\xBB\xBFhi"), "hi");
+        // truncated multi-byte sequence at the end of the buffer
+        assert_eq!(detect_encoding(&"héllo".as_bytes()[..2]), UTF_8);
+        assert_eq!(detect_encoding(b""), UTF_8);
+    }
+}
@@ -50,6 +50,8 @@ pub fn get_file_size(path: &Path) -> Option<u64> {
 
 #[derive(Debug)]
 pub enum FileType {
+    Archive,
+    Directory,
     Text,
     Image,
     Other,
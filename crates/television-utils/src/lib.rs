@@ -1,3 +1,5 @@
+pub mod command;
+pub mod encoding;
 pub mod files;
 pub mod indices;
 pub mod stdin;
@@ -1,5 +1,7 @@
-use lazy_static::lazy_static;
 use std::fmt::Write;
+use std::sync::atomic::{AtomicU32, Ordering};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 /// Returns the index of the next character boundary in the given string.
 ///
@@ -62,6 +64,69 @@ pub fn prev_char_boundary(s: &str, start: usize) -> usize {
     i
 }
 
+/// Returns the byte index of the grapheme cluster boundary following `start`.
+///
+/// Unlike `next_char_boundary`, this always moves forward by one whole grapheme
+/// cluster (i.e. a visible glyph such as a flag emoji or an accented letter built
+/// from combining marks). If `start` is in the middle of a cluster, the end of that
+/// cluster is returned. If `start` is at or past the end of the string, the length
+/// of the string is returned.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::next_grapheme_boundary;
+///
+/// let s = "abc";
+/// assert_eq!(next_grapheme_boundary(s, 0), 1);
+/// assert_eq!(next_grapheme_boundary(s, 3), 3);
+///
+/// // "e" followed by a combining acute accent
+/// let s = "e\u{301}!";
+/// assert_eq!(next_grapheme_boundary(s, 0), 3);
+/// assert_eq!(next_grapheme_boundary(s, 1), 3);
+///
+/// // regional indicators forming the French flag
+/// let s = "🇫🇷";
+/// assert_eq!(next_grapheme_boundary(s, 0), 8);
+/// ```
+pub fn next_grapheme_boundary(s: &str, start: usize) -> usize {
+    if start >= s.len() {
+        return s.len();
+    }
+    s.grapheme_indices(true)
+        .map(|(i, _)| i)
+        .find(|i| *i > start)
+        .unwrap_or(s.len())
+}
+
+/// Returns the byte index of the grapheme cluster boundary preceding `start`.
+///
+/// Unlike `prev_char_boundary`, this always moves backward by one whole grapheme
+/// cluster. If `start` is in the middle of a cluster, the start of that cluster is
+/// returned. If `start` is 0, 0 is returned.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::prev_grapheme_boundary;
+///
+/// let s = "abc";
+/// assert_eq!(prev_grapheme_boundary(s, 3), 2);
+/// assert_eq!(prev_grapheme_boundary(s, 0), 0);
+///
+/// // "e" followed by a combining acute accent
+/// let s = "!e\u{301}";
+/// assert_eq!(prev_grapheme_boundary(s, 4), 1);
+/// assert_eq!(prev_grapheme_boundary(s, 2), 1);
+/// ```
+pub fn prev_grapheme_boundary(s: &str, start: usize) -> usize {
+    let start = start.min(s.len());
+    s.grapheme_indices(true)
+        .map(|(i, _)| i)
+        .take_while(|i| *i < start)
+        .last()
+        .unwrap_or(0)
+}
+
 /// Returns a slice of the given string that starts and ends at character boundaries.
 ///
 /// If the given start index is greater than the end index, or if either index is out of bounds,
@@ -85,14 +150,41 @@ pub fn slice_at_char_boundaries(
     start_byte_index: usize,
     end_byte_index: usize,
 ) -> &str {
+    try_slice_at_char_boundaries(s, start_byte_index, end_byte_index)
+        .unwrap_or(EMPTY_STRING)
+}
+
+/// Like `slice_at_char_boundaries`, but returns `None` instead of an empty
+/// string if the given start index is greater than the end index, or if
+/// either index is out of bounds.
+///
+/// This lets callers tell empty slices apart from invalid ranges.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::try_slice_at_char_boundaries;
+///
+/// let s = "Hello, World!";
+/// assert_eq!(try_slice_at_char_boundaries(s, 0, 0), Some(""));
+/// assert_eq!(try_slice_at_char_boundaries(s, 0, 1), Some("H"));
+/// assert_eq!(try_slice_at_char_boundaries(s, 2, 1), None);
+/// assert_eq!(try_slice_at_char_boundaries(s, 0, 14), None);
+/// ```
+pub fn try_slice_at_char_boundaries(
+    s: &str,
+    start_byte_index: usize,
+    end_byte_index: usize,
+) -> Option<&str> {
     if start_byte_index > end_byte_index
         || start_byte_index > s.len()
         || end_byte_index > s.len()
     {
-        return EMPTY_STRING;
+        return None;
     }
-    &s[prev_char_boundary(s, start_byte_index)
-        ..next_char_boundary(s, end_byte_index)]
+    Some(
+        &s[prev_char_boundary(s, start_byte_index)
+            ..next_char_boundary(s, end_byte_index)],
+    )
 }
 
 /// Returns a slice of the given string that starts at the beginning and ends at a character
@@ -135,9 +227,39 @@ fn try_parse_utf8_char(input: &[u8]) -> Option<(char, usize)> {
     decoded.map(|(seq, n)| (seq.chars().next().unwrap(), n))
 }
 
-lazy_static! {
-    /// The Unicode symbol to use for non-printable characters.
-    static ref NULL_SYMBOL: char = char::from_u32(0x2400).unwrap();
+/// The Unicode symbol to use for non-printable characters.
+pub const NULL_SYMBOL: char = '\u{2400}';
+
+/// The symbol non-printable characters are replaced with by default, stored
+/// as its code point since there's no atomic `char`.
+static DEFAULT_REPLACEMENT_SYMBOL: AtomicU32 =
+    AtomicU32::new(NULL_SYMBOL as u32);
+
+/// Set the symbol `ReplaceNonPrintableConfig::default()` substitutes
+/// non-printable characters with, which is `NULL_SYMBOL` unless set.
+///
+/// This applies to every function of this module that doesn't take a
+/// `ReplaceNonPrintableConfig` (e.g. `preprocess_line`), and should be set
+/// before any input is processed.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::{
+///     preprocess_line, set_default_replacement_symbol,
+/// };
+///
+/// set_default_replacement_symbol('?');
+/// assert_eq!(preprocess_line("Hello\x00World"), "Hello?World");
+/// ```
+pub fn set_default_replacement_symbol(symbol: char) {
+    DEFAULT_REPLACEMENT_SYMBOL.store(symbol as u32, Ordering::Relaxed);
+}
+
+/// The symbol `ReplaceNonPrintableConfig::default()` substitutes
+/// non-printable characters with (see `set_default_replacement_symbol`).
+pub fn default_replacement_symbol() -> char {
+    char::from_u32(DEFAULT_REPLACEMENT_SYMBOL.load(Ordering::Relaxed))
+        .unwrap_or(NULL_SYMBOL)
 }
 
 pub const EMPTY_STRING: &str = "";
@@ -146,12 +268,108 @@ pub const TAB_WIDTH: usize = 4;
 const SPACE_CHARACTER: char = ' ';
 const TAB_CHARACTER: char = '\t';
 const LINE_FEED_CHARACTER: char = '\x0A';
+const VERTICAL_TAB_CHARACTER: char = '\x0B';
+const FORM_FEED_CHARACTER: char = '\x0C';
 const DELETE_CHARACTER: char = '\x7F';
 const BOM_CHARACTER: char = '\u{FEFF}';
 const NULL_CHARACTER: char = '\x00';
 const UNIT_SEPARATOR_CHARACTER: char = '\u{001F}';
 const APPLICATION_PROGRAM_COMMAND_CHARACTER: char = '\u{009F}';
 
+/// The horizontal rule form feeds are rendered as when `page_breaks` is set.
+pub const PAGE_BREAK_RULE: &str = "────────────────────────────────────────";
+
+/// Options controlling how `replace_non_printable_with_config` transforms its input.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::ReplaceNonPrintableConfig;
+///
+/// let config = ReplaceNonPrintableConfig::default()
+///     .tab_width(8)
+///     .keep_wide_unicode(true);
+/// assert_eq!(config.tab_width, 8);
+/// assert!(config.keep_wide_unicode);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplaceNonPrintableConfig {
+    /// The number of spaces to replace a tab character with.
+    pub tab_width: usize,
+    /// Whether tabs advance to the next multiple of `tab_width` columns, as
+    /// editors render them, instead of always expanding to `tab_width`
+    /// spaces.
+    pub tab_stops: bool,
+    /// Whether to pass through characters above `\u{0700}` instead of
+    /// replacing them with the replacement symbol.
+    pub keep_wide_unicode: bool,
+    /// The character to substitute non-printable characters with.
+    pub replacement_symbol: char,
+    /// Whether to strip ANSI escape sequences before replacing non-printable
+    /// characters (see `strip_ansi_escape_codes`).
+    pub strip_ansi_escape_codes: bool,
+    /// Whether to render form feeds as a horizontal rule on its own line
+    /// (`PAGE_BREAK_RULE`) and vertical tabs as line breaks, instead of
+    /// replacing them with the replacement symbol.
+    ///
+    /// The output then contains `\n` characters, which callers should split
+    /// lines on.
+    pub page_breaks: bool,
+}
+
+impl Default for ReplaceNonPrintableConfig {
+    fn default() -> Self {
+        Self {
+            tab_width: TAB_WIDTH,
+            tab_stops: false,
+            keep_wide_unicode: false,
+            replacement_symbol: default_replacement_symbol(),
+            strip_ansi_escape_codes: false,
+            page_breaks: false,
+        }
+    }
+}
+
+impl ReplaceNonPrintableConfig {
+    /// Set the number of spaces to replace a tab character with.
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Set whether tabs advance to the next tab stop.
+    pub fn tab_stops(mut self, tab_stops: bool) -> Self {
+        self.tab_stops = tab_stops;
+        self
+    }
+
+    /// Set whether to pass through characters above `\u{0700}`.
+    pub fn keep_wide_unicode(mut self, keep_wide_unicode: bool) -> Self {
+        self.keep_wide_unicode = keep_wide_unicode;
+        self
+    }
+
+    /// Set the character to substitute non-printable characters with.
+    pub fn replacement_symbol(mut self, replacement_symbol: char) -> Self {
+        self.replacement_symbol = replacement_symbol;
+        self
+    }
+
+    /// Set whether to strip ANSI escape sequences from the input.
+    pub fn strip_ansi_escape_codes(
+        mut self,
+        strip_ansi_escape_codes: bool,
+    ) -> Self {
+        self.strip_ansi_escape_codes = strip_ansi_escape_codes;
+        self
+    }
+
+    /// Set whether to render form feeds and vertical tabs as line breaks.
+    pub fn page_breaks(mut self, page_breaks: bool) -> Self {
+        self.page_breaks = page_breaks;
+        self
+    }
+}
+
 /// Replaces non-printable characters in the given byte slice with default printable characters.
 ///
 /// The tab width is used to determine how many spaces to replace a tab character with.
@@ -182,7 +400,58 @@ const APPLICATION_PROGRAM_COMMAND_CHARACTER: char = '\u{009F}';
 /// assert_eq!(output, "Hello␀World!");
 /// ```
 pub fn replace_non_printable(input: &[u8], tab_width: usize) -> String {
+    replace_non_printable_with_config(
+        input,
+        &ReplaceNonPrintableConfig::default().tab_width(tab_width),
+    )
+}
+
+/// Replaces non-printable characters in the given byte slice according to the given config.
+///
+/// See `ReplaceNonPrintableConfig` for the available options.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::{
+///     replace_non_printable_with_config, ReplaceNonPrintableConfig,
+/// };
+///
+/// let input = "नमस्ते".as_bytes();
+/// let config = ReplaceNonPrintableConfig::default();
+/// assert_eq!(
+///     replace_non_printable_with_config(input, &config),
+///     "␀␀␀␀␀␀"
+/// );
+///
+/// let config = config.keep_wide_unicode(true);
+/// assert_eq!(replace_non_printable_with_config(input, &config), "नमस्ते");
+///
+/// let input = b"Hello\x00World!";
+/// let config = ReplaceNonPrintableConfig::default().replacement_symbol('?');
+/// assert_eq!(replace_non_printable_with_config(input, &config), "Hello?World!");
+///
+/// let input = b"id\tname";
+/// let config = ReplaceNonPrintableConfig::default().tab_width(4);
+/// assert_eq!(replace_non_printable_with_config(input, &config), "id    name");
+/// let config = config.tab_stops(true);
+/// assert_eq!(replace_non_printable_with_config(input, &config), "id  name");
+/// ```
+pub fn replace_non_printable_with_config(
+    input: &[u8],
+    config: &ReplaceNonPrintableConfig,
+) -> String {
+    let stripped;
+    let input = if config.strip_ansi_escape_codes {
+        stripped = strip_ansi_escape_bytes(input);
+        &stripped[..]
+    } else {
+        input
+    };
     let mut output = String::new();
+    // the display column reached at byte `measured` of the current line of
+    // the output, only tracked for tab stops
+    let mut column = 0;
+    let mut measured = 0;
 
     let mut idx = 0;
     let len = input.len();
@@ -194,23 +463,46 @@ pub fn replace_non_printable(input: &[u8], tab_width: usize) -> String {
                 // space
                 SPACE_CHARACTER => output.push(' '),
                 // tab
+                TAB_CHARACTER if config.tab_stops && config.tab_width > 0 => {
+                    column += output[measured..].width();
+                    let spaces = config.tab_width - column % config.tab_width;
+                    output.push_str(&" ".repeat(spaces));
+                    column += spaces;
+                    measured = output.len();
+                }
                 TAB_CHARACTER => {
-                    output.push_str(&" ".repeat(tab_width));
+                    output.push_str(&" ".repeat(config.tab_width));
                 }
                 // line feed
-                LINE_FEED_CHARACTER => {}
+                LINE_FEED_CHARACTER => {
+                    column = 0;
+                    measured = output.len();
+                }
+                // page breaks
+                VERTICAL_TAB_CHARACTER if config.page_breaks => {
+                    output.push('\n');
+                    column = 0;
+                    measured = output.len();
+                }
+                FORM_FEED_CHARACTER if config.page_breaks => {
+                    output.push('\n');
+                    output.push_str(PAGE_BREAK_RULE);
+                    output.push('\n');
+                    column = 0;
+                    measured = output.len();
+                }
 
                 // ASCII control characters from 0x00 to 0x1F
                 // + control characters from \u{007F} to \u{009F}
                 NULL_CHARACTER..=UNIT_SEPARATOR_CHARACTER
                 | DELETE_CHARACTER..=APPLICATION_PROGRAM_COMMAND_CHARACTER => {
-                    output.push(*NULL_SYMBOL);
+                    output.push(config.replacement_symbol);
                 }
                 // don't print BOMs
                 BOM_CHARACTER => {}
                 // Unicode characters above 0x0700 seem unstable with ratatui
-                c if c > '\u{0700}' => {
-                    output.push(*NULL_SYMBOL);
+                c if c > '\u{0700}' && !config.keep_wide_unicode => {
+                    output.push(config.replacement_symbol);
                 }
                 // everything else
                 c => output.push(c),
@@ -224,6 +516,84 @@ pub fn replace_non_printable(input: &[u8], tab_width: usize) -> String {
     output
 }
 
+const ESCAPE_BYTE: u8 = 0x1B;
+const BELL_BYTE: u8 = 0x07;
+
+/// Removes ANSI escape sequences from the given byte slice.
+///
+/// This strips CSI sequences (`ESC [ ... <final>`, e.g. SGR colors and cursor
+/// movements), OSC sequences (`ESC ] ... BEL` or `ESC ] ... ESC \`) as well as
+/// other two-byte escape sequences. Incomplete sequences at the end of the input
+/// (e.g. when a buffer was cut in the middle of one) are dropped, and a bare `ESC`
+/// that doesn't start a known sequence is removed on its own.
+///
+/// Invalid UTF-8 in the remaining bytes is replaced with `U+FFFD`.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::strip_ansi_escape_codes;
+///
+/// let input = b"\x1b[1;31mError:\x1b[0m something went wrong";
+/// assert_eq!(strip_ansi_escape_codes(input), "Error: something went wrong");
+///
+/// let input = b"\x1b]0;window title\x07Hello";
+/// assert_eq!(strip_ansi_escape_codes(input), "Hello");
+///
+/// let input = b"Hello\x1b[3";
+/// assert_eq!(strip_ansi_escape_codes(input), "Hello");
+/// ```
+pub fn strip_ansi_escape_codes(input: &[u8]) -> String {
+    String::from_utf8_lossy(&strip_ansi_escape_bytes(input)).into_owned()
+}
+
+fn strip_ansi_escape_bytes(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+
+    let mut idx = 0;
+    let len = input.len();
+    while idx < len {
+        if input[idx] != ESCAPE_BYTE {
+            output.push(input[idx]);
+            idx += 1;
+            continue;
+        }
+        match input.get(idx + 1) {
+            // CSI: parameter bytes, intermediate bytes, then a final byte
+            Some(b'[') => {
+                idx += 2;
+                while idx < len && !(0x40..=0x7E).contains(&input[idx]) {
+                    idx += 1;
+                }
+                // skip the final byte
+                idx += 1;
+            }
+            // OSC: terminated by BEL or by ST (`ESC \`)
+            Some(b']') => {
+                idx += 2;
+                while idx < len {
+                    if input[idx] == BELL_BYTE {
+                        idx += 1;
+                        break;
+                    }
+                    if input[idx] == ESCAPE_BYTE
+                        && input.get(idx + 1) == Some(&b'\\')
+                    {
+                        idx += 2;
+                        break;
+                    }
+                    idx += 1;
+                }
+            }
+            // other two-byte sequences (e.g. `ESC 7`, `ESC M`)
+            Some(b) if (0x30..=0x7E).contains(b) => idx += 2,
+            // bare ESC
+            _ => idx += 1,
+        }
+    }
+
+    output
+}
+
 /// The threshold for considering a buffer to be printable ASCII.
 ///
 /// This is used to determine whether a file is likely to be a text file
@@ -260,7 +630,91 @@ pub fn proportion_of_printable_ascii_characters(buffer: &[u8]) -> f32 {
     printable as f32 / buffer.len() as f32
 }
 
-const MAX_LINE_LENGTH: usize = 300;
+/// Returns the proportion of printable characters in the given buffer,
+/// decoded as UTF-8.
+///
+/// Unlike [`proportion_of_printable_ascii_characters`], non-ASCII text
+/// (accented Latin, Cyrillic, CJK...) scores high. Whitespace is considered
+/// printable and each invalid UTF-8 sequence counts as one non-printable
+/// character.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::proportion_of_printable_utf8_characters;
+///
+/// let buffer = "Hello, World!".as_bytes();
+/// let proportion = proportion_of_printable_utf8_characters(buffer);
+/// assert_eq!(proportion, 1.0);
+///
+/// let buffer = "Grüße!\x00".as_bytes();
+/// let proportion = proportion_of_printable_utf8_characters(buffer);
+/// assert_eq!(proportion, 0.85714287);
+///
+/// let buffer = b"\xff\xfe\x00\x01";
+/// let proportion = proportion_of_printable_utf8_characters(buffer);
+/// assert_eq!(proportion, 0.0);
+/// ```
+pub fn proportion_of_printable_utf8_characters(buffer: &[u8]) -> f32 {
+    let mut printable: usize = 0;
+    let mut total: usize = 0;
+    for chunk in buffer.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            total += 1;
+            if !c.is_control() || c.is_whitespace() {
+                printable += 1;
+            }
+        }
+        if !chunk.invalid().is_empty() {
+            total += 1;
+        }
+    }
+    printable as f32 / total as f32
+}
+
+/// Whether the given sample of a file's contents is likely binary data.
+///
+/// Samples containing a NUL byte are considered binary (which is what git
+/// does), as are those with too few printable characters. UTF-16 text, NUL
+/// bytes included, thus looks binary: it should be detected beforehand.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::is_likely_binary;
+///
+/// assert!(!is_likely_binary(b"Hello, World!"));
+/// assert!(!is_likely_binary("Привет, мир!".as_bytes()));
+/// assert!(is_likely_binary(b"Hello,\x00World!"));
+/// assert!(is_likely_binary(b"\x89PNG\r\n\x1a\n\xff\xfe\xfd"));
+/// ```
+pub fn is_likely_binary(buffer: &[u8]) -> bool {
+    if buffer.is_empty() {
+        return false;
+    }
+    // the cheap ASCII check comes first
+    buffer.contains(&0)
+        || !(proportion_of_printable_ascii_characters(buffer)
+            > PRINTABLE_ASCII_THRESHOLD
+            || proportion_of_printable_utf8_characters(buffer)
+                > PRINTABLE_ASCII_THRESHOLD)
+}
+
+/// The default maximum length (in bytes) of a preprocessed line.
+pub const MAX_LINE_LENGTH: usize = 300;
+
+/// The maximum length (in bytes) of lines displayed in `width` columns.
+///
+/// Lines are truncated in bytes while widths are expressed in columns, so up
+/// to 4 bytes (the longest UTF-8 encoded char) are allowed per column.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::max_line_length_for_width;
+///
+/// assert_eq!(max_line_length_for_width(80), 320);
+/// ```
+pub fn max_line_length_for_width(width: usize) -> usize {
+    width.saturating_mul(4)
+}
 
 /// Preprocesses a line of text for display.
 ///
@@ -284,25 +738,109 @@ const MAX_LINE_LENGTH: usize = 300;
 /// assert_eq!(processed.len(), 300);
 /// ```
 pub fn preprocess_line(line: &str) -> String {
-    replace_non_printable(
+    preprocess_line_with_tab_width(line, TAB_WIDTH)
+}
+
+/// Preprocesses a line of text for display, truncating it to `max_line_length` bytes.
+///
+/// This behaves exactly like `preprocess_line` except that the line is truncated at
+/// `max_line_length` instead of the default `MAX_LINE_LENGTH`. Truncation always
+/// happens on a char boundary.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::preprocess_line_with_max_length;
+///
+/// let line = "a".repeat(400);
+/// assert_eq!(preprocess_line_with_max_length(&line, 10), "a".repeat(10));
+/// assert_eq!(preprocess_line_with_max_length(&line, 1000), line);
+///
+/// // "é" is 2 bytes long, truncation extends to the next char boundary
+/// assert_eq!(preprocess_line_with_max_length("éééé", 3), "éé");
+/// ```
+pub fn preprocess_line_with_max_length(
+    line: &str,
+    max_line_length: usize,
+) -> String {
+    preprocess_line_with_config(
+        line,
+        max_line_length,
+        &ReplaceNonPrintableConfig::default(),
+    )
+}
+
+/// Preprocesses a line of text for display using the given tab width.
+///
+/// This behaves exactly like `preprocess_line` except that tab characters are
+/// replaced with `tab_width` spaces instead of the default `TAB_WIDTH`.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::preprocess_line_with_tab_width;
+///
+/// let line = "\tHello, World!";
+/// assert_eq!(preprocess_line_with_tab_width(line, 2), "  Hello, World!");
+/// assert_eq!(
+///     preprocess_line_with_tab_width(line, 8),
+///     "        Hello, World!"
+/// );
+/// ```
+pub fn preprocess_line_with_tab_width(line: &str, tab_width: usize) -> String {
+    preprocess_line_with_config(
+        line,
+        MAX_LINE_LENGTH,
+        &ReplaceNonPrintableConfig::default().tab_width(tab_width),
+    )
+}
+
+/// Preprocesses a line of text for display using the given config.
+///
+/// This behaves exactly like `preprocess_line` except that the line is truncated
+/// at `max_line_length` bytes and non-printable characters are handled according
+/// to `config` (see `ReplaceNonPrintableConfig`).
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::{
+///     preprocess_line_with_config, ReplaceNonPrintableConfig,
+/// };
+///
+/// let config = ReplaceNonPrintableConfig::default().replacement_symbol('·');
+/// assert_eq!(
+///     preprocess_line_with_config("\x00World\x7F!", 300, &config),
+///     "·World·!"
+/// );
+/// ```
+pub fn preprocess_line_with_config(
+    line: &str,
+    max_line_length: usize,
+    config: &ReplaceNonPrintableConfig,
+) -> String {
+    replace_non_printable_with_config(
         {
-            if line.len() > MAX_LINE_LENGTH {
-                slice_up_to_char_boundary(line, MAX_LINE_LENGTH)
+            if line.len() > max_line_length {
+                slice_up_to_char_boundary(line, max_line_length)
             } else {
                 line
             }
         }
         .trim_end_matches(['\r', '\n', '\0'])
         .as_bytes(),
-        TAB_WIDTH,
+        config,
     )
 }
 
-/// Shrink a string to a maximum length, adding an ellipsis in the middle.
+/// Shrink a string to a maximum display width, adding an ellipsis in the middle.
 ///
-/// If the string is shorter than the maximum length, it is returned as is.
-/// If the string is longer than the maximum length, it is shortened and an ellipsis is added in
-/// the middle.
+/// The maximum length is expressed in terminal columns (as reported by the
+/// `unicode-width` crate) rather than in bytes, which means wide characters
+/// (e.g. CJK) count for two columns and zero-width characters (e.g. combining
+/// accents) don't count at all.
+///
+/// If the string fits within the maximum length, it is returned as is.
+/// Otherwise, it is shortened and an ellipsis is added in the middle. The
+/// string is only ever split on grapheme cluster boundaries so that combining
+/// sequences and emoji sequences are never broken apart.
 ///
 /// # Examples
 /// ```
@@ -311,17 +849,105 @@ pub fn preprocess_line(line: &str) -> String {
 /// let s = "Hello, World!";
 /// assert_eq!(shrink_with_ellipsis(s, 13), "Hello, World!");
 /// assert_eq!(shrink_with_ellipsis(s, 6), "H…!");
+///
+/// let s = "こんにちは世界";
+/// assert_eq!(shrink_with_ellipsis(s, 14), "こんにちは世界");
+/// assert_eq!(shrink_with_ellipsis(s, 12), "こん…世界");
 /// ```
 pub fn shrink_with_ellipsis(s: &str, max_length: usize) -> String {
-    if s.len() <= max_length {
-        return s.to_string();
+    match ellipsis_bounds(s, max_length) {
+        Some((first_half_end, second_half_start)) => {
+            format!("{}…{}", &s[..first_half_end], &s[second_half_start..])
+        }
+        None => s.to_string(),
+    }
+}
+
+/// Shrink a string like [`shrink_with_ellipsis`] and remap the given byte
+/// ranges (e.g. match ranges) onto the shrunk string.
+///
+/// Ranges falling in the elided middle are dropped, ranges in the visible
+/// ends are shifted accordingly and ranges spanning the ellipsis are split in
+/// two.
+///
+/// # Examples
+/// ```
+/// use television_utils::strings::shrink_with_ellipsis_and_ranges;
+///
+/// let (shrunk, ranges) = shrink_with_ellipsis_and_ranges(
+///     "Hello, World!",
+///     10,
+///     &[(0, 1), (5, 6), (11, 13)],
+/// );
+/// assert_eq!(shrunk, "Hel…ld!");
+/// assert_eq!(ranges, vec![(0, 1), (7, 9)]);
+/// ```
+pub fn shrink_with_ellipsis_and_ranges(
+    s: &str,
+    max_length: usize,
+    ranges: &[(u32, u32)],
+) -> (String, Vec<(u32, u32)>) {
+    let Some((first_half_end, second_half_start)) =
+        ellipsis_bounds(s, max_length)
+    else {
+        return (s.to_string(), ranges.to_vec());
+    };
+    let first_half_end = u32::try_from(first_half_end).unwrap();
+    let second_half_start = u32::try_from(second_half_start).unwrap();
+    // the shrunk string has the ellipsis where the middle used to be
+    let ellipsis_end = first_half_end + u32::try_from('…'.len_utf8()).unwrap();
+    let shift = |i: u32| i - second_half_start + ellipsis_end;
+
+    let mut remapped = Vec::new();
+    for &(start, end) in ranges {
+        if start < first_half_end {
+            remapped.push((start, end.min(first_half_end)));
+        }
+        if end > second_half_start {
+            remapped.push((shift(start.max(second_half_start)), shift(end)));
+        }
+    }
+    (
+        format!(
+            "{}…{}",
+            &s[..first_half_end as usize],
+            &s[second_half_start as usize..]
+        ),
+        remapped,
+    )
+}
+
+/// Compute the end of the first half and the start of the second half of a
+/// string shrunk with an ellipsis, or `None` if it doesn't need shrinking.
+fn ellipsis_bounds(s: &str, max_length: usize) -> Option<(usize, usize)> {
+    if s.width() <= max_length {
+        return None;
     }
 
     let half_max_length = (max_length / 2).saturating_sub(2);
-    let first_half = slice_up_to_char_boundary(s, half_max_length);
-    let second_half =
-        slice_at_char_boundaries(s, s.len() - half_max_length, s.len());
-    format!("{first_half}…{second_half}")
+
+    let mut first_half_width = 0;
+    let first_half_end = s
+        .grapheme_indices(true)
+        .take_while(|(_, g)| {
+            first_half_width += g.width();
+            first_half_width <= half_max_length
+        })
+        .last()
+        .map_or(0, |(i, g)| i + g.len());
+
+    let mut second_half_width = 0;
+    let second_half_start = s
+        .grapheme_indices(true)
+        .rev()
+        .take_while(|(i, g)| {
+            second_half_width += g.width();
+            second_half_width <= half_max_length && *i >= first_half_end
+        })
+        .last()
+        .map_or(s.len(), |(i, _)| i);
+
+    Some((first_half_end, second_half_start))
 }
 
 #[cfg(test)]
@@ -401,6 +1027,21 @@ mod tests {
         test_slice_at_char_boundaries("👋🌍!", 0, 9, "👋🌍!");
     }
 
+    #[test]
+    fn test_try_slice_at_char_boundaries() {
+        let s = "Hello, World!";
+        assert_eq!(try_slice_at_char_boundaries(s, 0, 0), Some(""));
+        assert_eq!(try_slice_at_char_boundaries(s, 13, 13), Some(""));
+        assert_eq!(try_slice_at_char_boundaries(s, 0, 13), Some(s));
+        assert_eq!(try_slice_at_char_boundaries("👋🌍!", 1, 5), Some("👋🌍"));
+        // inverted range
+        assert_eq!(try_slice_at_char_boundaries(s, 5, 4), None);
+        // out of bounds
+        assert_eq!(try_slice_at_char_boundaries(s, 0, 30), None);
+        assert_eq!(try_slice_at_char_boundaries(s, 14, 14), None);
+        assert_eq!(try_slice_at_char_boundaries("", 0, 1), None);
+    }
+
     fn test_replace_non_printable(input: &str, expected: &str) {
         let actual = replace_non_printable(input.as_bytes(), 2);
         assert_eq!(actual, expected);
@@ -446,6 +1087,222 @@ mod tests {
         test_replace_non_printable("Àì", "Àì␀");
     }
 
+    fn test_replace_non_printable_keeping_wide_unicode(
+        input: &str,
+        expected: &str,
+    ) {
+        let config = ReplaceNonPrintableConfig::default()
+            .tab_width(2)
+            .keep_wide_unicode(true);
+        let actual =
+            replace_non_printable_with_config(input.as_bytes(), &config);
+        assert_eq!(actual, expected);
+    }
+
+    const FAMILY_EMOJI: &str = "👨\u{200d}👩\u{200d}👧";
+    const COMBINING_E: &str = "e\u{301}";
+    const FLAG: &str = "🇫🇷";
+
+    #[test]
+    fn test_next_grapheme_boundary() {
+        for cluster in [FAMILY_EMOJI, COMBINING_E, FLAG] {
+            let s = format!("a{cluster}b");
+            assert_eq!(next_grapheme_boundary(&s, 0), 1);
+            assert_eq!(next_grapheme_boundary(&s, 1), 1 + cluster.len());
+            // from the middle of the cluster
+            assert_eq!(next_grapheme_boundary(&s, 2), 1 + cluster.len());
+            assert_eq!(next_grapheme_boundary(&s, 1 + cluster.len()), s.len());
+            assert_eq!(next_grapheme_boundary(&s, s.len()), s.len());
+        }
+    }
+
+    #[test]
+    fn test_prev_grapheme_boundary() {
+        for cluster in [FAMILY_EMOJI, COMBINING_E, FLAG] {
+            let s = format!("a{cluster}b");
+            assert_eq!(prev_grapheme_boundary(&s, s.len()), 1 + cluster.len());
+            assert_eq!(prev_grapheme_boundary(&s, 1 + cluster.len()), 1);
+            // from the middle of the cluster
+            assert_eq!(prev_grapheme_boundary(&s, 2), 1);
+            assert_eq!(prev_grapheme_boundary(&s, 1), 0);
+            assert_eq!(prev_grapheme_boundary(&s, 0), 0);
+        }
+    }
+
+    #[test]
+    fn test_replace_non_printable_arabic() {
+        // the `ﷲ` ligature lives in the Arabic Presentation Forms block
+        test_replace_non_printable("مرحبا ﷲ", "مرحبا ␀");
+        test_replace_non_printable_keeping_wide_unicode("مرحبا ﷲ", "مرحبا ﷲ");
+    }
+
+    #[test]
+    fn test_replace_non_printable_multi_codepoint_emoji() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        test_replace_non_printable(family, "␀␀␀␀␀");
+        test_replace_non_printable_keeping_wide_unicode(family, family);
+    }
+
+    #[test]
+    fn test_replace_non_printable_keeping_wide_unicode_still_replaces_control_characters(
+    ) {
+        test_replace_non_printable_keeping_wide_unicode(
+            "\u{FEFF}שלום\tעולם\x00",
+            "שלום  עולם␀",
+        );
+    }
+
+    #[test]
+    fn test_replace_non_printable_tab_stops() {
+        let fixed = ReplaceNonPrintableConfig::default().tab_width(4);
+        let tab_stops = fixed.tab_stops(true);
+        let input = b"a\tb\tc\nabcde\tf";
+        assert_eq!(
+            replace_non_printable_with_config(input, &fixed),
+            "a    b    cabcde    f"
+        );
+        assert_eq!(
+            replace_non_printable_with_config(input, &tab_stops),
+            "a   b   cabcde   f"
+        );
+        // a tab right on a tab stop advances to the next one
+        assert_eq!(
+            replace_non_printable_with_config(b"abcd\te", &tab_stops),
+            "abcd    e"
+        );
+        // wide characters take two columns
+        let tab_stops = tab_stops.keep_wide_unicode(true);
+        assert_eq!(
+            replace_non_printable_with_config(
+                "日本\tx".as_bytes(),
+                &tab_stops
+            ),
+            "日本    x"
+        );
+    }
+
+    #[test]
+    fn test_replace_non_printable_form_feed() {
+        let input = b"page 1\x0cpage 2";
+        let config = ReplaceNonPrintableConfig::default();
+        assert_eq!(
+            replace_non_printable_with_config(input, &config),
+            "page 1␀page 2"
+        );
+        let config = config.page_breaks(true);
+        assert_eq!(
+            replace_non_printable_with_config(input, &config),
+            format!("page 1\n{PAGE_BREAK_RULE}\npage 2")
+        );
+    }
+
+    #[test]
+    fn test_replace_non_printable_vertical_tab() {
+        let input = b"a\tb\x0b\tc";
+        let config = ReplaceNonPrintableConfig::default()
+            .tab_width(4)
+            .tab_stops(true);
+        assert_eq!(
+            replace_non_printable_with_config(input, &config),
+            "a   b␀  c"
+        );
+        // the column is reset after the break
+        let config = config.page_breaks(true);
+        assert_eq!(
+            replace_non_printable_with_config(input, &config),
+            "a   b\n    c"
+        );
+    }
+
+    #[test]
+    fn test_replace_non_printable_custom_replacement_symbol() {
+        let config = ReplaceNonPrintableConfig::default()
+            .tab_width(2)
+            .replacement_symbol('·');
+        assert_eq!(
+            replace_non_printable_with_config(b"a\x00b\x7F\tc", &config),
+            "a·b·  c"
+        );
+        assert_eq!(
+            replace_non_printable_with_config("नमस्ते".as_bytes(), &config),
+            "······"
+        );
+    }
+
+    #[test]
+    fn test_strip_ansi_escape_codes_colors() {
+        assert_eq!(
+            strip_ansi_escape_codes(
+                b"\x1b[31mred\x1b[0m \x1b[1;32mgreen\x1b[m"
+            ),
+            "red green"
+        );
+        assert_eq!(
+            strip_ansi_escape_codes(b"\x1b[38;5;208morange\x1b[39m"),
+            "orange"
+        );
+        assert_eq!(
+            strip_ansi_escape_codes(b"\x1b[38;2;255;128;0mtruecolor"),
+            "truecolor"
+        );
+    }
+
+    #[test]
+    fn test_strip_ansi_escape_codes_cursor_moves() {
+        assert_eq!(strip_ansi_escape_codes(b"a\x1b[2Ab\x1b[10;20Hc"), "abc");
+        assert_eq!(
+            strip_ansi_escape_codes(b"\x1b[?25lhidden\x1b[?25h"),
+            "hidden"
+        );
+        assert_eq!(strip_ansi_escape_codes(b"\x1b7saved\x1b8"), "saved");
+    }
+
+    #[test]
+    fn test_strip_ansi_escape_codes_osc() {
+        assert_eq!(
+            strip_ansi_escape_codes(b"\x1b]8;;https://example.com\x1b\\link"),
+            "link"
+        );
+        assert_eq!(strip_ansi_escape_codes(b"\x1b]0;title\x07text"), "text");
+    }
+
+    #[test]
+    fn test_strip_ansi_escape_codes_bare_escape() {
+        assert_eq!(strip_ansi_escape_codes(b"foo\x1b"), "foo");
+        assert_eq!(strip_ansi_escape_codes(b"foo\x1b\x1b[0mbar"), "foobar");
+        assert_eq!(strip_ansi_escape_codes(b"foo\x1b\tbar"), "foo\tbar");
+    }
+
+    #[test]
+    fn test_strip_ansi_escape_codes_incomplete_sequences() {
+        assert_eq!(strip_ansi_escape_codes(b"foo\x1b["), "foo");
+        assert_eq!(strip_ansi_escape_codes(b"foo\x1b[38;5"), "foo");
+        assert_eq!(strip_ansi_escape_codes(b"foo\x1b]0;unterminated"), "foo");
+    }
+
+    #[test]
+    fn test_strip_ansi_escape_codes_keeps_utf8() {
+        assert_eq!(
+            strip_ansi_escape_codes("\x1b[1m日本語\x1b[0m".as_bytes()),
+            "日本語"
+        );
+    }
+
+    #[test]
+    fn test_preprocess_line_stripping_ansi_escape_codes() {
+        let config =
+            ReplaceNonPrintableConfig::default().strip_ansi_escape_codes(true);
+        assert_eq!(
+            preprocess_line_with_config(
+                "\x1b[01;34mdir\x1b[0m\n",
+                MAX_LINE_LENGTH,
+                &config
+            ),
+            "dir"
+        );
+        assert_eq!(preprocess_line("\x1b[0mfoo"), "␀[0mfoo");
+    }
+
     fn test_proportion_of_printable_ascii_characters(
         input: &str,
         expected: f32,
@@ -468,6 +1325,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_proportion_of_printable_utf8_characters_cyrillic() {
+        let text = "Привет, мир!\nЭто обычный текстовый файл.\n";
+        assert!(
+            proportion_of_printable_ascii_characters(text.as_bytes())
+                < PRINTABLE_ASCII_THRESHOLD
+        );
+        assert_eq!(
+            proportion_of_printable_utf8_characters(text.as_bytes()),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_proportion_of_printable_utf8_characters_japanese() {
+        let text = "こんにちは、世界！\nこれはテキストファイルです。\n";
+        assert!(
+            proportion_of_printable_ascii_characters(text.as_bytes())
+                < PRINTABLE_ASCII_THRESHOLD
+        );
+        assert_eq!(
+            proportion_of_printable_utf8_characters(text.as_bytes()),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_proportion_of_printable_utf8_characters_binary() {
+        let binary: Vec<u8> = (0..=255).collect();
+        assert!(
+            proportion_of_printable_utf8_characters(&binary)
+                < PRINTABLE_ASCII_THRESHOLD
+        );
+    }
+
+    #[test]
+    fn test_is_likely_binary_ascii() {
+        assert!(!is_likely_binary(
+            b"fn main() {\n    println!(\"hi\");\n}\n"
+        ));
+        assert!(!is_likely_binary(b""));
+    }
+
+    #[test]
+    fn test_is_likely_binary_embedded_nul() {
+        let mut text = b"some text ".repeat(100);
+        text[500] = 0;
+        assert!(is_likely_binary(&text));
+    }
+
+    #[test]
+    fn test_is_likely_binary_utf16() {
+        let utf16: Vec<u8> = "Hello, World!"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        assert!(is_likely_binary(&utf16));
+    }
+
     fn test_preprocess_line(input: &str, expected: &str) {
         let actual = preprocess_line(input);
         assert_eq!(actual, expected);
@@ -482,4 +1398,140 @@ mod tests {
         test_preprocess_line("Hello, World!\u{FEFF}", "Hello, World!");
         test_preprocess_line(&"a".repeat(400), &"a".repeat(300));
     }
+
+    fn test_preprocess_line_with_tab_width(
+        input: &str,
+        tab_width: usize,
+        expected: &str,
+    ) {
+        let actual = preprocess_line_with_tab_width(input, tab_width);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_preprocess_line_with_max_length_below_default() {
+        let line = "a".repeat(400);
+        assert_eq!(
+            preprocess_line_with_max_length(&line, 100),
+            "a".repeat(100)
+        );
+        assert_eq!(preprocess_line_with_max_length(&line, 0), "");
+        // 'ф' is 2 bytes long
+        assert_eq!(preprocess_line_with_max_length("фффф", 4), "фф");
+        assert_eq!(preprocess_line_with_max_length("фффф", 5), "ффф");
+    }
+
+    #[test]
+    fn test_preprocess_line_with_max_length_above_default() {
+        let line = "a".repeat(1000);
+        assert_eq!(
+            preprocess_line_with_max_length(&line, 500),
+            "a".repeat(500)
+        );
+        assert_eq!(preprocess_line_with_max_length(&line, 2000), line);
+        // 'é' is 2 bytes long
+        let accents = "é".repeat(400);
+        assert_eq!(
+            preprocess_line_with_max_length(&accents, 2 * MAX_LINE_LENGTH + 1),
+            "é".repeat(MAX_LINE_LENGTH + 1)
+        );
+    }
+
+    #[test]
+    fn test_preprocess_line_with_tab_width_cases() {
+        test_preprocess_line_with_tab_width("\tfoo", 2, "  foo");
+        test_preprocess_line_with_tab_width("\tfoo", 8, "        foo");
+        test_preprocess_line_with_tab_width("a\tb\n", 2, "a  b");
+        test_preprocess_line_with_tab_width("a\tb\n", 8, "a        b");
+        test_preprocess_line_with_tab_width(
+            "\tfoo",
+            TAB_WIDTH,
+            &preprocess_line("\tfoo"),
+        );
+    }
+
+    fn test_shrink_with_ellipsis(
+        input: &str,
+        max_length: usize,
+        expected: &str,
+    ) {
+        let actual = shrink_with_ellipsis(input, max_length);
+        assert_eq!(actual, expected);
+        assert!(actual.width() <= max_length);
+    }
+
+    #[test]
+    fn test_shrink_with_ellipsis_ascii() {
+        test_shrink_with_ellipsis("Hello, World!", 13, "Hello, World!");
+        test_shrink_with_ellipsis("Hello, World!", 6, "H…!");
+        test_shrink_with_ellipsis("Hello, World!", 10, "Hel…ld!");
+    }
+
+    #[test]
+    fn test_shrink_with_ellipsis_cjk() {
+        // each of these characters is two columns wide
+        let s = "こんにちは世界";
+        assert_eq!(s.width(), 14);
+        test_shrink_with_ellipsis(s, 14, s);
+        test_shrink_with_ellipsis(s, 12, "こん…世界");
+        test_shrink_with_ellipsis(s, 10, "こ…界");
+        assert_eq!(shrink_with_ellipsis(s, 10).width(), 5);
+    }
+
+    #[test]
+    fn test_shrink_with_ellipsis_combining_accents() {
+        // "e" followed by a combining acute accent renders as a single column
+        let s = "e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}";
+        assert_eq!(s.width(), 7);
+        test_shrink_with_ellipsis(s, 7, s);
+        test_shrink_with_ellipsis(s, 6, "e\u{301}…e\u{301}");
+    }
+
+    #[test]
+    fn test_shrink_with_ellipsis_and_ranges() {
+        let s = "/very/long/path/to/some/file.rs";
+        let (shrunk, ranges) = shrink_with_ellipsis_and_ranges(
+            s,
+            20,
+            // "very", "path", "file", "rs"
+            &[(1, 5), (11, 15), (24, 28), (29, 31)],
+        );
+        assert_eq!(shrunk, "/very/lo…/file.rs");
+        let slices: Vec<&str> = ranges
+            .iter()
+            .map(|(start, end)| &shrunk[*start as usize..*end as usize])
+            .collect();
+        // "path" was elided
+        assert_eq!(slices, vec!["very", "file", "rs"]);
+    }
+
+    #[test]
+    fn test_shrink_with_ellipsis_and_ranges_across_ellipsis() {
+        let (shrunk, ranges) =
+            shrink_with_ellipsis_and_ranges("Hello, World!", 10, &[(1, 12)]);
+        assert_eq!(shrunk, "Hel…ld!");
+        let slices: Vec<&str> = ranges
+            .iter()
+            .map(|(start, end)| &shrunk[*start as usize..*end as usize])
+            .collect();
+        assert_eq!(slices, vec!["el", "ld"]);
+    }
+
+    #[test]
+    fn test_shrink_with_ellipsis_and_ranges_fits() {
+        assert_eq!(
+            shrink_with_ellipsis_and_ranges("short", 10, &[(0, 2)]),
+            (String::from("short"), vec![(0, 2)])
+        );
+    }
+
+    #[test]
+    fn test_shrink_with_ellipsis_zwj_emoji() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let s = format!("{family}{family}{family}{family}{family}");
+        let actual = shrink_with_ellipsis(&s, 9);
+        assert!(actual.width() <= 9);
+        // the emoji sequences should never be split apart
+        assert_eq!(actual, format!("{family}…{family}"));
+    }
 }
@@ -2,8 +2,10 @@ use bat::assets::HighlightingAssets;
 use gag::Gag;
 use std::path::{Path, PathBuf};
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Style, Theme};
-use syntect::parsing::SyntaxSet;
+use syntect::highlighting::{
+    HighlightIterator, HighlightState, Highlighter, Style, Theme, ThemeSet,
+};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
 use tracing::warn;
 
 pub fn compute_highlights_for_path(
@@ -12,28 +14,165 @@ pub fn compute_highlights_for_path(
     syntax_set: &SyntaxSet,
     syntax_theme: &Theme,
 ) -> color_eyre::Result<Vec<Vec<(Style, String)>>> {
-    let syntax =
-        syntax_set
-            .find_syntax_for_file(file_path)?
-            .unwrap_or_else(|| {
-                warn!(
-                    "No syntax found for {:?}, defaulting to plain text",
-                    file_path
-                );
-                syntax_set.find_syntax_plain_text()
-            });
-    let mut highlighter = HighlightLines::new(syntax, syntax_theme);
-    let mut highlighted_lines = Vec::new();
-    for line in lines {
-        let hl_regions = highlighter.highlight_line(&line, syntax_set)?;
-        highlighted_lines.push(
-            hl_regions
-                .iter()
-                .map(|(style, text)| (*style, (*text).to_string()))
+    let syntax = find_syntax_for_path(
+        file_path,
+        lines.first().map(String::as_str),
+        syntax_set,
+    );
+    compute_highlights(syntax, lines, syntax_set, syntax_theme)
+}
+
+/// Find the syntax of the file at `file_path`, based on its name or on the
+/// shebang of its `first_line`, defaulting to plain text.
+pub fn find_syntax_for_path<'a>(
+    file_path: &Path,
+    first_line: Option<&str>,
+    syntax_set: &'a SyntaxSet,
+) -> &'a SyntaxReference {
+    // the file is only opened when its extension is unknown, it may not
+    // exist when highlighting decompressed content
+    syntax_set
+        .find_syntax_for_file(file_path)
+        .ok()
+        .flatten()
+        .or_else(|| {
+            first_line
+                .and_then(|line| find_syntax_for_shebang(line, syntax_set))
+        })
+        .unwrap_or_else(|| {
+            warn!(
+                "No syntax found for {:?}, defaulting to plain text",
+                file_path
+            );
+            syntax_set.find_syntax_plain_text()
+        })
+}
+
+/// Highlight the given lines as plain text, which is fast whatever their
+/// content.
+pub fn compute_plain_highlights(
+    lines: Vec<String>,
+    syntax_set: &SyntaxSet,
+    syntax_theme: &Theme,
+) -> color_eyre::Result<Vec<Vec<(Style, String)>>> {
+    compute_highlights(
+        syntax_set.find_syntax_plain_text(),
+        lines,
+        syntax_set,
+        syntax_theme,
+    )
+}
+
+fn compute_highlights(
+    syntax: &SyntaxReference,
+    lines: Vec<String>,
+    syntax_set: &SyntaxSet,
+    syntax_theme: &Theme,
+) -> color_eyre::Result<Vec<Vec<(Style, String)>>> {
+    LineHighlighter::new(syntax, syntax_theme).highlight_lines(
+        &lines,
+        syntax_set,
+        syntax_theme,
+    )
+}
+
+/// Highlights lines in order, keeping track of the state of the highlighting
+/// in between so that it can be resumed later on (e.g. once more lines of a
+/// file have been read).
+///
+/// The same theme has to be used for all the lines.
+#[derive(Debug, Clone)]
+pub struct LineHighlighter {
+    parse_state: ParseState,
+    highlight_state: HighlightState,
+}
+
+impl LineHighlighter {
+    pub fn new(syntax: &SyntaxReference, syntax_theme: &Theme) -> Self {
+        let highlighter = Highlighter::new(syntax_theme);
+        Self {
+            parse_state: ParseState::new(syntax),
+            highlight_state: HighlightState::new(
+                &highlighter,
+                ScopeStack::new(),
+            ),
+        }
+    }
+
+    /// Highlight the lines following the ones highlighted so far.
+    pub fn highlight_lines(
+        &mut self,
+        lines: &[String],
+        syntax_set: &SyntaxSet,
+        syntax_theme: &Theme,
+    ) -> color_eyre::Result<Vec<Vec<(Style, String)>>> {
+        let highlighter = Highlighter::new(syntax_theme);
+        let mut highlighted_lines = Vec::with_capacity(lines.len());
+        for line in lines {
+            let ops = self.parse_state.parse_line(line, syntax_set)?;
+            highlighted_lines.push(
+                HighlightIterator::new(
+                    &mut self.highlight_state,
+                    &ops,
+                    line,
+                    &highlighter,
+                )
+                .map(|(style, text)| (style, text.to_string()))
                 .collect(),
-        );
+            );
+        }
+        Ok(highlighted_lines)
+    }
+}
+
+/// Extract the name of the interpreter from a shebang line, without any
+/// version suffix.
+///
+/// # Examples
+/// ```
+/// use television_utils::syntax::shebang_interpreter;
+///
+/// assert_eq!(shebang_interpreter("#!/usr/bin/env python3"), Some("python"));
+/// assert_eq!(shebang_interpreter("#!/bin/bash -e"), Some("bash"));
+/// assert_eq!(shebang_interpreter("#!/usr/bin/env -S node --foo"), Some("node"));
+/// assert_eq!(shebang_interpreter("print('hello')"), None);
+/// ```
+pub fn shebang_interpreter(line: &str) -> Option<&str> {
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-'))?;
     }
-    Ok(highlighted_lines)
+    let interpreter =
+        program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    (!interpreter.is_empty()).then_some(interpreter)
+}
+
+/// Find the syntax matching the interpreter of a shebang line.
+pub fn find_syntax_for_shebang<'a>(
+    line: &str,
+    syntax_set: &'a SyntaxSet,
+) -> Option<&'a SyntaxReference> {
+    let extension = match shebang_interpreter(line)? {
+        "python" => "py",
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => "sh",
+        "node" | "nodejs" => "js",
+        "ruby" => "rb",
+        "perl" => "pl",
+        _ => return None,
+    };
+    syntax_set.find_syntax_by_extension(extension)
+}
+
+/// Build a new syntax set from `syntax_set` extended with the
+/// `.sublime-syntax` definitions found in `directory` (recursively).
+pub fn load_user_syntaxes(
+    syntax_set: &SyntaxSet,
+    directory: &Path,
+) -> color_eyre::Result<SyntaxSet> {
+    let mut builder = syntax_set.clone().into_builder();
+    builder.add_from_folder(directory, true)?;
+    Ok(builder.build())
 }
 
 #[allow(dead_code)]
@@ -126,8 +265,58 @@ pub fn load_highlighting_assets() -> HighlightingAssets {
         .unwrap_or_else(|_| HighlightingAssets::from_binary())
 }
 
+/// The theme used when none is configured or when the configured one can't be
+/// loaded.
+pub const DEFAULT_THEME_NAME: &str = "base16-ocean.dark";
+
+pub fn default_theme() -> Theme {
+    ThemeSet::load_defaults().themes[DEFAULT_THEME_NAME].clone()
+}
+
+/// The background of the terminal, used to pick a readable theme.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TerminalBackground {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl TerminalBackground {
+    /// Detect the background of the terminal from the `COLORFGBG`
+    /// environment variable set by some terminals, defaulting to dark.
+    pub fn detect() -> Self {
+        std::env::var("COLORFGBG")
+            .ok()
+            .and_then(|value| Self::from_colorfgbg(&value))
+            .unwrap_or_default()
+    }
+
+    /// Parse a `COLORFGBG` value, i.e. `fg;bg` or `fg;default;bg` where
+    /// colors are ANSI color indices.
+    fn from_colorfgbg(value: &str) -> Option<Self> {
+        let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+        // white and the bright colors (but for bright black) are light
+        if background == 7 || (9..=15).contains(&background) {
+            Some(TerminalBackground::Light)
+        } else {
+            Some(TerminalBackground::Dark)
+        }
+    }
+}
+
+/// Whether the given theme setting designates a `.tmTheme` file rather than
+/// the name of a bundled theme.
+fn is_theme_path(theme: &str) -> bool {
+    let path = Path::new(theme);
+    path.extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("tmTheme"))
+        || path.is_file()
+}
+
 pub trait HighlightingAssetsExt {
     fn get_theme_no_output(&self, theme_name: &str) -> &Theme;
+
+    fn load_theme(&self, theme: &str) -> Theme;
 }
 
 impl HighlightingAssetsExt for HighlightingAssets {
@@ -142,4 +331,200 @@ impl HighlightingAssetsExt for HighlightingAssets {
         let theme = self.get_theme(theme_name);
         theme
     }
+
+    /// Load a theme from either the name of a bundled theme or the path to a
+    /// `.tmTheme` file.
+    ///
+    /// If the file can't be loaded, a warning is logged and the default theme
+    /// is returned.
+    fn load_theme(&self, theme: &str) -> Theme {
+        if !is_theme_path(theme) {
+            return self.get_theme_no_output(theme).clone();
+        }
+        ThemeSet::get_theme(theme).unwrap_or_else(|e| {
+            warn!(
+                "Could not load theme from {:?}: {}, falling back to {}",
+                theme, e, DEFAULT_THEME_NAME
+            );
+            default_theme()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terminal_background_from_colorfgbg() {
+        let parse = TerminalBackground::from_colorfgbg;
+        assert_eq!(parse("15;0"), Some(TerminalBackground::Dark));
+        assert_eq!(parse("0;15"), Some(TerminalBackground::Light));
+        assert_eq!(parse("0;default;7"), Some(TerminalBackground::Light));
+        assert_eq!(parse("7;8"), Some(TerminalBackground::Dark));
+        assert_eq!(parse("15;default"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    const MINIMAL_THEME: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>name</key>
+    <string>Minimal</string>
+    <key>settings</key>
+    <array>
+        <dict>
+            <key>settings</key>
+            <dict>
+                <key>background</key>
+                <string>#102030</string>
+                <key>foreground</key>
+                <string>#A0B0C0</string>
+            </dict>
+        </dict>
+    </array>
+</dict>
+</plist>
+"#;
+
+    #[test]
+    fn test_load_theme_from_path() {
+        let path = std::env::temp_dir()
+            .join(format!("tv-minimal-{}.tmTheme", std::process::id()));
+        std::fs::write(&path, MINIMAL_THEME).unwrap();
+
+        let theme = HighlightingAssets::from_binary()
+            .load_theme(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(theme.name.as_deref(), Some("Minimal"));
+        let background = theme.settings.background.unwrap();
+        assert_eq!((background.r, background.g, background.b), (16, 32, 48));
+    }
+
+    #[test]
+    fn test_load_theme_from_invalid_path_falls_back_to_default() {
+        let theme = HighlightingAssets::from_binary()
+            .load_theme("/does/not/exist/theme.tmTheme");
+        assert_eq!(theme.name, default_theme().name);
+    }
+
+    const CUSTOM_SYNTAX: &str = r"%YAML 1.2
+---
+name: MyLang
+file_extensions: [myext]
+scope: source.mylang
+contexts:
+  main:
+    - match: '\b(when|then)\b'
+      scope: keyword.control.mylang
+";
+
+    /// A small stand-in for the bundled syntaxes, which are slow to rebuild.
+    fn plain_text_syntax_set() -> SyntaxSet {
+        let mut builder = SyntaxSet::new().into_builder();
+        builder.add_plain_text_syntax();
+        builder.build()
+    }
+
+    #[test]
+    fn test_load_user_syntaxes() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-syntaxes-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("mylang.sublime-syntax"), CUSTOM_SYNTAX)
+            .unwrap();
+        let path = directory.join("rules.myext");
+        std::fs::write(&path, "when ready then go\n").unwrap();
+
+        let bundled = plain_text_syntax_set();
+        let syntax_set = load_user_syntaxes(&bundled, &directory).unwrap();
+
+        assert!(bundled.find_syntax_by_extension("myext").is_none());
+        assert_eq!(
+            syntax_set.find_syntax_by_extension("myext").unwrap().name,
+            "MyLang"
+        );
+
+        let theme = default_theme();
+        let lines = vec![String::from("when ready then go\n")];
+        let plain = compute_highlights_for_path(
+            &path,
+            lines.clone(),
+            &bundled,
+            &theme,
+        )
+        .unwrap();
+        let highlighted =
+            compute_highlights_for_path(&path, lines, &syntax_set, &theme)
+                .unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(plain[0].len(), 1);
+        assert!(highlighted[0].len() > 1);
+        assert_eq!(highlighted[0][0].1, "when");
+        assert_ne!(highlighted[0][0].0, highlighted[0][1].0);
+    }
+
+    #[test]
+    fn test_load_user_syntaxes_from_missing_directory() {
+        assert!(load_user_syntaxes(
+            &plain_text_syntax_set(),
+            Path::new("/does/not/exist")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_find_syntax_for_shebang() {
+        let assets = HighlightingAssets::from_binary();
+        let syntax_set = assets.get_syntax_set().unwrap();
+        let syntax_name = |line: &str| {
+            find_syntax_for_shebang(line, syntax_set).map(|s| s.name.as_str())
+        };
+        assert_eq!(syntax_name("#!/usr/bin/env python3"), Some("Python"));
+        assert_eq!(syntax_name("#!/usr/bin/python2.7"), Some("Python"));
+        assert_eq!(
+            syntax_name("#!/bin/bash"),
+            syntax_set
+                .find_syntax_by_extension("sh")
+                .map(|s| s.name.as_str())
+        );
+        assert_eq!(
+            syntax_name("#!/usr/bin/env node"),
+            syntax_set
+                .find_syntax_by_extension("js")
+                .map(|s| s.name.as_str())
+        );
+        assert_eq!(syntax_name("#!/usr/bin/env ruby"), Some("Ruby"));
+        assert_eq!(syntax_name("#!/usr/bin/perl -w"), Some("Perl"));
+        assert_eq!(syntax_name("#!/usr/bin/env frobnicate"), None);
+        assert_eq!(syntax_name("#!"), None);
+    }
+
+    #[test]
+    fn test_unknown_shebang_is_highlighted_as_plain_text() {
+        let path = std::env::temp_dir()
+            .join(format!("tv-unknown-shebang-{}", std::process::id()));
+        let lines = vec![
+            String::from("#!/usr/bin/env frobnicate\n"),
+            String::from("def foo(): return 'bar'\n"),
+        ];
+        std::fs::write(&path, lines.concat()).unwrap();
+        let assets = HighlightingAssets::from_binary();
+        let highlighted = compute_highlights_for_path(
+            &path,
+            lines,
+            assets.get_syntax_set().unwrap(),
+            &default_theme(),
+        );
+        std::fs::remove_file(&path).unwrap();
+        assert!(highlighted.unwrap().iter().all(|line| line.len() == 1));
+    }
+
+    #[test]
+    fn test_load_bundled_theme_by_name() {
+        let theme = HighlightingAssets::from_binary().load_theme("Nord");
+        assert_eq!(theme.name.as_deref(), Some("Nord"));
+    }
 }
@@ -28,6 +28,15 @@ pub enum Action {
     /// Move the cursor to the end of the input buffer.
     #[serde(alias = "go_to_input_end")]
     GoToInputEnd,
+    /// Delete the word before the cursor from the input buffer.
+    #[serde(alias = "delete_prev_word")]
+    DeletePrevWord,
+    /// Insert the contents of the clipboard at the cursor position.
+    #[serde(alias = "paste")]
+    PasteFromClipboard,
+    /// Clear the input buffer.
+    #[serde(alias = "clear_input")]
+    ClearInput,
     // rendering actions
     /// Render the terminal user interface screen.
     #[serde(skip)]
@@ -55,9 +64,29 @@ pub enum Action {
     /// Select the previous entry in the currently focused list.
     #[serde(alias = "select_prev_entry")]
     SelectPrevEntry,
-    /// Copy the currently selected entry to the clipboard.
-    #[serde(alias = "copy_entry_to_clipboard")]
-    CopyEntryToClipboard,
+    /// Copy the name of the currently selected entry to the clipboard.
+    #[serde(
+        alias = "copy_entry_name",
+        alias = "copy_entry_to_clipboard",
+        alias = "CopyEntryToClipboard"
+    )]
+    CopyEntryName,
+    /// Copy the value of the currently selected entry (e.g. the matched line
+    /// of a text search) to the clipboard, or its name if it has none.
+    #[serde(alias = "copy_entry_value")]
+    CopyEntryValue,
+    /// Toggle the mark on the entry currently under the cursor.
+    #[serde(alias = "toggle_selection")]
+    ToggleSelection,
+    /// Mark all entries matching the current pattern.
+    #[serde(alias = "select_all")]
+    SelectAll,
+    /// Unmark all entries matching the current pattern.
+    #[serde(alias = "deselect_all")]
+    DeselectAll,
+    /// Unmark all marked entries.
+    #[serde(alias = "clear_selection")]
+    ClearSelection,
     // preview actions
     /// Scroll the preview up by one line.
     #[serde(alias = "scroll_preview_up")]
@@ -71,9 +100,34 @@ pub enum Action {
     /// Scroll the preview down by half a page.
     #[serde(alias = "scroll_preview_half_page_down")]
     ScrollPreviewHalfPageDown,
+    /// Scroll the preview left by one column.
+    #[serde(alias = "scroll_preview_left")]
+    ScrollPreviewLeft,
+    /// Scroll the preview right by one column.
+    #[serde(alias = "scroll_preview_right")]
+    ScrollPreviewRight,
+    /// Scroll the preview left by half a page.
+    #[serde(alias = "scroll_preview_half_page_left")]
+    ScrollPreviewHalfPageLeft,
+    /// Scroll the preview right by half a page.
+    #[serde(alias = "scroll_preview_half_page_right")]
+    ScrollPreviewHalfPageRight,
+    /// Scroll the preview to its first line.
+    #[serde(alias = "scroll_preview_to_top", alias = "scroll_preview_top")]
+    ScrollPreviewToTop,
+    /// Scroll the preview to its last line.
+    #[serde(
+        alias = "scroll_preview_to_bottom",
+        alias = "scroll_preview_bottom"
+    )]
+    ScrollPreviewToBottom,
     /// Open the currently selected entry in the default application.
     #[serde(skip)]
     OpenEntry,
+    /// Open the currently selected entry in the user's editor, at its line
+    /// number if it has one.
+    #[serde(alias = "open_in_editor")]
+    OpenInEditor,
     // application actions
     /// Tick the application state.
     #[serde(skip)]
@@ -90,6 +144,16 @@ pub enum Action {
     /// Toggle the help bar.
     #[serde(alias = "toggle_help")]
     ToggleHelp,
+    /// Toggle the preview pane.
+    #[serde(alias = "toggle_preview")]
+    TogglePreview,
+    /// Switch to the next syntax theme for file previews.
+    #[serde(alias = "cycle_theme")]
+    CycleTheme,
+    /// Cycle between smart case, case-insensitive and case-sensitive
+    /// matching.
+    #[serde(alias = "toggle_case")]
+    ToggleCaseSensitivity,
     /// Signal an error with the given message.
     #[serde(skip)]
     Error(String),
@@ -97,10 +161,18 @@ pub enum Action {
     #[serde(skip)]
     NoOp,
     // channel actions
+    /// Load the entries of the current channel again.
+    #[serde(alias = "reload")]
+    Reload,
     /// Toggle the remote control channel.
     #[serde(alias = "toggle_remote_control")]
     ToggleRemoteControl,
     /// Toggle the remote control in `send to channel` mode.
     #[serde(alias = "toggle_send_to_channel")]
     ToggleSendToChannel,
+    /// Use the entries matching the current pattern as the source of the
+    /// given channel (e.g. `text` to search the contents of the matched
+    /// files) and switch to it.
+    #[serde(alias = "pipe_to_channel")]
+    PipeToChannel(String),
 }
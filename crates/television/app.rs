@@ -6,7 +6,8 @@ use derive_deref::Deref;
 use tokio::sync::{mpsc, Mutex};
 use tracing::{debug, info};
 
-use crate::config::{parse_key, KeyBindings};
+use crate::config::{get_cache_dir, parse_key, KeyBindings};
+use crate::last_queries::{LastQueries, LAST_QUERIES_FILE_NAME};
 use crate::television::{Mode, Television};
 use crate::{
     action::Action,
@@ -15,7 +16,7 @@ use crate::{
     render::{render, RenderingTask},
 };
 use television_channels::channels::TelevisionChannel;
-use television_channels::entry::Entry;
+use television_channels::entry::{Entry, PreviewType};
 
 #[derive(Deref, Default, Debug)]
 pub struct Keymap(pub HashMap<Mode, HashMap<Key, Action>>);
@@ -48,6 +49,16 @@ impl Keymap {
         }
         Ok(self)
     }
+
+    /// Get the action bound to the given key in the given mode, if any.
+    pub fn get_action(&self, mode: Mode, key: &Key) -> Option<&Action> {
+        self.0.get(&mode).and_then(|keymap| keymap.get(key))
+    }
+}
+
+/// Where the last query used on each channel is stored.
+fn last_queries() -> LastQueries {
+    LastQueries::new(get_cache_dir().join(LAST_QUERIES_FILE_NAME))
 }
 
 /// The main application struct that holds the state of the application.
@@ -80,32 +91,49 @@ pub struct App {
 /// The outcome of an action.
 #[derive(Debug)]
 pub enum ActionOutcome {
-    Entry(Entry),
-    Passthrough(Entry, String),
+    Entries(Vec<Entry>),
+    Passthrough(Vec<Entry>, String),
+    OpenInEditor(Entry),
     None,
 }
 
 /// The result of the application.
 #[derive(Debug)]
 pub struct AppOutput {
-    pub selected_entry: Option<Entry>,
+    pub selected_entries: Option<Vec<Entry>>,
     pub passthrough: Option<String>,
+    /// The entry to open in the user's editor once the application exited.
+    pub entry_to_edit: Option<Entry>,
+    /// The query typed in the channel's input when the application exited.
+    pub query: String,
 }
 
 impl From<ActionOutcome> for AppOutput {
     fn from(outcome: ActionOutcome) -> Self {
         match outcome {
-            ActionOutcome::Entry(entry) => Self {
-                selected_entry: Some(entry),
+            ActionOutcome::Entries(entries) => Self {
+                selected_entries: Some(entries),
                 passthrough: None,
+                entry_to_edit: None,
+                query: String::new(),
             },
-            ActionOutcome::Passthrough(entry, key) => Self {
-                selected_entry: Some(entry),
+            ActionOutcome::Passthrough(entries, key) => Self {
+                selected_entries: Some(entries),
                 passthrough: Some(key),
+                entry_to_edit: None,
+                query: String::new(),
+            },
+            ActionOutcome::OpenInEditor(entry) => Self {
+                selected_entries: None,
+                passthrough: None,
+                entry_to_edit: Some(entry),
+                query: String::new(),
             },
             ActionOutcome::None => Self {
-                selected_entry: None,
+                selected_entries: None,
                 passthrough: None,
+                entry_to_edit: None,
+                query: String::new(),
             },
         }
     }
@@ -122,8 +150,17 @@ impl App {
         let (render_tx, _) = mpsc::unbounded_channel();
         let (_, event_rx) = mpsc::unbounded_channel();
         let (event_abort_tx, _) = mpsc::unbounded_channel();
-        let television = Arc::new(Mutex::new(Television::new(channel)));
         let config = Config::new()?;
+        let mut television = Television::new(channel);
+        if config.ui.restore_last_query {
+            if let Some(query) =
+                last_queries().load(&television.channel.name())
+            {
+                debug!("Restoring the last query: {:?}", query);
+                television.set_channel_query(&query);
+            }
+        }
+        let television = Arc::new(Mutex::new(television));
         let keymap = Keymap::from(&config.keybindings).with_mode_mappings(
             Mode::Channel,
             passthrough_keybindings
@@ -152,6 +189,11 @@ impl App {
         })
     }
 
+    /// Never preview entries, whatever the configuration says.
+    pub fn disable_preview(&mut self) {
+        self.config.ui.show_preview = false;
+    }
+
     /// Run the application main loop.
     ///
     /// This function will start the event loop and the rendering loop and handle
@@ -211,7 +253,18 @@ impl App {
                 // wait for the rendering task to finish
                 rendering_task.await??;
 
-                return Ok(AppOutput::from(action_outcome));
+                let television = self.television.lock().await;
+                if self.config.ui.restore_last_query {
+                    last_queries().save(
+                        &television.channel.name(),
+                        television.channel_query(),
+                    );
+                }
+
+                return Ok(AppOutput {
+                    query: television.channel_query().to_string(),
+                    ..AppOutput::from(action_outcome)
+                });
             }
         }
     }
@@ -236,21 +289,21 @@ impl App {
                     Key::Delete => return Action::DeleteNextChar,
                     Key::Left => return Action::GoToPrevChar,
                     Key::Right => return Action::GoToNextChar,
-                    Key::Home | Key::Ctrl('a') => {
-                        return Action::GoToInputStart
-                    }
-                    Key::End | Key::Ctrl('e') => return Action::GoToInputEnd,
+                    Key::Ctrl('a') => return Action::GoToInputStart,
+                    Key::Ctrl('e') => return Action::GoToInputEnd,
                     Key::Char(c) => return Action::AddInputChar(c),
                     _ => {}
                 }
-                // get action based on keybindings
+                // get action based on keybindings, home and end move the
+                // input cursor unless they're bound to something else
                 self.keymap
-                    .get(&self.television.lock().await.mode)
-                    .and_then(|keymap| keymap.get(&keycode).cloned())
-                    .unwrap_or(if let Key::Char(c) = keycode {
-                        Action::AddInputChar(c)
-                    } else {
-                        Action::NoOp
+                    .get_action(self.television.lock().await.mode, &keycode)
+                    .cloned()
+                    .unwrap_or(match keycode {
+                        Key::Char(c) => Action::AddInputChar(c),
+                        Key::Home => Action::GoToInputStart,
+                        Key::End => Action::GoToInputEnd,
+                        _ => Action::NoOp,
                     })
             }
             // terminal events
@@ -293,26 +346,45 @@ impl App {
                 Action::SelectAndExit => {
                     self.should_quit = true;
                     self.render_tx.send(RenderingTask::Quit)?;
-                    if let Some(entry) =
-                        self.television.lock().await.get_selected_entry(None)
+                    if let Some(entries) =
+                        self.television.lock().await.get_selected_entries(None)
                     {
-                        return Ok(ActionOutcome::Entry(entry));
+                        return Ok(ActionOutcome::Entries(entries));
                     }
                     return Ok(ActionOutcome::None);
                 }
                 Action::SelectPassthrough(passthrough) => {
                     self.should_quit = true;
                     self.render_tx.send(RenderingTask::Quit)?;
-                    if let Some(entry) =
-                        self.television.lock().await.get_selected_entry(None)
+                    if let Some(entries) =
+                        self.television.lock().await.get_selected_entries(None)
                     {
                         return Ok(ActionOutcome::Passthrough(
-                            entry,
+                            entries,
                             passthrough,
                         ));
                     }
                     return Ok(ActionOutcome::None);
                 }
+                Action::OpenInEditor => {
+                    let entry = self
+                        .television
+                        .lock()
+                        .await
+                        .get_selected_entry(None)
+                        .filter(|entry| {
+                            matches!(
+                                entry.preview_type,
+                                PreviewType::Files | PreviewType::Directory
+                            )
+                        });
+                    // the editor is launched once the terminal is restored
+                    if let Some(entry) = entry {
+                        self.should_quit = true;
+                        self.render_tx.send(RenderingTask::Quit)?;
+                        return Ok(ActionOutcome::OpenInEditor(entry));
+                    }
+                }
                 Action::ClearScreen => {
                     self.render_tx.send(RenderingTask::ClearScreen)?;
                 }
@@ -1,14 +1,66 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use color_eyre::{eyre::eyre, Result};
 
 use crate::config::{get_config_dir, get_data_dir};
-use television_channels::channels::CliTvChannel;
+use television_channels::channels::{
+    cable::{self, CableChannelPrototype},
+    files::WalkOptions,
+    CliTvChannel, TelevisionChannel,
+};
+
+/// The channel selected from the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedCliChannel {
+    /// One of the built-in channels.
+    Builtin(CliTvChannel),
+    /// A channel defined in the `cable_channels` section of the config.
+    Cable(String),
+}
+
+impl ParsedCliChannel {
+    fn parse(channel: &str) -> Result<Self, String> {
+        Ok(CliTvChannel::from_str(channel, true)
+            .map_or_else(|_| Self::Cable(channel.to_string()), Self::Builtin))
+    }
+
+    /// Instantiate the selected channel, looking cable channels up in the
+    /// given prototypes.
+    ///
+    /// `dedup` forces cable channels to drop duplicate lines even if their
+    /// prototype doesn't ask for it.
+    pub fn to_channel(
+        &self,
+        cable_channels: &[CableChannelPrototype],
+        dedup: bool,
+    ) -> Result<TelevisionChannel> {
+        match self {
+            Self::Builtin(channel) => Ok(channel.to_channel()),
+            Self::Cable(name) => cable_channels
+                .iter()
+                .find(|prototype| &prototype.name == name)
+                .map(|prototype| {
+                    TelevisionChannel::Cable(cable::Channel::new(
+                        &CableChannelPrototype {
+                            dedup: prototype.dedup || dedup,
+                            ..prototype.clone()
+                        },
+                    ))
+                })
+                .ok_or_else(|| eyre!("Unknown channel: {name}")),
+        }
+    }
+}
 
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
 pub struct Cli {
     /// Which channel shall we watch?
-    #[arg(value_enum, default_value = "files")]
-    pub channel: CliTvChannel,
+    ///
+    /// Either one of the built-in channels (env, files, git-repos, text,
+    /// alias) or the name of a channel defined in the `cable_channels`
+    /// section of the config.
+    #[arg(default_value = "files", value_parser = ParsedCliChannel::parse)]
+    pub channel: ParsedCliChannel,
 
     /// Tick rate, i.e. number of ticks per second
     #[arg(short, long, value_name = "FLOAT", default_value_t = 50.0)]
@@ -23,14 +75,61 @@ pub struct Cli {
     /// to be handled by the parent process.
     #[arg(short, long, value_name = "STRING")]
     pub passthrough_keybindings: Option<String>,
+
+    /// Print the selected entries as JSON (an array of objects, even when a
+    /// single entry is selected)
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+
+    /// Separate the selected entries with NUL bytes instead of newlines (e.g.
+    /// to feed them to `xargs -0`)
+    #[arg(long, default_value_t = false, conflicts_with = "json")]
+    pub print0: bool,
+
+    /// Also print the final query on exit, even if nothing was selected.
+    /// Each record is then prefixed with `query<TAB>` or `entry<TAB>`, the
+    /// query always coming first (possibly empty), or JSON output becomes
+    /// an object with `query` and `entries` fields
+    #[arg(long, default_value_t = false)]
+    pub print_query: bool,
+
+    /// List the files ignored by `.gitignore`, `.ignore` or global git
+    /// excludes in the files channel
+    #[arg(long, default_value_t = false)]
+    pub no_ignore: bool,
+
+    /// Follow symlinks to directories in the files channel
+    #[arg(long, default_value_t = false)]
+    pub follow_links: bool,
+
+    /// Only list files up to this many directories deep in the files
+    /// channel (1 only lists the files of the current directory)
+    #[arg(long, value_name = "DEPTH")]
+    pub max_depth: Option<usize>,
+
+    /// Drop duplicate entries of the stdin and cable channels, only
+    /// keeping the first occurrence of each line
+    #[arg(long, default_value_t = false)]
+    pub dedup: bool,
+
+    /// Don't preview entries at all, which saves loading the previewers
+    /// when merely filtering a list
+    #[arg(long, default_value_t = false)]
+    pub no_preview: bool,
 }
 
 #[derive(Debug)]
 pub struct PostProcessedCli {
-    pub channel: CliTvChannel,
+    pub channel: ParsedCliChannel,
     pub tick_rate: f64,
     pub frame_rate: f64,
     pub passthrough_keybindings: Vec<String>,
+    pub json: bool,
+    pub print0: bool,
+    pub print_query: bool,
+    pub walk_options: WalkOptions,
+    pub dedup: bool,
+    pub no_preview: bool,
 }
 
 impl From<Cli> for PostProcessedCli {
@@ -47,6 +146,15 @@ impl From<Cli> for PostProcessedCli {
             tick_rate: cli.tick_rate,
             frame_rate: cli.frame_rate,
             passthrough_keybindings,
+            json: cli.json,
+            print0: cli.print0,
+            print_query: cli.print_query,
+            walk_options: WalkOptions::default()
+                .respect_ignore(!cli.no_ignore)
+                .follow_links(cli.follow_links)
+                .max_depth(cli.max_depth),
+            dedup: cli.dedup,
+            no_preview: cli.no_preview,
         }
     }
 }
@@ -91,3 +199,31 @@ Config directory: {config_dir_path}
 Data directory: {data_dir_path}"
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_channel() {
+        assert_eq!(
+            ParsedCliChannel::parse("git-repos"),
+            Ok(ParsedCliChannel::Builtin(CliTvChannel::GitRepos))
+        );
+        assert_eq!(
+            ParsedCliChannel::parse("Env"),
+            Ok(ParsedCliChannel::Builtin(CliTvChannel::Env))
+        );
+        assert_eq!(
+            ParsedCliChannel::parse("git-branch"),
+            Ok(ParsedCliChannel::Cable("git-branch".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_unknown_cable_channel() {
+        let result =
+            ParsedCliChannel::Cable("nope".to_string()).to_channel(&[], false);
+        assert_eq!(result.err().unwrap().to_string(), "Unknown channel: nope");
+    }
+}
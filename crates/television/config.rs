@@ -9,8 +9,13 @@ use lazy_static::lazy_static;
 use previewers::PreviewersConfig;
 use serde::Deserialize;
 use styles::Styles;
+use television_channels::channels::cable::CableChannelPrototype;
 use tracing::{debug, warn};
 use ui::UiConfig;
+pub use ui::{
+    BorderStyle, InputPosition, LayoutOrientation, PreviewBackground,
+    ResultsOrder, ResultsOverflow,
+};
 
 mod keybindings;
 mod previewers;
@@ -42,6 +47,8 @@ pub struct Config {
     pub ui: UiConfig,
     #[serde(default)]
     pub previewers: PreviewersConfig,
+    #[serde(default)]
+    pub cable_channels: Vec<CableChannelPrototype>,
 }
 
 lazy_static! {
@@ -59,6 +66,12 @@ lazy_static! {
             // otherwise, use the XDG config directory + 'television'
             env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).map(|p| p.join(PROJECT_NAME.as_str())).filter(|p| p.is_absolute())
         });
+    pub static ref CACHE_FOLDER: Option<PathBuf> =
+        // if `TELEVISION_CACHE` is set, use that as the cache directory
+        env::var_os(format!("{}_CACHE", PROJECT_NAME_UPPER.clone())).map(PathBuf::from).or_else(|| {
+            // otherwise, use the XDG cache directory + 'television'
+            env::var_os("XDG_CACHE_HOME").map(PathBuf::from).map(|p| p.join(PROJECT_NAME.as_str())).filter(|p| p.is_absolute())
+        });
 }
 
 const CONFIG_FILE_NAME: &str = "config.toml";
@@ -95,14 +108,7 @@ impl Config {
                     )
                 })?;
 
-            for (mode, default_bindings) in default_config.keybindings.iter() {
-                let user_bindings = cfg.keybindings.entry(*mode).or_default();
-                for (command, key) in default_bindings {
-                    user_bindings
-                        .entry(command.clone())
-                        .or_insert_with(|| *key);
-                }
-            }
+            cfg.keybindings.merge_defaults(&default_config.keybindings);
 
             for (mode, default_styles) in default_config.styles.iter() {
                 let user_styles = cfg.styles.entry(*mode).or_default();
@@ -146,6 +152,19 @@ pub fn get_config_dir() -> PathBuf {
     directory
 }
 
+pub fn get_cache_dir() -> PathBuf {
+    let directory = if let Some(s) = CACHE_FOLDER.clone() {
+        debug!("Using cache directory: {:?}", s);
+        s
+    } else if let Some(proj_dirs) = project_directory() {
+        debug!("Falling back to default cache dir");
+        proj_dirs.cache_dir().to_path_buf()
+    } else {
+        PathBuf::from(".").join(".cache")
+    };
+    directory
+}
+
 fn project_directory() -> Option<ProjectDirs> {
     ProjectDirs::from("com", "", env!("CARGO_PKG_NAME"))
 }
@@ -3,6 +3,7 @@ use crate::event::{convert_raw_event_to_key, Key};
 use crate::television::Mode;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use derive_deref::{Deref, DerefMut};
+use serde::de::{value::StrDeserializer, Error};
 use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 
@@ -15,25 +16,57 @@ impl<'de> Deserialize<'de> for KeyBindings {
         D: Deserializer<'de>,
     {
         let parsed_map =
-            HashMap::<Mode, HashMap<Action, String>>::deserialize(
+            HashMap::<Mode, HashMap<String, String>>::deserialize(
                 deserializer,
             )?;
 
-        let keybindings = parsed_map
-            .into_iter()
-            .map(|(mode, inner_map)| {
-                let converted_inner_map = inner_map
-                    .into_iter()
-                    .map(|(cmd, key_str)| (cmd, parse_key(&key_str).unwrap()))
-                    .collect();
-                (mode, converted_inner_map)
-            })
-            .collect();
+        let mut keybindings = config::Map::new();
+        for (mode, inner_map) in parsed_map {
+            let mut converted_inner_map = config::Map::new();
+            for (action_str, key_str) in inner_map {
+                let action = Action::deserialize(
+                    StrDeserializer::<D::Error>::new(&action_str),
+                )
+                .map_err(|_| {
+                    D::Error::custom(format!(
+                        "unknown action `{action_str}` in {mode:?} keybindings"
+                    ))
+                })?;
+                let key = parse_key(&key_str).map_err(|e| {
+                    D::Error::custom(format!(
+                        "invalid key for `{action_str}` in {mode:?} \
+                         keybindings: {e}"
+                    ))
+                })?;
+                converted_inner_map.insert(action, key);
+            }
+            keybindings.insert(mode, converted_inner_map);
+        }
 
         Ok(KeyBindings(keybindings))
     }
 }
 
+impl KeyBindings {
+    /// Fill in the bindings missing from `self` with the given defaults.
+    ///
+    /// A default binding is skipped if its key is already bound to another
+    /// action in the same mode, so that user bindings always take precedence.
+    pub fn merge_defaults(&mut self, defaults: &KeyBindings) {
+        for (mode, default_bindings) in defaults.iter() {
+            let user_bindings = self.entry(*mode).or_default();
+            for (action, key) in default_bindings {
+                if user_bindings.contains_key(action)
+                    || user_bindings.values().any(|k| k == key)
+                {
+                    continue;
+                }
+                user_bindings.insert(action.clone(), *key);
+            }
+        }
+    }
+}
+
 pub fn parse_key_event(raw: &str) -> color_eyre::Result<KeyEvent, String> {
     let raw_lower = raw.to_ascii_lowercase();
     let (remaining, modifiers) = extract_modifiers(&raw_lower);
@@ -198,6 +231,146 @@ pub fn parse_key(raw: &str) -> color_eyre::Result<Key, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::Keymap;
+    use crate::event::Key;
+
+    fn parse_keybindings(raw: &str) -> Result<KeyBindings, toml::de::Error> {
+        toml::from_str(raw)
+    }
+
+    #[test]
+    fn test_deserialize_keybindings() {
+        let keybindings = parse_keybindings(
+            r#"
+            [Channel]
+            quit = "esc"
+            select_next_entry = "ctrl-n"
+            select_prev_entry = "alt-k"
+
+            [RemoteControl]
+            toggle_remote_control = "ctrl-r"
+            "#,
+        )
+        .unwrap();
+        let channel = keybindings.get(&Mode::Channel).unwrap();
+        assert_eq!(channel.get(&Action::Quit), Some(&Key::Esc));
+        assert_eq!(
+            channel.get(&Action::SelectNextEntry),
+            Some(&Key::Ctrl('n'))
+        );
+        assert_eq!(
+            channel.get(&Action::SelectPrevEntry),
+            Some(&Key::Alt('k'))
+        );
+
+        let keymap = Keymap::from(&keybindings);
+        assert_eq!(
+            keymap.get_action(Mode::Channel, &Key::Ctrl('n')),
+            Some(&Action::SelectNextEntry)
+        );
+        assert_eq!(
+            keymap.get_action(Mode::RemoteControl, &Key::Ctrl('r')),
+            Some(&Action::ToggleRemoteControl)
+        );
+        assert_eq!(keymap.get_action(Mode::Channel, &Key::Ctrl('r')), None);
+    }
+
+    #[test]
+    fn test_copy_action_aliases() {
+        let keybindings = parse_keybindings(
+            r#"
+            [Channel]
+            copy_entry_to_clipboard = "ctrl-y"
+            copy_entry_value = "alt-y"
+            "#,
+        )
+        .unwrap();
+        let channel = keybindings.get(&Mode::Channel).unwrap();
+        assert_eq!(channel.get(&Action::CopyEntryName), Some(&Key::Ctrl('y')));
+        assert_eq!(channel.get(&Action::CopyEntryValue), Some(&Key::Alt('y')));
+
+        let keybindings = parse_keybindings(
+            r#"
+            [Channel]
+            copy_entry_name = "ctrl-y"
+            "#,
+        )
+        .unwrap();
+        let channel = keybindings.get(&Mode::Channel).unwrap();
+        assert_eq!(channel.get(&Action::CopyEntryName), Some(&Key::Ctrl('y')));
+    }
+
+    #[test]
+    fn test_scroll_preview_aliases() {
+        let keybindings = parse_keybindings(
+            r#"
+            [Channel]
+            scroll_preview_top = "home"
+            scroll_preview_bottom = "end"
+            "#,
+        )
+        .unwrap();
+        let channel = keybindings.get(&Mode::Channel).unwrap();
+        assert_eq!(channel.get(&Action::ScrollPreviewToTop), Some(&Key::Home));
+        assert_eq!(
+            channel.get(&Action::ScrollPreviewToBottom),
+            Some(&Key::End)
+        );
+    }
+
+    #[test]
+    fn test_unknown_action() {
+        let err = parse_keybindings(
+            r#"
+            [Channel]
+            launch_rockets = "ctrl-l"
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown action `launch_rockets`"));
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        let err = parse_keybindings(
+            r#"
+            [Channel]
+            quit = "ctrl-nope"
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("invalid key for `quit`"));
+    }
+
+    #[test]
+    fn test_merge_defaults() {
+        let defaults = parse_keybindings(
+            r#"
+            [Channel]
+            quit = "esc"
+            select_next_entry = "down"
+            select_prev_entry = "up"
+            "#,
+        )
+        .unwrap();
+        let mut keybindings = parse_keybindings(
+            r#"
+            [Channel]
+            select_next_entry = "ctrl-n"
+            quit = "up"
+            "#,
+        )
+        .unwrap();
+        keybindings.merge_defaults(&defaults);
+
+        let keymap = Keymap::from(&keybindings);
+        let resolve = |key| keymap.get_action(Mode::Channel, &key).cloned();
+        assert_eq!(resolve(Key::Ctrl('n')), Some(Action::SelectNextEntry));
+        assert_eq!(resolve(Key::Up), Some(Action::Quit));
+        // overridden by the user
+        assert_eq!(resolve(Key::Down), None);
+        assert_eq!(resolve(Key::Esc), None);
+    }
 
     #[test]
     fn test_simple_keys() {
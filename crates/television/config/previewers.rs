@@ -1,8 +1,18 @@
 use config::ValueKind;
 use serde::Deserialize;
+
+use super::get_cache_dir;
+use super::ui::parse_hex_color;
+use ratatui::style::Color;
 use std::collections::HashMap;
+use std::time::Duration;
 use television_previewers::previewers;
+use television_previewers::previewers::image::DEFAULT_IMAGE_BACKGROUND;
+use television_previewers::previewers::ImageProtocol;
 use television_previewers::previewers::PreviewerConfig;
+use television_utils::strings::TAB_WIDTH;
+use television_utils::syntax::TerminalBackground;
+use tracing::warn;
 
 #[derive(Clone, Debug, Deserialize, Default)]
 pub struct PreviewersConfig {
@@ -13,12 +23,50 @@ pub struct PreviewersConfig {
     pub file: FilePreviewerConfig,
     #[serde(default)]
     pub env_var: EnvVarPreviewerConfig,
+    #[serde(default)]
+    pub command: CommandPreviewerConfig,
 }
 
 impl From<PreviewersConfig> for PreviewerConfig {
     fn from(val: PreviewersConfig) -> Self {
-        PreviewerConfig::default()
-            .file(previewers::FilePreviewerConfig::new(val.file.theme.clone()))
+        let mut file =
+            previewers::FilePreviewerConfig::new(val.file.theme.clone())
+                .tab_width(val.file.tab_width)
+                .pretty_print_json(val.file.pretty_print_json)
+                .hex_preview_max_bytes(val.file.hex_preview_max_bytes)
+                .archive_max_entries(val.file.archive_max_entries)
+                .prefer_git_diff(val.file.prefer_git_diff)
+                .show_git_blame(val.file.show_git_blame)
+                .table_max_rows(val.file.table_max_rows)
+                .render_markdown(val.file.render_markdown)
+                .page_breaks(val.file.page_breaks)
+                .max_highlighted_line_length(
+                    val.file.max_highlighted_line_length,
+                )
+                .text_extensions(val.file.text_extensions.clone())
+                .render_images(val.file.image_protocol().is_some())
+                .image_background(val.file.image_background())
+                .preview_timeout(Duration::from_millis(
+                    val.file.preview_timeout_ms,
+                ));
+        if let Some(light_theme) = val.file.light_theme {
+            file = file
+                .light_theme(light_theme)
+                .terminal_background(TerminalBackground::detect());
+        }
+        if let Some(syntax_directory) = val.file.syntax_directory {
+            file = file.syntax_directory(syntax_directory);
+        }
+        if val.file.persistent_cache {
+            file = file
+                .persistent_cache_directory(get_cache_dir().join("previews"));
+        }
+        // the timeout applies to preview commands as well
+        let command = previewers::CommandPreviewerConfig::default()
+            .preview_timeout(Duration::from_millis(
+                val.file.preview_timeout_ms,
+            ));
+        PreviewerConfig::default().file(file).command(command)
     }
 }
 
@@ -29,6 +77,7 @@ impl From<PreviewersConfig> for ValueKind {
         m.insert(String::from("directory"), val.directory.into());
         m.insert(String::from("file"), val.file.into());
         m.insert(String::from("env_var"), val.env_var.into());
+        m.insert(String::from("command"), val.command.into());
         ValueKind::Table(m)
     }
 }
@@ -51,16 +100,213 @@ impl From<DirectoryPreviewerConfig> for ValueKind {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Default)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct FilePreviewerConfig {
     //pub max_file_size: u64,
     pub theme: String,
+    #[serde(default)]
+    pub light_theme: Option<String>,
+    #[serde(default = "default_tab_width")]
+    pub tab_width: usize,
+    #[serde(default = "default_true")]
+    pub pretty_print_json: bool,
+    #[serde(default = "default_hex_preview_max_bytes")]
+    pub hex_preview_max_bytes: usize,
+    #[serde(default = "default_archive_max_entries")]
+    pub archive_max_entries: usize,
+    #[serde(default)]
+    pub syntax_directory: Option<String>,
+    #[serde(default)]
+    pub prefer_git_diff: bool,
+    #[serde(default)]
+    pub show_git_blame: bool,
+    #[serde(default = "default_table_max_rows")]
+    pub table_max_rows: usize,
+    #[serde(default = "default_true")]
+    pub render_markdown: bool,
+    #[serde(default)]
+    pub page_breaks: bool,
+    #[serde(default = "default_max_highlighted_line_length")]
+    pub max_highlighted_line_length: usize,
+    #[serde(default)]
+    pub text_extensions: Vec<String>,
+    #[serde(default)]
+    pub persistent_cache: bool,
+    #[serde(default = "default_preview_timeout_ms")]
+    pub preview_timeout_ms: u64,
+    #[serde(default = "default_image_protocol")]
+    pub image_protocol: String,
+    #[serde(default)]
+    pub image_background: Option<String>,
+}
+
+impl FilePreviewerConfig {
+    /// The protocol used to draw images, `None` if they are disabled.
+    ///
+    /// `auto` guesses the protocol supported by the terminal, unknown
+    /// protocols fall back to half blocks.
+    pub fn image_protocol(&self) -> Option<ImageProtocol> {
+        match self.image_protocol.as_str() {
+            "none" => None,
+            "auto" => Some(ImageProtocol::detect()),
+            protocol => Some(protocol.parse().unwrap_or_else(|e| {
+                warn!("{}, using half blocks", e);
+                ImageProtocol::Halfblocks
+            })),
+        }
+    }
+
+    /// The RGB color transparent pixels of images are blended with.
+    pub fn image_background(&self) -> [u8; 3] {
+        match parse_hex_color("image_background", &self.image_background) {
+            Some(Color::Rgb(r, g, b)) => [r, g, b],
+            _ => DEFAULT_IMAGE_BACKGROUND,
+        }
+    }
+}
+
+fn default_tab_width() -> usize {
+    TAB_WIDTH
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_hex_preview_max_bytes() -> usize {
+    previewers::DEFAULT_HEX_PREVIEW_MAX_BYTES
+}
+
+fn default_archive_max_entries() -> usize {
+    previewers::archive::DEFAULT_ARCHIVE_MAX_ENTRIES
+}
+
+fn default_table_max_rows() -> usize {
+    previewers::table::DEFAULT_TABLE_MAX_ROWS
+}
+
+fn default_max_highlighted_line_length() -> usize {
+    previewers::DEFAULT_MAX_HIGHLIGHTED_LINE_LENGTH
+}
+
+fn default_image_protocol() -> String {
+    String::from("auto")
+}
+
+fn default_preview_timeout_ms() -> u64 {
+    u64::try_from(previewers::DEFAULT_PREVIEW_TIMEOUT.as_millis())
+        .unwrap_or(u64::MAX)
+}
+
+impl Default for FilePreviewerConfig {
+    fn default() -> Self {
+        Self {
+            theme: String::new(),
+            light_theme: None,
+            tab_width: TAB_WIDTH,
+            pretty_print_json: true,
+            hex_preview_max_bytes: previewers::DEFAULT_HEX_PREVIEW_MAX_BYTES,
+            archive_max_entries: default_archive_max_entries(),
+            syntax_directory: None,
+            prefer_git_diff: false,
+            show_git_blame: false,
+            table_max_rows: default_table_max_rows(),
+            render_markdown: true,
+            page_breaks: false,
+            max_highlighted_line_length: default_max_highlighted_line_length(),
+            text_extensions: Vec::new(),
+            persistent_cache: false,
+            preview_timeout_ms: default_preview_timeout_ms(),
+            image_protocol: default_image_protocol(),
+            image_background: None,
+        }
+    }
 }
 
 impl From<FilePreviewerConfig> for ValueKind {
     fn from(val: FilePreviewerConfig) -> Self {
         let mut m = HashMap::new();
         m.insert(String::from("theme"), ValueKind::String(val.theme).into());
+        m.insert(
+            String::from("tab_width"),
+            ValueKind::U64(val.tab_width as u64).into(),
+        );
+        m.insert(
+            String::from("pretty_print_json"),
+            ValueKind::Boolean(val.pretty_print_json).into(),
+        );
+        m.insert(
+            String::from("hex_preview_max_bytes"),
+            ValueKind::U64(val.hex_preview_max_bytes as u64).into(),
+        );
+        m.insert(
+            String::from("archive_max_entries"),
+            ValueKind::U64(val.archive_max_entries as u64).into(),
+        );
+        m.insert(
+            String::from("prefer_git_diff"),
+            ValueKind::Boolean(val.prefer_git_diff).into(),
+        );
+        m.insert(
+            String::from("show_git_blame"),
+            ValueKind::Boolean(val.show_git_blame).into(),
+        );
+        m.insert(
+            String::from("table_max_rows"),
+            ValueKind::U64(val.table_max_rows as u64).into(),
+        );
+        m.insert(
+            String::from("render_markdown"),
+            ValueKind::Boolean(val.render_markdown).into(),
+        );
+        m.insert(
+            String::from("page_breaks"),
+            ValueKind::Boolean(val.page_breaks).into(),
+        );
+        m.insert(
+            String::from("max_highlighted_line_length"),
+            ValueKind::U64(val.max_highlighted_line_length as u64).into(),
+        );
+        m.insert(
+            String::from("text_extensions"),
+            ValueKind::Array(
+                val.text_extensions
+                    .into_iter()
+                    .map(|extension| ValueKind::String(extension).into())
+                    .collect(),
+            )
+            .into(),
+        );
+        m.insert(
+            String::from("persistent_cache"),
+            ValueKind::Boolean(val.persistent_cache).into(),
+        );
+        m.insert(
+            String::from("preview_timeout_ms"),
+            ValueKind::U64(val.preview_timeout_ms).into(),
+        );
+        m.insert(
+            String::from("image_protocol"),
+            ValueKind::String(val.image_protocol).into(),
+        );
+        if let Some(image_background) = val.image_background {
+            m.insert(
+                String::from("image_background"),
+                ValueKind::String(image_background).into(),
+            );
+        }
+        if let Some(light_theme) = val.light_theme {
+            m.insert(
+                String::from("light_theme"),
+                ValueKind::String(light_theme).into(),
+            );
+        }
+        if let Some(syntax_directory) = val.syntax_directory {
+            m.insert(
+                String::from("syntax_directory"),
+                ValueKind::String(syntax_directory).into(),
+            );
+        }
         ValueKind::Table(m)
     }
 }
@@ -73,3 +319,95 @@ impl From<EnvVarPreviewerConfig> for ValueKind {
         ValueKind::Table(HashMap::new())
     }
 }
+
+/// Commands overriding the preview of the entries of a channel, by channel
+/// name (e.g. `files = "bat --color=always {}"`).
+#[derive(Clone, Debug, Deserialize, Default)]
+pub struct CommandPreviewerConfig(HashMap<String, String>);
+
+impl CommandPreviewerConfig {
+    /// Get the preview command template configured for the given channel.
+    ///
+    /// Channel names are compared ignoring case, dashes and underscores so
+    /// that e.g. `git-repos` matches the `GitRepos` channel.
+    pub fn for_channel(&self, channel_name: &str) -> Option<&str> {
+        let normalize = |name: &str| {
+            name.chars()
+                .filter(|c| !matches!(c, '-' | '_'))
+                .collect::<String>()
+                .to_lowercase()
+        };
+        let channel_name = normalize(channel_name);
+        self.0
+            .iter()
+            .find(|(name, _)| normalize(name) == channel_name)
+            .map(|(_, command)| command.as_str())
+    }
+}
+
+impl From<CommandPreviewerConfig> for ValueKind {
+    fn from(val: CommandPreviewerConfig) -> Self {
+        ValueKind::Table(
+            val.0
+                .into_iter()
+                .map(|(channel, command)| {
+                    (channel, ValueKind::String(command).into())
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_command_for_channel() {
+        let config: PreviewersConfig = toml::from_str(
+            r#"
+            [file]
+            theme = "base16"
+
+            [command]
+            files = "bat --color=always {}"
+            git-repos = "git -C {} log --oneline"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.command.for_channel("Files"),
+            Some("bat --color=always {}")
+        );
+        assert_eq!(
+            config.command.for_channel("GitRepos"),
+            Some("git -C {} log --oneline")
+        );
+        assert_eq!(config.command.for_channel("Env"), None);
+    }
+
+    #[test]
+    fn test_image_options() {
+        let config: FilePreviewerConfig = toml::from_str(
+            r##"
+            theme = "base16"
+            image_protocol = "sixel"
+            image_background = "#102030"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.image_protocol(), Some(ImageProtocol::Sixel));
+        assert_eq!(config.image_background(), [0x10, 0x20, 0x30]);
+
+        let config: FilePreviewerConfig = toml::from_str(
+            r#"
+            theme = "base16"
+            image_protocol = "none"
+            image_background = "magenta"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.image_protocol(), None);
+        assert_eq!(config.image_background(), DEFAULT_IMAGE_BACKGROUND);
+    }
+}
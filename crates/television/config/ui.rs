@@ -1,14 +1,229 @@
+use crate::ui::results::ResultsListColors;
 use config::ValueKind;
-use serde::Deserialize;
+use ratatui::style::Color;
+use ratatui::widgets::{BorderType, ListDirection};
+use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
+use std::str::FromStr;
+use television_utils::strings::NULL_SYMBOL;
+use tracing::warn;
 
 const DEFAULT_UI_SCALE: u16 = 90;
+/// The bounds of the percentage of the terminal used by the UI.
+const MIN_UI_SCALE: u16 = 10;
+const MAX_UI_SCALE: u16 = 100;
+const DEFAULT_HIGHLIGHT_SYMBOL: &str = "> ";
+const DEFAULT_PREVIEW_SIZE: u16 = 50;
+const DEFAULT_QUERY_DEBOUNCE_MS: u64 = 50;
+
+/// The order in which results are displayed.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultsOrder {
+    /// The best match is displayed at the bottom, right above the input.
+    #[default]
+    BottomToTop,
+    /// The best match is displayed at the top, like fzf's default layout.
+    TopToBottom,
+}
+
+impl From<ResultsOrder> for ListDirection {
+    fn from(order: ResultsOrder) -> Self {
+        match order {
+            ResultsOrder::BottomToTop => ListDirection::BottomToTop,
+            ResultsOrder::TopToBottom => ListDirection::TopToBottom,
+        }
+    }
+}
+
+impl From<ResultsOrder> for ValueKind {
+    fn from(order: ResultsOrder) -> Self {
+        ValueKind::String(
+            match order {
+                ResultsOrder::BottomToTop => "bottom_to_top",
+                ResultsOrder::TopToBottom => "top_to_bottom",
+            }
+            .to_string(),
+        )
+    }
+}
+
+/// How the results and the preview are arranged.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutOrientation {
+    /// The results on the left, the preview on the right.
+    #[default]
+    Horizontal,
+    /// The results on top, the preview below.
+    Vertical,
+}
+
+impl From<LayoutOrientation> for ValueKind {
+    fn from(orientation: LayoutOrientation) -> Self {
+        ValueKind::String(
+            match orientation {
+                LayoutOrientation::Horizontal => "horizontal",
+                LayoutOrientation::Vertical => "vertical",
+            }
+            .to_string(),
+        )
+    }
+}
+
+/// Where the input bar is placed relative to the results.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InputPosition {
+    /// Above the results, like telescope.
+    Top,
+    /// Below the results, like fzf.
+    #[default]
+    Bottom,
+}
+
+impl From<InputPosition> for ValueKind {
+    fn from(position: InputPosition) -> Self {
+        ValueKind::String(
+            match position {
+                InputPosition::Top => "top",
+                InputPosition::Bottom => "bottom",
+            }
+            .to_string(),
+        )
+    }
+}
+
+/// How to display results that are too wide for the results list.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultsOverflow {
+    /// Clip the end of the result.
+    #[default]
+    Truncate,
+    /// Shrink the name of the result with an ellipsis in the middle.
+    Ellipsis,
+    /// Wrap the result onto continuation lines.
+    Wrap,
+}
+
+impl From<ResultsOverflow> for ValueKind {
+    fn from(overflow: ResultsOverflow) -> Self {
+        ValueKind::String(
+            match overflow {
+                ResultsOverflow::Truncate => "truncate",
+                ResultsOverflow::Ellipsis => "ellipsis",
+                ResultsOverflow::Wrap => "wrap",
+            }
+            .to_string(),
+        )
+    }
+}
+
+/// The background of the preview pane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreviewBackground {
+    Color(Color),
+    /// The background of the current syntax theme.
+    Theme,
+}
+
+/// How the borders of panels are drawn.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BorderStyle {
+    #[default]
+    Rounded,
+    Plain,
+    Thick,
+    Double,
+    /// Panels have no borders at all.
+    None,
+}
+
+impl BorderStyle {
+    /// The type of the borders, or `None` if there aren't any.
+    pub fn border_type(self) -> Option<BorderType> {
+        match self {
+            BorderStyle::Rounded => Some(BorderType::Rounded),
+            BorderStyle::Plain => Some(BorderType::Plain),
+            BorderStyle::Thick => Some(BorderType::Thick),
+            BorderStyle::Double => Some(BorderType::Double),
+            BorderStyle::None => None,
+        }
+    }
+}
+
+impl From<BorderStyle> for ValueKind {
+    fn from(style: BorderStyle) -> Self {
+        ValueKind::String(
+            match style {
+                BorderStyle::Rounded => "rounded",
+                BorderStyle::Plain => "plain",
+                BorderStyle::Thick => "thick",
+                BorderStyle::Double => "double",
+                BorderStyle::None => "none",
+            }
+            .to_string(),
+        )
+    }
+}
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct UiConfig {
     pub use_nerd_font_icons: bool,
+    #[serde(deserialize_with = "deserialize_ui_scale")]
     pub ui_scale: u16,
     pub show_help_bar: bool,
+    /// Whether entries are previewed at all. When off, the preview pane is
+    /// never shown and previewers are never loaded.
+    pub show_preview: bool,
+    /// The percentage of the width given to the preview pane.
+    pub preview_size: u16,
+    pub layout: LayoutOrientation,
+    pub input_position: InputPosition,
+    pub highlight_symbol: String,
+    /// The symbol non-printable characters are replaced with, in results
+    /// and previews.
+    pub replacement_symbol: char,
+    pub results_order: ResultsOrder,
+    pub show_scrollbar: bool,
+    /// Whether to show the index of the selected result and the number of
+    /// results (e.g. ` 12 / 4571 `) in the input bar.
+    pub show_result_count: bool,
+    pub results_overflow: ResultsOverflow,
+    /// Whether to show line numbers in the gutter of previews.
+    pub preview_line_numbers: bool,
+    /// Whether to wrap long lines in previews instead of clipping them.
+    pub preview_wrap: bool,
+    /// How long to wait after the last keystroke before running the query,
+    /// in milliseconds (0 runs it on every keystroke).
+    pub query_debounce_ms: u64,
+    /// Whether to restore the last query used on a channel when opening it
+    /// again.
+    pub restore_last_query: bool,
+    /// Whether to draw matches spanning whole words in bold, to tell them
+    /// apart from matches in the middle of words.
+    pub bold_whole_word_matches: bool,
+    /// How the borders of panels are drawn.
+    pub border_style: BorderStyle,
+    /// The color of the borders of panels, as a hex string (e.g. `#5fafff`).
+    #[serde(default)]
+    pub border_color: Option<String>,
+    /// The background of the preview pane, as a hex string or `theme` to
+    /// use the background of the syntax theme (the terminal's if unset).
+    #[serde(default)]
+    pub preview_bg: Option<String>,
+    #[serde(default)]
+    pub result_name_fg: Option<String>,
+    #[serde(default)]
+    pub result_preview_fg: Option<String>,
+    #[serde(default)]
+    pub result_line_number_fg: Option<String>,
+    #[serde(default)]
+    pub result_selected_bg: Option<String>,
+    #[serde(default)]
+    pub result_match_fg: Option<String>,
 }
 
 impl Default for UiConfig {
@@ -17,8 +232,121 @@ impl Default for UiConfig {
             use_nerd_font_icons: false,
             ui_scale: DEFAULT_UI_SCALE,
             show_help_bar: true,
+            show_preview: true,
+            preview_size: DEFAULT_PREVIEW_SIZE,
+            layout: LayoutOrientation::default(),
+            input_position: InputPosition::default(),
+            highlight_symbol: DEFAULT_HIGHLIGHT_SYMBOL.to_string(),
+            replacement_symbol: NULL_SYMBOL,
+            results_order: ResultsOrder::default(),
+            show_scrollbar: true,
+            show_result_count: true,
+            results_overflow: ResultsOverflow::default(),
+            preview_line_numbers: true,
+            preview_wrap: false,
+            query_debounce_ms: DEFAULT_QUERY_DEBOUNCE_MS,
+            restore_last_query: true,
+            bold_whole_word_matches: false,
+            border_style: BorderStyle::default(),
+            border_color: None,
+            preview_bg: None,
+            result_name_fg: None,
+            result_preview_fg: None,
+            result_line_number_fg: None,
+            result_selected_bg: None,
+            result_match_fg: None,
+        }
+    }
+}
+
+impl UiConfig {
+    /// Build the colors of the results list, using the defaults for colors
+    /// that aren't configured or can't be parsed.
+    pub fn results_list_colors(&self) -> ResultsListColors {
+        let mut colors = ResultsListColors::default()
+            .bold_whole_word_matches(self.bold_whole_word_matches);
+        if let Some(c) =
+            parse_hex_color("result_name_fg", &self.result_name_fg)
+        {
+            colors = colors.result_name_fg(c);
+        }
+        if let Some(c) =
+            parse_hex_color("result_preview_fg", &self.result_preview_fg)
+        {
+            colors = colors.result_preview_fg(c);
+        }
+        if let Some(c) = parse_hex_color(
+            "result_line_number_fg",
+            &self.result_line_number_fg,
+        ) {
+            colors = colors.result_line_number_fg(c);
+        }
+        if let Some(c) =
+            parse_hex_color("result_selected_bg", &self.result_selected_bg)
+        {
+            colors = colors.result_selected_bg(c);
         }
+        if let Some(c) =
+            parse_hex_color("result_match_fg", &self.result_match_fg)
+        {
+            colors = colors.result_match_fg(c);
+        }
+        colors
+    }
+
+    /// The color of the borders of panels, using the default if it isn't
+    /// configured or can't be parsed.
+    pub fn border_color(&self) -> Color {
+        parse_hex_color("border_color", &self.border_color)
+            .unwrap_or(crate::ui::BORDER_COLOR)
+    }
+
+    /// The background of the preview pane, or `None` to keep the terminal's
+    /// if it isn't configured or can't be parsed.
+    pub fn preview_background(&self) -> Option<PreviewBackground> {
+        if self.preview_bg.as_deref() == Some("theme") {
+            return Some(PreviewBackground::Theme);
+        }
+        parse_hex_color("preview_bg", &self.preview_bg)
+            .map(PreviewBackground::Color)
+    }
+}
+
+/// Clamp the given UI scale into a usable range, logging a warning if it was
+/// out of bounds.
+fn clamp_ui_scale(ui_scale: u16) -> u16 {
+    let clamped = ui_scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    if clamped != ui_scale {
+        warn!(
+            "ui_scale should be between {} and {}, got {}, using {}",
+            MIN_UI_SCALE, MAX_UI_SCALE, ui_scale, clamped
+        );
     }
+    clamped
+}
+
+fn deserialize_ui_scale<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    u16::deserialize(deserializer).map(clamp_ui_scale)
+}
+
+/// Parse a hex color string such as `#5fafff`, logging a warning if it's
+/// invalid.
+pub(crate) fn parse_hex_color(
+    name: &str,
+    value: &Option<String>,
+) -> Option<Color> {
+    let value = value.as_ref()?;
+    let is_hex = value.len() == 7
+        && value.starts_with('#')
+        && value[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if let (true, Ok(color)) = (is_hex, Color::from_str(value)) {
+        return Some(color);
+    }
+    warn!("Invalid color for {}: {:?}, using the default", name, value);
+    None
 }
 
 impl From<UiConfig> for ValueKind {
@@ -36,6 +364,207 @@ impl From<UiConfig> for ValueKind {
             String::from("show_help_bar"),
             ValueKind::Boolean(val.show_help_bar).into(),
         );
+        m.insert(
+            String::from("preview_size"),
+            ValueKind::U64(val.preview_size.into()).into(),
+        );
+        m.insert(String::from("layout"), ValueKind::from(val.layout).into());
+        m.insert(
+            String::from("input_position"),
+            ValueKind::from(val.input_position).into(),
+        );
+        m.insert(
+            String::from("highlight_symbol"),
+            ValueKind::String(val.highlight_symbol).into(),
+        );
+        m.insert(
+            String::from("replacement_symbol"),
+            ValueKind::String(val.replacement_symbol.to_string()).into(),
+        );
+        m.insert(
+            String::from("results_order"),
+            ValueKind::from(val.results_order).into(),
+        );
+        m.insert(
+            String::from("show_preview"),
+            ValueKind::Boolean(val.show_preview).into(),
+        );
+        m.insert(
+            String::from("show_scrollbar"),
+            ValueKind::Boolean(val.show_scrollbar).into(),
+        );
+        m.insert(
+            String::from("show_result_count"),
+            ValueKind::Boolean(val.show_result_count).into(),
+        );
+        m.insert(
+            String::from("results_overflow"),
+            ValueKind::from(val.results_overflow).into(),
+        );
+        m.insert(
+            String::from("preview_line_numbers"),
+            ValueKind::Boolean(val.preview_line_numbers).into(),
+        );
+        m.insert(
+            String::from("preview_wrap"),
+            ValueKind::Boolean(val.preview_wrap).into(),
+        );
+        m.insert(
+            String::from("query_debounce_ms"),
+            ValueKind::U64(val.query_debounce_ms).into(),
+        );
+        m.insert(
+            String::from("restore_last_query"),
+            ValueKind::Boolean(val.restore_last_query).into(),
+        );
+        m.insert(
+            String::from("bold_whole_word_matches"),
+            ValueKind::Boolean(val.bold_whole_word_matches).into(),
+        );
+        m.insert(
+            String::from("border_style"),
+            ValueKind::from(val.border_style).into(),
+        );
+        for (name, color) in [
+            ("border_color", val.border_color),
+            ("preview_bg", val.preview_bg),
+            ("result_name_fg", val.result_name_fg),
+            ("result_preview_fg", val.result_preview_fg),
+            ("result_line_number_fg", val.result_line_number_fg),
+            ("result_selected_bg", val.result_selected_bg),
+            ("result_match_fg", val.result_match_fg),
+        ] {
+            if let Some(color) = color {
+                m.insert(String::from(name), ValueKind::String(color).into());
+            }
+        }
         ValueKind::Table(m)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_list_colors() {
+        let config = UiConfig {
+            result_name_fg: Some(String::from("#5fafff")),
+            result_match_fg: Some(String::from("#FF8700")),
+            ..UiConfig::default()
+        };
+        let colors = config.results_list_colors();
+        assert_eq!(colors.result_name_fg, Color::Rgb(0x5f, 0xaf, 0xff));
+        assert_eq!(colors.result_match_fg, Color::Rgb(0xff, 0x87, 0x00));
+        let defaults = ResultsListColors::default();
+        assert_eq!(colors.result_preview_fg, defaults.result_preview_fg);
+        assert_eq!(colors.result_selected_bg, defaults.result_selected_bg);
+    }
+
+    fn load_ui_config(ui_scale: i64) -> UiConfig {
+        config::Config::builder()
+            .set_default("ui", UiConfig::default())
+            .unwrap()
+            .set_override("ui.ui_scale", ui_scale)
+            .unwrap()
+            .build()
+            .unwrap()
+            .get("ui")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_ui_scale_too_small() {
+        assert_eq!(load_ui_config(0).ui_scale, MIN_UI_SCALE);
+        assert_eq!(load_ui_config(9).ui_scale, MIN_UI_SCALE);
+    }
+
+    #[test]
+    fn test_ui_scale_too_large() {
+        assert_eq!(load_ui_config(101).ui_scale, MAX_UI_SCALE);
+        assert_eq!(load_ui_config(900).ui_scale, MAX_UI_SCALE);
+    }
+
+    #[test]
+    fn test_ui_scale_in_range() {
+        assert_eq!(load_ui_config(10).ui_scale, 10);
+        assert_eq!(load_ui_config(80).ui_scale, 80);
+        assert_eq!(load_ui_config(100).ui_scale, 100);
+    }
+
+    #[test]
+    fn test_clamped_ui_scale_round_trip() {
+        let ui_config = load_ui_config(900);
+        let round_trip: UiConfig = config::Config::builder()
+            .set_default("ui", ui_config)
+            .unwrap()
+            .build()
+            .unwrap()
+            .get("ui")
+            .unwrap();
+        assert_eq!(round_trip.ui_scale, MAX_UI_SCALE);
+    }
+
+    #[test]
+    fn test_replacement_symbol() {
+        let config: UiConfig = config::Config::builder()
+            .set_default("ui", UiConfig::default())
+            .unwrap()
+            .build()
+            .unwrap()
+            .get("ui")
+            .unwrap();
+        assert_eq!(config.replacement_symbol, NULL_SYMBOL);
+
+        let config: UiConfig = config::Config::builder()
+            .set_default("ui", UiConfig::default())
+            .unwrap()
+            .set_override("ui.replacement_symbol", "·")
+            .unwrap()
+            .build()
+            .unwrap()
+            .get("ui")
+            .unwrap();
+        assert_eq!(config.replacement_symbol, '·');
+    }
+
+    #[test]
+    fn test_border_config() {
+        let config: UiConfig = config::Config::builder()
+            .set_default("ui", UiConfig::default())
+            .unwrap()
+            .set_override("ui.border_style", "none")
+            .unwrap()
+            .set_override("ui.border_color", "#5fafff")
+            .unwrap()
+            .build()
+            .unwrap()
+            .get("ui")
+            .unwrap();
+        assert_eq!(config.border_style, BorderStyle::None);
+        assert_eq!(config.border_style.border_type(), None);
+        assert_eq!(config.border_color(), Color::Rgb(0x5f, 0xaf, 0xff));
+
+        let config = UiConfig::default();
+        assert_eq!(
+            config.border_style.border_type(),
+            Some(BorderType::Rounded)
+        );
+        assert_eq!(config.border_color(), crate::ui::BORDER_COLOR);
+    }
+
+    #[test]
+    fn test_invalid_colors_fall_back_to_defaults() {
+        let config = UiConfig {
+            result_name_fg: Some(String::from("#5faf")),
+            result_preview_fg: Some(String::from("red")),
+            result_selected_bg: Some(String::from("#gggggg")),
+            ..UiConfig::default()
+        };
+        let colors = config.results_list_colors();
+        let defaults = ResultsListColors::default();
+        assert_eq!(colors.result_name_fg, defaults.result_name_fg);
+        assert_eq!(colors.result_preview_fg, defaults.result_preview_fg);
+        assert_eq!(colors.result_selected_bg, defaults.result_selected_bg);
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::Path;
+use std::process::Command;
+
+use color_eyre::Result;
+use television_channels::entry::Entry;
+
+/// The environment variables the editor is looked up in, by order of
+/// precedence.
+const EDITOR_ENV_VARS: [&str; 2] = ["EDITOR", "VISUAL"];
+
+/// Get the user's editor from `$EDITOR`, falling back to `$VISUAL`.
+pub fn editor_from_env() -> Option<String> {
+    EDITOR_ENV_VARS
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|editor| !editor.trim().is_empty())
+}
+
+/// Build the command line opening `path` at the given line with `editor`.
+///
+/// `editor` may contain arguments (e.g. `code --wait`). The syntax used to
+/// jump to the line depends on the editor, editors that aren't known to
+/// support it just open the file.
+pub fn editor_command_line(
+    editor: &str,
+    path: &str,
+    line_number: Option<usize>,
+) -> Vec<String> {
+    let mut command_line: Vec<String> =
+        editor.split_whitespace().map(String::from).collect();
+    let program = command_line
+        .first()
+        .and_then(|program| Path::new(program).file_stem())
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match (line_number, program.as_str()) {
+        (
+            Some(line_number),
+            "vi" | "vim" | "nvim" | "gvim" | "nano" | "emacs" | "emacsclient"
+            | "kak" | "micro",
+        ) => {
+            command_line.push(format!("+{line_number}"));
+            command_line.push(path.to_string());
+        }
+        (Some(line_number), "code" | "code-insiders" | "codium") => {
+            command_line.push("--goto".to_string());
+            command_line.push(format!("{path}:{line_number}"));
+        }
+        (Some(line_number), "hx" | "helix" | "subl") => {
+            command_line.push(format!("{path}:{line_number}"));
+        }
+        _ => command_line.push(path.to_string()),
+    }
+    command_line
+}
+
+/// Build the command line opening `path` in the default application.
+fn default_application_command_line(path: &str) -> Vec<String> {
+    let opener: &[&str] = if cfg!(target_os = "macos") {
+        &["open"]
+    } else if cfg!(target_os = "windows") {
+        &["cmd", "/C", "start", ""]
+    } else {
+        &["xdg-open"]
+    };
+    opener
+        .iter()
+        .map(ToString::to_string)
+        .chain(std::iter::once(path.to_string()))
+        .collect()
+}
+
+/// Open the given entry in the user's editor, at its line number if it has
+/// one.
+///
+/// When no editor is set, the entry is opened in the default application.
+pub fn open_in_editor(entry: &Entry) -> Result<()> {
+    let command_line = editor_from_env().map_or_else(
+        || default_application_command_line(&entry.name),
+        |editor| editor_command_line(&editor, &entry.name, entry.line_number),
+    );
+    Command::new(&command_line[0])
+        .args(&command_line[1..])
+        .status()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_line(editor: &str, line_number: Option<usize>) -> String {
+        editor_command_line(editor, "src/main.rs", line_number).join(" ")
+    }
+
+    #[test]
+    fn test_terminal_editors() {
+        assert_eq!(command_line("vim", Some(42)), "vim +42 src/main.rs");
+        assert_eq!(command_line("nvim", Some(42)), "nvim +42 src/main.rs");
+        assert_eq!(command_line("nano", Some(42)), "nano +42 src/main.rs");
+        assert_eq!(
+            command_line("emacs -nw", Some(42)),
+            "emacs -nw +42 src/main.rs"
+        );
+        assert_eq!(
+            command_line("/usr/bin/nvim", Some(1)),
+            "/usr/bin/nvim +1 src/main.rs"
+        );
+        assert_eq!(command_line("hx", Some(42)), "hx src/main.rs:42");
+    }
+
+    #[test]
+    fn test_vscode() {
+        assert_eq!(
+            command_line("code", Some(42)),
+            "code --goto src/main.rs:42"
+        );
+        assert_eq!(
+            command_line("code --wait", Some(42)),
+            "code --wait --goto src/main.rs:42"
+        );
+    }
+
+    #[test]
+    fn test_without_line_number() {
+        assert_eq!(command_line("vim", None), "vim src/main.rs");
+        assert_eq!(command_line("code", None), "code src/main.rs");
+    }
+
+    #[test]
+    fn test_unknown_editor() {
+        assert_eq!(command_line("ed", Some(42)), "ed src/main.rs");
+    }
+
+    #[test]
+    fn test_default_application() {
+        let command_line = default_application_command_line("notes.pdf");
+        assert_eq!(command_line.last().unwrap(), "notes.pdf");
+        assert!(command_line.len() > 1);
+    }
+}
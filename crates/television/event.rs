@@ -45,6 +45,8 @@ pub enum Key {
     CtrlUp,
     CtrlDown,
     CtrlDelete,
+    ShiftLeft,
+    ShiftRight,
     AltSpace,
     AltEnter,
     AltBackspace,
@@ -86,6 +88,8 @@ impl Display for Key {
             Key::CtrlUp => write!(f, "Ctrl-Up"),
             Key::CtrlDown => write!(f, "Ctrl-Down"),
             Key::CtrlDelete => write!(f, "Ctrl-Del"),
+            Key::ShiftLeft => write!(f, "Shift-Left"),
+            Key::ShiftRight => write!(f, "Shift-Right"),
             Key::AltSpace => write!(f, "Alt-Space"),
             Key::AltEnter => write!(f, "Alt-Enter"),
             Key::AltBackspace => write!(f, "Alt-Backspace"),
@@ -246,11 +250,13 @@ pub fn convert_raw_event_to_key(event: KeyEvent) -> Key {
         Left => match event.modifiers {
             KeyModifiers::CONTROL => Key::CtrlLeft,
             KeyModifiers::ALT => Key::AltLeft,
+            KeyModifiers::SHIFT => Key::ShiftLeft,
             _ => Key::Left,
         },
         Right => match event.modifiers {
             KeyModifiers::CONTROL => Key::CtrlRight,
             KeyModifiers::ALT => Key::AltRight,
+            KeyModifiers::SHIFT => Key::ShiftRight,
             _ => Key::Right,
         },
         Home => Key::Home,
@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use tracing::debug;
+
+/// The name of the file, in the cache directory, holding the last queries.
+pub const LAST_QUERIES_FILE_NAME: &str = "last_queries.json";
+
+/// The last query used on each channel, persisted across runs so that a
+/// search can be resumed when reopening the same channel.
+///
+/// Queries are stored as a JSON object mapping channel names to queries.
+#[derive(Debug)]
+pub struct LastQueries {
+    path: PathBuf,
+}
+
+impl LastQueries {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        LastQueries { path: path.into() }
+    }
+
+    /// The last query used on the given channel, if any.
+    pub fn load(&self, channel: &str) -> Option<String> {
+        self.read().remove(channel)
+    }
+
+    /// Remember the query used on the given channel, an empty query
+    /// forgetting the previous one.
+    ///
+    /// Errors are only logged since losing the query isn't worth failing
+    /// on exit.
+    pub fn save(&self, channel: &str, query: &str) {
+        let mut queries = self.read();
+        if query.is_empty() {
+            queries.remove(channel);
+        } else {
+            queries.insert(channel.to_string(), query.to_string());
+        }
+        let result = serde_json::to_string(&queries)
+            .map_err(std::io::Error::from)
+            .and_then(|content| {
+                if let Some(parent) = self.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&self.path, content)
+            });
+        if let Err(e) = result {
+            debug!("Could not save the last query: {:?}", e);
+        }
+    }
+
+    fn read(&self) -> BTreeMap<String, String> {
+        fs::read(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_then_load() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-last-queries-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        let last_queries =
+            LastQueries::new(directory.join(LAST_QUERIES_FILE_NAME));
+        assert_eq!(last_queries.load("files"), None);
+
+        last_queries.save("files", "src main");
+        last_queries.save("env", "PATH");
+        // a new instance simulates restarting the application
+        let last_queries =
+            LastQueries::new(directory.join(LAST_QUERIES_FILE_NAME));
+        assert_eq!(last_queries.load("files").as_deref(), Some("src main"));
+        assert_eq!(last_queries.load("env").as_deref(), Some("PATH"));
+
+        last_queries.save("files", "");
+        assert_eq!(last_queries.load("files"), None);
+        assert_eq!(last_queries.load("env").as_deref(), Some("PATH"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,15 @@
+pub mod action;
+pub mod app;
+pub mod cli;
+pub mod config;
+pub mod editor;
+pub mod errors;
+pub mod event;
+pub mod last_queries;
+pub mod logging;
+pub mod output;
+pub mod picker;
+pub mod render;
+pub mod television;
+pub mod tui;
+pub mod ui;
@@ -1,28 +1,19 @@
 use std::io::{stdout, IsTerminal, Write};
 
 use clap::Parser;
-use cli::PostProcessedCli;
 use color_eyre::Result;
-use television_channels::channels::TelevisionChannel;
+use television::cli::{ParsedCliChannel, PostProcessedCli};
+use television_channels::channels::{CliTvChannel, TelevisionChannel};
 use tracing::{debug, info};
 
-use crate::app::App;
-use crate::cli::Cli;
+use television::app::App;
+use television::cli::Cli;
+use television::config::Config;
+use television::{editor, errors, logging, output};
+use television_channels::channels::files::Channel as FilesChannel;
 use television_channels::channels::stdin::Channel as StdinChannel;
 use television_utils::stdin::is_readable_stdin;
-
-pub mod action;
-pub mod app;
-pub mod cli;
-pub mod config;
-pub mod errors;
-pub mod event;
-pub mod logging;
-pub mod picker;
-pub mod render;
-pub mod television;
-pub mod tui;
-pub mod ui;
+use television_utils::strings::set_default_replacement_symbol;
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
@@ -33,14 +24,30 @@ async fn main() -> Result<()> {
 
     debug!("{:?}", args);
 
+    // channels preprocess their entries as soon as they start loading them
+    let config = Config::new()?;
+    set_default_replacement_symbol(config.ui.replacement_symbol);
+
     match App::new(
         {
             if is_readable_stdin() {
                 debug!("Using stdin channel");
-                TelevisionChannel::Stdin(StdinChannel::default())
+                TelevisionChannel::Stdin(StdinChannel::with_dedup(args.dedup))
+            } else if args.channel
+                == ParsedCliChannel::Builtin(CliTvChannel::Files)
+            {
+                debug!("Using files channel with {:?}", args.walk_options);
+                TelevisionChannel::Files(FilesChannel::with_options(
+                    vec![std::env::current_dir()?],
+                    args.walk_options,
+                ))
             } else {
                 debug!("Using {:?} channel", args.channel);
-                args.channel.to_channel()
+                let cable_channels = match &args.channel {
+                    ParsedCliChannel::Builtin(_) => Vec::new(),
+                    ParsedCliChannel::Cable(_) => config.cable_channels,
+                };
+                args.channel.to_channel(&cable_channels, args.dedup)?
             }
         },
         args.tick_rate,
@@ -48,14 +55,52 @@ async fn main() -> Result<()> {
         args.passthrough_keybindings,
     ) {
         Ok(mut app) => {
+            if args.no_preview {
+                app.disable_preview();
+            }
             stdout().flush()?;
             let output = app.run(stdout().is_terminal()).await?;
             info!("{:?}", output);
             if let Some(passthrough) = output.passthrough {
                 writeln!(stdout(), "{passthrough}")?;
             }
-            if let Some(entry) = output.selected_entry {
-                writeln!(stdout(), "{}", entry.stdout_repr())?;
+            let terminator = if args.print0 { b'\0' } else { b'\n' };
+            if args.print_query {
+                let entries = output.selected_entries.unwrap_or_default();
+                if args.json {
+                    writeln!(
+                        stdout(),
+                        "{}",
+                        output::query_and_entries_to_json(
+                            &output.query,
+                            &entries
+                        )?
+                    )?;
+                } else {
+                    output::write_query_and_entries(
+                        &mut stdout(),
+                        &output.query,
+                        &entries,
+                        terminator,
+                    )?;
+                }
+            } else if let Some(entries) = output.selected_entries {
+                if args.json {
+                    writeln!(
+                        stdout(),
+                        "{}",
+                        output::entries_to_json(&entries)?
+                    )?;
+                } else {
+                    output::write_entries(
+                        &mut stdout(),
+                        &entries,
+                        terminator,
+                    )?;
+                }
+            }
+            if let Some(entry) = output.entry_to_edit {
+                editor::open_in_editor(&entry)?;
             }
             Ok(())
         }
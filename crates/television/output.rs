@@ -0,0 +1,210 @@
+use std::io::Write;
+
+use serde::Serialize;
+use television_channels::entry::Entry;
+
+/// The machine-readable representation of a selected entry.
+#[derive(Debug, Serialize)]
+struct JsonEntry<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line_number: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+    value: Option<&'a str>,
+}
+
+impl<'a> From<&'a Entry> for JsonEntry<'a> {
+    fn from(entry: &'a Entry) -> Self {
+        Self {
+            name: &entry.name,
+            line_number: entry.line_number,
+            column: entry.column,
+            value: entry.value.as_deref(),
+        }
+    }
+}
+
+/// The prefix of the query record when the query is printed.
+pub const QUERY_MARKER: &str = "query\t";
+/// The prefix of entry records when the query is printed.
+pub const ENTRY_MARKER: &str = "entry\t";
+
+/// Serialize the selected entries to JSON.
+///
+/// The entries are always serialized as an array of objects, whether a
+/// single entry or several marked entries were selected, so that scripts
+/// don't need to handle both shapes.
+pub fn entries_to_json(entries: &[Entry]) -> serde_json::Result<String> {
+    let json_entries: Vec<JsonEntry> =
+        entries.iter().map(JsonEntry::from).collect();
+    serde_json::to_string(&json_entries)
+}
+
+/// Serialize the final query and the selected entries (if any) to JSON, as
+/// an object with `query` and `entries` fields.
+pub fn query_and_entries_to_json(
+    query: &str,
+    entries: &[Entry],
+) -> serde_json::Result<String> {
+    let entries: Vec<JsonEntry> =
+        entries.iter().map(JsonEntry::from).collect();
+    serde_json::to_string(&serde_json::json!({
+        "query": query,
+        "entries": entries,
+    }))
+}
+
+/// Write the final query to `writer`, then the selected entries, each
+/// record being followed by `terminator`.
+///
+/// The query comes first, prefixed with `QUERY_MARKER`, even if it's empty
+/// and whether or not entries were selected. Entries are prefixed with
+/// `ENTRY_MARKER`.
+pub fn write_query_and_entries<W: Write>(
+    writer: &mut W,
+    query: &str,
+    entries: &[Entry],
+    terminator: u8,
+) -> std::io::Result<()> {
+    writer.write_all(QUERY_MARKER.as_bytes())?;
+    writer.write_all(query.as_bytes())?;
+    writer.write_all(&[terminator])?;
+    for entry in entries {
+        writer.write_all(ENTRY_MARKER.as_bytes())?;
+        writer.write_all(entry.stdout_repr().as_bytes())?;
+        writer.write_all(&[terminator])?;
+    }
+    Ok(())
+}
+
+/// Write the selected entries to `writer`, each one followed by
+/// `terminator` (e.g. `b'\n'`, or `b'\0'` for `xargs -0`).
+pub fn write_entries<W: Write>(
+    writer: &mut W,
+    entries: &[Entry],
+    terminator: u8,
+) -> std::io::Result<()> {
+    for entry in entries {
+        writer.write_all(entry.stdout_repr().as_bytes())?;
+        writer.write_all(&[terminator])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use television_channels::entry::PreviewType;
+
+    fn parse(entries: &[Entry]) -> Value {
+        serde_json::from_str(&entries_to_json(entries).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_nul_terminated_entries() {
+        let entries = [
+            Entry::new("my file.txt".to_string(), PreviewType::Files),
+            Entry::new("new\nline.rs".to_string(), PreviewType::Files)
+                .with_line_number(3),
+            Entry::new("lib.rs".to_string(), PreviewType::Files)
+                .with_line_number(7)
+                .with_column(12),
+        ];
+        let mut output = Vec::new();
+        write_entries(&mut output, &entries, b'\0').unwrap();
+        assert_eq!(output, b"my file.txt\0new\nline.rs:3\0lib.rs:7:12\0");
+    }
+
+    #[test]
+    fn test_single_entry() {
+        let entry = Entry::new("src/main.rs".to_string(), PreviewType::Files)
+            .with_value("fn main() {}".to_string())
+            .with_line_number(42)
+            .with_column(4);
+        assert_eq!(
+            parse(&[entry]),
+            json!([{
+                "name": "src/main.rs",
+                "line_number": 42,
+                "column": 4,
+                "value": "fn main() {}",
+            }])
+        );
+    }
+
+    #[test]
+    fn test_single_marked_entry() {
+        // a single marked entry has the same shape as several ones
+        let entry = Entry::new("Cargo.toml".to_string(), PreviewType::Files);
+        assert_eq!(
+            parse(&[entry]),
+            json!([{ "name": "Cargo.toml", "value": null }])
+        );
+    }
+
+    #[test]
+    fn test_query_without_selection() {
+        let mut output = Vec::new();
+        write_query_and_entries(&mut output, "new file.rs", &[], b'\n')
+            .unwrap();
+        assert_eq!(output, b"query\tnew file.rs\n");
+
+        // an empty query is still printed
+        let mut output = Vec::new();
+        write_query_and_entries(&mut output, "", &[], b'\0').unwrap();
+        assert_eq!(output, b"query\t\0");
+
+        let json: Value = serde_json::from_str(
+            &query_and_entries_to_json("foo", &[]).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json, json!({ "query": "foo", "entries": [] }));
+    }
+
+    #[test]
+    fn test_query_with_selection() {
+        let entries = [
+            Entry::new("src/main.rs".to_string(), PreviewType::Files)
+                .with_line_number(3),
+            Entry::new("Cargo.toml".to_string(), PreviewType::Files),
+        ];
+        let mut output = Vec::new();
+        write_query_and_entries(&mut output, "ma", &entries, b'\n').unwrap();
+        assert_eq!(
+            output,
+            b"query\tma\nentry\tsrc/main.rs:3\nentry\tCargo.toml\n"
+        );
+
+        let json: Value = serde_json::from_str(
+            &query_and_entries_to_json("ma", &entries[..1]).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "query": "ma",
+                "entries": [
+                    { "name": "src/main.rs", "line_number": 3, "value": null },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_multiple_entries() {
+        let entries = [
+            Entry::new("HOME".to_string(), PreviewType::EnvVar)
+                .with_value("/home/tv".to_string()),
+            Entry::new("Cargo.toml".to_string(), PreviewType::Files),
+        ];
+        assert_eq!(
+            parse(&entries),
+            json!([
+                { "name": "HOME", "value": "/home/tv" },
+                { "name": "Cargo.toml", "value": null },
+            ])
+        );
+    }
+}
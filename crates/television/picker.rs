@@ -33,6 +33,10 @@ impl Picker {
         self
     }
 
+    pub(crate) fn set_inverted(&mut self, inverted: bool) {
+        self._inverted = inverted;
+    }
+
     pub(crate) fn reset_selection(&mut self) {
         self.state.select(Some(0));
         self.relative_state.select(Some(0));
@@ -47,6 +51,12 @@ impl Picker {
         self.state.selected()
     }
 
+    /// The offset of the first visible entry, as expected by channels,
+    /// clamped to `u32::MAX`.
+    pub(crate) fn offset(&self) -> u32 {
+        u32::try_from(self.view_offset).unwrap_or(u32::MAX)
+    }
+
     pub(crate) fn select(&mut self, index: Option<usize>) {
         self.state.select(index);
     }
@@ -113,3 +123,44 @@ impl Picker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_next_in_both_directions() {
+        // bottom to top: moving down goes towards the first result
+        let mut picker = Picker::default();
+        picker.reset_selection();
+        picker.select_next(10, 7);
+        assert_eq!(picker.selected(), Some(9));
+        assert_eq!(picker.view_offset, 5);
+        picker.select_next(10, 7);
+        assert_eq!(picker.selected(), Some(8));
+
+        // top to bottom: moving down goes towards the last result
+        let mut picker = Picker::default();
+        picker.set_inverted(true);
+        picker.reset_selection();
+        for _ in 0..6 {
+            picker.select_next(10, 7);
+        }
+        assert_eq!(picker.selected(), Some(6));
+        assert_eq!(picker.relative_selected(), Some(4));
+        assert_eq!(picker.view_offset, 2);
+        picker.select_prev(10, 7);
+        assert_eq!(picker.selected(), Some(5));
+    }
+
+    #[test]
+    fn test_offset_is_clamped() {
+        let mut picker = Picker {
+            view_offset: 12,
+            ..Picker::default()
+        };
+        assert_eq!(picker.offset(), 12);
+        picker.view_offset = usize::MAX;
+        assert_eq!(picker.offset(), u32::MAX);
+    }
+}
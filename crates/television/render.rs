@@ -1,9 +1,16 @@
 use color_eyre::Result;
+use crossterm::{
+    cursor::{MoveTo, RestorePosition, SavePosition},
+    queue,
+    style::Print,
+};
 use ratatui::layout::Rect;
 use std::{
-    io::{stderr, stdout, LineWriter},
+    io::{stderr, stdout, LineWriter, Write},
     sync::Arc,
 };
+use television_previewers::previewers::image::KITTY_DELETE_IMAGES;
+use television_previewers::previewers::ImageProtocol;
 use tracing::{debug, warn};
 
 use tokio::{
@@ -12,6 +19,7 @@ use tokio::{
 };
 
 use crate::television::Television;
+use crate::ui::preview::ImagePlacement;
 use crate::{action::Action, config::Config, tui::Tui};
 
 #[derive(Debug)]
@@ -30,6 +38,58 @@ enum IoStream {
     BufferedStderr,
 }
 
+/// Draw a frame of the television, reporting errors to the app.
+fn draw<W: Write>(
+    tui: &mut Tui<W>,
+    television: &mut Television,
+    action_tx: &mpsc::UnboundedSender<Action>,
+) -> Result<()> {
+    tui.terminal.draw(|frame| {
+        if let Err(err) = television.draw(frame, frame.area()) {
+            warn!("Failed to draw: {:?}", err);
+            let _ = action_tx
+                .send(Action::Error(format!("Failed to draw: {err:?}")));
+        }
+    })?;
+    Ok(())
+}
+
+/// Draw the image placed by the last frame with its graphics protocol,
+/// replacing the one drawn before if it changed.
+///
+/// Graphics aren't part of ratatui's buffer, so getting rid of an image
+/// means deleting it (Kitty) or redrawing every cell over it.
+fn draw_image<W: Write>(
+    tui: &mut Tui<W>,
+    television: &mut Television,
+    action_tx: &mpsc::UnboundedSender<Action>,
+    drawn: &mut Option<ImagePlacement>,
+) -> Result<()> {
+    if *drawn == television.image_placement {
+        return Ok(());
+    }
+    if let Some(previous) = drawn.take() {
+        if previous.protocol == ImageProtocol::Kitty {
+            queue!(tui.terminal.backend_mut(), Print(KITTY_DELETE_IMAGES))?;
+        }
+        tui.terminal.clear()?;
+        draw(tui, television, action_tx)?;
+    }
+    if let Some(placement) = &television.image_placement {
+        let backend = tui.terminal.backend_mut();
+        queue!(
+            backend,
+            SavePosition,
+            MoveTo(placement.area.x, placement.area.y),
+            Print(&placement.sequence),
+            RestorePosition
+        )?;
+        backend.flush()?;
+    }
+    drawn.clone_from(&television.image_placement);
+    Ok(())
+}
+
 impl IoStream {
     fn to_stream(&self) -> Box<dyn std::io::Write + Send> {
         match self {
@@ -69,6 +129,9 @@ pub async fn render(
         .await
         .register_config_handler(config.clone())?;
 
+    // the image drawn with a graphics protocol, if any
+    let mut drawn_image = None;
+
     // Rendering loop
     loop {
         select! {
@@ -80,6 +143,7 @@ pub async fn render(
                     match task {
                         RenderingTask::ClearScreen => {
                             tui.terminal.clear()?;
+                            drawn_image = None;
                         }
                         RenderingTask::Render => {
                             let mut television = television.lock().await;
@@ -89,14 +153,8 @@ pub async fn render(
                                 // buffer with a `u16` index which means we can't support
                                 // terminal areas larger than `u16::MAX`.
                                 if size.width.checked_mul(size.height).is_some() {
-                                    tui.terminal.draw(|frame| {
-                                        if let Err(err) = television.draw(frame, frame.area()) {
-                                            warn!("Failed to draw: {:?}", err);
-                                            let _ = action_tx
-                                                .send(Action::Error(format!("Failed to draw: {err:?}")));
-                                        }
-                                    })?;
-
+                                    draw(&mut tui, &mut television, &action_tx)?;
+                                    draw_image(&mut tui, &mut television, &action_tx, &mut drawn_image)?;
                                 } else {
                                     warn!("Terminal area too large");
                                 }
@@ -104,6 +162,7 @@ pub async fn render(
                         }
                         RenderingTask::Resize(w, h) => {
                             tui.resize(Rect::new(0, 0, w, h))?;
+                            drawn_image = None;
                             action_tx.send(Action::Render)?;
                         }
                         RenderingTask::Suspend => {
@@ -114,6 +173,7 @@ pub async fn render(
                         }
                         RenderingTask::Resume => {
                             tui.enter()?;
+                            drawn_image = None;
                         }
                         RenderingTask::Quit => {
                             tui.exit()?;
@@ -1,25 +1,40 @@
 use crate::app::Keymap;
 use crate::picker::Picker;
 use crate::ui::input::actions::InputActionHandler;
+use crate::ui::input::InputRequest;
 use crate::ui::layout::{Dimensions, Layout};
+use crate::ui::preview::{max_line_width, CachedImage, ImagePlacement};
+use crate::ui::results::ResultsListColors;
 use crate::ui::spinner::Spinner;
 use crate::ui::spinner::SpinnerState;
-use crate::{action::Action, config::Config};
+use crate::ui::{panel_block, BORDER_COLOR};
+use crate::{
+    action::Action,
+    config::{Config, PreviewBackground, ResultsOrder},
+};
 use color_eyre::Result;
 use copypasta::{ClipboardContext, ClipboardProvider};
 use futures::executor::block_on;
-use ratatui::{layout::Rect, style::Color, widgets::Paragraph, Frame};
+use indexmap::IndexMap;
+use ratatui::{
+    layout::Rect,
+    style::Color,
+    widgets::{Block, Paragraph},
+    Frame,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use strum::Display;
 use television_channels::channels::{
-    remote_control::RemoteControl, OnAir, TelevisionChannel, UnitChannel,
+    remote_control::RemoteControl, OnAir, TelevisionChannel,
 };
-use television_channels::entry::{Entry, ENTRY_PLACEHOLDER};
-use television_previewers::previewers;
-use television_previewers::previewers::Previewer;
-use television_utils::strings::EMPTY_STRING;
+use television_channels::entry::{Entry, PreviewType, ENTRY_PLACEHOLDER};
+use television_fuzzy::matcher::config::CaseSensitivity;
+use television_previewers::previewers::{ImageProtocol, Previewer};
+use television_utils::strings::{max_line_length_for_width, EMPTY_STRING};
 use tokio::sync::mpsc::UnboundedSender;
+use tracing::warn;
 
 #[derive(
     PartialEq, Copy, Clone, Hash, Eq, Debug, Serialize, Deserialize, Display,
@@ -38,13 +53,37 @@ pub struct Television {
     pub(crate) remote_control: TelevisionChannel,
     pub mode: Mode,
     current_pattern: String,
+    /// When the input last changed, if the query hasn't been run since.
+    pending_query: Option<Instant>,
     pub(crate) results_picker: Picker,
     pub(crate) rc_picker: Picker,
+    /// The entries marked by the user, keyed by their `stdout_repr`.
+    pub(crate) marked_entries: IndexMap<String, Entry>,
+    pub(crate) results_list_colors: ResultsListColors,
+    /// The color of the borders of panels.
+    border_color: Color,
+    /// The background of the preview pane, the terminal's if `None`.
+    preview_background: Option<PreviewBackground>,
     results_area_height: u32,
-    pub previewer: Previewer,
+    /// The previewer, only loaded once an entry is first previewed since
+    /// loading it is costly.
+    pub previewer: Option<Previewer>,
     pub preview_scroll: Option<u16>,
+    /// The number of columns the preview content is scrolled by.
+    pub preview_horizontal_scroll: u16,
     pub preview_pane_height: u16,
+    /// Whether the preview pane is shown.
+    pub(crate) preview_visible: bool,
+    /// The name of the last selected syntax theme and when it was selected.
+    pub(crate) theme_notification: Option<(String, Instant)>,
+    /// The number of columns available to the preview content.
+    pub(crate) preview_content_width: u16,
     current_preview_total_lines: u16,
+    /// Whether to scroll to the bottom of the preview once it is fully
+    /// loaded.
+    scroll_preview_to_bottom_once_loaded: bool,
+    /// The width of the longest line of the current preview.
+    pub(crate) current_preview_max_line_width: u16,
     /// A cache for meta paragraphs (i.e. previews like "Not Supported", etc.).
     ///
     /// The key is a tuple of the preview name and the dimensions of the
@@ -52,8 +91,39 @@ pub struct Television {
     /// are rendered correctly even when resizing the terminal while still
     /// benefiting from a cache mechanism.
     pub meta_paragraph_cache: HashMap<(String, u16, u16), Paragraph<'static>>,
+    /// How image previews are drawn.
+    pub(crate) image_protocol: ImageProtocol,
+    /// The image drawn with a graphics protocol in the last frame, which
+    /// has to be written to the terminal once the frame is drawn.
+    pub image_placement: Option<ImagePlacement>,
+    /// The rendering of the last image previewed, for the area it was
+    /// rendered in.
+    pub(crate) image_cache: Option<CachedImage>,
     pub(crate) spinner: Spinner,
     pub(crate) spinner_state: SpinnerState,
+    /// How letter case is taken into account when matching.
+    pub(crate) case_sensitivity: CaseSensitivity,
+    /// Where pasted text is read from (i.e. the system clipboard).
+    read_clipboard: fn() -> Option<String>,
+    /// Where copied text is written to (i.e. the system clipboard).
+    write_clipboard: fn(String),
+}
+
+/// Read the contents of the system clipboard.
+fn read_system_clipboard() -> Option<String> {
+    ClipboardContext::new()
+        .and_then(|mut ctx| ctx.get_contents())
+        .map_err(|e| warn!("Failed to read the clipboard: {}", e))
+        .ok()
+}
+
+/// Write the given text to the system clipboard.
+fn write_system_clipboard(text: String) {
+    if let Err(e) =
+        ClipboardContext::new().and_then(|mut ctx| ctx.set_contents(text))
+    {
+        warn!("Failed to write to the clipboard: {}", e);
+    }
 }
 
 impl Television {
@@ -71,21 +141,88 @@ impl Television {
             ),
             mode: Mode::Channel,
             current_pattern: EMPTY_STRING.to_string(),
+            pending_query: None,
             results_picker: Picker::default(),
             rc_picker: Picker::default().inverted(),
+            marked_entries: IndexMap::new(),
+            results_list_colors: ResultsListColors::default(),
+            border_color: BORDER_COLOR,
+            preview_background: None,
             results_area_height: 0,
-            previewer: Previewer::default(),
+            previewer: None,
             preview_scroll: None,
+            preview_horizontal_scroll: 0,
             preview_pane_height: 0,
+            preview_visible: true,
+            theme_notification: None,
+            preview_content_width: 0,
             current_preview_total_lines: 0,
+            scroll_preview_to_bottom_once_loaded: false,
+            current_preview_max_line_width: 0,
             meta_paragraph_cache: HashMap::new(),
+            image_protocol: ImageProtocol::Halfblocks,
+            image_placement: None,
+            image_cache: None,
             spinner,
             spinner_state: SpinnerState::from(&spinner),
+            case_sensitivity: CaseSensitivity::default(),
+            read_clipboard: read_system_clipboard,
+            write_clipboard: write_system_clipboard,
         }
     }
 
-    pub fn current_channel(&self) -> UnitChannel {
-        UnitChannel::from(&self.channel)
+    /// The block surrounding a panel, with the configured borders.
+    pub(crate) fn panel_block(&self) -> Block<'static> {
+        panel_block(self.config.ui.border_style, self.border_color)
+    }
+
+    /// The query typed in the input of the channel.
+    pub fn channel_query(&self) -> &str {
+        self.results_picker.input.value()
+    }
+
+    /// Pre-fill the input of the channel with the given query and run it.
+    pub fn set_channel_query(&mut self, query: &str) {
+        self.results_picker.reset_input();
+        for c in query.chars() {
+            self.results_picker
+                .input
+                .handle(InputRequest::InsertChar(c));
+        }
+        if self.mode == Mode::Channel {
+            self.run_query();
+        }
+    }
+
+    /// Feed the entries matching the current pattern to the given channel
+    /// and switch to it.
+    ///
+    /// Returns an error action if the current channel's entries can't be
+    /// sent to a channel of that name.
+    fn pipe_to_channel(&mut self, target: &str) -> Option<Action> {
+        let normalize =
+            |name: &str| name.replace(['-', '_'], "").to_lowercase();
+        let Some(target_channel) = self
+            .channel
+            .available_transitions()
+            .into_iter()
+            .find(|channel| {
+                normalize(&channel.to_string()) == normalize(target)
+            })
+        else {
+            let message = format!(
+                "Cannot pipe the results of the {} channel to {:?}",
+                self.channel.name(),
+                target
+            );
+            warn!("{}", message);
+            return Some(Action::Error(message));
+        };
+        let new_channel = self.channel.transition_to(target_channel);
+        self.reset_picker_selection();
+        self.reset_picker_input();
+        self.change_channel(new_channel);
+        None
     }
 
     pub fn change_channel(&mut self, channel: TelevisionChannel) {
@@ -93,8 +230,52 @@ impl Television {
         self.reset_picker_selection();
         self.reset_picker_input();
         self.current_pattern = EMPTY_STRING.to_string();
+        self.pending_query = None;
+        self.marked_entries.clear();
         self.channel.shutdown();
         self.channel = channel;
+        self.channel.set_case_sensitivity(self.case_sensitivity);
+    }
+
+    /// Re-run the query once the input has stopped changing for
+    /// `query_debounce_ms`, so that bursts of keystrokes only trigger a
+    /// single match pass.
+    fn refresh_pattern(&mut self) {
+        if self.config.ui.query_debounce_ms == 0 {
+            self.run_query();
+        } else {
+            self.pending_query = Some(Instant::now());
+        }
+    }
+
+    /// Run the pending query if the input hasn't changed for
+    /// `query_debounce_ms` as of `now`.
+    fn run_debounced_query(&mut self, now: Instant) {
+        let debounce = Duration::from_millis(self.config.ui.query_debounce_ms);
+        if self
+            .pending_query
+            .is_some_and(|since| now.duration_since(since) >= debounce)
+        {
+            self.run_query();
+        }
+    }
+
+    /// Re-run the query if the input of the current mode has changed.
+    fn run_query(&mut self) {
+        self.pending_query = None;
+        let new_pattern = match self.mode {
+            Mode::Channel => self.results_picker.input.value(),
+            Mode::RemoteControl | Mode::SendToChannel => {
+                self.rc_picker.input.value()
+            }
+        }
+        .to_string();
+        if new_pattern != self.current_pattern {
+            self.current_pattern.clone_from(&new_pattern);
+            self.find(&new_pattern);
+            self.reset_picker_selection();
+            self.reset_preview_scroll();
+        }
     }
 
     fn find(&mut self, pattern: &str) {
@@ -122,6 +303,47 @@ impl Television {
         }
     }
 
+    /// Get the entries to act upon: the marked entries if there are any,
+    /// otherwise the entry under the cursor.
+    #[must_use]
+    pub fn get_selected_entries(
+        &mut self,
+        mode: Option<Mode>,
+    ) -> Option<Vec<Entry>> {
+        let mode = mode.unwrap_or(self.mode);
+        if mode == Mode::Channel && !self.marked_entries.is_empty() {
+            return Some(self.marked_entries.values().cloned().collect());
+        }
+        self.get_selected_entry(Some(mode)).map(|entry| vec![entry])
+    }
+
+    /// Mark the entry under the cursor, or unmark it if it was already
+    /// marked.
+    pub fn toggle_selection(&mut self) {
+        if let Some(entry) = self.get_selected_entry(Some(Mode::Channel)) {
+            let key = entry.stdout_repr();
+            if self.marked_entries.shift_remove(&key).is_none() {
+                self.marked_entries.insert(key, entry);
+            }
+        }
+    }
+
+    /// Mark all the entries matching the current pattern.
+    pub fn select_all(&mut self) {
+        let entries = self.channel.results(self.channel.result_count(), 0);
+        for entry in entries {
+            self.marked_entries.insert(entry.stdout_repr(), entry);
+        }
+    }
+
+    /// Unmark all the entries matching the current pattern.
+    pub fn deselect_all(&mut self) {
+        let entries = self.channel.results(self.channel.result_count(), 0);
+        for entry in entries {
+            self.marked_entries.shift_remove(&entry.stdout_repr());
+        }
+    }
+
     pub fn select_prev_entry(&mut self) {
         let (result_count, picker) = match self.mode {
             Mode::Channel => {
@@ -160,6 +382,8 @@ impl Television {
 
     fn reset_preview_scroll(&mut self) {
         self.preview_scroll = None;
+        self.preview_horizontal_scroll = 0;
+        self.scroll_preview_to_bottom_once_loaded = false;
     }
 
     fn reset_picker_selection(&mut self) {
@@ -180,25 +404,117 @@ impl Television {
         }
     }
 
+    /// The maximum scroll offset of the current preview.
+    fn max_preview_scroll(&self) -> u16 {
+        self.current_preview_total_lines
+            .saturating_sub(2 * self.preview_pane_height / 3)
+    }
+
     pub fn scroll_preview_down(&mut self, offset: u16) {
         if self.preview_scroll.is_none() {
             self.preview_scroll = Some(0);
         }
         if let Some(scroll) = self.preview_scroll {
-            self.preview_scroll = Some(
-                (scroll + offset).min(
-                    self.current_preview_total_lines
-                        .saturating_sub(2 * self.preview_pane_height / 3),
-                ),
-            );
+            let max_scroll = self.max_preview_scroll();
+            self.preview_scroll = Some((scroll + offset).min(max_scroll));
+            // we've reached the end of what's loaded, try to load more
+            if scroll + offset >= max_scroll && self.preview_visible {
+                if let Some(entry) = self.get_selected_entry(None) {
+                    let entry = self.with_preview_command(entry);
+                    block_on(self.previewer().load_more(&entry));
+                }
+            }
+        }
+    }
+
+    /// The background color of the preview pane, if it isn't the
+    /// terminal's.
+    pub(crate) fn preview_background_color(&mut self) -> Option<Color> {
+        match self.preview_background? {
+            PreviewBackground::Color(color) => Some(color),
+            PreviewBackground::Theme => self
+                .previewer()
+                .theme_background()
+                .map(|c| Color::Rgb(c.r, c.g, c.b)),
         }
     }
 
+    /// The previewer, loaded with the current configuration if needed.
+    fn previewer(&mut self) -> &mut Previewer {
+        self.previewer.get_or_insert_with(|| {
+            Previewer::new(Some(self.config.previewers.clone().into()))
+        })
+    }
+
+    /// Use the preview command configured for the current channel, if any,
+    /// to preview the given entry.
+    fn with_preview_command(&self, mut entry: Entry) -> Entry {
+        if entry.name.is_empty() {
+            return entry;
+        }
+        if let Some(command) = self
+            .config
+            .previewers
+            .command
+            .for_channel(&self.channel.name())
+        {
+            entry.preview_type = PreviewType::Command(command.to_string());
+        }
+        entry
+    }
+
     pub fn scroll_preview_up(&mut self, offset: u16) {
         if let Some(scroll) = self.preview_scroll {
             self.preview_scroll = Some(scroll.saturating_sub(offset));
         }
     }
+
+    pub fn scroll_preview_left(&mut self, offset: u16) {
+        self.preview_horizontal_scroll =
+            self.preview_horizontal_scroll.saturating_sub(offset);
+    }
+
+    pub fn scroll_preview_right(&mut self, offset: u16) {
+        self.preview_horizontal_scroll = clamp_horizontal_scroll(
+            self.preview_horizontal_scroll.saturating_add(offset),
+            self.current_preview_max_line_width,
+            self.preview_content_width,
+        );
+    }
+
+    pub fn scroll_preview_to_top(&mut self) {
+        if self.current_preview_total_lines > 0 {
+            self.preview_scroll = Some(0);
+        }
+    }
+
+    /// Scroll to the bottom of the preview.
+    ///
+    /// The remaining lines of partial previews are loaded first, the preview
+    /// is scrolled to the bottom again once they are.
+    pub fn scroll_preview_to_bottom(&mut self) {
+        if self.current_preview_total_lines == 0 {
+            return;
+        }
+        self.preview_scroll = Some(self.max_preview_scroll());
+        if self.preview_visible {
+            if let Some(entry) = self.get_selected_entry(None) {
+                let entry = self.with_preview_command(entry);
+                block_on(self.previewer().load_more(&entry));
+                self.scroll_preview_to_bottom_once_loaded = true;
+            }
+        }
+    }
+}
+
+/// Clamp a horizontal scroll offset so that the longest line of the preview
+/// can't be scrolled past.
+pub(crate) fn clamp_horizontal_scroll(
+    offset: u16,
+    max_line_width: u16,
+    visible_width: u16,
+) -> u16 {
+    offset.min(max_line_width.saturating_sub(visible_width))
 }
 
 // Styles
@@ -232,11 +548,25 @@ impl Television {
     pub fn register_config_handler(&mut self, config: Config) -> Result<()> {
         self.config = config;
         self.keymap = Keymap::from(&self.config.keybindings);
-        let previewer_config =
-            std::convert::Into::<previewers::PreviewerConfig>::into(
-                self.config.previewers.clone(),
-            );
-        self.previewer.set_config(previewer_config);
+        self.results_list_colors = self.config.ui.results_list_colors();
+        self.border_color = self.config.ui.border_color();
+        self.preview_background = self.config.ui.preview_background();
+        // moving "down" means going towards the start of the results when
+        // they're displayed bottom to top, and towards the end otherwise
+        self.results_picker.set_inverted(
+            self.config.ui.results_order == ResultsOrder::TopToBottom,
+        );
+        self.preview_visible = self.config.ui.show_preview;
+        // images are described instead when disabled
+        self.image_protocol = self
+            .config
+            .previewers
+            .file
+            .image_protocol()
+            .unwrap_or(ImageProtocol::Halfblocks);
+        self.image_cache = None;
+        // loaded again with the new configuration on the next preview
+        self.previewer = None;
         Ok(())
     }
 
@@ -248,11 +578,35 @@ impl Television {
     /// # Returns
     /// * `Result<Option<Action>>` - An action to be processed or none.
     pub async fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        // any other action (e.g. switching modes) should apply to the
+        // latest input
+        if self.pending_query.is_some()
+            && !matches!(
+                action,
+                Action::Tick
+                    | Action::Render
+                    | Action::AddInputChar(_)
+                    | Action::DeletePrevChar
+                    | Action::DeletePrevWord
+                    | Action::DeleteNextChar
+                    | Action::ClearInput
+                    | Action::GoToInputEnd
+                    | Action::GoToInputStart
+                    | Action::GoToNextChar
+                    | Action::GoToPrevChar
+                    | Action::PasteFromClipboard
+            )
+        {
+            self.run_query();
+        }
         match action {
+            Action::Tick => self.run_debounced_query(Instant::now()),
             // handle input actions
             Action::AddInputChar(_)
             | Action::DeletePrevChar
+            | Action::DeletePrevWord
             | Action::DeleteNextChar
+            | Action::ClearInput
             | Action::GoToInputEnd
             | Action::GoToInputStart
             | Action::GoToNextChar
@@ -264,19 +618,30 @@ impl Television {
                     }
                 };
                 input.handle_action(&action);
-                match action {
+                if matches!(
+                    action,
                     Action::AddInputChar(_)
-                    | Action::DeletePrevChar
-                    | Action::DeleteNextChar => {
-                        let new_pattern = input.value().to_string();
-                        if new_pattern != self.current_pattern {
-                            self.current_pattern.clone_from(&new_pattern);
-                            self.find(&new_pattern);
-                            self.reset_picker_selection();
-                            self.reset_preview_scroll();
+                        | Action::DeletePrevChar
+                        | Action::DeletePrevWord
+                        | Action::DeleteNextChar
+                        | Action::ClearInput
+                ) {
+                    self.refresh_pattern();
+                }
+            }
+            Action::PasteFromClipboard => {
+                if let Some(text) = (self.read_clipboard)() {
+                    let input = match self.mode {
+                        Mode::Channel => &mut self.results_picker.input,
+                        Mode::RemoteControl | Mode::SendToChannel => {
+                            &mut self.rc_picker.input
                         }
+                    };
+                    for c in text.chars().filter(|c| !matches!(c, '\n' | '\r'))
+                    {
+                        input.handle(InputRequest::InsertChar(c));
                     }
-                    _ => {}
+                    self.refresh_pattern();
                 }
             }
             Action::SelectNextEntry => {
@@ -291,6 +656,16 @@ impl Television {
             Action::ScrollPreviewUp => self.scroll_preview_up(1),
             Action::ScrollPreviewHalfPageDown => self.scroll_preview_down(20),
             Action::ScrollPreviewHalfPageUp => self.scroll_preview_up(20),
+            Action::ScrollPreviewLeft => self.scroll_preview_left(1),
+            Action::ScrollPreviewRight => self.scroll_preview_right(1),
+            Action::ScrollPreviewHalfPageLeft => {
+                self.scroll_preview_left(self.preview_content_width / 2);
+            }
+            Action::ScrollPreviewHalfPageRight => {
+                self.scroll_preview_right(self.preview_content_width / 2);
+            }
+            Action::ScrollPreviewToTop => self.scroll_preview_to_top(),
+            Action::ScrollPreviewToBottom => self.scroll_preview_to_bottom(),
             Action::ToggleRemoteControl => match self.mode {
                 Mode::Channel => {
                     self.remote_control = TelevisionChannel::RemoteControl(
@@ -338,14 +713,50 @@ impl Television {
                     }
                 }
             }
-            Action::CopyEntryToClipboard => {
+            Action::PipeToChannel(target) => {
                 if self.mode == Mode::Channel {
-                    if let Some(entry) = self.get_selected_entry(None) {
-                        let mut ctx = ClipboardContext::new().unwrap();
-                        ctx.set_contents(entry.name).unwrap();
+                    return Ok(self.pipe_to_channel(&target));
+                }
+            }
+            Action::CopyEntryName | Action::CopyEntryValue => {
+                if self.mode == Mode::Channel {
+                    if let Some(entries) = self.get_selected_entries(None) {
+                        let copy_value = action == Action::CopyEntryValue;
+                        (self.write_clipboard)(
+                            entries
+                                .into_iter()
+                                .map(|e| match e.value {
+                                    Some(value) if copy_value => value,
+                                    _ => e.name,
+                                })
+                                .collect::<Vec<_>>()
+                                .join("\n"),
+                        );
                     }
                 }
             }
+            Action::ToggleSelection => {
+                if self.mode == Mode::Channel {
+                    self.toggle_selection();
+                    self.reset_preview_scroll();
+                    self.select_next_entry();
+                }
+            }
+            Action::SelectAll => {
+                if self.mode == Mode::Channel {
+                    self.select_all();
+                }
+            }
+            Action::DeselectAll => {
+                if self.mode == Mode::Channel {
+                    self.deselect_all();
+                }
+            }
+            Action::ClearSelection => {
+                if self.mode == Mode::Channel {
+                    self.marked_entries.clear();
+                }
+            }
             Action::ToggleSendToChannel => match self.mode {
                 Mode::Channel | Mode::RemoteControl => {
                     self.mode = Mode::SendToChannel;
@@ -363,6 +774,30 @@ impl Television {
             Action::ToggleHelp => {
                 self.config.ui.show_help_bar = !self.config.ui.show_help_bar;
             }
+            Action::CycleTheme if self.config.ui.show_preview => {
+                let theme = self.previewer().cycle_theme();
+                self.theme_notification = Some((theme, Instant::now()));
+            }
+            Action::TogglePreview if self.config.ui.show_preview => {
+                self.preview_visible = !self.preview_visible;
+            }
+            Action::Reload => {
+                if self.mode == Mode::Channel {
+                    // the selection is kept as is so that the selected entry
+                    // stays the same if the results didn't change
+                    self.channel.reload();
+                    self.previewer().clear_command_previews();
+                    self.reset_preview_scroll();
+                }
+            }
+            Action::ToggleCaseSensitivity => {
+                self.case_sensitivity = self.case_sensitivity.next();
+                self.channel.set_case_sensitivity(self.case_sensitivity);
+                self.remote_control
+                    .set_case_sensitivity(self.case_sensitivity);
+                self.reset_picker_selection();
+                self.reset_preview_scroll();
+            }
             _ => {}
         }
         Ok(None)
@@ -382,13 +817,20 @@ impl Television {
             area,
             !matches!(self.mode, Mode::Channel),
             self.config.ui.show_help_bar,
+            self.preview_visible,
+            self.config.ui.preview_size,
+            self.config.ui.layout,
+            self.config.ui.input_position,
         );
 
+        self.image_placement = None;
+
         // help bar (metadata, keymaps, logo)
         self.draw_help_bar(f, &layout)?;
 
         self.results_area_height = u32::from(layout.results.height);
-        self.preview_pane_height = layout.preview_window.height;
+        self.preview_pane_height =
+            layout.preview_window.map_or(0, |area| area.height);
 
         // top left block: results
         self.draw_results_list(f, &layout)?;
@@ -396,22 +838,51 @@ impl Television {
         // bottom left block: input
         self.draw_input_box(f, &layout)?;
 
-        let selected_entry = self
-            .get_selected_entry(Some(Mode::Channel))
-            .unwrap_or(ENTRY_PLACEHOLDER);
-        let preview = block_on(self.previewer.preview(&selected_entry));
+        if let (Some(title_area), Some(content_area)) =
+            (layout.preview_title, layout.preview_window)
+        {
+            let selected_entry = self
+                .get_selected_entry(Some(Mode::Channel))
+                .unwrap_or(ENTRY_PLACEHOLDER);
+            let selected_entry = self.with_preview_command(selected_entry);
+            self.previewer()
+                .set_max_line_length(max_line_length_for_width(usize::from(
+                    content_area.width,
+                )));
+            let preview = block_on(self.previewer().preview(&selected_entry));
+            // load the preview up to the matched line so it can be scrolled to
+            if preview.partial
+                && selected_entry
+                    .line_number
+                    .map_or(false, |line| line > preview.total_lines())
+            {
+                block_on(self.previewer().load_more(&selected_entry));
+            }
 
-        // top right block: preview title
-        self.current_preview_total_lines = preview.total_lines();
-        self.draw_preview_title_block(f, &layout, &selected_entry, &preview)?;
+            // top right block: preview title
+            // the preview can't be scrolled past the first `u16::MAX` lines
+            self.current_preview_total_lines =
+                u16::try_from(preview.total_lines()).unwrap_or(u16::MAX);
+            if self.scroll_preview_to_bottom_once_loaded && !preview.partial {
+                self.scroll_preview_to_bottom_once_loaded = false;
+                self.preview_scroll = Some(self.max_preview_scroll());
+            }
+            self.current_preview_max_line_width = max_line_width(&preview);
+            self.draw_preview_title_block(
+                f,
+                title_area,
+                &selected_entry,
+                &preview,
+            )?;
 
-        // bottom right block: preview content
-        self.draw_preview_content_block(
-            f,
-            &layout,
-            &selected_entry,
-            &preview,
-        )?;
+            // bottom right block: preview content
+            self.draw_preview_content_block(
+                f,
+                content_area,
+                &selected_entry,
+                &preview,
+            )?;
+        }
 
         // remote control
         if matches!(self.mode, Mode::RemoteControl | Mode::SendToChannel) {
@@ -420,3 +891,312 @@ impl Television {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use television_channels::channels::files::Channel as FilesChannel;
+    use television_channels::channels::CliTvChannel;
+
+    #[test]
+    fn test_clamp_horizontal_scroll() {
+        assert_eq!(clamp_horizontal_scroll(10, 100, 40), 10);
+        assert_eq!(clamp_horizontal_scroll(60, 100, 40), 60);
+        assert_eq!(clamp_horizontal_scroll(61, 100, 40), 60);
+        assert_eq!(clamp_horizontal_scroll(u16::MAX, 100, 40), 60);
+        // everything fits, no scrolling
+        assert_eq!(clamp_horizontal_scroll(5, 30, 40), 0);
+        assert_eq!(clamp_horizontal_scroll(0, 0, 0), 0);
+    }
+
+    /// A television on the env channel, running queries on every keystroke.
+    fn env_television() -> Television {
+        let mut television = Television::new(CliTvChannel::Env.to_channel());
+        television.config.ui.query_debounce_ms = 0;
+        television
+    }
+
+    /// Let the channel's matcher catch up with the latest pattern.
+    fn wait_for_matcher(channel: &mut TelevisionChannel) {
+        for _ in 0..200 {
+            channel.results(1, 0);
+            if !channel.running() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_clear_input() {
+        let mut television = env_television();
+        for c in "zz-no-such-variable-zz".chars() {
+            television.update(Action::AddInputChar(c)).await.unwrap();
+        }
+        wait_for_matcher(&mut television.channel);
+        assert_eq!(television.channel.result_count(), 0);
+        television.results_picker.select(Some(3));
+
+        television.update(Action::ClearInput).await.unwrap();
+
+        assert_eq!(television.results_picker.input.value(), "");
+        assert_eq!(television.results_picker.input.cursor(), 0);
+        assert_eq!(television.current_pattern, "");
+        assert_eq!(television.results_picker.selected(), Some(0));
+        wait_for_matcher(&mut television.channel);
+        assert_eq!(
+            television.channel.result_count(),
+            television.channel.total_count()
+        );
+    }
+
+    async fn type_pattern(television: &mut Television, pattern: &str) {
+        television.update(Action::ClearInput).await.unwrap();
+        for c in pattern.chars() {
+            television.update(Action::AddInputChar(c)).await.unwrap();
+        }
+        wait_for_matcher(&mut television.channel);
+    }
+
+    #[tokio::test]
+    async fn test_select_and_deselect_all() {
+        let mut television = env_television();
+        wait_for_matcher(&mut television.channel);
+        let total_count = television.channel.total_count() as usize;
+        let name = television.channel.results(1, 0)[0].name.clone();
+
+        // the full matched set is marked, not just the visible entries
+        television.update(Action::SelectAll).await.unwrap();
+        assert_eq!(television.marked_entries.len(), total_count);
+
+        type_pattern(&mut television, &name).await;
+        let matching = television.channel.result_count() as usize;
+        assert!(matching >= 1 && matching <= total_count);
+        television.update(Action::DeselectAll).await.unwrap();
+        assert_eq!(television.marked_entries.len(), total_count - matching);
+        assert!(!television.marked_entries.contains_key(&name));
+
+        // selecting all with a narrower query only marks the narrower set
+        television.update(Action::ClearSelection).await.unwrap();
+        television.update(Action::SelectAll).await.unwrap();
+        assert_eq!(television.marked_entries.len(), matching);
+        assert!(television.marked_entries.contains_key(&name));
+    }
+
+    #[tokio::test]
+    async fn test_paste_from_clipboard() {
+        let mut television = env_television();
+        television.read_clipboard = || Some("PA\nTH\r\n".to_string());
+        television.update(Action::AddInputChar('$')).await.unwrap();
+        television.update(Action::GoToInputStart).await.unwrap();
+
+        television.update(Action::PasteFromClipboard).await.unwrap();
+
+        assert_eq!(television.results_picker.input.value(), "PATH$");
+        assert_eq!(television.results_picker.input.cursor(), 4);
+        assert_eq!(television.current_pattern, "PATH$");
+
+        // nothing to paste
+        television.read_clipboard = || None;
+        television.update(Action::PasteFromClipboard).await.unwrap();
+        assert_eq!(television.current_pattern, "PATH$");
+    }
+
+    /// The names of the entries of the channel once it has loaded `count`
+    /// of them.
+    fn loaded_names(
+        channel: &mut TelevisionChannel,
+        count: u32,
+    ) -> Vec<String> {
+        for _ in 0..500 {
+            channel.results(0, 0);
+            if channel.total_count() >= count && !channel.running() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let mut names: Vec<String> = channel
+            .results(channel.result_count(), 0)
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_pipe_to_channel() {
+        let directory = std::env::temp_dir()
+            .join(format!("tv-pipe-to-channel-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        for name in ["main.rs", "lib.rs", "README.md"] {
+            std::fs::write(directory.join(name), "fn main() {}\n").unwrap();
+        }
+        let mut television = Television::new(TelevisionChannel::Files(
+            FilesChannel::new(vec![directory.clone()]),
+        ));
+        television.config.ui.query_debounce_ms = 0;
+        loaded_names(&mut television.channel, 3);
+        type_pattern(&mut television, ".rs").await;
+        let matches = loaded_names(&mut television.channel, 0);
+        assert_eq!(matches.len(), 2);
+
+        let action = television
+            .update(Action::PipeToChannel("files".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(action, None);
+        assert_eq!(television.results_picker.input.value(), "");
+        assert_eq!(loaded_names(&mut television.channel, 2), matches);
+
+        // the env channel can't be fed files
+        let action = television
+            .update(Action::PipeToChannel("env".to_string()))
+            .await
+            .unwrap();
+        assert!(matches!(action, Some(Action::Error(_))));
+        assert_eq!(loaded_names(&mut television.channel, 2), matches);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    static COPIED: std::sync::Mutex<Vec<String>> =
+        std::sync::Mutex::new(Vec::new());
+
+    #[tokio::test]
+    async fn test_copy_entry_name_and_value() {
+        let mut television = env_television();
+        television.write_clipboard = |text| COPIED.lock().unwrap().push(text);
+        type_pattern(&mut television, "PATH").await;
+        let entry = television.get_selected_entry(None).unwrap();
+
+        television.update(Action::CopyEntryName).await.unwrap();
+        television.update(Action::CopyEntryValue).await.unwrap();
+
+        // entries without a value fall back to their name
+        let file = Entry::new("Cargo.toml".to_string(), PreviewType::Files);
+        television
+            .marked_entries
+            .insert(file.stdout_repr(), file.clone());
+        television.update(Action::CopyEntryValue).await.unwrap();
+
+        assert_eq!(
+            *COPIED.lock().unwrap(),
+            vec![entry.name, entry.value.unwrap(), file.name]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_debounced_query() {
+        let mut television = Television::new(CliTvChannel::Env.to_channel());
+        television.config.ui.query_debounce_ms = 1000;
+        let start = Instant::now();
+        for c in "PATH".chars() {
+            television.update(Action::AddInputChar(c)).await.unwrap();
+            television.run_debounced_query(start);
+            assert_eq!(television.current_pattern, "");
+        }
+
+        // still within the window of the last keystroke
+        television.update(Action::Tick).await.unwrap();
+        assert_eq!(television.current_pattern, "");
+
+        // a single query with the latest input once the window has passed
+        television
+            .run_debounced_query(Instant::now() + Duration::from_secs(1));
+        assert_eq!(television.current_pattern, "PATH");
+        assert!(television.pending_query.is_none());
+
+        // other actions don't wait for the window
+        television.update(Action::DeletePrevChar).await.unwrap();
+        television.update(Action::SelectNextEntry).await.unwrap();
+        assert_eq!(television.current_pattern, "PAT");
+    }
+
+    fn draw(television: &mut Television) -> ratatui::buffer::Buffer {
+        // the help bar needs keybindings, which the default config lacks
+        television.config.ui.show_help_bar = false;
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 24))
+                .unwrap();
+        terminal
+            .draw(|f| television.draw(f, f.area()).unwrap())
+            .unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    #[tokio::test]
+    async fn test_no_preview() {
+        let mut television = env_television();
+        let mut config = television.config.clone();
+        config.ui.show_preview = false;
+        television.register_config_handler(config).unwrap();
+        draw(&mut television);
+        // nothing was previewed so the previewer was never loaded
+        assert!(television.previewer.is_none());
+        assert!(!television.preview_visible);
+        television.update(Action::TogglePreview).await.unwrap();
+        television.update(Action::CycleTheme).await.unwrap();
+        television.scroll_preview_down(10);
+        draw(&mut television);
+        assert!(television.previewer.is_none());
+
+        let mut television = env_television();
+        draw(&mut television);
+        assert!(television.previewer.is_some());
+    }
+
+    #[test]
+    fn test_scroll_preview_to_bottom() {
+        let mut television = env_television();
+        // no preview: nothing to scroll
+        television.preview_visible = false;
+        television.scroll_preview_to_bottom();
+        assert_eq!(television.preview_scroll, None);
+        assert!(television.previewer.is_none());
+
+        // the last lines stay in the upper two thirds of the pane
+        television.current_preview_total_lines = 100;
+        television.preview_pane_height = 30;
+        television.scroll_preview_to_bottom();
+        assert_eq!(television.preview_scroll, Some(80));
+
+        // short previews aren't scrolled past their first line
+        television.current_preview_total_lines = 10;
+        television.scroll_preview_to_bottom();
+        assert_eq!(television.preview_scroll, Some(0));
+    }
+
+    #[test]
+    fn test_preview_background() {
+        let mut television = env_television();
+        // a cell in the middle of the preview pane
+        let preview_bg =
+            |television: &mut Television| draw(television)[(60, 12)].bg;
+        assert_eq!(preview_bg(&mut television), Color::Reset);
+
+        let mut config = television.config.clone();
+        config.ui.preview_bg = Some(String::from("#102030"));
+        television.register_config_handler(config.clone()).unwrap();
+        assert_eq!(preview_bg(&mut television), Color::Rgb(16, 32, 48));
+
+        config.ui.preview_bg = Some(String::from("theme"));
+        television.register_config_handler(config).unwrap();
+        let background = preview_bg(&mut television);
+        let theme_background = television
+            .previewer
+            .as_ref()
+            .and_then(Previewer::theme_background)
+            .unwrap();
+        assert_eq!(
+            background,
+            Color::Rgb(
+                theme_background.r,
+                theme_background.g,
+                theme_background.b
+            )
+        );
+    }
+}
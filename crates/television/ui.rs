@@ -1,4 +1,9 @@
-use ratatui::style::Color;
+use crate::config::BorderStyle;
+use devicons::FileIcon;
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, Borders};
+use std::str::FromStr;
+use tracing::warn;
 
 pub(crate) mod help;
 pub mod input;
@@ -13,3 +18,60 @@ pub mod results;
 pub mod spinner;
 
 pub const BORDER_COLOR: Color = Color::Blue;
+
+/// The block surrounding a panel, with the given borders.
+pub(crate) fn panel_block(
+    border_style: BorderStyle,
+    border_color: Color,
+) -> Block<'static> {
+    let block =
+        Block::default().border_style(Style::default().fg(border_color));
+    match border_style.border_type() {
+        Some(border_type) => {
+            block.borders(Borders::ALL).border_type(border_type)
+        }
+        None => block.borders(Borders::NONE),
+    }
+}
+
+/// The color of the given icon, or `fallback` if its color can't be parsed.
+pub(crate) fn icon_color(icon: &FileIcon, fallback: Color) -> Color {
+    Color::from_str(icon.color).unwrap_or_else(|_| {
+        warn!("Invalid color {:?} for icon {:?}", icon.color, icon.icon);
+        fallback
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+    use ratatui::widgets::Widget;
+
+    fn render(block: Block) -> Buffer {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 4, 3));
+        block.render(buffer.area, &mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn test_panel_block_borders() {
+        let buffer = render(panel_block(BorderStyle::Double, Color::Red));
+        let rows: Vec<String> = (0..3)
+            .map(|y| (0..4).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect();
+        assert_eq!(rows, vec!["╔══╗", "║  ║", "╚══╝"]);
+        let buffer = render(panel_block(BorderStyle::Rounded, Color::Red));
+        assert_eq!(buffer[(0, 0)].symbol(), "╭");
+        assert_eq!(buffer[(0, 0)].fg, Color::Red);
+        let buffer = render(panel_block(BorderStyle::Thick, Color::Red));
+        assert_eq!(buffer[(0, 0)].symbol(), "┏");
+
+        let block = panel_block(BorderStyle::None, Color::Red);
+        let area = Rect::new(0, 0, 4, 3);
+        // panels without borders get all of their area
+        assert_eq!(block.inner(area), area);
+        assert_eq!(render(block), Buffer::empty(area));
+    }
+}
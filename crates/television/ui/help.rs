@@ -4,14 +4,16 @@ use crate::ui::logo::build_logo_paragraph;
 use crate::ui::mode::mode_color;
 use ratatui::layout::Rect;
 use ratatui::prelude::{Color, Style};
-use ratatui::widgets::{Block, BorderType, Borders, Padding};
+use ratatui::widgets::{Block, Padding};
 use ratatui::Frame;
 
-pub fn draw_logo_block(f: &mut Frame, area: Rect, color: Color) {
-    let logo_block = Block::default()
-        .borders(Borders::ALL)
-        .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(Color::Blue))
+pub fn draw_logo_block(
+    f: &mut Frame,
+    area: Rect,
+    block: Block<'static>,
+    color: Color,
+) {
+    let logo_block = block
         .style(Style::default().fg(color))
         .padding(Padding::horizontal(1));
 
@@ -29,16 +31,19 @@ impl Television {
         if let Some(help_bar) = layout.help_bar {
             self.draw_metadata_block(f, help_bar.left);
             self.draw_keymaps_block(f, help_bar.middle)?;
-            draw_logo_block(f, help_bar.right, mode_color(self.mode));
+            draw_logo_block(
+                f,
+                help_bar.right,
+                self.panel_block(),
+                mode_color(self.mode),
+            );
         }
         Ok(())
     }
 
     fn draw_metadata_block(&self, f: &mut Frame, area: Rect) {
-        let metadata_block = Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(Color::Blue))
+        let metadata_block = self
+            .panel_block()
             .padding(Padding::horizontal(1))
             .style(Style::default());
 
@@ -52,10 +57,8 @@ impl Television {
         f: &mut Frame,
         area: Rect,
     ) -> color_eyre::Result<()> {
-        let keymaps_block = Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(Color::Blue))
+        let keymaps_block = self
+            .panel_block()
             .style(Style::default())
             .padding(Padding::horizontal(1));
 
@@ -1,6 +1,5 @@
 use crate::television::Television;
 use crate::ui::layout::Layout;
-use crate::ui::BORDER_COLOR;
 use color_eyre::eyre::Result;
 use ratatui::layout::{
     Alignment, Constraint, Direction, Layout as RatatuiLayout,
@@ -8,9 +7,12 @@ use ratatui::layout::{
 use ratatui::prelude::{Span, Style};
 use ratatui::style::Stylize;
 use ratatui::text::Line;
-use ratatui::widgets::{Block, BorderType, Borders, Paragraph};
+use ratatui::widgets::{Block, Paragraph};
 use ratatui::Frame;
 use television_channels::channels::OnAir;
+use television_utils::strings::{
+    next_grapheme_boundary, prev_char_boundary, prev_grapheme_boundary,
+};
 
 pub mod actions;
 pub mod backend;
@@ -51,7 +53,7 @@ pub type InputResponse = Option<StateChanged>;
 /// Example:
 ///
 /// ```
-/// use tui_input::Input;
+/// use television::ui::input::Input;
 ///
 /// let input: Input = "Hello World".into();
 ///
@@ -140,14 +142,10 @@ impl Input {
                 if self.cursor == 0 {
                     None
                 } else {
-                    self.cursor -= 1;
-                    self.value = self
-                        .value
-                        .chars()
-                        .enumerate()
-                        .filter(|(i, _)| i != &self.cursor)
-                        .map(|(_, c)| c)
-                        .collect();
+                    let end = self.byte_index(self.cursor);
+                    let start = prev_grapheme_boundary(&self.value, end);
+                    self.value.replace_range(start..end, "");
+                    self.cursor = self.value[..start].chars().count();
 
                     Some(StateChanged {
                         value: true,
@@ -160,13 +158,9 @@ impl Input {
                 if self.cursor == self.value.chars().count() {
                     None
                 } else {
-                    self.value = self
-                        .value
-                        .chars()
-                        .enumerate()
-                        .filter(|(i, _)| i != &self.cursor)
-                        .map(|(_, c)| c)
-                        .collect();
+                    let start = self.byte_index(self.cursor);
+                    let end = next_grapheme_boundary(&self.value, start);
+                    self.value.replace_range(start..end, "");
                     Some(StateChanged {
                         value: true,
                         cursor: false,
@@ -178,7 +172,11 @@ impl Input {
                 if self.cursor == 0 {
                     None
                 } else {
-                    self.cursor -= 1;
+                    let start = prev_grapheme_boundary(
+                        &self.value,
+                        self.byte_index(self.cursor),
+                    );
+                    self.cursor = self.value[..start].chars().count();
                     Some(StateChanged {
                         value: false,
                         cursor: true,
@@ -212,7 +210,11 @@ impl Input {
                 if self.cursor == self.value.chars().count() {
                     None
                 } else {
-                    self.cursor += 1;
+                    let end = next_grapheme_boundary(
+                        &self.value,
+                        self.byte_index(self.cursor),
+                    );
+                    self.cursor = self.value[..end].chars().count();
                     Some(StateChanged {
                         value: false,
                         cursor: true,
@@ -259,22 +261,10 @@ impl Input {
                 if self.cursor == 0 {
                     None
                 } else {
-                    let remaining = self.value.chars().skip(self.cursor);
-                    let rev = self
-                        .value
-                        .chars()
-                        .rev()
-                        .skip(
-                            self.value.chars().count().max(self.cursor)
-                                - self.cursor,
-                        )
-                        .skip_while(|c| !c.is_alphanumeric())
-                        .skip_while(|c| c.is_alphanumeric())
-                        .collect::<Vec<char>>();
-                    let rev_len = rev.len();
-                    self.value =
-                        rev.into_iter().rev().chain(remaining).collect();
-                    self.cursor = rev_len;
+                    let end = self.byte_index(self.cursor);
+                    let start = self.prev_word_start(end);
+                    self.value.replace_range(start..end, "");
+                    self.cursor = self.value[..start].chars().count();
                     Some(StateChanged {
                         value: true,
                         cursor: true,
@@ -341,6 +331,32 @@ impl Input {
         }
     }
 
+    /// Get the byte index in the value corresponding to the given char index.
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(char_index)
+            .map_or_else(|| self.value.len(), |(index, _)| index)
+    }
+
+    /// Get the byte index of the start of the whitespace-delimited word
+    /// preceding the given byte index, whitespace in between included.
+    fn prev_word_start(&self, end: usize) -> usize {
+        let mut start = end;
+        let mut in_word = false;
+        while start > 0 {
+            let prev = prev_char_boundary(&self.value, start - 1);
+            let is_whitespace =
+                self.value[prev..start].chars().all(char::is_whitespace);
+            if in_word && is_whitespace {
+                break;
+            }
+            in_word |= !is_whitespace;
+            start = prev;
+        }
+        start
+    }
+
     /// Get a reference to the current value.
     pub fn value(&self) -> &str {
         self.value.as_str()
@@ -412,17 +428,30 @@ impl std::fmt::Display for Input {
     }
 }
 
+/// The label showing the (1-based) index of the selected result and the
+/// number of results, e.g. ` 12 / 4571 `.
+fn result_count_label(selected: Option<usize>, result_count: u32) -> String {
+    let selected = if result_count == 0 {
+        0
+    } else {
+        selected.unwrap_or(0) + 1
+    };
+    format!(" {selected} / {result_count} ")
+}
+
 impl Television {
     pub(crate) fn draw_input_box(
         &mut self,
         f: &mut Frame,
         layout: &Layout,
     ) -> Result<()> {
-        let input_block = Block::default()
+        let input_block = self
+            .panel_block()
             .title_top(Line::from(" Pattern ").alignment(Alignment::Center))
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(BORDER_COLOR))
+            .title_top(
+                Line::from(format!(" {} ", self.case_sensitivity))
+                    .alignment(Alignment::Right),
+            )
             .style(Style::default());
 
         let input_block_inner = input_block.inner(layout.input);
@@ -442,9 +471,11 @@ impl Television {
                 // input field
                 Constraint::Fill(1),
                 // result count
-                Constraint::Length(
-                    3 * ((total_count as f32).log10().ceil() as u16 + 1) + 3,
-                ),
+                Constraint::Length(if self.config.ui.show_result_count {
+                    3 * ((total_count as f32).log10().ceil() as u16 + 1) + 3
+                } else {
+                    0
+                }),
                 // spinner
                 Constraint::Length(1),
             ])
@@ -476,7 +507,7 @@ impl Television {
             .alignment(Alignment::Left);
         f.render_widget(input, inner_input_chunks[1]);
 
-        if self.channel.running() {
+        if self.channel.running() || self.channel.loading() {
             f.render_stateful_widget(
                 self.spinner,
                 inner_input_chunks[3],
@@ -484,25 +515,21 @@ impl Television {
             );
         }
 
-        let result_count = self.channel.result_count();
-        let result_count_block = Block::default();
-        let result_count_paragraph = Paragraph::new(Span::styled(
-            format!(
-                " {} / {} ",
-                if result_count == 0 {
-                    0
-                } else {
-                    self.results_picker.selected().unwrap_or(0) + 1
-                },
-                result_count,
-            ),
-            Style::default()
-                .fg(crate::television::DEFAULT_RESULTS_COUNT_FG)
-                .italic(),
-        ))
-        .block(result_count_block)
-        .alignment(Alignment::Right);
-        f.render_widget(result_count_paragraph, inner_input_chunks[2]);
+        if self.config.ui.show_result_count {
+            let result_count_block = Block::default();
+            let result_count_paragraph = Paragraph::new(Span::styled(
+                result_count_label(
+                    self.results_picker.selected(),
+                    self.channel.result_count(),
+                ),
+                Style::default()
+                    .fg(crate::television::DEFAULT_RESULTS_COUNT_FG)
+                    .italic(),
+            ))
+            .block(result_count_block)
+            .alignment(Alignment::Right);
+            f.render_widget(result_count_paragraph, inner_input_chunks[2]);
+        }
 
         // Make the cursor visible and ask tui-rs to put it at the
         // specified coordinates after rendering
@@ -526,6 +553,14 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_result_count_label() {
+        assert_eq!(result_count_label(Some(11), 4571), " 12 / 4571 ");
+        assert_eq!(result_count_label(None, 3), " 1 / 3 ");
+        // a stale selection isn't shown when nothing matches
+        assert_eq!(result_count_label(Some(4), 0), " 0 / 0 ");
+    }
+
     #[test]
     fn format() {
         let input: Input = TEXT.into();
@@ -700,6 +735,107 @@ mod tests {
         assert_eq!(input.cursor(), 2);
     }
 
+    const FAMILY_EMOJI: &str = "👨\u{200d}👩\u{200d}👧";
+    const COMBINING_E: &str = "e\u{301}";
+    const FLAG: &str = "🇫🇷";
+
+    #[test]
+    fn move_over_grapheme_clusters() {
+        for cluster in [FAMILY_EMOJI, COMBINING_E, FLAG] {
+            let value = format!("a{cluster}b");
+            let cluster_len = cluster.chars().count();
+            let mut input = Input::from(value.as_str()).with_cursor(1);
+
+            input.handle(InputRequest::GoToNextChar);
+            assert_eq!(input.cursor(), 1 + cluster_len);
+            input.handle(InputRequest::GoToPrevChar);
+            assert_eq!(input.cursor(), 1);
+            input.handle(InputRequest::GoToPrevChar);
+            assert_eq!(input.cursor(), 0);
+        }
+    }
+
+    #[test]
+    fn delete_grapheme_clusters() {
+        for cluster in [FAMILY_EMOJI, COMBINING_E, FLAG] {
+            let value = format!("a{cluster}b");
+            let cluster_len = cluster.chars().count();
+
+            let mut input =
+                Input::from(value.as_str()).with_cursor(1 + cluster_len);
+            let resp = input.handle(InputRequest::DeletePrevChar);
+            assert_eq!(
+                resp,
+                Some(StateChanged {
+                    value: true,
+                    cursor: true,
+                })
+            );
+            assert_eq!(input.value(), "ab");
+            assert_eq!(input.cursor(), 1);
+
+            let mut input = Input::from(value.as_str()).with_cursor(1);
+            input.handle(InputRequest::DeleteNextChar);
+            assert_eq!(input.value(), "ab");
+            assert_eq!(input.cursor(), 1);
+        }
+    }
+
+    #[test]
+    fn delete_prev_word_mid_word() {
+        let mut input = Input::from("foo bar baz").with_cursor(6);
+        let resp = input.handle(InputRequest::DeletePrevWord);
+        assert_eq!(
+            resp,
+            Some(StateChanged {
+                value: true,
+                cursor: true,
+            })
+        );
+        assert_eq!(input.value(), "foo r baz");
+        assert_eq!(input.cursor(), 4);
+
+        let mut input: Input = "héllo wörld".into();
+        input.handle(InputRequest::DeletePrevWord);
+        assert_eq!(input.value(), "héllo ");
+        assert_eq!(input.cursor(), 6);
+    }
+
+    #[test]
+    fn delete_prev_word_at_space() {
+        // trailing spaces are deleted along with the word
+        let mut input: Input = "foo bar   ".into();
+        input.handle(InputRequest::DeletePrevWord);
+        assert_eq!(input.value(), "foo ");
+        assert_eq!(input.cursor(), 4);
+
+        // punctuation is part of the word
+        let mut input: Input = "first second, third.".into();
+        input.handle(InputRequest::DeletePrevWord);
+        input.handle(InputRequest::DeletePrevWord);
+        assert_eq!(input.value(), "first ");
+        assert_eq!(input.cursor(), 6);
+    }
+
+    #[test]
+    fn delete_prev_word_at_start_of_buffer() {
+        let mut input = Input::from("foo bar").with_cursor(0);
+        assert_eq!(input.handle(InputRequest::DeletePrevWord), None);
+        assert_eq!(input.value(), "foo bar");
+        assert_eq!(input.cursor(), 0);
+
+        // the first word is deleted up to the start of the buffer
+        let mut input = Input::from("foo bar").with_cursor(4);
+        input.handle(InputRequest::DeletePrevWord);
+        assert_eq!(input.value(), "bar");
+        assert_eq!(input.cursor(), 0);
+
+        let mut input = Input::from("   bar").with_cursor(3);
+        input.handle(InputRequest::DeletePrevWord);
+        assert_eq!(input.value(), "bar");
+        assert_eq!(input.cursor(), 0);
+    }
+
     #[test]
     fn multispace_characters() {
         let input: Input = "Ｈｅｌｌｏ, ｗｏｒｌｄ!".into();
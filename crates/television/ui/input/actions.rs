@@ -17,6 +17,9 @@ impl InputActionHandler for Input {
             Action::DeletePrevChar => {
                 self.handle(InputRequest::DeletePrevChar)
             }
+            Action::DeletePrevWord => {
+                self.handle(InputRequest::DeletePrevWord)
+            }
             Action::DeleteNextChar => {
                 self.handle(InputRequest::DeleteNextChar)
             }
@@ -24,6 +27,7 @@ impl InputActionHandler for Input {
             Action::GoToNextChar => self.handle(InputRequest::GoToNextChar),
             Action::GoToInputStart => self.handle(InputRequest::GoToStart),
             Action::GoToInputEnd => self.handle(InputRequest::GoToEnd),
+            Action::ClearInput => self.handle(InputRequest::DeleteLine),
             _ => None,
         }
     }
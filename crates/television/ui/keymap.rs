@@ -53,6 +53,15 @@ impl Television {
             key_color,
         ));
 
+        // Toggle preview
+        let toggle_preview_keys =
+            keys_for_action(keymap, &Action::TogglePreview);
+        let toggle_preview_row = Row::new(build_cells_for_key_groups(
+            "Toggle preview",
+            vec![toggle_preview_keys],
+            key_color,
+        ));
+
         // Select entry
         let select_entry_keys = keys_for_action(keymap, &Action::SelectEntry);
         let select_entry_row = Row::new(build_cells_for_key_groups(
@@ -62,11 +71,11 @@ impl Television {
         ));
 
         // Copy entry to clipboard
-        let copy_entry_keys =
-            keys_for_action(keymap, &Action::CopyEntryToClipboard);
+        let copy_entry_keys = keys_for_action(keymap, &Action::CopyEntryName);
+        let copy_value_keys = keys_for_action(keymap, &Action::CopyEntryValue);
         let copy_entry_row = Row::new(build_cells_for_key_groups(
             "Copy entry to clipboard",
-            vec![copy_entry_keys],
+            vec![copy_entry_keys, copy_value_keys],
             key_color,
         ));
 
@@ -103,6 +112,7 @@ impl Television {
             vec![
                 results_row,
                 preview_row,
+                toggle_preview_row,
                 select_entry_row,
                 copy_entry_row,
                 send_to_channel_row,
@@ -221,7 +231,7 @@ impl Television {
 /// Build the corresponding spans for a group of keys.
 ///
 /// # Example
-/// ```rust
+/// ```ignore
 /// use ratatui::text::Span;
 /// use television::ui::help::build_spans_for_key_groups;
 ///
@@ -269,10 +279,11 @@ fn build_cells_for_key_groups(
     cells
 }
 
-/// Get the keys for a given action.
+/// Get the keys for a given action, sorted so that they are displayed in a
+/// stable order.
 ///
 /// # Example
-/// ```rust
+/// ```ignore
 /// use std::collections::HashMap;
 /// use television::action::Action;
 /// use television::ui::help::keys_for_action;
@@ -289,9 +300,74 @@ fn keys_for_action(
     keymap: &HashMap<Key, Action>,
     action: &Action,
 ) -> Vec<String> {
-    keymap
+    let mut keys: Vec<String> = keymap
         .iter()
         .filter(|(_key, act)| *act == action)
         .map(|(key, _act)| format!("{key}"))
-        .collect()
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Keymap;
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+    use television_channels::channels::CliTvChannel;
+
+    fn television_with_bindings(bindings: Vec<(Key, Action)>) -> Television {
+        let mut television = Television::new(CliTvChannel::Env.to_channel());
+        television.keymap = Keymap(HashMap::from([(
+            Mode::Channel,
+            bindings.into_iter().collect(),
+        )]));
+        television
+    }
+
+    /// The rows of the keymap table as displayed.
+    fn displayed_hints(television: &Television) -> Vec<String> {
+        let area = Rect::new(0, 0, 80, 10);
+        let mut buffer = Buffer::empty(area);
+        television
+            .build_keymap_table()
+            .unwrap()
+            .render(area, &mut buffer);
+        (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hints_follow_keybindings() {
+        let television = television_with_bindings(vec![
+            (Key::Ctrl('p'), Action::TogglePreview),
+            (Key::Enter, Action::SelectEntry),
+            (Key::Esc, Action::Quit),
+            (Key::Ctrl('c'), Action::Quit),
+        ]);
+        let hints = displayed_hints(&television);
+        assert!(hints.contains(&format!("Toggle preview: {}", Key::Ctrl('p'))));
+        assert!(hints.contains(&format!("Select entry: {}", Key::Enter)));
+        // alternate keys are sorted
+        let mut quit_keys = [Key::Esc.to_string(), Key::Ctrl('c').to_string()];
+        quit_keys.sort();
+        assert!(hints.contains(&format!("Quit: {}", quit_keys.join(", "))));
+
+        // rebinding an action changes its hint
+        let television = television_with_bindings(vec![
+            (Key::Alt('p'), Action::TogglePreview),
+            (Key::Esc, Action::Quit),
+        ]);
+        let hints = displayed_hints(&television);
+        assert!(hints.contains(&format!("Toggle preview: {}", Key::Alt('p'))));
+        assert!(hints.contains(&"Select entry No keybindings".to_string()));
+    }
 }
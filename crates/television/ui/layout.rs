@@ -1,6 +1,8 @@
 use ratatui::layout;
 use ratatui::layout::{Constraint, Direction, Rect};
 
+use crate::config::{InputPosition, LayoutOrientation};
+
 pub struct Dimensions {
     pub x: u16,
     pub y: u16,
@@ -45,8 +47,8 @@ pub struct Layout {
     pub help_bar: Option<HelpBarLayout>,
     pub results: Rect,
     pub input: Rect,
-    pub preview_title: Rect,
-    pub preview_window: Rect,
+    pub preview_title: Option<Rect>,
+    pub preview_window: Option<Rect>,
     pub remote_control: Option<Rect>,
 }
 
@@ -56,8 +58,8 @@ impl Layout {
         help_bar: Option<HelpBarLayout>,
         results: Rect,
         input: Rect,
-        preview_title: Rect,
-        preview_window: Rect,
+        preview_title: Option<Rect>,
+        preview_window: Option<Rect>,
         remote_control: Option<Rect>,
     ) -> Self {
         Self {
@@ -70,11 +72,16 @@ impl Layout {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn build(
         dimensions: &Dimensions,
         area: Rect,
         with_remote: bool,
         with_help_bar: bool,
+        with_preview: bool,
+        preview_size: u16,
+        orientation: LayoutOrientation,
+        input_position: InputPosition,
     ) -> Self {
         let main_block = centered_rect(dimensions.x, dimensions.y, area);
         // split the main block into two vertical chunks (help bar + rest)
@@ -84,7 +91,7 @@ impl Layout {
         if with_help_bar {
             let hz_chunks = layout::Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Constraint::Max(9), Constraint::Fill(1)])
+                .constraints([Constraint::Max(10), Constraint::Fill(1)])
                 .split(main_block);
             main_rect = hz_chunks[1];
 
@@ -111,44 +118,72 @@ impl Layout {
             help_bar_layout = None;
         }
 
-        // split the main block into two vertical chunks
-        let constraints = if with_remote {
+        // the remote control always sits on the right
+        let (content_rect, remote_control) = if with_remote {
+            let chunks = layout::Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Fill(1), Constraint::Length(24)])
+                .split(main_rect);
+            (chunks[0], Some(chunks[1]))
+        } else {
+            (main_rect, None)
+        };
+
+        // split the rest into results and preview, side by side or stacked
+        let constraints = if with_preview {
+            let preview_size =
+                preview_size.clamp(MIN_PREVIEW_SIZE, MAX_PREVIEW_SIZE);
             vec![
-                Constraint::Fill(1),
-                Constraint::Fill(1),
-                Constraint::Length(24),
+                Constraint::Fill(100 - preview_size),
+                Constraint::Fill(preview_size),
             ]
         } else {
-            vec![Constraint::Percentage(50), Constraint::Percentage(50)]
+            vec![Constraint::Fill(1)]
         };
-        let vt_chunks = layout::Layout::default()
-            .direction(Direction::Horizontal)
+        let content_chunks = layout::Layout::default()
+            .direction(match orientation {
+                LayoutOrientation::Horizontal => Direction::Horizontal,
+                LayoutOrientation::Vertical => Direction::Vertical,
+            })
             .constraints(constraints)
-            .split(main_rect);
+            .split(content_rect);
 
-        // left block: results + input field
-        let left_chunks = layout::Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(3), Constraint::Length(3)])
-            .split(vt_chunks[0]);
+        // results + input field
+        let (results, input) = match input_position {
+            InputPosition::Top => {
+                let chunks = layout::Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(3)])
+                    .split(content_chunks[0]);
+                (chunks[1], chunks[0])
+            }
+            InputPosition::Bottom => {
+                let chunks = layout::Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(3)])
+                    .split(content_chunks[0]);
+                (chunks[0], chunks[1])
+            }
+        };
 
-        // right block: preview title + preview
-        let right_chunks = layout::Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Min(3)])
-            .split(vt_chunks[1]);
+        // preview title + preview
+        let (preview_title, preview_window) = if with_preview {
+            let preview_chunks = layout::Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(3)])
+                .split(content_chunks[1]);
+            (Some(preview_chunks[0]), Some(preview_chunks[1]))
+        } else {
+            (None, None)
+        };
 
         Self::new(
             help_bar_layout,
-            left_chunks[0],
-            left_chunks[1],
-            right_chunks[0],
-            right_chunks[1],
-            if with_remote {
-                Some(vt_chunks[2])
-            } else {
-                None
-            },
+            results,
+            input,
+            preview_title,
+            preview_window,
+            remote_control,
         )
     }
 }
@@ -179,3 +214,209 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 // UI size
 const UI_WIDTH_PERCENT: u16 = 95;
 const UI_HEIGHT_PERCENT: u16 = 95;
+
+/// The bounds of the percentage of the width given to the preview pane.
+pub const MIN_PREVIEW_SIZE: u16 = 10;
+pub const MAX_PREVIEW_SIZE: u16 = 90;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Rect = Rect::new(0, 0, 200, 50);
+    const HORIZONTAL: LayoutOrientation = LayoutOrientation::Horizontal;
+    const BOTTOM: InputPosition = InputPosition::Bottom;
+
+    #[test]
+    fn test_results_expand_without_preview() {
+        let dimensions = Dimensions::from(100);
+        let with_preview = Layout::build(
+            &dimensions,
+            AREA,
+            false,
+            false,
+            true,
+            50,
+            HORIZONTAL,
+            BOTTOM,
+        );
+        let without_preview = Layout::build(
+            &dimensions,
+            AREA,
+            false,
+            false,
+            false,
+            50,
+            HORIZONTAL,
+            BOTTOM,
+        );
+
+        assert_eq!(with_preview.results.width, 100);
+        assert!(with_preview.preview_window.is_some());
+        assert_eq!(without_preview.results.width, 200);
+        assert_eq!(without_preview.input.width, 200);
+        assert!(without_preview.preview_title.is_none());
+        assert!(without_preview.preview_window.is_none());
+    }
+
+    #[test]
+    fn test_remote_control_without_preview() {
+        let dimensions = Dimensions::from(100);
+        let layout = Layout::build(
+            &dimensions,
+            AREA,
+            true,
+            false,
+            false,
+            50,
+            HORIZONTAL,
+            BOTTOM,
+        );
+
+        assert_eq!(layout.results.width, 176);
+        assert!(layout.preview_window.is_none());
+        assert_eq!(layout.remote_control.unwrap().width, 24);
+    }
+
+    #[test]
+    fn test_preview_size() {
+        let dimensions = Dimensions::from(100);
+        let layout = Layout::build(
+            &dimensions,
+            AREA,
+            false,
+            false,
+            true,
+            30,
+            HORIZONTAL,
+            BOTTOM,
+        );
+
+        assert_eq!(layout.preview_window.unwrap().width, 60);
+        assert_eq!(layout.preview_title.unwrap().width, 60);
+        assert_eq!(layout.results.width, 140);
+    }
+
+    #[test]
+    fn test_preview_size_is_clamped() {
+        let dimensions = Dimensions::from(100);
+        let preview_width = |preview_size| {
+            Layout::build(
+                &dimensions,
+                AREA,
+                false,
+                false,
+                true,
+                preview_size,
+                HORIZONTAL,
+                BOTTOM,
+            )
+            .preview_window
+            .unwrap()
+            .width
+        };
+
+        assert_eq!(preview_width(0), 20);
+        assert_eq!(preview_width(5), 20);
+        assert_eq!(preview_width(95), 180);
+        assert_eq!(preview_width(u16::MAX), 180);
+    }
+
+    fn assert_partitions(layout: &Layout, area: Rect) {
+        let rects = [
+            layout.results,
+            layout.input,
+            layout.preview_title.unwrap(),
+            layout.preview_window.unwrap(),
+        ];
+        for (i, a) in rects.iter().enumerate() {
+            assert!(area.contains(a.as_position()), "{a:?} not in {area:?}");
+            for b in &rects[i + 1..] {
+                assert!(!a.intersects(*b), "{a:?} overlaps {b:?}");
+            }
+        }
+        let total: u32 = rects.iter().map(|r| r.area()).sum();
+        assert_eq!(total, area.area());
+    }
+
+    #[test]
+    fn test_horizontal_layout() {
+        let dimensions = Dimensions::from(100);
+        let layout = Layout::build(
+            &dimensions,
+            AREA,
+            false,
+            false,
+            true,
+            50,
+            HORIZONTAL,
+            BOTTOM,
+        );
+        assert_partitions(&layout, AREA);
+
+        let preview = layout.preview_window.unwrap();
+        assert_eq!(layout.results.y, preview.y - 3);
+        assert_eq!(layout.results.x + layout.results.width, preview.x);
+        assert_eq!(layout.input.y + layout.input.height, AREA.height);
+    }
+
+    #[test]
+    fn test_vertical_layout() {
+        let dimensions = Dimensions::from(100);
+        let layout = Layout::build(
+            &dimensions,
+            AREA,
+            false,
+            false,
+            true,
+            50,
+            LayoutOrientation::Vertical,
+            BOTTOM,
+        );
+        assert_partitions(&layout, AREA);
+
+        // results and input on top, preview below, all full width
+        let preview_title = layout.preview_title.unwrap();
+        for rect in [layout.results, layout.input, preview_title] {
+            assert_eq!(rect.width, AREA.width);
+        }
+        assert_eq!(layout.input.y + layout.input.height, preview_title.y);
+        assert_eq!(layout.results.height + layout.input.height, 25);
+    }
+
+    #[test]
+    fn test_input_position() {
+        let dimensions = Dimensions::from(100);
+        for orientation in
+            [LayoutOrientation::Horizontal, LayoutOrientation::Vertical]
+        {
+            let top = Layout::build(
+                &dimensions,
+                AREA,
+                false,
+                false,
+                true,
+                50,
+                orientation,
+                InputPosition::Top,
+            );
+            assert_partitions(&top, AREA);
+            assert_eq!(top.input.y, 0);
+            assert_eq!(top.results.y, 3);
+
+            let bottom = Layout::build(
+                &dimensions,
+                AREA,
+                false,
+                false,
+                true,
+                50,
+                orientation,
+                InputPosition::Bottom,
+            );
+            assert_partitions(&bottom, AREA);
+            assert_eq!(bottom.results.y, 0);
+            assert_eq!(bottom.input.y, bottom.results.height);
+        }
+    }
+}
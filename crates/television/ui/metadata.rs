@@ -78,7 +78,7 @@ impl Television {
                 Style::default().fg(METADATA_FIELD_NAME_COLOR),
             )),
             Cell::from(Span::styled(
-                self.current_channel().to_string(),
+                self.channel.name(),
                 Style::default().fg(METADATA_FIELD_VALUE_COLOR),
             )),
         ]);
@@ -1,20 +1,27 @@
-use crate::television::Television;
-use crate::ui::layout::Layout;
-use crate::ui::BORDER_COLOR;
+use crate::television::{Mode, Television};
+use crate::ui::icon_color;
+use crate::ui::results::wrap_line;
 use color_eyre::eyre::Result;
 use ratatui::layout::{Alignment, Rect};
 use ratatui::prelude::{Color, Line, Modifier, Span, Style, Stylize, Text};
-use ratatui::widgets::{Block, BorderType, Borders, Padding, Paragraph, Wrap};
+use ratatui::widgets::{Block, Padding, Paragraph, Wrap};
 use ratatui::Frame;
-use std::str::FromStr;
+use std::ops::Range;
 use std::sync::Arc;
+use std::time::Duration;
 use syntect::highlighting::Color as SyntectColor;
 use television_channels::channels::OnAir;
 use television_channels::entry::Entry;
+use television_fuzzy::matcher::config::CaseSensitivity;
+use television_previewers::previewers::image::{
+    graphics_sequence, halfblocks, Image, ImageProtocol,
+};
 use television_previewers::previewers::{
-    Preview, PreviewContent, FILE_TOO_LARGE_MSG, PREVIEW_NOT_SUPPORTED_MSG,
+    Preview, PreviewContent, BROKEN_SYMLINK_MSG, FILE_TOO_LARGE_MSG,
+    PREVIEW_NOT_SUPPORTED_MSG, PREVIEW_TIMED_OUT_MSG, SPECIAL_FILE_MSG,
 };
 use television_utils::strings::{shrink_with_ellipsis, EMPTY_STRING};
+use unicode_width::UnicodeWidthChar;
 
 //  preview
 pub const DEFAULT_PREVIEW_TITLE_FG: Color = Color::Blue;
@@ -22,12 +29,274 @@ const DEFAULT_SELECTED_PREVIEW_BG: Color = Color::Rgb(50, 50, 50);
 const DEFAULT_PREVIEW_CONTENT_FG: Color = Color::Rgb(150, 150, 180);
 const DEFAULT_PREVIEW_GUTTER_FG: Color = Color::Rgb(70, 70, 70);
 const DEFAULT_PREVIEW_GUTTER_SELECTED_FG: Color = Color::Rgb(255, 150, 150);
+const DEFAULT_PREVIEW_MATCH_BG: Color = Color::Rgb(100, 85, 30);
+const DEFAULT_PREVIEW_TARGET_BG: Color = Color::Rgb(150, 100, 40);
+
+/// How long the name of a newly selected syntax theme is shown for.
+const THEME_NOTIFICATION_DURATION: Duration = Duration::from_secs(2);
+
+/// The width of the line number gutter (line number and separator).
+const PREVIEW_GUTTER_WIDTH: u16 = 9;
+
+/// The size of terminal cells in pixels (width, height) when the terminal
+/// doesn't report it.
+const DEFAULT_CELL_SIZE: (u16, u16) = (8, 16);
+
+/// An image drawn with a graphics protocol.
+///
+/// Ratatui only knows about text, so the escape sequence drawing the image
+/// is written to the terminal at the top left corner of `area` once the
+/// frame is drawn, over blank cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImagePlacement {
+    pub area: Rect,
+    pub protocol: ImageProtocol,
+    pub sequence: Arc<str>,
+}
+
+/// An image rendered for an area of the preview pane.
+#[derive(Debug, Clone)]
+pub struct CachedImage {
+    image: Arc<Image>,
+    area: Rect,
+    protocol: ImageProtocol,
+    rendering: ImageRendering,
+}
+
+#[derive(Debug, Clone)]
+enum ImageRendering {
+    Graphics(ImagePlacement),
+    Halfblocks(Vec<Line<'static>>),
+}
+
+/// The size of terminal cells in pixels (width, height).
+fn cell_size() -> (u16, u16) {
+    crossterm::terminal::window_size()
+        .ok()
+        .filter(|size| {
+            size.columns > 0
+                && size.rows > 0
+                && size.width > 0
+                && size.height > 0
+        })
+        .map_or(DEFAULT_CELL_SIZE, |size| {
+            (size.width / size.columns, size.height / size.rows)
+        })
+}
+
+/// The width of the longest line of a preview, in columns.
+pub(crate) fn max_line_width(preview: &Preview) -> u16 {
+    let width = match &preview.content {
+        PreviewContent::PlainText(lines) => {
+            lines.iter().map(|l| Line::from(l.as_str()).width()).max()
+        }
+        PreviewContent::AnsiText(lines) => lines.iter().map(Line::width).max(),
+        PreviewContent::SyntectHighlightedText(lines) => lines
+            .iter()
+            .map(|l| {
+                l.iter()
+                    .map(|(_, s)| Span::raw(s.as_str()).width())
+                    .sum::<usize>()
+            })
+            .max(),
+        _ => None,
+    };
+    u16::try_from(width.unwrap_or(0)).unwrap_or(u16::MAX)
+}
+
+/// Skip the first `columns` columns of the given spans.
+///
+/// Wide characters that would be cut in half are dropped entirely.
+fn skip_columns<'a>(
+    spans: impl IntoIterator<Item = Span<'a>>,
+    columns: usize,
+) -> Vec<Span<'a>> {
+    let mut skipped = 0;
+    let mut result = Vec::new();
+    for span in spans {
+        if skipped >= columns {
+            result.push(span);
+            continue;
+        }
+        let mut content = String::new();
+        for c in span.content.chars() {
+            if skipped < columns {
+                skipped += c.width().unwrap_or(0);
+            } else {
+                content.push(c);
+            }
+        }
+        if !content.is_empty() {
+            result.push(Span::styled(content, span.style));
+        }
+    }
+    result
+}
+
+/// Highlights the occurrences of the terms of the current query in
+/// previews.
+struct QueryHighlighter {
+    terms: Vec<String>,
+    case_sensitive: bool,
+}
+
+impl QueryHighlighter {
+    fn new(query: &str, case_sensitivity: CaseSensitivity) -> Self {
+        let terms: Vec<String> = query
+            .split_whitespace()
+            // negated terms don't appear in matches
+            .filter(|term| !term.starts_with('!'))
+            .map(|term| {
+                term.trim_start_matches(['\'', '^'])
+                    .trim_end_matches('$')
+                    .to_string()
+            })
+            .filter(|term| !term.is_empty())
+            .collect();
+        let case_sensitive = match case_sensitivity {
+            CaseSensitivity::Sensitive => true,
+            CaseSensitivity::Insensitive => false,
+            CaseSensitivity::Smart => {
+                terms.iter().any(|t| t.chars().any(char::is_uppercase))
+            }
+        };
+        let terms = if case_sensitive {
+            terms
+        } else {
+            terms.iter().map(|t| t.to_ascii_lowercase()).collect()
+        };
+        QueryHighlighter {
+            terms,
+            case_sensitive,
+        }
+    }
+
+    /// The (sorted and non-overlapping) byte ranges of `line` matching one of
+    /// the terms.
+    fn match_ranges(&self, line: &str) -> Vec<Range<usize>> {
+        if self.terms.is_empty() {
+            return Vec::new();
+        }
+        // ASCII lowercasing keeps byte offsets unchanged
+        let line = if self.case_sensitive {
+            line.to_string()
+        } else {
+            line.to_ascii_lowercase()
+        };
+        let mut ranges: Vec<Range<usize>> = self
+            .terms
+            .iter()
+            .flat_map(|term| {
+                line.match_indices(term.as_str())
+                    .map(|(start, m)| start..start + m.len())
+            })
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => {
+                    last.end = last.end.max(range.end);
+                }
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    /// Split the given spans of a line so that matches get a distinct
+    /// background.
+    fn highlight<'a>(&self, spans: Vec<Span<'a>>) -> Vec<Span<'a>> {
+        let line: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        let ranges = self.match_ranges(&line);
+        if ranges.is_empty() {
+            return spans;
+        }
+        restyle_ranges(spans, &ranges, |style| {
+            style.bg(DEFAULT_PREVIEW_MATCH_BG)
+        })
+    }
+}
+
+/// Split the given spans of a line so that the given (sorted and
+/// non-overlapping) byte ranges of the line get restyled.
+fn restyle_ranges<'a>(
+    spans: Vec<Span<'a>>,
+    ranges: &[Range<usize>],
+    restyle: impl Fn(Style) -> Style,
+) -> Vec<Span<'a>> {
+    let mut result = Vec::new();
+    let mut offset = 0;
+    for span in spans {
+        let content = span.content.as_ref();
+        let end = offset + content.len();
+        let mut position = offset;
+        for range in ranges.iter().filter(|r| r.start < end && r.end > offset)
+        {
+            let start = range.start.max(offset);
+            let stop = range.end.min(end);
+            if start > position {
+                result.push(Span::styled(
+                    content[position - offset..start - offset].to_string(),
+                    span.style,
+                ));
+            }
+            result.push(Span::styled(
+                content[start - offset..stop - offset].to_string(),
+                restyle(span.style),
+            ));
+            position = stop;
+        }
+        if position < end {
+            result.push(Span::styled(
+                content[position - offset..].to_string(),
+                span.style,
+            ));
+        }
+        offset = end;
+    }
+    result
+}
+
+/// The byte range of `line` pointed at by a (1-based) column: the word
+/// starting at that column, or the single character there if it isn't part
+/// of a word.
+fn target_range(line: &str, column: usize) -> Option<Range<usize>> {
+    let (start, first) = line.char_indices().nth(column.checked_sub(1)?)?;
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let end = if is_word(first) {
+        line[start..]
+            .char_indices()
+            .find(|(_, c)| !is_word(*c))
+            .map_or(line.len(), |(i, _)| start + i)
+    } else {
+        start + first.len_utf8()
+    };
+    Some(start..end)
+}
+
+/// Highlight the span of a line pointed at by the column of the selected
+/// entry, if any.
+fn highlight_target(
+    spans: Vec<Span<'_>>,
+    column: Option<usize>,
+) -> Vec<Span<'_>> {
+    let line: String = spans.iter().map(|s| s.content.as_ref()).collect();
+    match column.and_then(|column| target_range(&line, column)) {
+        Some(range) => restyle_ranges(spans, &[range], |style| {
+            style
+                .bg(DEFAULT_PREVIEW_TARGET_BG)
+                .add_modifier(Modifier::BOLD)
+        }),
+        None => spans,
+    }
+}
 
 impl Television {
     pub(crate) fn draw_preview_title_block(
         &self,
         f: &mut Frame,
-        layout: &Layout,
+        area: Rect,
         selected_entry: &Entry,
         preview: &Arc<Preview>,
     ) -> Result<()> {
@@ -41,43 +310,66 @@ impl Television {
                     icon_str.push(' ');
                     icon_str
                 },
-                Style::default().fg(Color::from_str(icon.color)?),
+                Style::default()
+                    .fg(icon_color(icon, DEFAULT_PREVIEW_TITLE_FG)),
             ));
         }
         preview_title_spans.push(Span::styled(
             shrink_with_ellipsis(
                 &preview.title,
-                layout.preview_window.width.saturating_sub(4) as usize,
+                area.width.saturating_sub(4) as usize,
             ),
             Style::default().fg(DEFAULT_PREVIEW_TITLE_FG).bold(),
         ));
         let preview_title = Paragraph::new(Line::from(preview_title_spans))
-            .block(
-                Block::default()
-                    .padding(Padding::horizontal(1))
-                    .borders(Borders::ALL)
-                    .border_type(BorderType::Rounded)
-                    .border_style(Style::default().fg(BORDER_COLOR)),
-            )
+            .block(self.panel_block().padding(Padding::horizontal(1)))
             .alignment(Alignment::Left);
-        f.render_widget(preview_title, layout.preview_title);
+        f.render_widget(preview_title, area);
         Ok(())
     }
 
     pub(crate) fn draw_preview_content_block(
         &mut self,
         f: &mut Frame,
-        layout: &Layout,
+        area: Rect,
         selected_entry: &Entry,
         preview: &Arc<Preview>,
     ) -> Result<()> {
-        let preview_outer_block = Block::default()
+        let mut style = Style::default();
+        if let Some(background) = self.preview_background_color() {
+            style = style.bg(background);
+        }
+        let mut preview_outer_block = self
+            .panel_block()
             .title_top(Line::from(" Preview ").alignment(Alignment::Center))
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(BORDER_COLOR))
-            .style(Style::default())
+            .style(style)
             .padding(Padding::right(1));
+        if let Some((theme, selected_at)) = &self.theme_notification {
+            if selected_at.elapsed() < THEME_NOTIFICATION_DURATION {
+                preview_outer_block = preview_outer_block.title_bottom(
+                    Line::from(format!(" Theme: {theme} "))
+                        .alignment(Alignment::Right)
+                        .italic(),
+                );
+            }
+        }
+        // the scroll offset is initialized early so that the indicator
+        // matches what's drawn
+        let target_line = selected_entry
+            .line_number
+            .map(|l| u16::try_from(l).unwrap_or(0));
+        let visible_lines = preview_outer_block.inner(area).height;
+        self.maybe_init_preview_scroll(preview, target_line, visible_lines);
+        if let Some(indicator) = scroll_indicator(
+            self.preview_scroll.unwrap_or(0),
+            visible_lines,
+            preview.total_lines(),
+            preview.partial,
+        ) {
+            preview_outer_block = preview_outer_block.title_bottom(
+                Line::from(indicator).alignment(Alignment::Left),
+            );
+        }
 
         let preview_inner_block =
             Block::default().style(Style::default()).padding(Padding {
@@ -86,30 +378,98 @@ impl Television {
                 bottom: 0,
                 left: 1,
             });
-        let inner = preview_outer_block.inner(layout.preview_window);
-        f.render_widget(preview_outer_block, layout.preview_window);
-
-        //if let PreviewContent::Image(img) = &preview.content {
-        //    let image_component = StatefulImage::new(None);
-        //    frame.render_stateful_widget(
-        //        image_component,
-        //        inner,
-        //        &mut img.clone(),
-        //    );
-        //} else {
-        let preview_block = self.build_preview_paragraph(
-            preview_inner_block,
-            inner,
-            preview,
-            selected_entry
-                .line_number
-                .map(|l| u16::try_from(l).unwrap_or(0)),
-        );
-        f.render_widget(preview_block, inner);
-        //}
+        let inner = preview_outer_block.inner(area);
+        f.render_widget(preview_outer_block, area);
+
+        // account for the gutter and the inner block's padding
+        let gutter_width = if self.config.ui.preview_line_numbers {
+            PREVIEW_GUTTER_WIDTH
+        } else {
+            0
+        };
+        self.preview_content_width =
+            inner.width.saturating_sub(gutter_width + 2);
+        // wrapped lines don't need to be scrolled horizontally
+        let max_line_width = if self.config.ui.preview_wrap {
+            0
+        } else {
+            self.current_preview_max_line_width
+        };
+        self.preview_horizontal_scroll =
+            crate::television::clamp_horizontal_scroll(
+                self.preview_horizontal_scroll,
+                max_line_width,
+                self.preview_content_width,
+            );
+
+        if let PreviewContent::Image(image) = &preview.content {
+            let image_area = preview_inner_block.inner(inner);
+            f.render_widget(preview_inner_block, inner);
+            self.draw_image(f, image_area, image);
+        } else {
+            let preview_block = self.build_preview_paragraph(
+                preview_inner_block,
+                inner,
+                preview,
+                target_line,
+                selected_entry.column,
+            );
+            f.render_widget(preview_block, inner);
+        }
         Ok(())
     }
 
+    /// Draw an image with half blocks, or leave its area blank and record
+    /// where the graphics protocol of the terminal should draw it.
+    fn draw_image(&mut self, f: &mut Frame, area: Rect, image: &Arc<Image>) {
+        // graphics would be drawn over the remote control's popup
+        let protocol = if self.mode == Mode::Channel {
+            self.image_protocol
+        } else {
+            ImageProtocol::Halfblocks
+        };
+        let cached = self.image_cache.as_ref().is_some_and(|cached| {
+            Arc::ptr_eq(&cached.image, image)
+                && cached.area == area
+                && cached.protocol == protocol
+        });
+        if !cached {
+            let rendering = match graphics_sequence(
+                protocol,
+                image,
+                area.width,
+                area.height,
+                cell_size(),
+            ) {
+                Some(sequence) => ImageRendering::Graphics(ImagePlacement {
+                    area,
+                    protocol,
+                    sequence: Arc::from(sequence),
+                }),
+                None => ImageRendering::Halfblocks(halfblocks(
+                    image,
+                    area.width,
+                    area.height,
+                )),
+            };
+            self.image_cache = Some(CachedImage {
+                image: image.clone(),
+                area,
+                protocol,
+                rendering,
+            });
+        }
+        match self.image_cache.as_ref().map(|cached| &cached.rendering) {
+            Some(ImageRendering::Graphics(placement)) => {
+                self.image_placement = Some(placement.clone());
+            }
+            Some(ImageRendering::Halfblocks(lines)) => {
+                f.render_widget(Paragraph::new(lines.clone()), area);
+            }
+            None => {}
+        }
+    }
+
     #[allow(dead_code)]
     const FILL_CHAR_SLANTED: char = '╱';
     const FILL_CHAR_EMPTY: char = ' ';
@@ -120,42 +480,75 @@ impl Television {
         inner: Rect,
         preview: &Arc<Preview>,
         target_line: Option<u16>,
+        target_column: Option<usize>,
     ) -> Paragraph<'b> {
-        self.maybe_init_preview_scroll(target_line, inner.height);
+        self.maybe_init_preview_scroll(preview, target_line, inner.height);
+        let layout = PreviewLayout {
+            // account for the inner block's padding
+            wrap_width: self
+                .config
+                .ui
+                .preview_wrap
+                .then(|| usize::from(inner.width.saturating_sub(2))),
+            horizontal_scroll: usize::from(self.preview_horizontal_scroll),
+            scroll: self.preview_scroll.unwrap_or(0),
+        };
+        let highlighter = QueryHighlighter::new(
+            self.results_picker.input.value(),
+            self.case_sensitivity,
+        );
+        let gutter = Gutter {
+            line_number_fg: self
+                .config
+                .ui
+                .preview_line_numbers
+                .then_some(self.results_list_colors.result_line_number_fg),
+            blame: None,
+        };
         match &preview.content {
             PreviewContent::PlainText(content) => {
                 let mut lines = Vec::new();
                 for (i, line) in content.iter().enumerate() {
-                    lines.push(Line::from(vec![
-                        build_line_number_span(i + 1).style(Style::default().fg(
-                            if matches!(
-                                target_line,
-                                Some(l) if l == u16::try_from(i).unwrap_or(0) + 1
-                            )
-                            {
-                                DEFAULT_PREVIEW_GUTTER_SELECTED_FG
-                            } else {
-                                DEFAULT_PREVIEW_GUTTER_FG
-                            },
-                        )),
-                        Span::styled(" │ ",
-                                     Style::default().fg(DEFAULT_PREVIEW_GUTTER_FG).dim()),
-                        Span::styled(
-                            line.to_string(),
-                            Style::default().fg(DEFAULT_PREVIEW_CONTENT_FG).bg(
-                                if matches!(target_line, Some(l) if l == u16::try_from(i).unwrap() + 1) {
-                                    DEFAULT_SELECTED_PREVIEW_BG
-                                } else {
-                                    Color::Reset
-                                },
-                            ),
-                        ),
-                    ]));
+                    let selected =
+                        target_line.is_some_and(|l| usize::from(l) == i + 1);
+                    // the background is only set on the selected line so
+                    // that others keep the background of the pane
+                    let mut style =
+                        Style::default().fg(DEFAULT_PREVIEW_CONTENT_FG);
+                    if selected {
+                        style = style.bg(DEFAULT_SELECTED_PREVIEW_BG);
+                    }
+                    let spans = highlighter.highlight(vec![Span::styled(
+                        line.to_string(),
+                        style,
+                    )]);
+                    lines.extend(layout.rows(
+                        i,
+                        gutter.spans(i, selected),
+                        if selected {
+                            highlight_target(spans, target_column)
+                        } else {
+                            spans
+                        },
+                    ));
                 }
                 let text = Text::from(lines);
                 Paragraph::new(text)
                     .block(preview_block)
-                    .scroll((self.preview_scroll.unwrap_or(0), 0))
+                    .scroll((layout.paragraph_scroll(), 0))
+            }
+            PreviewContent::AnsiText(content) => {
+                let mut lines = Vec::new();
+                for (i, line) in content.iter().enumerate() {
+                    lines.extend(layout.rows(
+                        i,
+                        gutter.spans(i, false),
+                        line.spans.clone(),
+                    ));
+                }
+                Paragraph::new(Text::from(lines))
+                    .block(preview_block)
+                    .scroll((layout.paragraph_scroll(), 0))
             }
             PreviewContent::PlainTextWrapped(content) => {
                 let mut lines = Vec::new();
@@ -173,13 +566,19 @@ impl Television {
             PreviewContent::SyntectHighlightedText(highlighted_lines) => {
                 compute_paragraph_from_highlighted_lines(
                     highlighted_lines,
+                    &Gutter {
+                        blame: preview.blame.as_deref(),
+                        ..gutter
+                    },
+                    &highlighter,
                     target_line.map(|l| l as usize),
-                    self.preview_scroll.unwrap_or(0),
+                    target_column,
+                    &layout,
                     self.preview_pane_height,
                 )
                 .block(preview_block)
                 .alignment(Alignment::Left)
-                .scroll((self.preview_scroll.unwrap_or(0), 0))
+                .scroll((layout.paragraph_scroll(), 0))
             }
             // meta
             PreviewContent::Loading => self
@@ -209,18 +608,62 @@ impl Television {
                 .block(preview_block)
                 .alignment(Alignment::Left)
                 .style(Style::default().add_modifier(Modifier::ITALIC)),
+            PreviewContent::TimedOut => self
+                .build_meta_preview_paragraph(
+                    inner,
+                    PREVIEW_TIMED_OUT_MSG,
+                    Self::FILL_CHAR_EMPTY,
+                )
+                .block(preview_block)
+                .alignment(Alignment::Left)
+                .style(Style::default().add_modifier(Modifier::ITALIC)),
+            PreviewContent::BrokenSymlink(target) => self
+                .build_meta_preview_paragraph(
+                    inner,
+                    &format!("{BROKEN_SYMLINK_MSG} -> {target}"),
+                    Self::FILL_CHAR_EMPTY,
+                )
+                .block(preview_block)
+                .alignment(Alignment::Left)
+                .style(Style::default().add_modifier(Modifier::ITALIC)),
+            PreviewContent::SpecialFile(kind) => self
+                .build_meta_preview_paragraph(
+                    inner,
+                    &format!("{SPECIAL_FILE_MSG}: {kind}"),
+                    Self::FILL_CHAR_EMPTY,
+                )
+                .block(preview_block)
+                .alignment(Alignment::Left)
+                .style(Style::default().add_modifier(Modifier::ITALIC)),
             _ => Paragraph::new(Text::raw(EMPTY_STRING)),
         }
     }
 
+    /// Initialize the scroll offset of a newly shown preview so that its
+    /// target line is centered.
+    ///
+    /// This waits for the preview to be loaded (up to the target line) so
+    /// that the offset can be clamped to its length.
     pub fn maybe_init_preview_scroll(
         &mut self,
+        preview: &Preview,
         target_line: Option<u16>,
         height: u16,
     ) {
-        if self.preview_scroll.is_none() && !self.channel.running() {
-            self.preview_scroll =
-                Some(target_line.unwrap_or(0).saturating_sub(height / 3));
+        let total_lines =
+            u16::try_from(preview.total_lines()).unwrap_or(u16::MAX);
+        let target_loaded = !preview.partial
+            || target_line.map_or(true, |line| line <= total_lines);
+        if self.preview_scroll.is_none()
+            && !self.channel.running()
+            && !matches!(preview.content, PreviewContent::Loading)
+            && target_loaded
+        {
+            self.preview_scroll = Some(initial_preview_scroll(
+                target_line,
+                height,
+                (!preview.partial).then_some(total_lines),
+            ));
         }
     }
 
@@ -295,63 +738,237 @@ impl Television {
     }
 }
 
+/// The scroll offset centering `target_line` (1-based) in a preview pane of
+/// the given height.
+///
+/// When the total number of lines of the preview is known, the offset is
+/// clamped so that the pane stays filled near the end of the preview.
+fn initial_preview_scroll(
+    target_line: Option<u16>,
+    height: u16,
+    total_lines: Option<u16>,
+) -> u16 {
+    let Some(target_line) = target_line else {
+        return 0;
+    };
+    let scroll = target_line.saturating_sub(1).saturating_sub(height / 2);
+    total_lines
+        .map_or(scroll, |total| scroll.min(total.saturating_sub(height)))
+}
+
+/// The position of the first visible line of a preview scrolled by
+/// `scroll` lines, e.g. ` 41/200 20% `, or `None` if the whole preview
+/// fits in the `visible_lines` of the pane.
+///
+/// The percentage goes from 0 at the top to 100 once the last line is
+/// visible. A `+` marks previews which are only partially loaded.
+fn scroll_indicator(
+    scroll: u16,
+    visible_lines: u16,
+    total_lines: usize,
+    partial: bool,
+) -> Option<String> {
+    let (scroll, visible_lines) =
+        (usize::from(scroll), usize::from(visible_lines));
+    if !partial && total_lines <= visible_lines {
+        return None;
+    }
+    let max_scroll = total_lines.saturating_sub(visible_lines).max(1);
+    let percentage = (scroll * 100 / max_scroll).min(100);
+    Some(format!(
+        " {}/{}{} {}% ",
+        (scroll + 1).min(total_lines),
+        total_lines,
+        if partial { "+" } else { "" },
+        percentage
+    ))
+}
+
+/// How the lines of a preview are laid out in the preview pane.
+struct PreviewLayout {
+    /// The width (in columns) at which lines are wrapped, `None` if they
+    /// aren't wrapped.
+    wrap_width: Option<usize>,
+    /// The number of columns hidden on the left of unwrapped lines.
+    horizontal_scroll: usize,
+    /// The index of the first visible line.
+    scroll: u16,
+}
+
+impl PreviewLayout {
+    /// The rows displaying the line at index `i`, made of the given gutter
+    /// and content spans.
+    ///
+    /// Wrapped lines span several rows, the gutter of continuation rows
+    /// being left blank. Since a line can then take any number of rows,
+    /// lines above the scroll offset are skipped instead of being scrolled
+    /// past (see [`PreviewLayout::paragraph_scroll`]).
+    fn rows<'a>(
+        &self,
+        i: usize,
+        gutter: Vec<Span<'a>>,
+        content: Vec<Span<'a>>,
+    ) -> Vec<Line<'a>> {
+        let Some(wrap_width) = self.wrap_width else {
+            let mut spans = gutter;
+            spans.extend(skip_columns(content, self.horizontal_scroll));
+            return vec![Line::from(spans)];
+        };
+        if i < usize::from(self.scroll) {
+            return Vec::new();
+        }
+        let gutter_width: usize = gutter.iter().map(Span::width).sum();
+        let blank_gutter: Vec<Span<'a>> = match gutter.split_last() {
+            // keep the separator
+            Some((separator, numbers)) => vec![
+                Span::raw(
+                    " ".repeat(numbers.iter().map(Span::width).sum::<usize>()),
+                ),
+                separator.clone(),
+            ],
+            None => Vec::new(),
+        };
+        wrap_line(Line::from(content), wrap_width.saturating_sub(gutter_width))
+            .into_iter()
+            .enumerate()
+            .map(|(row, line)| {
+                let mut spans = if row == 0 {
+                    gutter.clone()
+                } else {
+                    blank_gutter.clone()
+                };
+                spans.extend(line.spans);
+                Line::from(spans)
+            })
+            .collect()
+    }
+
+    /// The vertical scroll offset of the paragraph holding the rows.
+    fn paragraph_scroll(&self) -> u16 {
+        if self.wrap_width.is_some() {
+            0
+        } else {
+            self.scroll
+        }
+    }
+}
+
+/// What's shown in the gutter of previews, left of their content.
+#[derive(Clone, Copy)]
+struct Gutter<'a> {
+    /// The color of line numbers, which are hidden if `None`.
+    line_number_fg: Option<Color>,
+    /// The blame annotation of each line, if any.
+    blame: Option<&'a [String]>,
+}
+
+impl Gutter<'_> {
+    /// The gutter spans of the line at index `i`.
+    fn spans(&self, i: usize, selected: bool) -> Vec<Span<'static>> {
+        let mut spans = Vec::new();
+        if let Some(line_number_fg) = self.line_number_fg {
+            spans.push(build_line_number_span(i + 1).style(
+                Style::default().fg(if selected {
+                    DEFAULT_PREVIEW_GUTTER_SELECTED_FG
+                } else {
+                    line_number_fg
+                }),
+            ));
+        }
+        if let Some(blame) = self.blame {
+            let width = blame.first().map_or(0, |b| b.chars().count());
+            spans.push(Span::styled(
+                format!(" {:width$}", blame.get(i).map_or("", String::as_str)),
+                Style::default().fg(DEFAULT_PREVIEW_GUTTER_FG).dim(),
+            ));
+        }
+        if !spans.is_empty() {
+            spans.push(Span::styled(
+                " │ ",
+                Style::default().fg(DEFAULT_PREVIEW_GUTTER_FG).dim(),
+            ));
+        }
+        spans
+    }
+}
+
 fn build_line_number_span<'a>(line_number: usize) -> Span<'a> {
     Span::from(format!("{line_number:5} "))
 }
 
 fn compute_paragraph_from_highlighted_lines(
     highlighted_lines: &[Vec<(syntect::highlighting::Style, String)>],
+    gutter: &Gutter,
+    highlighter: &QueryHighlighter,
     line_specifier: Option<usize>,
-    scroll: u16,
+    column: Option<usize>,
+    layout: &PreviewLayout,
     preview_pane_height: u16,
 ) -> Paragraph<'static> {
-    let preview_lines: Vec<Line> = highlighted_lines
+    Paragraph::new(highlighted_preview_lines(
+        highlighted_lines,
+        gutter,
+        highlighter,
+        line_specifier,
+        column,
+        layout,
+        preview_pane_height,
+    ))
+}
+
+/// Build the lines of a syntax-highlighted preview.
+///
+/// Only the lines visible with the given scroll offset are actually built,
+/// the others are left empty. The span at `column`, if any, of the
+/// specified line is highlighted.
+fn highlighted_preview_lines(
+    highlighted_lines: &[Vec<(syntect::highlighting::Style, String)>],
+    gutter: &Gutter,
+    highlighter: &QueryHighlighter,
+    line_specifier: Option<usize>,
+    column: Option<usize>,
+    layout: &PreviewLayout,
+    preview_pane_height: u16,
+) -> Vec<Line<'static>> {
+    let scroll = usize::from(layout.scroll);
+    highlighted_lines
         .iter()
         .enumerate()
-        .map(|(i, l)| {
-            if i < scroll as usize
-                || i >= (scroll + preview_pane_height) as usize
-            {
-                return Line::from(Span::raw(EMPTY_STRING));
+        .flat_map(|(i, l)| {
+            if i < scroll || i >= scroll + usize::from(preview_pane_height) {
+                return if layout.wrap_width.is_some() {
+                    Vec::new()
+                } else {
+                    vec![Line::from(Span::raw(EMPTY_STRING))]
+                };
             }
-            let line_number =
-                build_line_number_span(i + 1).style(Style::default().fg(
-                    if line_specifier.is_some()
-                        && i == line_specifier.unwrap() - 1
-                    {
-                        DEFAULT_PREVIEW_GUTTER_SELECTED_FG
-                    } else {
-                        DEFAULT_PREVIEW_GUTTER_FG
-                    },
-                ));
-            Line::from_iter(
-                std::iter::once(line_number)
-                    .chain(std::iter::once(Span::styled(
-                        " │ ",
-                        Style::default().fg(DEFAULT_PREVIEW_GUTTER_FG).dim(),
-                    )))
-                    .chain(l.iter().cloned().map(|sr| {
+            let selected = line_specifier.is_some_and(|line| i + 1 == line);
+            let spans = highlighter.highlight(
+                l.iter()
+                    .map(|sr| {
                         convert_syn_region_to_span(
-                            &(sr.0, sr.1),
-                            if line_specifier.is_some()
-                                && i == line_specifier.unwrap() - 1
-                            {
-                                Some(SyntectColor {
-                                    r: 50,
-                                    g: 50,
-                                    b: 50,
-                                    a: 255,
-                                })
-                            } else {
-                                None
-                            },
+                            sr,
+                            selected.then_some(SyntectColor {
+                                r: 50,
+                                g: 50,
+                                b: 50,
+                                a: 255,
+                            }),
                         )
-                    })),
+                    })
+                    .collect(),
+            );
+            layout.rows(
+                i,
+                gutter.spans(i, selected),
+                if selected {
+                    highlight_target(spans, column)
+                } else {
+                    spans
+                },
             )
         })
-        .collect();
-
-    Paragraph::new(preview_lines)
+        .collect()
 }
 
 pub fn convert_syn_region_to_span<'a>(
@@ -377,3 +994,314 @@ fn convert_syn_color_to_ratatui_color(
 ) -> Color {
     Color::Rgb(color.r, color.g, color.b)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use television_channels::channels::CliTvChannel;
+    use television_previewers::previewers::image::decode_image;
+
+    #[test]
+    fn test_draw_image() {
+        let bytes = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/crates/television-previewers/src/previewers/fixtures/tiny.png"
+        ))
+        .unwrap();
+        let image = Arc::new(
+            decode_image(&bytes, [0; 3], &AtomicBool::new(false)).unwrap(),
+        );
+        let mut television = Television::new(CliTvChannel::Env.to_channel());
+        television.image_protocol = ImageProtocol::Kitty;
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(20, 10))
+                .unwrap();
+        let area = Rect::new(2, 1, 10, 5);
+        terminal
+            .draw(|f| television.draw_image(f, area, &image))
+            .unwrap();
+        let placement = television.image_placement.clone().unwrap();
+        assert_eq!(placement.area, area);
+        assert!(placement.sequence.starts_with("\x1b_Ga=T"));
+        // the cells under the image are left blank
+        assert_eq!(terminal.backend().buffer()[(2, 1)].symbol(), " ");
+
+        // the remote control is drawn over the preview
+        television.image_placement = None;
+        television.mode = Mode::RemoteControl;
+        terminal
+            .draw(|f| television.draw_image(f, area, &image))
+            .unwrap();
+        assert!(television.image_placement.is_none());
+        assert_eq!(terminal.backend().buffer()[(2, 1)].symbol(), "▀");
+    }
+
+    #[test]
+    fn test_skip_columns() {
+        let red = Style::default().fg(Color::Red);
+        let spans = vec![Span::raw("abc"), Span::styled("defg", red)];
+        assert_eq!(skip_columns(spans.clone(), 0), spans);
+        assert_eq!(
+            skip_columns(spans.clone(), 2),
+            vec![Span::raw("c"), Span::styled("defg", red)]
+        );
+        assert_eq!(
+            skip_columns(spans.clone(), 3),
+            vec![Span::styled("defg", red)]
+        );
+        assert_eq!(
+            skip_columns(spans.clone(), 5),
+            vec![Span::styled("fg", red)]
+        );
+        assert!(skip_columns(spans, 10).is_empty());
+    }
+
+    #[test]
+    fn test_scrolled_preview_gutter() {
+        let highlighted_lines: Vec<_> = (1..=100)
+            .map(|i| {
+                vec![(syntect::highlighting::Style::default(), format!("{i}"))]
+            })
+            .collect();
+        let highlighter = QueryHighlighter::new("", CaseSensitivity::Smart);
+        let layout = PreviewLayout {
+            wrap_width: None,
+            horizontal_scroll: 0,
+            scroll: 40,
+        };
+        let gutter = Gutter {
+            line_number_fg: Some(Color::Yellow),
+            blame: None,
+        };
+        let lines = highlighted_preview_lines(
+            &highlighted_lines,
+            &gutter,
+            &highlighter,
+            None,
+            None,
+            &layout,
+            10,
+        );
+        assert_eq!(lines.len(), 100);
+        // lines above the scroll offset aren't rendered
+        assert_eq!(lines[39].to_string(), "");
+        // the numbers are those of the lines of the file
+        assert_eq!(lines[40].to_string(), "   41  │ 41");
+        assert_eq!(lines[49].to_string(), "   50  │ 50");
+        assert_eq!(lines[40].spans[0].style.fg, Some(Color::Yellow));
+        assert_eq!(lines[50].to_string(), "");
+
+        let gutter = Gutter {
+            line_number_fg: None,
+            blame: None,
+        };
+        let lines = highlighted_preview_lines(
+            &highlighted_lines,
+            &gutter,
+            &highlighter,
+            None,
+            None,
+            &layout,
+            10,
+        );
+        assert_eq!(lines[40].to_string(), "41");
+    }
+
+    #[test]
+    fn test_target_range() {
+        let line = "    let total = compute(x);";
+        assert_eq!(&line[target_range(line, 9).unwrap()], "total");
+        // a column in the middle of a word points at its end
+        assert_eq!(&line[target_range(line, 19).unwrap()], "mpute");
+        assert_eq!(&line[target_range(line, 24).unwrap()], "(");
+        assert_eq!(target_range(line, 0), None);
+        assert_eq!(target_range(line, 100), None);
+        // columns are counted in characters
+        let line = "é = ünïcode";
+        assert_eq!(&line[target_range(line, 5).unwrap()], "ünïcode");
+    }
+
+    #[test]
+    fn test_target_span_highlight() {
+        let highlighted_lines: Vec<_> = ["fn main() {", "    run(args);", "}"]
+            .iter()
+            .map(|line| {
+                vec![(
+                    syntect::highlighting::Style::default(),
+                    (*line).to_string(),
+                )]
+            })
+            .collect();
+        let highlighter = QueryHighlighter::new("", CaseSensitivity::Smart);
+        let layout = PreviewLayout {
+            wrap_width: None,
+            horizontal_scroll: 0,
+            scroll: 0,
+        };
+        let gutter = Gutter {
+            line_number_fg: None,
+            blame: None,
+        };
+        let lines = highlighted_preview_lines(
+            &highlighted_lines,
+            &gutter,
+            &highlighter,
+            Some(2),
+            Some(9),
+            &layout,
+            10,
+        );
+        let spans: Vec<(&str, Option<Color>)> = lines[1]
+            .spans
+            .iter()
+            .map(|s| (s.content.as_ref(), s.style.bg))
+            .collect();
+        let selected_bg = Some(Color::Rgb(50, 50, 50));
+        assert_eq!(
+            spans,
+            vec![
+                ("    run(", selected_bg),
+                ("args", Some(DEFAULT_PREVIEW_TARGET_BG)),
+                (");", selected_bg),
+            ]
+        );
+        // other lines aren't affected
+        assert_eq!(lines[0].spans.len(), 1);
+        assert_eq!(lines[2].spans.len(), 1);
+    }
+
+    #[test]
+    fn test_wrapped_preview_line() {
+        let red = Style::default().fg(Color::Red);
+        let gutter = Gutter {
+            line_number_fg: Some(Color::Yellow),
+            blame: None,
+        };
+        let layout = PreviewLayout {
+            // 9 columns of gutter, 6 of content
+            wrap_width: Some(15),
+            horizontal_scroll: 0,
+            scroll: 0,
+        };
+        let rows = layout.rows(
+            0,
+            gutter.spans(0, false),
+            vec![Span::styled("fn main", red), Span::raw("() {}")],
+        );
+        let rows: Vec<String> = rows.iter().map(ToString::to_string).collect();
+        assert_eq!(rows, vec!["    1  │ fn mai", "       │ n() {}"]);
+        // styles are kept across rows
+        let rows = layout.rows(
+            0,
+            Vec::new(),
+            vec![Span::styled("a".repeat(40), red)],
+        );
+        assert_eq!(rows.len(), 3);
+        assert!(rows.iter().all(|row| row.spans[0].style == red));
+
+        let unwrapped = PreviewLayout {
+            wrap_width: None,
+            ..layout
+        };
+        assert_eq!(
+            unwrapped
+                .rows(0, Vec::new(), vec![Span::raw("a".repeat(40))])
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_initial_preview_scroll() {
+        // line 50 ends up in the middle of a 20 lines high pane
+        assert_eq!(initial_preview_scroll(Some(50), 20, Some(200)), 39);
+        assert_eq!(initial_preview_scroll(Some(50), 20, None), 39);
+        // near the top
+        assert_eq!(initial_preview_scroll(Some(5), 20, Some(200)), 0);
+        // near the bottom, the pane stays filled
+        assert_eq!(initial_preview_scroll(Some(195), 20, Some(200)), 180);
+        // shorter than the pane
+        assert_eq!(initial_preview_scroll(Some(8), 20, Some(10)), 0);
+        assert_eq!(initial_preview_scroll(None, 20, Some(200)), 0);
+    }
+
+    #[test]
+    fn test_scroll_indicator() {
+        // top, middle and bottom of a 200 lines preview in a 20 lines pane
+        assert_eq!(scroll_indicator(0, 20, 200, false).unwrap(), " 1/200 0% ");
+        assert_eq!(
+            scroll_indicator(90, 20, 200, false).unwrap(),
+            " 91/200 50% "
+        );
+        assert_eq!(
+            scroll_indicator(180, 20, 200, false).unwrap(),
+            " 181/200 100% "
+        );
+        // previews can be scrolled past their last line
+        assert_eq!(
+            scroll_indicator(190, 20, 200, false).unwrap(),
+            " 191/200 100% "
+        );
+        assert_eq!(
+            scroll_indicator(10, 20, 100, true).unwrap(),
+            " 11/100+ 12% "
+        );
+        // everything fits
+        assert_eq!(scroll_indicator(0, 20, 20, false), None);
+        assert_eq!(scroll_indicator(0, 20, 3, false), None);
+    }
+
+    #[test]
+    fn test_query_match_ranges() {
+        let highlighter =
+            QueryHighlighter::new("main ^fn", CaseSensitivity::Smart);
+        assert_eq!(
+            highlighter.match_ranges("fn Main() { main_loop(); }"),
+            vec![0..2, 3..7, 12..16]
+        );
+        // smart case: an uppercase letter makes the query case-sensitive
+        let highlighter =
+            QueryHighlighter::new("Main", CaseSensitivity::Smart);
+        assert_eq!(highlighter.match_ranges("main Main"), vec![5..9]);
+        // overlapping matches are merged, negated terms are ignored
+        let highlighter =
+            QueryHighlighter::new("abc bcd !a", CaseSensitivity::Smart);
+        assert_eq!(highlighter.match_ranges("xabcdx"), vec![1..5]);
+        assert!(QueryHighlighter::new("", CaseSensitivity::Smart)
+            .match_ranges("anything")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_query_highlight_spans() {
+        let red = Style::default().fg(Color::Red);
+        let matched = red.bg(DEFAULT_PREVIEW_MATCH_BG);
+        let highlighter =
+            QueryHighlighter::new("needle", CaseSensitivity::Smart);
+        // a match spanning two spans keeps the style of each
+        assert_eq!(
+            highlighter.highlight(vec![
+                Span::styled("a nee", red),
+                Span::raw("dle b"),
+            ]),
+            vec![
+                Span::styled("a ", red),
+                Span::styled("nee", matched),
+                Span::raw("dle").bg(DEFAULT_PREVIEW_MATCH_BG),
+                Span::raw(" b"),
+            ]
+        );
+        let spans = vec![Span::styled("nothing here", red)];
+        assert_eq!(highlighter.highlight(spans.clone()), spans);
+    }
+
+    #[test]
+    fn test_skip_columns_wide_chars() {
+        // wide characters that would be cut in half are skipped entirely
+        assert_eq!(
+            skip_columns(vec![Span::raw("日本語")], 3),
+            vec![Span::raw("語")]
+        );
+    }
+}
@@ -1,16 +1,14 @@
+use crate::config::ResultsOverflow;
 use crate::television::Television;
 use crate::ui::logo::build_remote_logo_paragraph;
 use crate::ui::mode::mode_color;
 use crate::ui::results::{build_results_list, ResultsListColors};
-use crate::ui::BORDER_COLOR;
 use color_eyre::eyre::Result;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::prelude::Style;
 use ratatui::style::{Color, Stylize};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{
-    Block, BorderType, Borders, ListDirection, Padding, Paragraph,
-};
+use ratatui::widgets::{Block, ListDirection, Padding, Paragraph};
 use ratatui::Frame;
 use television_channels::channels::OnAir;
 
@@ -38,10 +36,8 @@ impl Television {
     }
 
     fn draw_rc_channels(&mut self, f: &mut Frame, area: &Rect) -> Result<()> {
-        let rc_block = Block::default()
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(BORDER_COLOR))
+        let rc_block = self
+            .panel_block()
             .style(Style::default())
             .padding(Padding::right(1));
 
@@ -52,8 +48,8 @@ impl Television {
         }
 
         let entries = self.remote_control.results(
-            area.height.saturating_sub(2).into(),
-            u32::try_from(self.rc_picker.view_offset)?,
+            rc_block.inner(*area).height.into(),
+            self.rc_picker.offset(),
         );
 
         let channel_list = build_results_list(
@@ -65,6 +61,10 @@ impl Television {
                     .result_name_fg(mode_color(self.mode)),
             ),
             self.config.ui.use_nerd_font_icons,
+            &self.config.ui.highlight_symbol,
+            None,
+            ResultsOverflow::Truncate,
+            usize::from(area.width),
         );
 
         f.render_stateful_widget(
@@ -76,13 +76,11 @@ impl Television {
     }
 
     fn draw_rc_input(&mut self, f: &mut Frame, area: &Rect) -> Result<()> {
-        let input_block = Block::default()
+        let input_block = self
+            .panel_block()
             .title_top(
                 Line::from("Remote Control").alignment(Alignment::Center),
             )
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(BORDER_COLOR))
             .style(Style::default());
 
         let input_block_inner = input_block.inner(*area);
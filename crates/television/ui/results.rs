@@ -1,31 +1,49 @@
+use crate::config::{ResultsOrder, ResultsOverflow};
 use crate::television::Television;
+use crate::ui::icon_color;
 use crate::ui::layout::Layout;
-use crate::ui::BORDER_COLOR;
 use color_eyre::eyre::Result;
-use ratatui::layout::Alignment;
-use ratatui::prelude::{Color, Line, Span, Style};
+use indexmap::IndexMap;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Margin, Rect};
+use ratatui::prelude::{Color, Line, Modifier, Span, Style, Text};
 use ratatui::widgets::{
-    Block, BorderType, Borders, List, ListDirection, Padding,
+    Block, HighlightSpacing, List, ListDirection, ListItem, ListState,
+    Padding, Scrollbar, ScrollbarOrientation, ScrollbarState, StatefulWidget,
 };
 use ratatui::Frame;
-use std::str::FromStr;
 use television_channels::channels::OnAir;
 use television_channels::entry::Entry;
 use television_utils::strings::{
-    next_char_boundary, slice_at_char_boundaries,
+    max_line_length_for_width, next_char_boundary,
+    preprocess_line_with_max_length, shrink_with_ellipsis,
+    shrink_with_ellipsis_and_ranges, try_slice_at_char_boundaries,
+    EMPTY_STRING,
 };
+use tracing::warn;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 // Styles
 const DEFAULT_RESULT_NAME_FG: Color = Color::Blue;
 const DEFAULT_RESULT_PREVIEW_FG: Color = Color::Rgb(150, 150, 150);
 const DEFAULT_RESULT_LINE_NUMBER_FG: Color = Color::Yellow;
 const DEFAULT_RESULT_SELECTED_BG: Color = Color::Rgb(50, 50, 50);
+const DEFAULT_RESULT_MATCH_FG: Color = Color::Red;
+const DEFAULT_RESULT_MARKER_FG: Color = Color::Green;
 
+const MARKER_SYMBOL: &str = "✓ ";
+const NO_MARKER_SYMBOL: &str = "  ";
+
+#[derive(Clone, Copy, Debug)]
 pub struct ResultsListColors {
     pub result_name_fg: Color,
     pub result_preview_fg: Color,
     pub result_line_number_fg: Color,
     pub result_selected_bg: Color,
+    pub result_match_fg: Color,
+    pub result_match_bg: Option<Color>,
+    /// Whether to draw matches spanning whole words in bold.
+    pub bold_whole_word_matches: bool,
 }
 
 impl Default for ResultsListColors {
@@ -35,11 +53,13 @@ impl Default for ResultsListColors {
             result_preview_fg: DEFAULT_RESULT_PREVIEW_FG,
             result_line_number_fg: DEFAULT_RESULT_LINE_NUMBER_FG,
             result_selected_bg: DEFAULT_RESULT_SELECTED_BG,
+            result_match_fg: DEFAULT_RESULT_MATCH_FG,
+            result_match_bg: None,
+            bold_whole_word_matches: false,
         }
     }
 }
 
-#[allow(dead_code)]
 impl ResultsListColors {
     pub fn result_name_fg(mut self, color: Color) -> Self {
         self.result_name_fg = color;
@@ -60,65 +80,264 @@ impl ResultsListColors {
         self.result_selected_bg = color;
         self
     }
+
+    pub fn result_match_fg(mut self, color: Color) -> Self {
+        self.result_match_fg = color;
+        self
+    }
+
+    pub fn result_match_bg(mut self, color: Color) -> Self {
+        self.result_match_bg = Some(color);
+        self
+    }
+
+    pub fn bold_whole_word_matches(mut self, bold: bool) -> Self {
+        self.bold_whole_word_matches = bold;
+        self
+    }
+
+    /// The style used to draw the matched parts of results.
+    fn match_style(&self) -> Style {
+        let style = Style::default().fg(self.result_match_fg);
+        match self.result_match_bg {
+            Some(bg) => style.bg(bg),
+            None => style,
+        }
+    }
+
+    /// The style used to draw matches spanning whole words.
+    fn whole_word_match_style(&self) -> Style {
+        if self.bold_whole_word_matches {
+            self.match_style().add_modifier(Modifier::BOLD)
+        } else {
+            self.match_style()
+        }
+    }
+}
+
+/// Split a line into several lines that are at most `width` columns wide,
+/// preserving the style of each span.
+pub(crate) fn wrap_line(line: Line<'_>, width: usize) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut current_line = Vec::new();
+    let mut current_width = 0;
+    for span in line.spans {
+        let mut chunk = String::new();
+        for c in span.content.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if current_width + char_width > width && current_width > 0 {
+                if !chunk.is_empty() {
+                    current_line.push(Span::styled(
+                        std::mem::take(&mut chunk),
+                        span.style,
+                    ));
+                }
+                lines.push(Line::from(std::mem::take(&mut current_line)));
+                current_width = 0;
+            }
+            chunk.push(c);
+            current_width += char_width;
+        }
+        if !chunk.is_empty() {
+            current_line.push(Span::styled(chunk, span.style));
+        }
+    }
+    if !current_line.is_empty() || lines.is_empty() {
+        lines.push(Line::from(current_line));
+    }
+    lines
+}
+
+/// Sort the given `(start, end)` ranges and merge those that overlap or
+/// touch, so that each highlighted chunk is a single span.
+fn merge_ranges(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut ranges = ranges.to_vec();
+    ranges.sort_unstable();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => {
+                *last_end = (*last_end).max(end);
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Whether the `start..end` range of `text` spans whole words, i.e. isn't
+/// preceded or followed by a word character.
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    match (text.get(..start), text.get(end..)) {
+        (Some(before), Some(after)) => {
+            !before.chars().next_back().is_some_and(is_word_char)
+                && !after.chars().next().is_some_and(is_word_char)
+        }
+        _ => false,
+    }
+}
+
+/// Split `text` into spans, highlighting the given match ranges.
+///
+/// Matches spanning whole words are drawn with `whole_word_match_style`.
+fn highlighted_spans<'s>(
+    text: &'s str,
+    match_ranges: &[(u32, u32)],
+    style: Style,
+    match_style: Style,
+    whole_word_match_style: Style,
+) -> Vec<Span<'s>> {
+    // match ranges come from the matcher so invalid ones are bugs, which
+    // are logged rather than silently rendered as empty spans
+    let slice = |start, end| {
+        try_slice_at_char_boundaries(text, start, end).unwrap_or_else(|| {
+            warn!("Invalid range {}..{} in {:?}", start, end, text);
+            EMPTY_STRING
+        })
+    };
+    let mut spans = Vec::new();
+    let mut last_match_end = 0;
+    for (start, end) in merge_ranges(match_ranges)
+        .into_iter()
+        .map(|(s, e)| (s as usize, e as usize))
+    {
+        spans.push(Span::styled(slice(last_match_end, start), style));
+        let style = if is_whole_word(text, start, end) {
+            whole_word_match_style
+        } else {
+            match_style
+        };
+        spans.push(Span::styled(slice(start, end), style));
+        last_match_end = end;
+    }
+    spans.push(Span::styled(
+        &text[next_char_boundary(text, last_match_end)..],
+        style,
+    ));
+    spans
+}
+
+/// Truncate `text` to `max_length` bytes, clipping its match ranges
+/// accordingly.
+fn truncate_with_ranges(
+    text: &str,
+    max_length: usize,
+    match_ranges: &[(u32, u32)],
+) -> (String, Vec<(u32, u32)>) {
+    let text = preprocess_line_with_max_length(text, max_length);
+    let len = u32::try_from(text.len()).unwrap_or(u32::MAX);
+    let match_ranges = match_ranges
+        .iter()
+        .filter(|(start, _)| *start < len)
+        .map(|(start, end)| (*start, (*end).min(len)))
+        .collect();
+    (text, match_ranges)
 }
 
+/// Build the list widget displaying the given entries.
+///
+/// `width` is the number of columns available to each entry, which is used
+/// to handle entries that don't fit according to `overflow`. Entry values
+/// longer than what that width can display are truncated.
+#[allow(clippy::too_many_arguments)]
 pub fn build_results_list<'a, 'b>(
     results_block: Block<'b>,
     entries: &'a [Entry],
     list_direction: ListDirection,
     results_list_colors: Option<ResultsListColors>,
     use_icons: bool,
+    highlight_symbol: &'a str,
+    marked_entries: Option<&IndexMap<String, Entry>>,
+    overflow: ResultsOverflow,
+    width: usize,
 ) -> List<'a>
 where
     'b: 'a,
 {
     let results_list_colors = results_list_colors.unwrap_or_default();
+    let marked_entries = marked_entries.filter(|m| !m.is_empty());
+    let max_value_length = max_line_length_for_width(width);
     List::new(entries.iter().map(|entry| {
         let mut spans = Vec::new();
+        // optional marker
+        if let Some(marked_entries) = marked_entries {
+            if marked_entries.contains_key(&entry.stdout_repr()) {
+                spans.push(Span::styled(
+                    MARKER_SYMBOL,
+                    Style::default().fg(DEFAULT_RESULT_MARKER_FG),
+                ));
+            } else {
+                spans.push(Span::raw(NO_MARKER_SYMBOL));
+            }
+        }
         // optional icon
         if entry.icon.is_some() && use_icons {
             let icon = entry.icon.as_ref().unwrap();
             spans.push(Span::styled(
                 icon.to_string(),
-                Style::default().fg(Color::from_str(icon.color).unwrap()),
+                Style::default()
+                    .fg(icon_color(icon, results_list_colors.result_name_fg)),
             ));
             spans.push(Span::raw(" "));
         }
         // entry name
-        if let Some(name_match_ranges) = &entry.name_match_ranges {
-            let mut last_match_end = 0;
-            for (start, end) in name_match_ranges
-                .iter()
-                .map(|(s, e)| (*s as usize, *e as usize))
+        let name_style =
+            Style::default().fg(results_list_colors.result_name_fg);
+        // the room left for the name once the prefix and the line number
+        // are drawn, if it should be shrunk with an ellipsis
+        let name_width = (overflow == ResultsOverflow::Ellipsis).then(|| {
+            let line_number_width =
+                entry.position_suffix().map_or(0, |p| p.len());
+            width
+                .saturating_sub(spans.iter().map(Span::width).sum::<usize>())
+                .saturating_sub(line_number_width)
+        });
+        match (&entry.name_match_ranges, name_width) {
+            (Some(name_match_ranges), Some(name_width))
+                if entry.name.width() > name_width =>
             {
-                spans.push(Span::styled(
-                    slice_at_char_boundaries(
+                let (name, name_match_ranges) =
+                    shrink_with_ellipsis_and_ranges(
                         &entry.name,
-                        last_match_end,
-                        start,
-                    ),
-                    Style::default().fg(results_list_colors.result_name_fg),
+                        name_width,
+                        name_match_ranges,
+                    );
+                spans.extend(
+                    highlighted_spans(
+                        &name,
+                        &name_match_ranges,
+                        name_style,
+                        results_list_colors.match_style(),
+                        results_list_colors.whole_word_match_style(),
+                    )
+                    .into_iter()
+                    .map(|s| Span::styled(s.content.into_owned(), s.style)),
+                );
+            }
+            (Some(name_match_ranges), _) => {
+                spans.extend(highlighted_spans(
+                    &entry.name,
+                    name_match_ranges,
+                    name_style,
+                    results_list_colors.match_style(),
+                    results_list_colors.whole_word_match_style(),
                 ));
+            }
+            (None, Some(name_width)) => {
                 spans.push(Span::styled(
-                    slice_at_char_boundaries(&entry.name, start, end),
-                    Style::default().fg(Color::Red),
+                    shrink_with_ellipsis(entry.display_name(), name_width),
+                    name_style,
                 ));
-                last_match_end = end;
             }
-            spans.push(Span::styled(
-                &entry.name[next_char_boundary(&entry.name, last_match_end)..],
-                Style::default().fg(results_list_colors.result_name_fg),
-            ));
-        } else {
-            spans.push(Span::styled(
-                entry.display_name(),
-                Style::default().fg(results_list_colors.result_name_fg),
-            ));
+            (None, None) => {
+                spans.push(Span::styled(entry.display_name(), name_style));
+            }
         }
-        // optional line number
-        if let Some(line_number) = entry.line_number {
+        // optional line number (and column)
+        if let Some(position) = entry.position_suffix() {
             spans.push(Span::styled(
-                format!(":{line_number}"),
+                position,
                 Style::default().fg(results_list_colors.result_line_number_fg),
             ));
         }
@@ -126,65 +345,134 @@ where
         if let Some(preview) = &entry.value {
             spans.push(Span::raw(": "));
 
-            if let Some(preview_match_ranges) = &entry.value_match_ranges {
-                if !preview_match_ranges.is_empty() {
-                    let mut last_match_end = 0;
-                    for (start, end) in preview_match_ranges
-                        .iter()
-                        .map(|(s, e)| (*s as usize, *e as usize))
-                    {
-                        spans.push(Span::styled(
-                            slice_at_char_boundaries(
-                                preview,
-                                last_match_end,
-                                start,
-                            ),
-                            Style::default()
-                                .fg(results_list_colors.result_preview_fg),
-                        ));
-                        spans.push(Span::styled(
-                            slice_at_char_boundaries(preview, start, end),
-                            Style::default().fg(Color::Red),
-                        ));
-                        last_match_end = end;
-                    }
-                    spans.push(Span::styled(
-                        &preview[next_char_boundary(
-                            preview,
-                            preview_match_ranges.last().unwrap().1 as usize,
-                        )..],
-                        Style::default()
-                            .fg(results_list_colors.result_preview_fg),
-                    ));
-                }
-            } else {
-                spans.push(Span::styled(
+            let preview_style =
+                Style::default().fg(results_list_colors.result_preview_fg);
+            if preview.len() > max_value_length {
+                let (preview, preview_match_ranges) = truncate_with_ranges(
+                    preview,
+                    max_value_length,
+                    entry.value_match_ranges.as_deref().unwrap_or_default(),
+                );
+                spans.extend(
+                    highlighted_spans(
+                        &preview,
+                        &preview_match_ranges,
+                        preview_style,
+                        results_list_colors.match_style(),
+                        results_list_colors.whole_word_match_style(),
+                    )
+                    .into_iter()
+                    .map(|s| Span::styled(s.content.into_owned(), s.style)),
+                );
+            } else if let Some(preview_match_ranges) =
+                &entry.value_match_ranges
+            {
+                spans.extend(highlighted_spans(
                     preview,
-                    Style::default().fg(results_list_colors.result_preview_fg),
+                    preview_match_ranges,
+                    preview_style,
+                    results_list_colors.match_style(),
+                    results_list_colors.whole_word_match_style(),
                 ));
+            } else {
+                spans.push(Span::styled(preview, preview_style));
+            }
+        }
+        let line = Line::from(spans);
+        match overflow {
+            ResultsOverflow::Wrap => {
+                ListItem::new(Text::from(wrap_line(line, width)))
+            }
+            ResultsOverflow::Truncate | ResultsOverflow::Ellipsis => {
+                ListItem::new(line)
             }
         }
-        Line::from(spans)
     }))
     .direction(list_direction)
     .highlight_style(
         Style::default().bg(results_list_colors.result_selected_bg),
     )
-    .highlight_symbol("> ")
+    .highlight_symbol(highlight_symbol)
+    // keep unselected rows aligned with the selected one
+    .highlight_spacing(HighlightSpacing::Always)
     .block(results_block)
 }
 
+/// Render the given entries the way the results list draws them, without
+/// a terminal (e.g. for tests or a headless mode).
+///
+/// Each entry takes a single row of `width` columns, entries that don't fit
+/// being truncated, and none of them is selected.
+pub fn render_results(
+    entries: &[Entry],
+    results_list_colors: Option<ResultsListColors>,
+    use_icons: bool,
+    width: u16,
+) -> Buffer {
+    let list = build_results_list(
+        Block::default(),
+        entries,
+        ListDirection::TopToBottom,
+        results_list_colors,
+        use_icons,
+        "",
+        None,
+        ResultsOverflow::Truncate,
+        usize::from(width),
+    );
+    let height = u16::try_from(entries.len()).unwrap_or(u16::MAX);
+    let area = Rect::new(0, 0, width, height);
+    let mut buffer = Buffer::empty(area);
+    StatefulWidget::render(list, area, &mut buffer, &mut ListState::default());
+    buffer
+}
+
+/// Like `render_results`, but only keeps the text of each row, trailing
+/// whitespace excluded.
+pub fn render_results_to_lines(
+    entries: &[Entry],
+    use_icons: bool,
+    width: u16,
+) -> Vec<String> {
+    let buffer = render_results(entries, None, use_icons, width);
+    let area = buffer.area;
+    (0..area.height)
+        .map(|y| {
+            (0..area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Compute the position of the scrollbar thumb for the selected entry.
+///
+/// The scrollbar always goes from top to bottom, so the position has to be
+/// flipped when the best results are displayed at the bottom.
+fn scrollbar_position(
+    selected: usize,
+    result_count: usize,
+    order: ResultsOrder,
+) -> usize {
+    match order {
+        ResultsOrder::TopToBottom => selected,
+        ResultsOrder::BottomToTop => {
+            result_count.saturating_sub(1).saturating_sub(selected)
+        }
+    }
+}
+
 impl Television {
     pub(crate) fn draw_results_list(
         &mut self,
         f: &mut Frame,
         layout: &Layout,
     ) -> Result<()> {
-        let results_block = Block::default()
+        let results_block = self
+            .panel_block()
             .title_top(Line::from(" Results ").alignment(Alignment::Center))
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(BORDER_COLOR))
             .style(Style::default())
             .padding(Padding::right(1));
 
@@ -194,17 +482,24 @@ impl Television {
             self.results_picker.relative_select(Some(0));
         }
 
-        let entries = self.channel.results(
-            layout.results.height.saturating_sub(2).into(),
-            u32::try_from(self.results_picker.view_offset)?,
-        );
+        // the borders and padding may leave no room at all
+        let inner = results_block.inner(layout.results);
+        let entries = self
+            .channel
+            .results(inner.height.into(), self.results_picker.offset());
 
         let results_list = build_results_list(
             results_block,
             &entries,
-            ListDirection::BottomToTop,
-            None,
+            self.config.ui.results_order.into(),
+            Some(self.results_list_colors),
             self.config.ui.use_nerd_font_icons,
+            &self.config.ui.highlight_symbol,
+            Some(&self.marked_entries),
+            self.config.ui.results_overflow,
+            // account for the symbol
+            usize::from(inner.width)
+                .saturating_sub(self.config.ui.highlight_symbol.width()),
         );
 
         f.render_stateful_widget(
@@ -212,6 +507,431 @@ impl Television {
             layout.results,
             &mut self.results_picker.relative_state,
         );
+
+        let visible_height = usize::from(inner.height);
+        if self.config.ui.show_scrollbar
+            && result_count as usize > visible_height
+        {
+            let mut scrollbar_state =
+                ScrollbarState::new(result_count as usize)
+                    .viewport_content_length(visible_height)
+                    .position(scrollbar_position(
+                        self.results_picker.selected().unwrap_or(0),
+                        result_count as usize,
+                        self.config.ui.results_order,
+                    ));
+            f.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                    .begin_symbol(None)
+                    .end_symbol(None),
+                layout.results.inner(Margin {
+                    vertical: 1,
+                    horizontal: 0,
+                }),
+                &mut scrollbar_state,
+            );
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use television_channels::entry::PreviewType;
+
+    fn entries() -> Vec<Entry> {
+        vec![
+            Entry::new(String::from("first"), PreviewType::Basic),
+            Entry::new(String::from("second"), PreviewType::Basic),
+        ]
+    }
+
+    fn render_rows(
+        symbol: &str,
+        marked_entries: Option<&IndexMap<String, Entry>>,
+    ) -> Vec<String> {
+        render_rows_in_order(symbol, marked_entries, ResultsOrder::TopToBottom)
+    }
+
+    fn render_rows_in_order(
+        symbol: &str,
+        marked_entries: Option<&IndexMap<String, Entry>>,
+        order: ResultsOrder,
+    ) -> Vec<String> {
+        let entries = entries();
+        let list = build_results_list(
+            Block::default(),
+            &entries,
+            order.into(),
+            None,
+            false,
+            symbol,
+            marked_entries,
+            ResultsOverflow::Truncate,
+            12,
+        );
+        let area = Rect::new(0, 0, 12, 2);
+        let mut buffer = Buffer::empty(area);
+        let mut state = ListState::default().with_selected(Some(0));
+        StatefulWidget::render(list, area, &mut buffer, &mut state);
+        (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_results_order() {
+        assert_eq!(
+            render_rows_in_order("> ", None, ResultsOrder::TopToBottom),
+            vec!["> first     ", "  second    "]
+        );
+        assert_eq!(
+            render_rows_in_order("> ", None, ResultsOrder::BottomToTop),
+            vec!["  second    ", "> first     "]
+        );
+    }
+
+    #[test]
+    fn test_wrap_line() {
+        let name_style = Style::default().fg(Color::Blue);
+        let match_style = Style::default().fg(Color::Red);
+        let line = Line::from(vec![
+            Span::styled("src/te", name_style),
+            Span::styled("levis", match_style),
+            Span::styled("ion.rs", name_style),
+        ]);
+        assert_eq!(
+            wrap_line(line, 8),
+            vec![
+                Line::from(vec![
+                    Span::styled("src/te", name_style),
+                    Span::styled("le", match_style),
+                ]),
+                Line::from(vec![
+                    Span::styled("vis", match_style),
+                    Span::styled("ion.r", name_style),
+                ]),
+                Line::from(Span::styled("s", name_style)),
+            ]
+        );
+        assert_eq!(
+            wrap_line(Line::from("短い名前"), 5),
+            vec![Line::from("短い"), Line::from("名前")]
+        );
+        assert_eq!(wrap_line(Line::from(""), 5), vec![Line::default()]);
+    }
+
+    #[test]
+    fn test_wrapped_selection_spans_all_lines() {
+        let entries =
+            vec![Entry::new(String::from("abcdefghij"), PreviewType::Basic)];
+        let list = build_results_list(
+            Block::default(),
+            &entries,
+            ListDirection::TopToBottom,
+            None,
+            false,
+            "> ",
+            None,
+            ResultsOverflow::Wrap,
+            5,
+        );
+        let area = Rect::new(0, 0, 7, 2);
+        let mut buffer = Buffer::empty(area);
+        let mut state = ListState::default().with_selected(Some(0));
+        StatefulWidget::render(list, area, &mut buffer, &mut state);
+        let rows: Vec<String> = (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect();
+        assert_eq!(rows, vec!["> abcde", "  fghij"]);
+        for y in 0..area.height {
+            assert_eq!(buffer[(0, y)].bg, DEFAULT_RESULT_SELECTED_BG);
+        }
+    }
+
+    #[test]
+    fn test_ellipsis_overflow_keeps_visible_matches() {
+        let entries = vec![Entry::new(
+            String::from("/very/long/path/to/some/file.rs"),
+            PreviewType::Basic,
+        )
+        .with_name_match_ranges(vec![(1, 5), (11, 15), (24, 28)])];
+        let list = build_results_list(
+            Block::default(),
+            &entries,
+            ListDirection::TopToBottom,
+            None,
+            false,
+            "",
+            None,
+            ResultsOverflow::Ellipsis,
+            20,
+        );
+        let area = Rect::new(0, 0, 20, 1);
+        let mut buffer = Buffer::empty(area);
+        let mut state = ListState::default().with_selected(Some(0));
+        StatefulWidget::render(list, area, &mut buffer, &mut state);
+        let row: String =
+            (0..area.width).map(|x| buffer[(x, 0)].symbol()).collect();
+        assert_eq!(row, "/very/lo…/file.rs   ");
+        let matched: String = (0..area.width)
+            .filter(|x| buffer[(*x, 0)].fg == DEFAULT_RESULT_MATCH_FG)
+            .map(|x| buffer[(x, 0)].symbol())
+            .collect();
+        assert_eq!(matched, "veryfile");
+    }
+
+    #[test]
+    fn test_truncate_with_ranges() {
+        let text = "a".repeat(20);
+        let (truncated, ranges) =
+            truncate_with_ranges(&text, 8, &[(0, 2), (6, 12), (15, 18)]);
+        assert_eq!(truncated, "a".repeat(8));
+        assert_eq!(ranges, vec![(0, 2), (6, 8)]);
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        assert_eq!(merge_ranges(&[(0, 2), (2, 4)]), vec![(0, 4)]);
+        assert_eq!(merge_ranges(&[(0, 3), (1, 2), (2, 5)]), vec![(0, 5)]);
+        assert_eq!(
+            merge_ranges(&[(6, 8), (0, 2), (1, 3)]),
+            vec![(0, 3), (6, 8)]
+        );
+        assert_eq!(merge_ranges(&[]), vec![]);
+    }
+
+    #[test]
+    fn test_adjacent_matches_are_one_span() {
+        let style = Style::default();
+        let match_style = Style::default().fg(Color::Red);
+        let spans = highlighted_spans(
+            "abcdef",
+            &[(0, 2), (2, 4)],
+            style,
+            match_style,
+            match_style,
+        );
+        assert_eq!(
+            spans,
+            vec![
+                Span::styled("", style),
+                Span::styled("abcd", match_style),
+                Span::styled("ef", style),
+            ]
+        );
+        // even an empty list of ranges keeps the text
+        assert_eq!(
+            highlighted_spans("abc", &[], style, match_style, match_style),
+            vec![Span::styled("abc", style)]
+        );
+    }
+
+    #[test]
+    fn test_whole_word_matches() {
+        let colors =
+            ResultsListColors::default().bold_whole_word_matches(true);
+        let style = Style::default();
+        let spans = highlighted_spans(
+            "src/main.rs maintain",
+            &[(4, 8), (12, 16)],
+            style,
+            colors.match_style(),
+            colors.whole_word_match_style(),
+        );
+        assert_eq!(
+            spans,
+            vec![
+                Span::styled("src/", style),
+                Span::styled(
+                    "main",
+                    colors.match_style().add_modifier(Modifier::BOLD)
+                ),
+                Span::styled(".rs ", style),
+                Span::styled("main", colors.match_style()),
+                Span::styled("tain", style),
+            ]
+        );
+        // matches at the edges of the text are bounded by it
+        assert!(is_whole_word("main", 0, 4));
+        assert!(!is_whole_word("main_rs", 0, 4));
+        assert!(!is_whole_word("domain", 2, 6));
+        // disabled by default
+        let colors = ResultsListColors::default();
+        assert_eq!(colors.whole_word_match_style(), colors.match_style());
+    }
+
+    #[test]
+    fn test_channel_icon() {
+        let entries =
+            vec![Entry::new(String::from("main"), PreviewType::Basic)
+                .with_icon(devicons::FileIcon {
+                    icon: 'b',
+                    color: "#f1502f",
+                })];
+        let buffer = render_results(&entries, None, true, 10);
+        assert_eq!(buffer[(0, 0)].symbol(), "b");
+        assert_eq!(buffer[(0, 0)].fg, Color::Rgb(0xf1, 0x50, 0x2f));
+    }
+
+    #[test]
+    fn test_invalid_icon_color() {
+        let entries =
+            vec![Entry::new(String::from("main.rs"), PreviewType::Basic)
+                .with_icon(devicons::FileIcon {
+                    icon: 'x',
+                    color: "not a color",
+                })];
+        let buffer = render_results(&entries, None, true, 10);
+        assert_eq!(render_results_to_lines(&entries, true, 10), ["x main.rs"]);
+        // the icon falls back to the color of names
+        assert_eq!(buffer[(0, 0)].fg, DEFAULT_RESULT_NAME_FG);
+    }
+
+    #[test]
+    fn test_render_results_snapshot() {
+        let entries = vec![
+            Entry::new(String::from("src/main.rs"), PreviewType::Basic)
+                .with_name_match_ranges(vec![(4, 8)]),
+            Entry::new(String::from("src/lib.rs"), PreviewType::Basic)
+                .with_line_number(12)
+                .with_value(String::from("pub fn main() {}"))
+                .with_value_match_ranges(vec![(7, 11)]),
+            Entry::new(
+                String::from("a/very/long/path/to/main.rs"),
+                PreviewType::Basic,
+            )
+            .with_name_match_ranges(vec![(20, 24)]),
+        ];
+        assert_eq!(
+            render_results_to_lines(&entries, false, 30),
+            [
+                "src/main.rs",
+                "src/lib.rs:12: pub fn main() {",
+                "a/very/long/path/to/main.rs",
+            ]
+        );
+
+        let colors = ResultsListColors::default();
+        let buffer = render_results(&entries, Some(colors), false, 30);
+        let styled = |y: u16| -> Vec<(String, Color)> {
+            let mut chunks: Vec<(String, Color)> = Vec::new();
+            for x in 0..buffer.area.width {
+                let cell = &buffer[(x, y)];
+                match chunks.last_mut() {
+                    Some((text, fg)) if *fg == cell.fg => {
+                        text.push_str(cell.symbol());
+                    }
+                    _ => chunks.push((cell.symbol().to_string(), cell.fg)),
+                }
+            }
+            chunks
+        };
+        let chunk = |text: &str, fg| (text.to_string(), fg);
+        assert_eq!(
+            styled(0),
+            [
+                chunk("src/", DEFAULT_RESULT_NAME_FG),
+                chunk("main", DEFAULT_RESULT_MATCH_FG),
+                chunk(".rs", DEFAULT_RESULT_NAME_FG),
+                chunk(&" ".repeat(19), Color::Reset),
+            ]
+        );
+        assert_eq!(
+            styled(1),
+            [
+                chunk("src/lib.rs", DEFAULT_RESULT_NAME_FG),
+                chunk(":12", DEFAULT_RESULT_LINE_NUMBER_FG),
+                chunk(": ", Color::Reset),
+                chunk("pub fn ", DEFAULT_RESULT_PREVIEW_FG),
+                chunk("main", DEFAULT_RESULT_MATCH_FG),
+                chunk("() {", DEFAULT_RESULT_PREVIEW_FG),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scrollbar_position() {
+        assert_eq!(scrollbar_position(0, 50, ResultsOrder::TopToBottom), 0);
+        assert_eq!(scrollbar_position(10, 50, ResultsOrder::TopToBottom), 10);
+        // the best match is at the bottom of the list
+        assert_eq!(scrollbar_position(0, 50, ResultsOrder::BottomToTop), 49);
+        assert_eq!(scrollbar_position(49, 50, ResultsOrder::BottomToTop), 0);
+        assert_eq!(scrollbar_position(0, 0, ResultsOrder::BottomToTop), 0);
+    }
+
+    #[test]
+    fn test_custom_highlight_symbol() {
+        assert_eq!(
+            render_rows("❯❯ ", None),
+            vec!["❯❯ first    ", "   second   "]
+        );
+    }
+
+    #[test]
+    fn test_empty_highlight_symbol() {
+        assert_eq!(
+            render_rows("", None),
+            vec!["first       ", "second      "]
+        );
+    }
+
+    #[test]
+    fn test_marked_entries() {
+        let second = entries().remove(1);
+        let marked = IndexMap::from([(second.stdout_repr(), second)]);
+        assert_eq!(
+            render_rows("> ", Some(&marked)),
+            vec![">   first   ", "  ✓ second  "]
+        );
+        // no room is taken when nothing is marked
+        assert_eq!(
+            render_rows("> ", Some(&IndexMap::new())),
+            vec!["> first     ", "  second    "]
+        );
+    }
+
+    fn draw_results(television: &mut Television, height: u16) -> Buffer {
+        let area = Rect::new(0, 0, 20, height);
+        let layout =
+            Layout::new(None, area, Rect::default(), None, None, None);
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(20, 3))
+                .unwrap();
+        terminal
+            .draw(|f| television.draw_results_list(f, &layout).unwrap())
+            .unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    #[tokio::test]
+    async fn test_tiny_results_area() {
+        let mut television = Television::new(
+            television_channels::channels::CliTvChannel::Env.to_channel(),
+        );
+        // only the borders fit, or not even those
+        for height in [0, 1, 2] {
+            draw_results(&mut television, height);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_view_offset() {
+        let mut television = Television::new(
+            television_channels::channels::CliTvChannel::Env.to_channel(),
+        );
+        television.results_picker.view_offset = usize::MAX;
+        let buffer = draw_results(&mut television, 3);
+        let row: String = (0..20).map(|x| buffer[(x, 1)].symbol()).collect();
+        assert_eq!(row.trim_matches(|c| c == '│' || c == ' '), "");
+    }
+}